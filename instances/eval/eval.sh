@@ -26,7 +26,7 @@ eval_for_m() {
     do
         cargo run -rq -- generate -j $INSTANCE_PATH/jobs-n${n}-m${m}.csv -c $INSTANCE_PATH/constraints-n${n}-m${m}.csv -n $n -m $m --min 1 --max $max -o 8 --min-chain 1 --max-chain $n --concave
     done
-    echo "ms,n,m,makespan" | tee $ILP_FILE $LP_FILE $DP_FILE
+    echo "ms,n,m,makespan,lp_bound,rounding_ratio" | tee $ILP_FILE $LP_FILE $DP_FILE
     for n in `seq 10 10 $N`
     do
         time timeout 15 cargo run -rq -- solve-dp -j $INSTANCE_PATH/jobs-n${n}-m${m}.csv -c $INSTANCE_PATH/constraints-n${n}-m${m}.csv >> $DP_FILE
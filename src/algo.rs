@@ -1,5 +1,21 @@
 // In this file we define the data structures used in the algorithm
 
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    fmt,
+    fmt::Write,
+    sync::{Arc, OnceLock},
+};
+
+use clap::ValueEnum;
+use itertools::Itertools;
+use log::debug;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::provenance::hash_contents;
+
 /// A problem instance
 #[derive(Debug)]
 pub struct Instance {
@@ -9,30 +25,1149 @@ pub struct Instance {
     pub jobs: Vec<Job>,
     /// A partial ordering on the jobs
     pub constraints: Vec<Constraint>,
-    /// The maximum number of seconds in the universe
-    pub max_time: i32,
+    /// Precedence constraints that schedules may break at a penalty
+    pub soft_constraints: Vec<SoftConstraint>,
+    /// Time windows in which fewer processors may be busy, empty if all
+    /// processors may be used at all times
+    pub capacity: Vec<CapacityWindow>,
+    /// Time windows in which single processors are down for maintenance,
+    /// sorted by processor and start time
+    pub downtime: Vec<Downtime>,
+    /// Jobs that must run at fixed start times on fixed processors, sorted by
+    /// job index
+    pub pinned: Vec<ScheduledJob>,
+    /// Names of the processors, empty if they are called "Processor <i>"
+    pub machine_names: Vec<String>,
+    /// Latest completion time considered by solvers, computed on first use
+    /// unless it was set explicitly
+    horizon: OnceLock<i32>,
+    /// Lazily computed adjacency lists of the constraints
+    adjacency: OnceLock<Adjacency>,
+    /// Lazily computed transitive reduction of the constraints
+    reduction: OnceLock<Vec<Constraint>>,
+    /// Lazily computed sorted positions of the jobs that every job reaches
+    /// along the constraints
+    descendants: OnceLock<Vec<Vec<usize>>>,
+    /// Lazily computed latest start times that the pinned jobs allow
+    pin_deadlines: OnceLock<Vec<Option<i32>>>,
+    /// Lazily computed shared copies of the jobs, which the scheduled jobs
+    /// of all schedules of the instance point to
+    shared_jobs: OnceLock<Vec<Arc<Job>>>,
 }
 impl Instance {
+    /// Creates a new instance from its jobs and constraints
+    pub fn new(processor_count: usize, jobs: Vec<Job>, constraints: Vec<Constraint>) -> Self {
+        Self {
+            processor_count,
+            jobs,
+            constraints,
+            soft_constraints: vec![],
+            capacity: vec![],
+            downtime: vec![],
+            pinned: vec![],
+            machine_names: vec![],
+            horizon: OnceLock::new(),
+            adjacency: OnceLock::new(),
+            reduction: OnceLock::new(),
+            descendants: OnceLock::new(),
+            pin_deadlines: OnceLock::new(),
+            shared_jobs: OnceLock::new(),
+        }
+    }
+    /// Overrides the horizon instead of computing it from the jobs
+    pub fn with_horizon(mut self, horizon: i32) -> Self {
+        self.horizon = OnceLock::from(horizon);
+        self
+    }
+    /// Adds precedence constraints that schedules may break at a penalty
+    pub fn with_soft_constraints(mut self, soft_constraints: Vec<SoftConstraint>) -> Self {
+        self.soft_constraints = soft_constraints;
+        self
+    }
+    /// Limits the number of busy processors during the given time windows
+    pub fn with_capacity(mut self, capacity: Vec<CapacityWindow>) -> Self {
+        self.capacity = capacity;
+        self
+    }
+    /// Takes the given processors down during the given time windows
+    pub fn with_downtime(mut self, mut downtime: Vec<Downtime>) -> Self {
+        downtime.sort_by_key(|down| (down.processor, down.from, down.to));
+        self.downtime = downtime;
+        self
+    }
+    /// Fixes the start time, allotment, and processors of the given jobs
+    pub fn with_pinned(mut self, mut pinned: Vec<ScheduledJob>) -> Self {
+        pinned.sort_by_key(|pin| pin.job.index);
+        self.pinned = pinned;
+        self
+    }
+    /// Names the processors, one name per processor
+    pub fn with_machine_names(mut self, machine_names: Vec<String>) -> Self {
+        self.machine_names = machine_names;
+        self
+    }
+    /// Returns the name of the given processor, see `machine_name`
+    pub fn machine_name(&self, processor: usize) -> String {
+        machine_name(&self.machine_names, processor)
+    }
+    /// Returns how the job with the given index is pinned, if it is
+    pub fn pin(&self, index: usize) -> Option<&ScheduledJob> {
+        self.pinned
+            .binary_search_by_key(&index, |pin| pin.job.index)
+            .ok()
+            .map(|position| &self.pinned[position])
+    }
+    /// Creates a copy of the instance with only the first `processor_count`
+    /// processors, dropping the processing times for larger allotments and
+    /// the other processors from the affinities. Pinned jobs are kept as they
+    /// are, so they must fit onto the remaining processors.
+    pub fn with_processor_count(&self, processor_count: usize) -> Self {
+        assert!(
+            (1..=self.processor_count).contains(&processor_count),
+            "cannot restrict {} processors to {processor_count}",
+            self.processor_count
+        );
+        let jobs = self
+            .jobs
+            .iter()
+            .map(|job| {
+                let mut job = job.clone();
+                job.processing_times.truncate(processor_count);
+                job.synthesized = job.synthesized.min(processor_count);
+                if let Some(allowed) = &mut job.affinity {
+                    allowed.retain(|&p| p < processor_count);
+                }
+                job
+            })
+            .collect::<Vec<_>>();
+        let pinned = self
+            .pinned
+            .iter()
+            .map(|pin| ScheduledJob {
+                job: Arc::new(jobs[pin.job.index].clone()),
+                ..pin.clone()
+            })
+            .collect();
+        let downtime = self
+            .downtime
+            .iter()
+            .filter(|down| down.processor < processor_count)
+            .copied()
+            .collect();
+        Self::new(processor_count, jobs, self.constraints.clone())
+            .with_soft_constraints(self.soft_constraints.clone())
+            .with_capacity(self.capacity.clone())
+            .with_downtime(downtime)
+            .with_pinned(pinned)
+            .with_machine_names(
+                self.machine_names
+                    .iter()
+                    .take(processor_count)
+                    .cloned()
+                    .collect(),
+            )
+    }
+    /// Creates a copy of the instance in which every job is replaced by its
+    /// concave closure, see `Job::concave_closure`
+    pub fn concave_closure(&self) -> Self {
+        let jobs = self
+            .jobs
+            .iter()
+            .map(Job::concave_closure)
+            .collect::<Vec<_>>();
+        let pinned = self
+            .pinned
+            .iter()
+            .map(|pin| ScheduledJob {
+                job: Arc::new(jobs[pin.job.index].clone()),
+                ..pin.clone()
+            })
+            .collect();
+        Self::new(self.processor_count, jobs, self.constraints.clone())
+            .with_soft_constraints(self.soft_constraints.clone())
+            .with_capacity(self.capacity.clone())
+            .with_downtime(self.downtime.clone())
+            .with_pinned(pinned)
+            .with_machine_names(self.machine_names.clone())
+    }
+    /// Creates a copy of the instance in which one unit of time stands for
+    /// `granularity` units of the original instance. Processing times, minimum
+    /// lags, capacity windows, and downtime are rounded outwards and maximum lags
+    /// inwards, so that every schedule of the copy stays feasible when its
+    /// times are multiplied by `granularity`. Pinned jobs cannot be coarsened.
+    pub fn coarsen(&self, granularity: i32) -> Self {
+        assert!(granularity >= 1, "granularity must be at least 1");
+        assert!(self.pinned.is_empty(), "cannot coarsen pinned jobs");
+        let ceil = |value: i32| -(-value).div_euclid(granularity);
+        let floor = |value: i32| value.div_euclid(granularity);
+        let jobs = self
+            .jobs
+            .iter()
+            .map(|job| Job {
+                processing_times: job.processing_times.iter().map(|&p| ceil(p)).collect(),
+                ..job.clone()
+            })
+            .collect();
+        let constraints = self
+            .constraints
+            .iter()
+            .map(|&Constraint(left, right, lag)| {
+                Constraint(
+                    left,
+                    right,
+                    Lag {
+                        min: ceil(lag.min),
+                        max: lag.max.map(floor),
+                    },
+                )
+            })
+            .collect();
+        let soft_constraints = self
+            .soft_constraints
+            .iter()
+            .map(|soft| SoftConstraint {
+                min_lag: ceil(soft.min_lag),
+                ..*soft
+            })
+            .collect();
+        let capacity = self
+            .capacity
+            .iter()
+            .map(|window| CapacityWindow {
+                from: floor(window.from),
+                to: ceil(window.to),
+                ..*window
+            })
+            .collect();
+        let downtime = self
+            .downtime
+            .iter()
+            .map(|down| Downtime {
+                from: floor(down.from),
+                to: ceil(down.to),
+                ..*down
+            })
+            .collect();
+        let coarse = Self::new(self.processor_count, jobs, constraints)
+            .with_soft_constraints(soft_constraints)
+            .with_capacity(capacity)
+            .with_downtime(downtime)
+            .with_machine_names(self.machine_names.clone());
+        match self.horizon.get() {
+            Some(&horizon) => coarse.with_horizon(ceil(horizon)),
+            None => coarse,
+        }
+    }
+    /// Returns a shared copy of the job at the given index, which schedules
+    /// refer to instead of copying the job. The copies are made on first use.
+    pub fn shared_job(&self, index: usize) -> Arc<Job> {
+        let shared = self
+            .shared_jobs
+            .get_or_init(|| self.jobs.iter().cloned().map(Arc::new).collect());
+        Arc::clone(&shared[index])
+    }
+    /// Returns the latest completion time that solvers need to consider. By
+    /// default, this is the makespan of executing all jobs one after another,
+    /// each with its slowest allotment and after its longest minimum lag,
+    /// which is feasible for every instance without maximum lags. Pinned jobs
+    /// and downtime extend it by the time at which the last of them ends.
+    pub fn horizon(&self) -> i32 {
+        *self.horizon.get_or_init(|| {
+            let adjacency = self.adjacency();
+            let blocked = self
+                .pinned
+                .iter()
+                .map(ScheduledJob::completion_time)
+                .chain(self.downtime.iter().map(|down| down.to))
+                .max()
+                .unwrap_or(0);
+            blocked
+                + self
+                    .jobs
+                    .iter()
+                    .enumerate()
+                    .map(|(j, job)| {
+                        let lag = adjacency.predecessors[j]
+                            .iter()
+                            .map(|&p| self.lag(p, j).min)
+                            .max()
+                            .unwrap_or(0);
+                        job.processing_times.iter().max().copied().unwrap_or(0) + lag
+                    })
+                    .sum::<i32>()
+        })
+    }
+    /// Returns the number of processors that may be busy at `time`, which is
+    /// the smallest capacity of all windows containing it, and at most the
+    /// number of processors that are not down
+    pub fn capacity_at(&self, time: i32) -> usize {
+        let down = self
+            .downtime
+            .iter()
+            .filter(|down| down.from <= time && time < down.to)
+            .map(|down| down.processor)
+            .dedup()
+            .count();
+        self.capacity
+            .iter()
+            .filter(|window| window.from <= time && time < window.to)
+            .map(|window| window.capacity)
+            .fold(self.processor_count.saturating_sub(down), usize::min)
+    }
+    /// Returns the times at which the capacity drops, i.e. at which a
+    /// capacity window starts or a processor goes down
+    pub fn capacity_drops(&self) -> impl Iterator<Item = i32> + '_ {
+        self.capacity
+            .iter()
+            .map(|window| window.from)
+            .chain(self.downtime.iter().map(|down| down.from))
+    }
+    /// Returns the times at which the capacity rises, i.e. at which a
+    /// capacity window ends or a processor comes back up
+    pub fn capacity_rises(&self) -> impl Iterator<Item = i32> + '_ {
+        self.capacity
+            .iter()
+            .map(|window| window.to)
+            .chain(self.downtime.iter().map(|down| down.to))
+    }
+    /// Checks whether the capacity is limited at any time, by capacity
+    /// windows or by downtime
+    pub fn has_capacity_limits(&self) -> bool {
+        !self.capacity.is_empty() || !self.downtime.is_empty()
+    }
+    /// Returns the sorted positions of the predecessors of the job at
+    /// position `j`
+    pub fn predecessor_indices(&self, j: usize) -> &[usize] {
+        &self.adjacency().predecessors[j]
+    }
+    /// Returns the sorted positions of the successors of the job at position
+    /// `j`
+    pub fn successor_indices(&self, j: usize) -> &[usize] {
+        &self.adjacency().successors[j]
+    }
     /// Computes a list of jobs that are predecessors of the given job
+    #[deprecated(note = "use `predecessor_indices`, which does not allocate")]
+    #[cfg_attr(
+        not(test),
+        expect(dead_code, reason = "kept for code outside of the CLI")
+    )]
     pub fn predecessors<'a>(&'a self, job: &Job) -> Vec<(usize, &'a Job)> {
-        self.jobs
-            .iter()
-            .enumerate()
-            .filter(|(_, j)| job.index != j.index && j.less_than(&self.constraints, job))
+        let adjacency = self.adjacency();
+        adjacency
+            .position(job)
+            .map(|pos| &adjacency.predecessors[pos])
+            .into_iter()
+            .flatten()
+            .map(|&j| (j, &self.jobs[j]))
             .collect()
     }
     /// Computes a list of jobs that are successors of the given job
+    #[deprecated(note = "use `successor_indices`, which does not allocate")]
+    #[cfg_attr(
+        not(test),
+        expect(dead_code, reason = "kept for code outside of the CLI")
+    )]
     pub fn successors<'a>(&'a self, job: &Job) -> Vec<(usize, &'a Job)> {
+        let adjacency = self.adjacency();
+        adjacency
+            .position(job)
+            .map(|pos| &adjacency.successors[pos])
+            .into_iter()
+            .flatten()
+            .map(|&j| (j, &self.jobs[j]))
+            .collect()
+    }
+    /// Returns `None` if the two jobs are incomparable. Returns `Some(true)`
+    /// if left is less than right and returns `Some(false)` if right is less
+    /// than left.
+    pub fn compare(&self, left: &Job, right: &Job) -> Option<bool> {
+        let adjacency = self.adjacency();
+        let (l, r) = (adjacency.position(left)?, adjacency.position(right)?);
+        if adjacency.successors[l].binary_search(&r).is_ok() {
+            Some(true)
+        } else if adjacency.successors[r].binary_search(&l).is_ok() {
+            Some(false)
+        } else {
+            None
+        }
+    }
+    /// Returns the lag between the jobs at the positions `left` and `right`,
+    /// which is the default lag if there is no constraint between them
+    pub fn lag(&self, left: usize, right: usize) -> Lag {
+        self.adjacency()
+            .lags
+            .get(&(left, right))
+            .copied()
+            .unwrap_or_default()
+    }
+    /// Orders the positions of the jobs such that every job comes after all
+    /// its predecessors. Jobs on or after a cycle are left out, see
+    /// `topo_order` to find the cycle instead.
+    pub fn topological_order(&self) -> Vec<usize> {
+        let adjacency = self.adjacency();
+        let mut remaining = adjacency
+            .predecessors
+            .iter()
+            .map(Vec::len)
+            .collect::<Vec<_>>();
+        let mut ready = (0..self.jobs.len())
+            .filter(|&j| remaining[j] == 0)
+            .collect::<Vec<_>>();
+        let mut order = Vec::with_capacity(self.jobs.len());
+        while let Some(j) = ready.pop() {
+            order.push(j);
+            for &s in &adjacency.successors[j] {
+                remaining[s] -= 1;
+                if remaining[s] == 0 {
+                    ready.push(s);
+                }
+            }
+        }
+        order
+    }
+    /// Computes the depth of every job by position, i.e. the largest number
+    /// of constraints on a path from a job without predecessors to it.
+    /// Panics if the constraints contain a cycle.
+    pub fn depths(&self) -> Vec<usize> {
+        let order = topo_order(self).unwrap_or_else(|e| panic!("cannot compute the depths: {e}"));
+        self.depths_along(&order)
+    }
+    /// Computes the depth of every job by position, visiting the jobs in the
+    /// topological `order`
+    fn depths_along(&self, order: &[usize]) -> Vec<usize> {
+        let adjacency = self.adjacency();
+        let mut depths = vec![0; self.jobs.len()];
+        for &j in order {
+            depths[j] = adjacency.predecessors[j]
+                .iter()
+                .map(|&p| depths[p] + 1)
+                .max()
+                .unwrap_or(0);
+        }
+        depths
+    }
+    /// Computes the transitive reduction of the constraints, i.e. the hard
+    /// constraints without those that follow from a longer path between the
+    /// same jobs. Only constraints with the default lag are dropped, since a
+    /// path of jobs without processing time cannot imply a minimum or maximum
+    /// lag. Duplicate constraints are merged. Takes O(V·E) time on first
+    /// use, after which the reduction is reused.
+    pub fn transitive_reduction(&self) -> &[Constraint] {
+        self.reduction.get_or_init(|| self.reduce())
+    }
+    /// Returns the sorted positions of the jobs that a path of constraints
+    /// leads to from the job at position `j`, which includes `j` itself only
+    /// if it lies on a cycle. The sets of all jobs are computed on first use
+    /// in O(V·E) time.
+    pub fn descendant_indices(&self, j: usize) -> &[usize] {
+        &self.descendants.get_or_init(|| self.close())[j]
+    }
+    /// Checks whether a path of constraints leads from the job at position
+    /// `from` to the one at position `to`
+    pub fn reaches(&self, from: usize, to: usize) -> bool {
+        self.descendant_indices(from).binary_search(&to).is_ok()
+    }
+    /// Computes the transitive reduction, see `transitive_reduction`
+    fn reduce(&self) -> Vec<Constraint> {
+        let adjacency = self.adjacency();
+        let n = self.jobs.len();
+        // the job from which every job was last reached, to avoid clearing
+        // the marks for every job
+        let mut reached_from = vec![usize::MAX; n];
+        let mut reduced = vec![];
+        for j in 0..n {
+            let mut stack = adjacency.successors[j]
+                .iter()
+                .flat_map(|&s| &adjacency.successors[s])
+                .copied()
+                .collect::<Vec<_>>();
+            while let Some(k) = stack.pop() {
+                if reached_from[k] != j {
+                    reached_from[k] = j;
+                    stack.extend(&adjacency.successors[k]);
+                }
+            }
+            for &k in &adjacency.successors[j] {
+                let lag = self.lag(j, k);
+                if reached_from[k] != j || lag != Lag::default() {
+                    reduced.push(Constraint(self.jobs[j].index, self.jobs[k].index, lag));
+                }
+            }
+        }
+        reduced
+    }
+    /// Computes the transitive sets, see `descendant_indices`
+    fn close(&self) -> Vec<Vec<usize>> {
+        let adjacency = self.adjacency();
+        (0..self.jobs.len())
+            .map(|j| {
+                let mut visited = vec![false; self.jobs.len()];
+                let mut stack = adjacency.successors[j].clone();
+                while let Some(k) = stack.pop() {
+                    if !std::mem::replace(&mut visited[k], true) {
+                        stack.extend(&adjacency.successors[k]);
+                    }
+                }
+                visited.iter().positions(|&v| v).collect()
+            })
+            .collect()
+    }
+    /// Describes every maximum lag that cannot be met, either because it is
+    /// below the minimum lag of the same constraint, or because the jobs on
+    /// another path between the two jobs take longer even with their fastest
+    /// allotments
+    pub fn lag_conflicts(&self) -> Vec<String> {
+        let adjacency = self.adjacency();
+        let order = self.topological_order();
+        let fastest = |j: usize| {
+            self.jobs[j]
+                .processing_times
+                .iter()
+                .min()
+                .copied()
+                .unwrap_or(0)
+        };
+        let mut conflicts = vec![];
+        for (&(a, b), lag) in adjacency.lags.iter().sorted_by_key(|(&edge, _)| edge) {
+            let Some(max) = lag.max else {
+                continue;
+            };
+            if lag.min > max {
+                conflicts.push(format!(
+                    "job {b} must start at least {} but at most {max} after job {a} completes",
+                    lag.min
+                ));
+                continue;
+            }
+            // earliest completion of every job after job `a` relative to the
+            // completion of `a`, following only paths that start at `a`
+            let mut completed_after: Vec<Option<i32>> = vec![None; self.jobs.len()];
+            completed_after[a] = Some(0);
+            for &j in order.iter().skip_while(|&&j| j != a).skip(1) {
+                completed_after[j] = adjacency.predecessors[j]
+                    .iter()
+                    .filter_map(|&p| completed_after[p].map(|c| c + self.lag(p, j).min))
+                    .max()
+                    .map(|start| start + fastest(j));
+            }
+            let earliest_start = adjacency.predecessors[b]
+                .iter()
+                .filter(|&&p| p != a)
+                .filter_map(|&p| completed_after[p].map(|c| c + self.lag(p, b).min))
+                .max()
+                .unwrap_or(lag.min);
+            if earliest_start > max {
+                conflicts.push(format!(
+                    "job {b} must start at most {max} after job {a} completes, but the jobs in between take at least {earliest_start}"
+                ));
+            }
+        }
+        conflicts
+    }
+    /// Finds jobs whose affinity rules out every processor or names
+    /// processors that do not exist
+    pub fn affinity_conflicts(&self) -> Vec<String> {
         self.jobs
             .iter()
-            .enumerate()
-            .filter(|(_, j)| job.index != j.index && j.greater_than(&self.constraints, job))
+            .filter_map(|job| {
+                let allowed = job.affinity.as_ref()?;
+                if allowed.is_empty() {
+                    return Some(format!("job {} may not run on any processor", job.index));
+                }
+                let missing = allowed
+                    .iter()
+                    .filter(|&&p| p >= self.processor_count)
+                    .join(", ");
+                (!missing.is_empty()).then(|| {
+                    format!(
+                        "job {} may run on processors {missing}, but there are only {} processors",
+                        job.index, self.processor_count
+                    )
+                })
+            })
+            .collect()
+    }
+    /// Checks whether any job is limited to some of the processors
+    pub fn has_affinity(&self) -> bool {
+        self.jobs.iter().any(|job| job.affinity.is_some())
+    }
+    /// Describes every pinned job that cannot run as pinned, either on its
+    /// own, next to the other pinned jobs, or after its predecessors, which
+    /// take at least as long as their fastest allotments unless they are
+    /// pinned themselves
+    pub fn pin_conflicts(&self) -> Vec<String> {
+        let mut conflicts = vec![];
+        for pin in &self.pinned {
+            let index = pin.job.index;
+            if pin.allotment == 0 || pin.allotment > pin.job.processing_times.len() {
+                conflicts.push(format!(
+                    "job {index} is pinned with invalid allotment {}",
+                    pin.allotment
+                ));
+            } else if pin.start_time < 0 {
+                conflicts.push(format!(
+                    "job {index} is pinned to start at {}",
+                    pin.start_time
+                ));
+            }
+        }
+        if !conflicts.is_empty() {
+            return conflicts;
+        }
+        let pinned = Schedule {
+            processor_count: self.processor_count,
+            jobs: self.pinned.clone(),
+        };
+        conflicts.extend(pinned.processor_violations(&self.machine_names));
+        conflicts.extend(pinned.capacity_violations(self));
+        conflicts.extend(pinned.downtime_violations(self));
+
+        let adjacency = self.adjacency();
+        let mut completed_at = vec![0; self.jobs.len()];
+        for j in self.topological_order() {
+            let ready = adjacency.predecessors[j]
+                .iter()
+                .map(|&p| completed_at[p] + self.lag(p, j).min)
+                .max()
+                .unwrap_or(0);
+            completed_at[j] = if let Some(pin) = self.pin(j) {
+                if pin.start_time < ready {
+                    conflicts.push(format!(
+                        "job {j} is pinned to start at {}, but its predecessors cannot complete before {ready}",
+                        pin.start_time
+                    ));
+                }
+                for &p in &adjacency.predecessors[j] {
+                    let (Some(max), Some(predecessor)) = (self.lag(p, j).max, self.pin(p)) else {
+                        continue;
+                    };
+                    if pin.start_time > predecessor.completion_time() + max {
+                        conflicts.push(format!(
+                            "job {j} is pinned to start at {}, more than {max} after its pinned predecessor {p} completes at {}",
+                            pin.start_time,
+                            predecessor.completion_time()
+                        ));
+                    }
+                }
+                pin.completion_time()
+            } else {
+                ready
+                    + self.jobs[j]
+                        .processing_times
+                        .iter()
+                        .min()
+                        .copied()
+                        .unwrap_or(0)
+            };
+        }
+        conflicts
+    }
+    /// Returns the latest time at which each job can start such that the
+    /// pinned jobs after it can still start on time, assuming that the jobs in
+    /// between take as long as their fastest allotments, or `None` if no
+    /// pinned job depends on it. Pinned jobs must start at their own start
+    /// times.
+    pub fn pin_deadlines(&self) -> &[Option<i32>] {
+        self.pin_deadlines.get_or_init(|| {
+            let adjacency = self.adjacency();
+            let mut deadlines = vec![None; self.jobs.len()];
+            for j in self.topological_order().into_iter().rev() {
+                deadlines[j] = if let Some(pin) = self.pin(j) {
+                    Some(pin.start_time)
+                } else {
+                    let fastest = self.jobs[j]
+                        .processing_times
+                        .iter()
+                        .min()
+                        .copied()
+                        .unwrap_or(0);
+                    adjacency.successors[j]
+                        .iter()
+                        .filter_map(|&k| deadlines[k].map(|start| start - self.lag(j, k).min))
+                        .min()
+                        .map(|completion| completion - fastest)
+                };
+            }
+            deadlines
+        })
+    }
+    /// Computes a list of chains of jobs that are comparable to each other.
+    /// Each chain contains job positions sorted by the partial order. The
+    /// number of chains is the width of the instance.
+    pub fn chains(&self) -> Vec<Vec<usize>> {
+        let mut chains: Vec<Vec<usize>> = vec![];
+        for (job_index, job) in self.jobs.iter().enumerate() {
+            if let Some(chain) = chains.iter_mut().find(|chain| {
+                // Check if the job is comparable to all jobs in the chain
+                chain
+                    .iter()
+                    .all(|&i| self.compare(&self.jobs[i], job).is_some())
+            }) {
+                chain.push(job_index);
+            } else {
+                chains.push(vec![job_index]);
+            }
+        }
+        for chain in &mut chains {
+            chain.sort_by(|&left, &right| {
+                match self.compare(&self.jobs[left], &self.jobs[right]) {
+                    Some(true) => Ordering::Less,
+                    Some(false) => Ordering::Greater,
+                    _ => panic!("chain contains two non-comparable jobs"),
+                }
+            });
+        }
+        chains
+    }
+    /// Groups the job positions into the weakly connected components of the
+    /// precedence graph. Each component is sorted, and the components are
+    /// ordered by their first job.
+    pub fn component_jobs(&self) -> Vec<Vec<usize>> {
+        let adjacency = self.adjacency();
+        let mut component = vec![None; self.jobs.len()];
+        let mut components = vec![];
+        for root in 0..self.jobs.len() {
+            if component[root].is_some() {
+                continue;
+            }
+            component[root] = Some(components.len());
+            let mut jobs = vec![root];
+            let mut stack = vec![root];
+            while let Some(j) = stack.pop() {
+                for &k in adjacency.predecessors[j]
+                    .iter()
+                    .chain(&adjacency.successors[j])
+                {
+                    if component[k].is_none() {
+                        component[k] = Some(components.len());
+                        jobs.push(k);
+                        stack.push(k);
+                    }
+                }
+            }
+            jobs.sort_unstable();
+            components.push(jobs);
+        }
+        components
+    }
+    /// Splits the instance into one instance per weakly connected component of
+    /// the precedence graph, in the order of `component_jobs`. The jobs of
+    /// each component are renumbered in the order of their original indices.
+    /// All components share the processors, the capacity profile, and the
+    /// horizon if it is already known, and keep their pinned jobs.
+    pub fn components(&self) -> Vec<Instance> {
+        self.component_jobs()
+            .into_iter()
+            .map(|positions| self.restrict_to(&positions))
             .collect()
     }
+    /// Creates an instance of only the jobs at the given sorted positions,
+    /// renumbered in that order, along with the hard and soft constraints and
+    /// pins among them. The instance shares the processors, the capacity profile, and
+    /// the horizon if it is already known.
+    pub fn restrict_to(&self, positions: &[usize]) -> Instance {
+        let mut renumbered = HashMap::new();
+        let jobs = positions
+            .iter()
+            .enumerate()
+            .map(|(k, &j)| {
+                let mut job = self.jobs[j].clone();
+                renumbered.insert(job.index, k);
+                job.index = k;
+                job
+            })
+            .collect::<Vec<_>>();
+        let constraints = self
+            .constraints
+            .iter()
+            .filter_map(|&Constraint(left, right, lag)| {
+                Some(Constraint(
+                    *renumbered.get(&left)?,
+                    *renumbered.get(&right)?,
+                    lag,
+                ))
+            })
+            .collect();
+        let soft_constraints = self
+            .soft_constraints
+            .iter()
+            .filter_map(|soft| {
+                Some(SoftConstraint {
+                    left: *renumbered.get(&soft.left)?,
+                    right: *renumbered.get(&soft.right)?,
+                    ..*soft
+                })
+            })
+            .collect();
+        let pinned = positions
+            .iter()
+            .filter_map(|&j| self.pin(j))
+            .map(|pin| ScheduledJob {
+                job: Arc::new(jobs[renumbered[&pin.job.index]].clone()),
+                ..pin.clone()
+            })
+            .collect();
+        let restricted = Instance::new(self.processor_count, jobs, constraints)
+            .with_soft_constraints(soft_constraints)
+            .with_capacity(self.capacity.clone())
+            .with_downtime(self.downtime.clone())
+            .with_pinned(pinned)
+            .with_machine_names(self.machine_names.clone());
+        match self.horizon.get() {
+            Some(&horizon) => restricted.with_horizon(horizon),
+            None => restricted,
+        }
+    }
+    /// Creates a copy of the instance in which the soft constraints for which
+    /// `keep` holds become hard constraints, from the largest penalty to the
+    /// smallest. A soft constraint is left out if it would close a cycle with
+    /// the constraints before it, or if both of its jobs are pinned such that
+    /// it is broken anyway. The copy has no soft constraints.
+    pub fn enforce_soft(&self, keep: impl Fn(&SoftConstraint) -> bool) -> Instance {
+        let adjacency = self.adjacency();
+        let mut successors = adjacency.successors.clone();
+        let mut constraints = self.constraints.clone();
+        let position = |index: usize| adjacency.positions.get(index).copied().flatten();
+        for soft in self
+            .soft_constraints
+            .iter()
+            .filter(|soft| keep(soft))
+            .sorted_by(|a, b| b.penalty.total_cmp(&a.penalty))
+        {
+            let (Some(l), Some(r)) = (position(soft.left), position(soft.right)) else {
+                continue;
+            };
+            let broken_by_pins = match (self.pin(soft.left), self.pin(soft.right)) {
+                (Some(left), Some(right)) => {
+                    right.start_time < left.completion_time() + soft.min_lag
+                }
+                _ => false,
+            };
+            if broken_by_pins || reaches(&successors, r, l) {
+                debug!(
+                    "Cannot enforce soft constraint {} -> {}",
+                    soft.left, soft.right
+                );
+                continue;
+            }
+            successors[l].push(r);
+            constraints.push(Constraint(
+                soft.left,
+                soft.right,
+                Lag {
+                    min: soft.min_lag,
+                    max: None,
+                },
+            ));
+        }
+        Instance::new(self.processor_count, self.jobs.clone(), constraints)
+            .with_capacity(self.capacity.clone())
+            .with_downtime(self.downtime.clone())
+            .with_pinned(self.pinned.clone())
+            .with_machine_names(self.machine_names.clone())
+    }
+    /// Computes the length of the longest path through the precedence
+    /// constraints if every job is executed on a single processor
+    pub fn critical_path_length(&self) -> i32 {
+        self.longest_path(|job| job.processing_time(1))
+    }
+    /// Computes the length of the longest path through the precedence
+    /// constraints if every job is executed with its fastest allotment, which
+    /// is a lower bound on the makespan of every schedule
+    pub fn min_critical_path_length(&self) -> i32 {
+        self.longest_path(|job| job.processing_times.iter().min().copied().unwrap_or(0))
+    }
+    /// Computes the length of the longest path through the precedence
+    /// constraints where each job takes `duration` seconds, including the
+    /// minimum lags between the jobs
+    fn longest_path(&self, duration: impl Fn(&Job) -> i32) -> i32 {
+        let adjacency = self.adjacency();
+        let mut finished_at = vec![0; self.jobs.len()];
+        for j in self.topological_order() {
+            finished_at[j] = adjacency.predecessors[j]
+                .iter()
+                .map(|&p| finished_at[p] + self.lag(p, j).min)
+                .max()
+                .unwrap_or(0)
+                + duration(&self.jobs[j]);
+        }
+        finished_at.into_iter().max().unwrap_or(0)
+    }
+    /// Returns the adjacency lists, computing them on first use
+    fn adjacency(&self) -> &Adjacency {
+        self.adjacency
+            .get_or_init(|| Adjacency::new(&self.jobs, &self.constraints))
+    }
+}
+
+/// The constraints of an instance contain a cycle, so its jobs have no
+/// topological order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CycleError {
+    /// Indices of the jobs along the cycle, where every job is a predecessor
+    /// of the next one, and the last job is a predecessor of the first one
+    pub jobs: Vec<usize>,
+}
+impl fmt::Display for CycleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the constraints contain the cycle {} -> {}",
+            self.jobs.iter().join(" -> "),
+            self.jobs[0]
+        )
+    }
+}
+
+/// Orders the positions of the jobs of `instance` such that every job comes
+/// after all its predecessors, with Kahn's algorithm over the adjacency
+/// lists. If the constraints contain a cycle, one of them is returned, which
+/// is a single job if a job is constrained to come after itself.
+pub fn topo_order(instance: &Instance) -> Result<Vec<usize>, CycleError> {
+    // the adjacency lists leave out constraints of a job on itself
+    let positions = &instance.adjacency().positions;
+    let self_loop = instance
+        .constraints
+        .iter()
+        .find(|Constraint(left, right, _)| {
+            left == right && positions.get(*left).copied().flatten().is_some()
+        });
+    if let Some(&Constraint(job, ..)) = self_loop {
+        return Err(CycleError { jobs: vec![job] });
+    }
+    let order = instance.topological_order();
+    let n = instance.jobs.len();
+    if order.len() == n {
+        return Ok(order);
+    }
+    // every job that the order leaves out has a predecessor that is left out,
+    // too, so following those predecessors has to reach a job again
+    let predecessors = &instance.adjacency().predecessors;
+    let mut sorted = vec![false; n];
+    for j in order {
+        sorted[j] = true;
+    }
+    let mut visited_at = vec![None; n];
+    let mut path = vec![];
+    let mut j = sorted
+        .iter()
+        .position(|&sorted| !sorted)
+        .expect("some job is left out");
+    while visited_at[j].is_none() {
+        visited_at[j] = Some(path.len());
+        path.push(j);
+        j = predecessors[j]
+            .iter()
+            .copied()
+            .find(|&p| !sorted[p])
+            .expect("every job that is left out has a predecessor that is left out");
+    }
+    let mut cycle = path.split_off(visited_at[j].expect("job was visited"));
+    cycle.reverse();
+    Err(CycleError {
+        jobs: cycle.into_iter().map(|j| instance.jobs[j].index).collect(),
+    })
+}
+
+/// Groups the positions of the jobs of `instance` by their depth, see
+/// `Instance::depths`, so that the constraints only lead from a layer to
+/// later ones. The positions within each layer are sorted.
+pub fn topo_layers(instance: &Instance) -> Result<Vec<Vec<usize>>, CycleError> {
+    let depths = instance.depths_along(&topo_order(instance)?);
+    let mut layers = vec![vec![]; depths.iter().max().map_or(0, |&depth| depth + 1)];
+    for (j, depth) in depths.into_iter().enumerate() {
+        layers[depth].push(j);
+    }
+    Ok(layers)
+}
+
+/// Checks whether a path along the `successors` leads from position `from` to
+/// position `to`
+fn reaches(successors: &[Vec<usize>], from: usize, to: usize) -> bool {
+    let mut visited = vec![false; successors.len()];
+    let mut stack = vec![from];
+    while let Some(j) = stack.pop() {
+        if j == to {
+            return true;
+        }
+        if !std::mem::replace(&mut visited[j], true) {
+            stack.extend(&successors[j]);
+        }
+    }
+    false
+}
+
+/// Enumerates the allotment vectors of `instance` whose total work, i.e. the
+/// sum of the allotment times the processing time of every job, is at most
+/// `max_total_work`. The vectors hold the allotments of the jobs by position
+/// and come in lexicographic order. Every job may get up to as many
+/// processors as its processing times and affinity allow, and pinned jobs
+/// keep the allotment of their pin. Branches whose work cannot stay within
+/// the budget, even if the remaining jobs get their allotments of least
+/// work, are skipped, so only feasible vectors are visited.
+pub fn allotment_space(instance: &Instance, max_total_work: i64) -> AllotmentSpace {
+    let options = instance
+        .jobs
+        .iter()
+        .map(|job| match instance.pin(job.index) {
+            Some(pin) => vec![(
+                pin.allotment,
+                pin.allotment as i64 * i64::from(pin.processing_time()),
+            )],
+            None => (1..=job.max_allotment().min(instance.processor_count))
+                .map(|l| (l, l as i64 * i64::from(job.processing_time(l))))
+                .collect(),
+        })
+        .collect::<Vec<Vec<_>>>();
+    let mut least_work_after = vec![0; options.len() + 1];
+    for j in (0..options.len()).rev() {
+        let least = options[j].iter().map(|&(_, work)| work).min().unwrap_or(0);
+        least_work_after[j] = least_work_after[j + 1] + least;
+    }
+    AllotmentSpace {
+        options,
+        least_work_after,
+        max_total_work,
+        choices: vec![],
+        work_before: vec![0],
+        done: false,
+    }
+}
+
+/// The allotment vectors within a work budget, see `allotment_space`
+#[derive(Debug)]
+pub struct AllotmentSpace {
+    /// Allotments of every job along with their work
+    options: Vec<Vec<(usize, i64)>>,
+    /// Least work of all jobs from every position on
+    least_work_after: Vec<i64>,
+    /// Largest total work of a vector
+    max_total_work: i64,
+    /// Index into the options of every job of the current vector, empty
+    /// before the first vector
+    choices: Vec<usize>,
+    /// Work of the jobs before every position of the current vector
+    work_before: Vec<i64>,
+    /// Whether all vectors were visited
+    done: bool,
+}
+impl AllotmentSpace {
+    /// Chooses the first option of the job at position `j` from `from` on
+    /// that keeps the vector within the budget
+    fn first_fit(&self, j: usize, from: usize) -> Option<usize> {
+        (from..self.options[j].len()).find(|&option| {
+            self.work_before[j] + self.options[j][option].1 + self.least_work_after[j + 1]
+                <= self.max_total_work
+        })
+    }
+    /// Completes the vector from position `j` on with the first options
+    /// that keep it within the budget, which always exist
+    fn fill_from(&mut self, j: usize) {
+        for j in j..self.options.len() {
+            let option = self.first_fit(j, 0).expect("least work fits the budget");
+            self.choices.push(option);
+            self.work_before
+                .push(self.work_before[j] + self.options[j][option].1);
+        }
+    }
+}
+impl Iterator for AllotmentSpace {
+    type Item = Vec<usize>;
+    fn next(&mut self) -> Option<Vec<usize>> {
+        if self.done {
+            return None;
+        }
+        if self.choices.is_empty() && self.work_before.len() == 1 {
+            if self.least_work_after[0] > self.max_total_work {
+                self.done = true;
+                return None;
+            }
+            self.fill_from(0);
+        } else {
+            // advance the last job that has another option within the budget
+            loop {
+                let Some(option) = self.choices.pop() else {
+                    self.done = true;
+                    return None;
+                };
+                self.work_before.pop();
+                let j = self.choices.len();
+                if let Some(next) = self.first_fit(j, option + 1) {
+                    self.choices.push(next);
+                    self.work_before
+                        .push(self.work_before[j] + self.options[j][next].1);
+                    self.fill_from(j + 1);
+                    break;
+                }
+            }
+        }
+        if self.options.is_empty() {
+            // the only vector of an instance without jobs is empty
+            self.done = true;
+        }
+        Some(
+            self.choices
+                .iter()
+                .enumerate()
+                .map(|(j, &option)| self.options[j][option].0)
+                .collect(),
+        )
+    }
+}
+
+/// Direct predecessors and successors of all jobs, both referring to jobs by
+/// their position in the job list
+#[derive(Debug)]
+struct Adjacency {
+    /// Maps job indices to positions in the job list
+    positions: Vec<Option<usize>>,
+    /// Sorted positions of the predecessors of each job
+    predecessors: Vec<Vec<usize>>,
+    /// Sorted positions of the successors of each job
+    successors: Vec<Vec<usize>>,
+    /// Lags between pairs of positions that differ from the default lag
+    lags: HashMap<(usize, usize), Lag>,
+}
+impl Adjacency {
+    fn new(jobs: &[Job], constraints: &[Constraint]) -> Self {
+        let mut positions = vec![None; jobs.iter().map(|job| job.index + 1).max().unwrap_or(0)];
+        for (pos, job) in jobs.iter().enumerate() {
+            positions[job.index] = Some(pos);
+        }
+        let mut predecessors = vec![vec![]; jobs.len()];
+        let mut successors = vec![vec![]; jobs.len()];
+        let mut lags: HashMap<(usize, usize), Lag> = HashMap::new();
+        for &Constraint(left, right, lag) in constraints {
+            let lookup = |index: usize| positions.get(index).copied().flatten();
+            if let (Some(l), Some(r)) = (lookup(left), lookup(right)) {
+                if l != r {
+                    successors[l].push(r);
+                    predecessors[r].push(l);
+                    if lag != Lag::default() {
+                        let merged = lags.get(&(l, r)).map_or(lag, |other| other.tighten(lag));
+                        lags.insert((l, r), merged);
+                    }
+                }
+            }
+        }
+        for list in predecessors.iter_mut().chain(successors.iter_mut()) {
+            list.sort_unstable();
+            list.dedup();
+        }
+        Self {
+            positions,
+            predecessors,
+            successors,
+            lags,
+        }
+    }
+    fn position(&self, job: &Job) -> Option<usize> {
+        self.positions.get(job.index).copied().flatten()
+    }
 }
 
 /// A job in a problem instance
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 pub struct Job {
     /// Index of the job, 1-indexed
     pub index: usize,
@@ -40,14 +1175,45 @@ pub struct Job {
     /// Element 0 is skipped, so the vector starts with the processing time
     /// needed if the job is scheduled on one machine.
     pub processing_times: Vec<i32>,
+    /// Number of trailing processing times that were not measured but
+    /// filled in when reading the instance
+    pub synthesized: usize,
+    /// Sorted processors on which the job may run, or `None` if it may run on
+    /// all of them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub affinity: Option<Vec<usize>>,
+    /// Free text that planners attached to the job, e.g. why it waits
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
 }
 impl Job {
     /// Looks up the processing time of the job based on the given allotment
     pub fn processing_time(&self, allotment: usize) -> i32 {
         self.processing_times[allotment - 1]
     }
+    /// Checks whether the job takes no time with any allotment. Such jobs are
+    /// instantaneous: they respect their constraints like every other job,
+    /// but occupy no processors.
+    pub fn is_instantaneous(&self) -> bool {
+        self.processing_times.iter().all(|&p| p == 0)
+    }
+    /// Checks whether the job may run on `processor`
+    pub fn allows(&self, processor: usize) -> bool {
+        self.affinity
+            .as_ref()
+            .is_none_or(|allowed| allowed.binary_search(&processor).is_ok())
+    }
+    /// Returns the largest allotment of the job, which is limited by its
+    /// processing times and its affinity
+    pub fn max_allotment(&self) -> usize {
+        self.affinity
+            .as_ref()
+            .map_or(self.processing_times.len(), |allowed| {
+                allowed.len().min(self.processing_times.len())
+            })
+    }
     /// Searches for the minimum feasible allotment for this job, given a target
-    /// processing time, can be rounded up or down. 
+    /// processing time, can be rounded up or down.
     pub fn closest_allotment(&self, processing_time: i32) -> usize {
         1 + self
             .processing_times
@@ -59,52 +1225,156 @@ impl Job {
             .expect("no processing times")
             .0
     }
+    /// Computes the concave closure of the job, whose work `l * p(l)` is the
+    /// lower convex envelope of the work of this job, i.e. the largest
+    /// function that is convex in the allotment and never exceeds it. Like
+    /// Graham's scan, the envelope is found in one pass over the allotments.
+    /// Allotments on the envelope keep their processing times, all others are
+    /// interpolated between their neighbors on the envelope and rounded up,
+    /// so no processing time grows.
+    pub fn concave_closure(&self) -> Job {
+        let work = self
+            .processing_times
+            .iter()
+            .zip(1_i64..)
+            .map(|(&p, l)| (l, l * i64::from(p)))
+            .collect::<Vec<_>>();
+        // the first and the last allotment are always on the envelope
+        let mut hull: Vec<(i64, i64)> = vec![];
+        for &(l, w) in &work {
+            while let [.., (l0, w0), (l1, w1)] = hull[..] {
+                // keep the last vertex only if it lies strictly below the
+                // line from the vertex before it to the new point
+                if (l1 - l0) * (w - w0) - (w1 - w0) * (l - l0) > 0 {
+                    break;
+                }
+                hull.pop();
+            }
+            hull.push((l, w));
+        }
+        let processing_times = self
+            .processing_times
+            .iter()
+            .zip(&work)
+            .map(
+                |(&p, &(l, _))| match hull.binary_search_by_key(&l, |&(x, _)| x) {
+                    Ok(_) => p,
+                    Err(k) => {
+                        let ((l0, w0), (l1, w1)) = (hull[k - 1], hull[k]);
+                        let envelope = (w0 * (l1 - l0) + (w1 - w0) * (l - l0)) as u64;
+                        envelope.div_ceil((l * (l1 - l0)) as u64) as i32
+                    }
+                },
+            )
+            .collect();
+        Job {
+            processing_times,
+            ..self.clone()
+        }
+    }
 }
+/// Formats a list of processors compactly as processors and ranges of
+/// processors separated by commas, e.g. `0-3,6`, which is also how the
+/// affinity of a job is read from the job file
+pub fn format_processors(processors: &[usize]) -> String {
+    processors
+        .iter()
+        .copied()
+        .sorted_unstable()
+        .enumerate()
+        .chunk_by(|&(i, p)| p - i)
+        .into_iter()
+        .map(|(_, run)| {
+            let run = run.map(|(_, p)| p).collect::<Vec<_>>();
+            match run[..] {
+                [single] => single.to_string(),
+                [first, .., last] => format!("{first}-{last}"),
+                [] => unreachable!("runs are never empty"),
+            }
+        })
+        .join(",")
+}
+
+/// Returns the name of `processor` among `machine_names`, which is
+/// "Processor <i>" if the processors have no names
+pub fn machine_name(machine_names: &[String], processor: usize) -> String {
+    machine_names
+        .get(processor)
+        .cloned()
+        .unwrap_or_else(|| format!("Processor {processor}"))
+}
+
 impl PartialEq for Job {
     fn eq(&self, other: &Self) -> bool {
         self.index == other.index
     }
 }
 
-/// Models the scheduling order of two jobs by their index
-#[derive(Debug)]
-pub struct Constraint(pub usize, pub usize);
-/// Implements a partial relation based on a list of constraints
-pub trait PartialRelation {
-    /// Returns `None` if self and other are incomparable. Returns `Some(true)`
-    /// if self is less than other and returns `Some(false)` if other is less
-    /// than self.
-    fn compare(&self, relation: &[Constraint], other: &Self) -> Option<bool>;
-    /// Returns `true` if self is comparable to other, and `false` of the two
-    /// values are incomparable
-    fn is_comparable(&self, relation: &[Constraint], other: &Self) -> bool {
-        self.compare(relation, other).is_some()
-    }
-    /// Returns `true` if self is in relation to other, and `false` otherwise
-    fn less_than(&self, relation: &[Constraint], other: &Self) -> bool {
-        self.compare(relation, other).is_some_and(|less| less)
-    }
-    // /// Returns `true` if other is in relation to self, and `false` otherwise
-    fn greater_than(&self, relation: &[Constraint], other: &Self) -> bool {
-        self.compare(relation, other).is_some_and(|less| !less)
-    }
-}
-impl PartialRelation for Job {
-    fn compare(&self, relation: &[Constraint], other: &Self) -> Option<bool> {
-        relation.iter().find_map(|&Constraint(left, right)| {
-            if self.index == left && other.index == right {
-                Some(true)
-            } else if other.index == left && self.index == right {
-                Some(false)
-            } else {
-                None
-            }
-        })
+/// Limits the number of busy processors from `from` until right before `to`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapacityWindow {
+    /// First point in time of the window
+    pub from: i32,
+    /// End of the window, which is no longer part of it
+    pub to: i32,
+    /// Number of processors that may be busy during the window, where 0
+    /// forbids running any job
+    pub capacity: usize,
+}
+
+/// Takes `processor` down for maintenance from `from` until right before `to`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Downtime {
+    /// The processor that is down
+    pub processor: usize,
+    /// First point in time at which the processor is down
+    pub from: i32,
+    /// Time at which the processor is up again
+    pub to: i32,
+}
+
+/// Models the scheduling order of two jobs by their index, along with the
+/// time that may pass between them
+#[derive(Debug, Clone)]
+pub struct Constraint(pub usize, pub usize, pub Lag);
+
+/// A precedence constraint between two jobs by their index that a schedule
+/// may break, which costs the penalty of the constraint
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, JsonSchema)]
+pub struct SoftConstraint {
+    /// Job that should complete first
+    pub left: usize,
+    /// Job that should start after the left job
+    pub right: usize,
+    /// Minimum time between the two jobs
+    pub min_lag: i32,
+    /// Cost of starting the right job too early
+    pub penalty: f64,
+}
+
+/// Time between the completion of a job and the start of its successor
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Lag {
+    /// Minimum time between the two jobs
+    pub min: i32,
+    /// Maximum time between the two jobs, if there is one
+    pub max: Option<i32>,
+}
+impl Lag {
+    /// Combines two lags between the same jobs such that both are met
+    pub fn tighten(self, other: Lag) -> Lag {
+        Lag {
+            min: self.min.max(other.min),
+            max: match (self.max, other.max) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            },
+        }
     }
 }
 
 /// A feasible job schedule
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Schedule {
     /// The number of processors available
     pub processor_count: usize,
@@ -112,17 +1382,588 @@ pub struct Schedule {
     pub jobs: Vec<ScheduledJob>,
 }
 
+impl Schedule {
+    /// Computes the makespan of the schedule, i.e. the latest completion time
+    pub fn makespan(&self) -> i32 {
+        self.jobs
+            .iter()
+            .map(ScheduledJob::completion_time)
+            .max()
+            .unwrap_or(0)
+    }
+    /// Hashes the start time, allotment, and processors of every job after
+    /// normalizing the schedule, so that schedules that only differ in the
+    /// order of their jobs or the names of their processors hash equally.
+    /// Like the file hashes of the provenance, the hash is stable across
+    /// Rust versions.
+    pub fn canonical_hash(&self) -> String {
+        let mut description = format!("{}\n", self.processor_count);
+        for (index, start_time, allotment, processors) in self.normalized() {
+            let _ = writeln!(
+                description,
+                "{index} {start_time} {allotment} {processors:?}"
+            );
+        }
+        hash_contents(description.as_bytes())
+    }
+    /// Checks whether both schedules run the same jobs at the same times with
+    /// the same allotments, and on the same processors up to renaming them.
+    /// Processors are renamed in the order in which they are first used, so
+    /// some schedules that only differ by a permutation of processors which
+    /// are first used by the same job are not detected as equivalent.
+    pub fn is_equivalent(&self, other: &Schedule) -> bool {
+        self.processor_count == other.processor_count && self.normalized() == other.normalized()
+    }
+    /// Sorts the jobs by their index and renames the processors in the order
+    /// in which jobs start on them, breaking ties by job index and the
+    /// previous name of the processor
+    fn normalized(&self) -> Vec<(usize, i32, usize, Vec<usize>)> {
+        let mut names = HashMap::new();
+        for job in self
+            .jobs
+            .iter()
+            .sorted_by_key(|job| (job.start_time, job.job.index))
+        {
+            for &processor in job.processors.iter().sorted_unstable() {
+                let next = names.len();
+                names.entry(processor).or_insert(next);
+            }
+        }
+        self.jobs
+            .iter()
+            .map(|job| {
+                (
+                    job.job.index,
+                    job.start_time,
+                    job.allotment,
+                    job.processors
+                        .iter()
+                        .map(|processor| names[processor])
+                        .sorted_unstable()
+                        .collect(),
+                )
+            })
+            .sorted_unstable()
+            .collect()
+    }
+    /// Computes the resource usage at every point in time where a job starts
+    /// or completes. The last point is the makespan, where no processor is
+    /// busy anymore and the cumulative work equals the total work.
+    pub fn resource_profile(&self) -> Vec<ProfilePoint> {
+        let mut events = self
+            .jobs
+            .iter()
+            .flat_map(|job| {
+                let allotment = job.allotment as i64;
+                [
+                    (job.start_time, allotment, 1),
+                    (job.completion_time(), -allotment, -1),
+                ]
+            })
+            .collect::<Vec<_>>();
+        events.sort_unstable_by_key(|&(time, _, _)| time);
+
+        let mut points: Vec<ProfilePoint> = vec![];
+        let (mut busy, mut active) = (0, 0);
+        for (time, processors, jobs) in events {
+            busy += processors;
+            active += jobs;
+            match points.last_mut() {
+                Some(last) if last.time == time => {
+                    last.busy_processors = busy as usize;
+                    last.active_jobs = active as usize;
+                }
+                last => {
+                    let cumulative_work = last.map_or(0, |last| {
+                        last.cumulative_work
+                            + last.busy_processors as i64 * i64::from(time - last.time)
+                    });
+                    points.push(ProfilePoint {
+                        time,
+                        busy_processors: busy as usize,
+                        active_jobs: active as usize,
+                        cumulative_work,
+                    });
+                }
+            }
+        }
+        points
+    }
+    /// Checks the schedule against the given `instance` and describes every
+    /// violated requirement. An empty list means that the schedule is feasible.
+    pub fn violations(&self, instance: &Instance) -> Vec<String> {
+        self.check(instance, false)
+    }
+    /// Checks a partial schedule, which leaves out some jobs of `instance`,
+    /// like `violations`. Jobs may be left out as long as all their
+    /// successors are left out, too.
+    pub fn partial_violations(&self, instance: &Instance) -> Vec<String> {
+        self.check(instance, true)
+    }
+    /// Returns the indices of the jobs of `instance` that the schedule leaves
+    /// out, in ascending order
+    pub fn deferred(&self, instance: &Instance) -> Vec<usize> {
+        let mut scheduled = vec![false; instance.jobs.len()];
+        for job in &self.jobs {
+            if let Some(slot) = scheduled.get_mut(job.job.index) {
+                *slot = true;
+            }
+        }
+        (0..instance.jobs.len())
+            .filter(|&j| !scheduled[j])
+            .collect()
+    }
+    /// Describes every violated requirement of `instance`, skipping the
+    /// requirement that every job is scheduled if the schedule is `partial`
+    fn check(&self, instance: &Instance, partial: bool) -> Vec<String> {
+        let mut violations = vec![];
+        let mut scheduled: Vec<Option<&ScheduledJob>> = vec![None; instance.jobs.len()];
+        for job in &self.jobs {
+            let index = job.job.index;
+            if job.allotment == 0 || job.allotment > job.job.processing_times.len() {
+                violations.push(format!(
+                    "job {index} has invalid allotment {}",
+                    job.allotment
+                ));
+                continue;
+            }
+            if job.allotment > job.job.max_allotment() {
+                violations.push(format!(
+                    "job {index} has allotment {} but may only run on {} processors",
+                    job.allotment,
+                    job.job.max_allotment()
+                ));
+            }
+            if job.start_time < 0 {
+                violations.push(format!("job {index} starts at {}", job.start_time));
+            }
+            match scheduled.get_mut(index) {
+                Some(Some(_)) => violations.push(format!("job {index} is scheduled twice")),
+                Some(slot) => *slot = Some(job),
+                None => violations.push(format!("job {index} is not part of the instance")),
+            }
+        }
+        for (index, job) in scheduled.iter().enumerate() {
+            if job.is_none() && !partial {
+                violations.push(format!("job {index} is not scheduled"));
+            }
+        }
+        for pin in &instance.pinned {
+            let Some(Some(job)) = scheduled.get(pin.job.index) else {
+                continue;
+            };
+            let moved = job.start_time != pin.start_time
+                || job.allotment != pin.allotment
+                || !job.processors.is_empty() && job.processors != pin.processors;
+            if moved {
+                violations.push(format!(
+                    "job {} is pinned to start at {} on processors {}, but starts at {} with allotment {}{}",
+                    pin.job.index,
+                    pin.start_time,
+                    format_processors(&pin.processors),
+                    job.start_time,
+                    job.allotment,
+                    if job.processors.is_empty() {
+                        String::new()
+                    } else {
+                        format!(" on processors {}", format_processors(&job.processors))
+                    }
+                ));
+            }
+        }
+        // the partial order is reflexive, so skip constraints of a job with itself
+        for Constraint(left, right, lag) in instance.constraints.iter().filter(|c| c.0 != c.1) {
+            if let (Some(None), Some(Some(_))) = (scheduled.get(*left), scheduled.get(*right)) {
+                if partial {
+                    violations.push(format!(
+                        "job {right} is scheduled, but its predecessor {left} is not"
+                    ));
+                }
+            }
+            if let (Some(Some(l)), Some(Some(r))) = (scheduled.get(*left), scheduled.get(*right)) {
+                if r.start_time < l.completion_time() {
+                    violations.push(format!(
+                        "job {right} starts at {} before its predecessor {left} completes at {}",
+                        r.start_time,
+                        l.completion_time()
+                    ));
+                } else if r.start_time < l.completion_time() + lag.min {
+                    violations.push(format!(
+                        "job {right} starts at {}, less than {} after its predecessor {left} completes at {}",
+                        r.start_time,
+                        lag.min,
+                        l.completion_time()
+                    ));
+                }
+                if let Some(max) = lag.max {
+                    if r.start_time > l.completion_time() + max {
+                        violations.push(format!(
+                            "job {right} starts at {}, more than {max} after its predecessor {left} completes at {}",
+                            r.start_time,
+                            l.completion_time()
+                        ));
+                    }
+                }
+            }
+        }
+        for point in self.resource_profile() {
+            if point.busy_processors > self.processor_count {
+                violations.push(format!(
+                    "{} of {} processors are busy at time {}",
+                    point.busy_processors, self.processor_count, point.time
+                ));
+            }
+        }
+        violations.extend(self.processor_violations(&instance.machine_names));
+        violations.extend(self.capacity_violations(instance));
+        violations.extend(self.downtime_violations(instance));
+        violations
+    }
+
+    /// Returns the soft constraints of the `instance` that the schedule
+    /// breaks, i.e. whose right job starts before the left job completes plus
+    /// the minimum lag. Jobs that are not scheduled break nothing, as they are
+    /// reported by `violations`.
+    pub fn soft_violations<'a>(&self, instance: &'a Instance) -> Vec<&'a SoftConstraint> {
+        let starts = self
+            .jobs
+            .iter()
+            .map(|job| (job.job.index, (job.start_time, job.completion_time())))
+            .collect::<HashMap<_, _>>();
+        instance
+            .soft_constraints
+            .iter()
+            .filter(
+                |soft| match (starts.get(&soft.left), starts.get(&soft.right)) {
+                    (Some(&(_, completion)), Some(&(start, _))) => {
+                        start < completion + soft.min_lag
+                    }
+                    _ => false,
+                },
+            )
+            .collect()
+    }
+
+    /// Checks the number of busy processors against the capacity profile of
+    /// the `instance`, which also counts the processors that are down. The
+    /// usage only rises when a job starts and the capacity only drops when a
+    /// window starts or a processor goes down, so it suffices to check these
+    /// points in time.
+    fn capacity_violations(&self, instance: &Instance) -> Vec<String> {
+        self.jobs
+            .iter()
+            .map(|job| job.start_time)
+            .chain(instance.capacity_drops())
+            .sorted_unstable()
+            .dedup()
+            .filter_map(|time| {
+                let capacity = instance.capacity_at(time);
+                let busy = self
+                    .jobs
+                    .iter()
+                    .filter(|job| job.start_time <= time && time < job.completion_time())
+                    .map(|job| job.allotment)
+                    .sum::<usize>();
+                // exceeding all processors is reported by the resource profile
+                (busy > capacity && capacity < self.processor_count).then(|| {
+                    format!(
+                        "{busy} processors are busy at time {time}, but the capacity is {capacity}"
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Checks that no job that was placed on explicit processors runs on a
+    /// processor while it is down. Jobs without explicit processors are only
+    /// checked by counting the processors that are up, see
+    /// `capacity_violations`.
+    fn downtime_violations(&self, instance: &Instance) -> Vec<String> {
+        self.jobs
+            .iter()
+            .filter(|job| job.processing_time() > 0)
+            .flat_map(|job| {
+                instance
+                    .downtime
+                    .iter()
+                    .filter(|down| {
+                        job.processors.contains(&down.processor)
+                            && down.from < job.completion_time()
+                            && job.start_time < down.to
+                    })
+                    .map(|down| {
+                        format!(
+                            "job {} runs on {} from {} to {}, but the processor is down from {} to {}",
+                            job.job.index,
+                            instance.machine_name(down.processor),
+                            job.start_time,
+                            job.completion_time(),
+                            down.from,
+                            down.to
+                        )
+                    })
+            })
+            .collect()
+    }
+
+    /// Checks the processors of all jobs that were placed on explicit
+    /// processors. The processors of a job need not be contiguous, but they
+    /// must exist, match its allotment, be allowed by its affinity, and not
+    /// be used by another job at the same time. Jobs without processing time
+    /// occupy no processors, so they never collide. The processors are
+    /// described by their `machine_names`.
+    fn processor_violations(&self, machine_names: &[String]) -> Vec<String> {
+        let name = |processor| machine_name(machine_names, processor);
+        let mut violations = vec![];
+        let mut busy = vec![vec![]; self.processor_count];
+        for job in self.jobs.iter().filter(|job| !job.processors.is_empty()) {
+            let index = job.job.index;
+            if job.processors.len() != job.allotment {
+                violations.push(format!(
+                    "job {index} runs on {} processors but has allotment {}",
+                    job.processors.len(),
+                    job.allotment
+                ));
+            }
+            for &processor in &job.processors {
+                if !job.job.allows(processor) {
+                    violations.push(format!(
+                        "job {index} runs on {}, which is not in its affinity",
+                        name(processor)
+                    ));
+                }
+                match busy.get_mut(processor) {
+                    Some(_) if job.processing_time() == 0 => {}
+                    Some(intervals) => {
+                        intervals.push((job.start_time, job.completion_time(), index));
+                    }
+                    None => violations.push(format!(
+                        "job {index} runs on {}, which does not exist",
+                        name(processor)
+                    )),
+                }
+            }
+        }
+        for (processor, mut intervals) in busy.into_iter().enumerate() {
+            intervals.sort_unstable();
+            for ((_, end, left), (start, _, right)) in intervals.into_iter().tuple_windows() {
+                if start < end {
+                    violations.push(format!(
+                        "jobs {left} and {right} both run on {} at time {start}",
+                        name(processor)
+                    ));
+                }
+            }
+        }
+        violations
+    }
+}
+
+/// The resource usage of a schedule from one event point until the next one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ProfilePoint {
+    /// Time at which a job starts or completes
+    pub time: i32,
+    /// Number of processors that are busy from this time on
+    pub busy_processors: usize,
+    /// Number of jobs that are running from this time on
+    pub active_jobs: usize,
+    /// Amount of work, i.e. busy processors times time, done before this time
+    pub cumulative_work: i64,
+}
+
+/// Default distance to the nearest integer within which values of a linear
+/// program are considered integral
+pub const INT_TOLERANCE: f64 = 1e-6;
+
+/// Direction in which a value of a linear program is rounded if it is not
+/// within tolerance of an integer
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Rounding {
+    /// Round up, e.g. for completion times
+    Up,
+    /// Round down, e.g. for processing times
+    #[default]
+    Down,
+    /// Round to the nearest integer, which may violate inequalities of the
+    /// linear program in either direction
+    Nearest,
+}
+
+/// Converts a value of a linear program to an integer. Values within
+/// `tolerance` of an integer are snapped to it, so that solver noise like
+/// 2.9999999 does not matter. All other values are rounded in `direction`,
+/// which should be chosen such that inequalities of the linear program remain
+/// satisfied.
+pub fn to_integer(value: f64, tolerance: f64, direction: Rounding) -> i32 {
+    let nearest = value.round();
+    if (value - nearest).abs() <= tolerance {
+        nearest as i32
+    } else {
+        match direction {
+            Rounding::Up => value.ceil() as i32,
+            Rounding::Down => value.floor() as i32,
+            Rounding::Nearest => nearest as i32,
+        }
+    }
+}
+
+/// Statistics collected by a solver while computing a schedule
+#[derive(Debug, Default)]
+pub struct SolverStats {
+    /// Optimal value of the linear program, which is a lower bound on the
+    /// makespan of the rounded schedule
+    pub lp_bound: Option<f64>,
+    /// Rounding parameter ρ, if the solver uses one
+    pub rho: Option<f64>,
+    /// Allotment parameter µ, if the solver uses one
+    pub mu: Option<usize>,
+    /// Largest delay of a job behind the start time targeted by the linear
+    /// program, if the solver follows such targets
+    pub max_target_deviation: Option<i32>,
+    /// How the allotment of each job was derived from the linear program,
+    /// empty if the solver does not round
+    pub rounding: Vec<RoundingDecision>,
+    /// Seed of the list scheduling run that produced the schedule, if ties
+    /// were broken at random
+    pub seed: Option<u64>,
+}
+
+/// How the allotment of a job was derived from a fractional solution
+#[derive(Debug, Clone)]
+pub struct RoundingDecision {
+    /// Index of the job
+    pub job: usize,
+    /// Values of the linear program that the allotment is based on
+    pub fractional_values: Vec<f64>,
+    /// The allotment the job is scheduled with
+    pub chosen_allotment: usize,
+    /// Why this allotment was chosen
+    pub reason: RoundingReason,
+    /// How critical the job is in the fractional solution, from 0 to 1, if
+    /// the solver scores it
+    pub criticality: Option<f64>,
+}
+
+/// The rule that determined the allotment of a job during rounding
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RoundingReason {
+    /// The allotment with the longest processing time among those whose
+    /// virtual processing time reaches ρ times their processing time
+    Threshold,
+    /// No virtual processing time reaches the threshold, so all processors
+    /// are used
+    BelowThreshold,
+    /// The allotment whose processing time is closest to the fractional one,
+    /// which differs by `distance`
+    Closest { distance: f64 },
+    /// The allotment `closest` to the fractional processing time exceeds µ
+    CappedByMu { closest: usize },
+    /// The `threshold` allotment was adjusted by the criticality of the job
+    DualGuided { threshold: usize },
+    /// The job is pinned to this allotment
+    Pinned,
+    /// The job takes no time, so a single processor suffices
+    Instantaneous,
+}
+impl fmt::Display for RoundingReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RoundingReason::Threshold => write!(f, "longest processing time above threshold"),
+            RoundingReason::BelowThreshold => {
+                write!(
+                    f,
+                    "no processing time above threshold, using all processors"
+                )
+            }
+            RoundingReason::Closest { distance } => {
+                write!(f, "closest processing time, off by {distance:.3}")
+            }
+            RoundingReason::CappedByMu { closest } => {
+                write!(f, "closest allotment {closest} capped at µ")
+            }
+            RoundingReason::DualGuided { threshold } => {
+                write!(f, "threshold allotment {threshold} adjusted by criticality")
+            }
+            RoundingReason::Pinned => write!(f, "pinned"),
+            RoundingReason::Instantaneous => write!(f, "no processing time, using one processor"),
+        }
+    }
+}
+impl SolverStats {
+    /// Computes how much worse the realized `makespan` is compared to the
+    /// bound of the linear program, if there is one
+    pub fn rounding_ratio(&self, makespan: i32) -> Option<f64> {
+        self.lp_bound
+            .filter(|&bound| bound > 0.0)
+            .map(|bound| f64::from(makespan) / bound)
+    }
+}
+
+/// The number of variables and constraints of a linear program
+#[derive(Debug, Clone, Copy)]
+pub struct ModelSize {
+    /// Number of variables
+    pub variables: usize,
+    /// Number of constraints
+    pub constraints: usize,
+    /// Number of variables summed over all constraints, i.e. the nonzero
+    /// coefficients of the constraint matrix, counted from the structure of
+    /// the instance
+    pub nonzeros: usize,
+}
+impl ModelSize {
+    /// Sum of variables and constraints, used to compare against a limit
+    pub fn total(self) -> usize {
+        self.variables + self.constraints
+    }
+    /// Rough estimate of the memory in bytes that the solver needs for the
+    /// model, assuming a few dozen bytes per coefficient, variable, and
+    /// constraint
+    pub fn memory_estimate(self) -> usize {
+        32 * self.nonzeros + 64 * (self.variables + self.constraints)
+    }
+}
+impl fmt::Display for ModelSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} variables, {} constraints, and {} nonzeros, taking about {} KiB",
+            self.variables,
+            self.constraints,
+            self.nonzeros,
+            self.memory_estimate().div_ceil(1024)
+        )
+    }
+}
+
 /// A job that was scheduled in a feasible schedule
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ScheduledJob {
-    /// The input job
-    pub job: Job,
+    /// The input job, shared with the instance so that building and cloning
+    /// schedules does not copy its processing times, see
+    /// `Instance::shared_job`
+    pub job: Arc<Job>,
     /// The job allotment
     pub allotment: usize,
     /// The integral starting time of the job
     pub start_time: i32,
+    /// The processors the job runs on, in ascending order, or empty if the
+    /// solver only decides how many processors the job gets
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub processors: Vec<usize>,
 }
 impl ScheduledJob {
+    /// Schedules a job that is not shared with an instance, e.g. one that was
+    /// built before the instance. Prefer `Instance::shared_job` otherwise.
+    pub fn new(job: Job, allotment: usize, start_time: i32, processors: Vec<usize>) -> Self {
+        Self {
+            job: Arc::new(job),
+            allotment,
+            start_time,
+            processors,
+        }
+    }
     /// Computes the processing time of the job based on the current allotment
     pub fn processing_time(&self) -> i32 {
         self.job.processing_time(self.allotment)
@@ -132,3 +1973,609 @@ impl ScheduledJob {
         self.start_time + self.processing_time()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+    use super::*;
+    use crate::builder::InstanceBuilder;
+
+    /// Three jobs on three processors, where the first two run side by side
+    /// and the last one runs on all processors after them
+    fn schedule(processors: [Vec<usize>; 3], starts: [i32; 3]) -> Schedule {
+        let instance = InstanceBuilder::new()
+            .processors(3)
+            .job(1)
+            .times([6, 4, 3])
+            .job(2)
+            .times([3, 3, 3])
+            .job(3)
+            .times([2, 2, 2])
+            .build()
+            .expect("valid instance");
+        let jobs = processors
+            .into_iter()
+            .zip(starts)
+            .enumerate()
+            .map(|(index, (processors, start_time))| ScheduledJob {
+                job: instance.shared_job(index),
+                allotment: processors.len(),
+                start_time,
+                processors,
+            })
+            .collect();
+        Schedule {
+            processor_count: 3,
+            jobs,
+        }
+    }
+
+    fn original() -> Schedule {
+        schedule([vec![0, 1], vec![2], vec![0, 1, 2]], [0, 0, 4])
+    }
+
+    #[test]
+    fn hash_ignores_processor_permutation() {
+        let permuted = schedule([vec![1, 2], vec![0], vec![0, 1, 2]], [0, 0, 4]);
+        assert!(original().is_equivalent(&permuted));
+        assert_eq!(original().canonical_hash(), permuted.canonical_hash());
+    }
+
+    #[test]
+    fn hash_ignores_job_order() {
+        let mut reordered = original();
+        reordered.jobs.reverse();
+        assert!(original().is_equivalent(&reordered));
+        assert_eq!(original().canonical_hash(), reordered.canonical_hash());
+    }
+
+    #[test]
+    fn hash_ignores_whitespace_of_schedule_files() {
+        let compact = serde_json::to_string(&original()).expect("serializable schedule");
+        let pretty = serde_json::to_string_pretty(&original()).expect("serializable schedule");
+        let read = |json: &str| {
+            serde_json::from_str::<Schedule>(json)
+                .expect("readable schedule")
+                .canonical_hash()
+        };
+        assert_eq!(read(&compact), read(&pretty));
+        assert_eq!(read(&compact), original().canonical_hash());
+    }
+
+    #[test]
+    fn hash_changes_with_start_time() {
+        let later = schedule([vec![0, 1], vec![2], vec![0, 1, 2]], [0, 1, 4]);
+        assert!(!original().is_equivalent(&later));
+        assert_ne!(original().canonical_hash(), later.canonical_hash());
+    }
+
+    #[test]
+    fn hash_changes_with_allotment() {
+        let narrower = schedule([vec![0], vec![2], vec![0, 1, 2]], [0, 0, 4]);
+        assert!(!original().is_equivalent(&narrower));
+        assert_ne!(original().canonical_hash(), narrower.canonical_hash());
+    }
+
+    /// Computes the concave closure of a job with the given processing times
+    fn closure(processing_times: &[i32]) -> Vec<i32> {
+        Job {
+            index: 0,
+            processing_times: processing_times.to_vec(),
+            synthesized: 0,
+            affinity: None,
+            note: None,
+        }
+        .concave_closure()
+        .processing_times
+    }
+
+    #[test]
+    fn closure_lowers_work_above_the_envelope() {
+        // the work 12, 16, 12 has 12 as its envelope at two processors
+        assert_eq!(closure(&[12, 8, 4]), [12, 6, 4]);
+        // the work 10, 12, 12 has 11 as its envelope at two processors, which
+        // is rounded up to the original time
+        assert_eq!(closure(&[10, 6, 4]), [10, 6, 4]);
+    }
+
+    #[test]
+    fn closure_keeps_concave_jobs() {
+        // the work 10, 12, 15, 20 is already convex
+        assert_eq!(closure(&[10, 6, 5, 5]), [10, 6, 5, 5]);
+        assert_eq!(closure(&[7]), [7]);
+        assert_eq!(closure(&[4, 4, 4]), [4, 4, 4]);
+    }
+
+    #[test]
+    fn closure_smooths_zig_zag() {
+        // the work 20, 30, 18, 24, 20 has the envelope 20, 19, 18, 19, 20
+        let smoothed = closure(&[20, 15, 6, 6, 4]);
+        assert_eq!(smoothed, [20, 10, 6, 5, 4]);
+        assert_eq!(closure(&smoothed), smoothed);
+    }
+
+    /// Builds an instance of `n` unit jobs on one processor whose constraints
+    /// follow a random hidden order of the jobs. If `chained`, consecutive jobs
+    /// of the hidden order are constrained, so that it is the only topological
+    /// order.
+    fn random_dag(rng: &mut StdRng, n: usize, chained: bool) -> (Instance, Vec<usize>) {
+        let mut hidden = (0..n).collect::<Vec<_>>();
+        hidden.shuffle(rng);
+        let mut constraints = vec![];
+        for i in 0..n {
+            for j in i + 1..n {
+                if (chained && j == i + 1) || rng.random_bool(0.3) {
+                    constraints.push(Constraint(hidden[i], hidden[j], Lag::default()));
+                }
+            }
+        }
+        let jobs = (0..n)
+            .map(|index| Job {
+                index,
+                processing_times: vec![1],
+                synthesized: 0,
+                affinity: None,
+                note: None,
+            })
+            .collect();
+        (Instance::new(1, jobs, constraints), hidden)
+    }
+
+    /// Computes the length of the longest path to every job by relaxing all
+    /// constraints as often as there are jobs
+    fn longest_paths(instance: &Instance) -> Vec<usize> {
+        let mut depths = vec![0; instance.jobs.len()];
+        for _ in 0..instance.jobs.len() {
+            for &Constraint(left, right, _) in &instance.constraints {
+                depths[right] = depths[right].max(depths[left] + 1);
+            }
+        }
+        depths
+    }
+
+    #[test]
+    fn topological_order_respects_constraints() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..200 {
+            let n = rng.random_range(0..=10);
+            let (instance, _) = random_dag(&mut rng, n, false);
+            let order = topo_order(&instance).expect("the constraints are acyclic");
+            assert_eq!(
+                order.iter().copied().sorted().collect::<Vec<_>>(),
+                (0..n).collect::<Vec<_>>()
+            );
+            let mut position = vec![0; n];
+            for (i, &j) in order.iter().enumerate() {
+                position[j] = i;
+            }
+            for &Constraint(left, right, _) in &instance.constraints {
+                assert!(position[left] < position[right], "{left} -> {right}");
+            }
+        }
+    }
+
+    #[test]
+    fn topological_layers_are_longest_path_depths() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..200 {
+            let n = rng.random_range(0..=10);
+            let (instance, _) = random_dag(&mut rng, n, false);
+            let layers = topo_layers(&instance).expect("the constraints are acyclic");
+            let depths = longest_paths(&instance);
+            for (depth, layer) in layers.iter().enumerate() {
+                assert!(!layer.is_empty(), "layer {depth} is empty");
+                assert!(layer.is_sorted());
+                for &j in layer {
+                    assert_eq!(depths[j], depth, "job {j}");
+                }
+            }
+            assert_eq!(layers.iter().map(Vec::len).sum::<usize>(), n);
+        }
+    }
+
+    #[test]
+    fn depths_of_diamond() {
+        // a diamond with a shortcut from its top to its bottom, which must
+        // not lower the depth of the bottom, and a job on its own
+        let instance = InstanceBuilder::new()
+            .processors(1)
+            .job(1)
+            .times([1])
+            .job(2)
+            .times([1])
+            .job(3)
+            .times([1])
+            .job(4)
+            .times([1])
+            .job(5)
+            .times([1])
+            .constraint(1, 2)
+            .constraint(1, 3)
+            .constraint(2, 4)
+            .constraint(3, 4)
+            .constraint(1, 4)
+            .build()
+            .expect("valid instance");
+        assert_eq!(instance.depths(), [0, 1, 1, 2, 0]);
+    }
+
+    #[test]
+    fn depths_are_longest_paths() {
+        let mut rng = StdRng::seed_from_u64(3);
+        for _ in 0..200 {
+            let n = rng.random_range(0..=10);
+            let (instance, _) = random_dag(&mut rng, n, false);
+            assert_eq!(instance.depths(), longest_paths(&instance));
+        }
+    }
+
+    /// Two jobs on two processors that should wait for each other in both
+    /// directions, where the first job should come first at a higher penalty
+    fn opposing_soft_constraints() -> Instance {
+        let jobs = (0..2)
+            .map(|index| Job {
+                index,
+                processing_times: vec![2, 2],
+                synthesized: 0,
+                affinity: None,
+                note: None,
+            })
+            .collect();
+        let soft = |left, right, penalty| SoftConstraint {
+            left,
+            right,
+            min_lag: 0,
+            penalty,
+        };
+        Instance::new(2, jobs, vec![]).with_soft_constraints(vec![soft(1, 0, 1.0), soft(0, 1, 2.0)])
+    }
+
+    #[test]
+    fn soft_constraints_are_enforced_by_penalty() {
+        let instance = opposing_soft_constraints();
+        let enforced = instance.enforce_soft(|_| true);
+        assert!(enforced.soft_constraints.is_empty());
+        assert_eq!(
+            enforced
+                .constraints
+                .iter()
+                .map(|&Constraint(left, right, _)| (left, right))
+                .collect::<Vec<_>>(),
+            [(0, 1)]
+        );
+        let enforced = instance.enforce_soft(|soft| soft.penalty < 1.5);
+        assert_eq!(
+            enforced
+                .constraints
+                .iter()
+                .map(|&Constraint(left, right, _)| (left, right))
+                .collect::<Vec<_>>(),
+            [(1, 0)]
+        );
+    }
+
+    #[test]
+    fn soft_violations_are_reported_separately() {
+        let instance = opposing_soft_constraints();
+        let schedule = Schedule {
+            processor_count: 2,
+            jobs: (0..2)
+                .map(|index| ScheduledJob {
+                    job: instance.shared_job(index),
+                    allotment: 1,
+                    start_time: 0,
+                    processors: vec![],
+                })
+                .collect(),
+        };
+        // both jobs run at once, which breaks both soft constraints but
+        // satisfies the instance
+        assert_eq!(schedule.violations(&instance), Vec::<String>::new());
+        assert_eq!(schedule.soft_violations(&instance).len(), 2);
+        let list = crate::list::greedy(&instance);
+        assert_eq!(list.violations(&instance), Vec::<String>::new());
+        assert_eq!(
+            list.soft_violations(&instance)
+                .iter()
+                .map(|soft| (soft.left, soft.right))
+                .collect::<Vec<_>>(),
+            [(1, 0)]
+        );
+        assert!(list.makespan() > schedule.makespan());
+    }
+
+    #[test]
+    fn cycles_are_reported_with_their_jobs() {
+        let mut rng = StdRng::seed_from_u64(2);
+        for _ in 0..200 {
+            let n = rng.random_range(1..=10);
+            let (dag, hidden) = random_dag(&mut rng, n, true);
+            // a constraint back to an earlier job closes a cycle along the
+            // chain of the hidden order, which may be a self-loop
+            let to = rng.random_range(0..n);
+            let from = rng.random_range(to..n);
+            let mut constraints = dag.constraints;
+            constraints.push(Constraint(hidden[from], hidden[to], Lag::default()));
+            let instance = Instance::new(1, dag.jobs, constraints);
+            let cycle = topo_order(&instance).expect_err("the constraints contain a cycle");
+            assert!(!cycle.jobs.is_empty());
+            assert!(cycle.jobs.iter().all_unique(), "{cycle}");
+            for (&left, &right) in cycle.jobs.iter().circular_tuple_windows() {
+                assert!(
+                    instance
+                        .constraints
+                        .iter()
+                        .any(|&Constraint(l, r, _)| (l, r) == (left, right)),
+                    "{left} -> {right} is no constraint of {cycle}"
+                );
+            }
+            assert!(topo_layers(&instance).is_err());
+        }
+    }
+
+    #[test]
+    fn self_loop_is_a_cycle() {
+        let (dag, _) = random_dag(&mut StdRng::seed_from_u64(3), 3, false);
+        let instance = Instance::new(1, dag.jobs, vec![Constraint(1, 1, Lag::default())]);
+        assert_eq!(topo_order(&instance), Err(CycleError { jobs: vec![1] }));
+        assert_eq!(
+            topo_order(&instance).unwrap_err().to_string(),
+            "the constraints contain the cycle 1 -> 1"
+        );
+    }
+
+    /// Computes which jobs reach which by closing the constraints
+    /// transitively, one intermediate job at a time
+    fn reachability(instance: &Instance) -> Vec<Vec<bool>> {
+        let n = instance.jobs.len();
+        let mut reach = vec![vec![false; n]; n];
+        for &Constraint(left, right, _) in &instance.constraints {
+            reach[left][right] = true;
+        }
+        for k in 0..n {
+            let via = reach[k].clone();
+            for row in reach.iter_mut().filter(|row| row[k]) {
+                for (r, &v) in row.iter_mut().zip(&via) {
+                    *r |= v;
+                }
+            }
+        }
+        reach
+    }
+
+    #[test]
+    fn cached_adjacency_matches_fresh_build() {
+        let mut rng = StdRng::seed_from_u64(4);
+        for _ in 0..100 {
+            let n = rng.random_range(1..=10);
+            let (dag, _) = random_dag(&mut rng, n, false);
+            let mut constraints = dag.constraints;
+            // a duplicate with a lag has to be merged into the first one
+            if let Some(&Constraint(left, right, _)) = constraints.first() {
+                constraints.push(Constraint(left, right, Lag { min: 2, max: None }));
+            }
+            let instance = Instance::new(1, dag.jobs, constraints);
+            for j in 0..n {
+                let _ = instance.predecessor_indices(j);
+            }
+            let fresh = Adjacency::new(&instance.jobs, &instance.constraints);
+            let cached = instance.adjacency();
+            assert_eq!(cached.positions, fresh.positions);
+            assert_eq!(cached.predecessors, fresh.predecessors);
+            assert_eq!(cached.successors, fresh.successors);
+            assert_eq!(cached.lags, fresh.lags);
+
+            // an instance built from the cached one starts with an empty
+            // cache, so that it sees its own constraints
+            let mut constraints = instance.constraints.clone();
+            constraints.push(Constraint(0, n - 1, Lag::default()));
+            let rebuilt = Instance::new(1, instance.jobs.clone(), constraints);
+            let fresh = Adjacency::new(&rebuilt.jobs, &rebuilt.constraints);
+            for j in 0..n {
+                assert_eq!(rebuilt.predecessor_indices(j), fresh.predecessors[j]);
+                assert_eq!(rebuilt.successor_indices(j), fresh.successors[j]);
+            }
+        }
+    }
+
+    #[test]
+    #[expect(deprecated)]
+    fn index_lookups_agree_with_job_lookups() {
+        let mut rng = StdRng::seed_from_u64(5);
+        for _ in 0..100 {
+            let n = rng.random_range(1..=10);
+            let (instance, _) = random_dag(&mut rng, n, false);
+            for (j, job) in instance.jobs.iter().enumerate() {
+                let positions = |jobs: Vec<(usize, &Job)>| {
+                    jobs.into_iter()
+                        .map(|(position, other)| {
+                            assert_eq!(other.index, instance.jobs[position].index);
+                            position
+                        })
+                        .collect::<Vec<_>>()
+                };
+                assert_eq!(
+                    positions(instance.predecessors(job)),
+                    instance.predecessor_indices(j)
+                );
+                assert_eq!(
+                    positions(instance.successors(job)),
+                    instance.successor_indices(j)
+                );
+            }
+        }
+    }
+
+    /// The constraints of `instance`'s transitive reduction as pairs of job
+    /// indices
+    fn reduced_pairs(instance: &Instance) -> Vec<(usize, usize)> {
+        instance
+            .transitive_reduction()
+            .iter()
+            .map(|&Constraint(left, right, _)| (left, right))
+            .sorted()
+            .collect()
+    }
+
+    #[test]
+    fn reduction_of_complete_chain_is_a_path() {
+        let n = 6;
+        let jobs = (0..n)
+            .map(|index| Job {
+                index,
+                processing_times: vec![1],
+                synthesized: 0,
+                affinity: None,
+                note: None,
+            })
+            .collect();
+        let constraints = (0..n)
+            .tuple_combinations()
+            .map(|(left, right)| Constraint(left, right, Lag::default()))
+            .collect::<Vec<_>>();
+        assert_eq!(constraints.len(), n * (n - 1) / 2);
+        let instance = Instance::new(1, jobs, constraints);
+        assert_eq!(
+            reduced_pairs(&instance),
+            (0..n).tuple_windows().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn reduction_removes_shortcut_of_diamond() {
+        let diamond = |shortcut: bool| {
+            let builder = InstanceBuilder::new()
+                .processors(1)
+                .job(1)
+                .times([1])
+                .job(2)
+                .times([1])
+                .job(3)
+                .times([1])
+                .job(4)
+                .times([1])
+                .constraint(1, 2)
+                .constraint(1, 3)
+                .constraint(2, 4)
+                .constraint(3, 4);
+            let builder = if shortcut {
+                builder.constraint(1, 4)
+            } else {
+                builder
+            };
+            builder.build().expect("valid instance")
+        };
+        let with_shortcut = diamond(true);
+        assert_eq!(with_shortcut.constraints.len(), 5);
+        assert_eq!(
+            reduced_pairs(&with_shortcut),
+            [(0, 1), (0, 2), (1, 3), (2, 3)]
+        );
+        assert_eq!(
+            reduced_pairs(&diamond(false)),
+            reduced_pairs(&with_shortcut)
+        );
+    }
+
+    #[test]
+    fn transitive_sets_match_closure() {
+        let mut rng = StdRng::seed_from_u64(5);
+        for _ in 0..100 {
+            let n = rng.random_range(0..=8);
+            let (instance, _) = random_dag(&mut rng, n, false);
+            let reach = reachability(&instance);
+            for (j, row) in reach.iter().enumerate() {
+                assert_eq!(
+                    instance.descendant_indices(j),
+                    row.iter().positions(|&r| r).collect::<Vec<_>>()
+                );
+            }
+            // the reduction is computed once and keeps the closure, but no
+            // constraint of it can be left out
+            let reduced = instance.transitive_reduction();
+            assert!(std::ptr::eq(reduced, instance.transitive_reduction()));
+            let with = |constraints: Vec<Constraint>| {
+                reachability(&Instance::new(1, instance.jobs.clone(), constraints))
+            };
+            assert_eq!(with(reduced.to_vec()), reach);
+            for skipped in 0..reduced.len() {
+                let mut fewer = reduced.to_vec();
+                fewer.remove(skipped);
+                assert_ne!(with(fewer), reach, "{:?} is redundant", reduced[skipped]);
+            }
+        }
+    }
+
+    #[test]
+    fn lookups_on_large_instances_are_fast() {
+        // 10k jobs in chains of ten, as in a large LIST run that looks up the
+        // predecessors of every pending job in every iteration
+        let n = 10_000;
+        let jobs = (0..n)
+            .map(|index| Job {
+                index,
+                processing_times: vec![1],
+                synthesized: 0,
+                affinity: None,
+                note: None,
+            })
+            .collect();
+        let constraints = (1..n)
+            .filter(|j| j % 10 != 0)
+            .map(|j| Constraint(j - 1, j, Lag::default()))
+            .collect();
+        let instance = Instance::new(1, jobs, constraints);
+        let before = std::time::Instant::now();
+        let mut released = 0;
+        for _ in 0..100 {
+            for j in 0..n {
+                released += instance
+                    .predecessor_indices(j)
+                    .iter()
+                    .map(|&p| instance.lag(p, j).min + 1)
+                    .sum::<i32>();
+                released += instance.successor_indices(j).len() as i32;
+            }
+        }
+        assert_eq!(released, 100 * 2 * 9 * 1000);
+        assert_eq!(instance.transitive_reduction().len(), 9 * 1000);
+        assert_eq!(instance.transitive_reduction().len(), 9 * 1000);
+        assert!(
+            before.elapsed() < std::time::Duration::from_secs(10),
+            "the lookups took {:?}",
+            before.elapsed()
+        );
+    }
+
+    #[test]
+    fn values_near_half_round_in_direction() {
+        for value in [2.5 - 1e-7, 2.5, 2.5 + 1e-7] {
+            assert_eq!(to_integer(value, INT_TOLERANCE, Rounding::Up), 3, "{value}");
+            assert_eq!(
+                to_integer(value, INT_TOLERANCE, Rounding::Down),
+                2,
+                "{value}"
+            );
+        }
+    }
+
+    #[test]
+    fn values_near_integers_snap() {
+        for value in [3.0 - 1e-7, 3.0, 3.0 + 1e-7] {
+            assert_eq!(to_integer(value, INT_TOLERANCE, Rounding::Up), 3, "{value}");
+            assert_eq!(
+                to_integer(value, INT_TOLERANCE, Rounding::Down),
+                3,
+                "{value}"
+            );
+        }
+        assert_eq!(to_integer(-1e-7, INT_TOLERANCE, Rounding::Up), 0);
+        // without tolerance, solver noise is rounded in the safe direction
+        assert_eq!(to_integer(3.0 - 1e-7, 0.0, Rounding::Up), 3);
+        assert_eq!(to_integer(3.0 - 1e-7, 0.0, Rounding::Down), 2);
+        assert_eq!(to_integer(3.0 + 1e-7, 0.0, Rounding::Up), 4);
+        // a larger tolerance snaps values further away
+        assert_eq!(to_integer(2.99, 0.05, Rounding::Down), 3);
+    }
+}
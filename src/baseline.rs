@@ -0,0 +1,320 @@
+// Records the makespans and runtimes of the algorithms on the test instances
+// in a small JSON database and compares later builds against it, so that
+// regressions in the schedule quality show up over months of development.
+
+use std::{fmt, path::Path, time::Instant};
+
+use log::info;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    diagnostics::{self, Category},
+    files,
+    metrics::Parallelism,
+    pareto::Algorithm,
+    provenance::hash_files,
+    reproducible,
+    run::{self, Input, RunConfig},
+    schema::{self, Document, BASELINE_VERSION},
+    testdata,
+};
+
+/// Runtimes that differ by fewer milliseconds than this are never flagged,
+/// because the difference is dominated by noise
+const RUNTIME_NOISE_MS: u64 = 50;
+
+/// The results of the algorithms on the test instances, as stored in the
+/// baseline database
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct Baseline {
+    /// Version of the database format, see `schema::BASELINE_VERSION`
+    pub schema_version: String,
+    /// The results, sorted by instance and algorithm
+    pub entries: Vec<Entry>,
+}
+
+/// The result of one algorithm on one test instance, along with the build
+/// that recorded it
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Entry {
+    /// Name of the instance, i.e. the `<name>` of its `jobs_<name>.csv` file
+    pub instance: String,
+    /// Name of the algorithm, as passed to --algorithms
+    pub algorithm: String,
+    /// FNV-1a hash of the job file followed by the constraint file, which
+    /// tells whether the instance changed since the entry was recorded
+    pub instance_hash: String,
+    /// Makespan of the schedule
+    pub makespan: i32,
+    /// Milliseconds needed to compute the schedule
+    pub runtime_ms: u64,
+    /// Whether the schedule satisfies the instance
+    pub valid: bool,
+    /// Version of the crate that recorded the entry
+    pub version: String,
+    /// Commit hash of the source tree that recorded the entry, if it was
+    /// known at build time
+    pub git_hash: Option<String>,
+    /// Seconds since the Unix epoch at which the entry was recorded
+    pub recorded_at: u64,
+    /// How the schedule uses the processors, missing in entries recorded
+    /// before version 1.1.0 of the database
+    #[serde(default)]
+    pub parallelism: Option<Parallelism>,
+}
+
+/// Relative changes beyond which a result counts as worse than its baseline
+#[derive(Clone, Copy, Debug)]
+pub struct Thresholds {
+    /// Relative amount by which a makespan may exceed its baseline
+    pub makespan: f64,
+    /// Relative amount by which a runtime may exceed its baseline
+    pub runtime: f64,
+}
+
+/// How a result compares to its baseline, ordered from the most to the least
+/// severe
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Status {
+    /// The schedule violates the instance
+    Invalid,
+    /// The makespan exceeds the baseline by more than the threshold
+    Regression,
+    /// The runtime exceeds the baseline by more than the threshold
+    Slower,
+    /// The instance changed since the baseline was recorded, so the results
+    /// cannot be compared
+    Changed,
+    /// There is no baseline for the instance and the algorithm
+    New,
+    /// The makespan is shorter than the baseline
+    Improved,
+    /// The runtime is below the baseline by more than the threshold
+    Faster,
+    /// Neither the makespan nor the runtime changed beyond the thresholds
+    Unchanged,
+}
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Status::Invalid => "INVALID",
+            Status::Regression => "REGRESSION",
+            Status::Slower => "slower",
+            Status::Changed => "changed instance",
+            Status::New => "new",
+            Status::Improved => "improved",
+            Status::Faster => "faster",
+            Status::Unchanged => "",
+        })
+    }
+}
+
+/// A result of the current build next to its baseline
+#[derive(Debug)]
+pub struct Delta {
+    /// The result of the current build
+    pub current: Entry,
+    /// The recorded result of the same algorithm on the same instance, if any
+    pub baseline: Option<Entry>,
+    /// How the result compares to the baseline
+    pub status: Status,
+}
+impl Delta {
+    /// Relative change of the makespan, if there is a comparable baseline
+    pub fn makespan_change(&self) -> Option<f64> {
+        let baseline = self.comparable()?;
+        Some(relative_change(
+            f64::from(self.current.makespan),
+            f64::from(baseline.makespan),
+        ))
+    }
+    /// Relative change of the runtime, if there is a comparable baseline
+    pub fn runtime_change(&self) -> Option<f64> {
+        let baseline = self.comparable()?;
+        Some(relative_change(
+            self.current.runtime_ms as f64,
+            baseline.runtime_ms as f64,
+        ))
+    }
+    /// The baseline, unless the instance changed since it was recorded
+    fn comparable(&self) -> Option<&Entry> {
+        self.baseline
+            .as_ref()
+            .filter(|baseline| baseline.instance_hash == self.current.instance_hash)
+    }
+}
+
+/// Solves every test instance in `dir` with every algorithm. The instances
+/// are solved one after another, so that the runtimes do not disturb each
+/// other. Schedules that violate their instance are reported.
+pub fn run(dir: &str, algorithms: &[Algorithm]) -> Vec<Entry> {
+    let recorded_at = reproducible::timestamp();
+    let mut entries = vec![];
+    for name in testdata::instance_names(dir) {
+        let (job_file, constraint_file) = testdata::instance_files(dir, &name);
+        let instance_hash = hash_files(&[&job_file, &constraint_file]);
+        for &algorithm in algorithms {
+            let start = Instant::now();
+            let outcome = run::run(RunConfig {
+                input: Input::Files {
+                    job_file: job_file.clone(),
+                    constraint_file: constraint_file.clone(),
+                },
+                algorithm,
+                schedule_json: None,
+                svg: None,
+            })
+            .unwrap_or_else(|e| panic!("cannot solve {name} with {algorithm}: {e}"));
+            let runtime_ms = start.elapsed().as_millis() as u64;
+            let schedule = &outcome.schedule;
+            if !outcome.violations.is_empty() {
+                diagnostics::report(
+                    Category::Verification,
+                    format!(
+                        "{algorithm} on {name} violates the instance: {}",
+                        outcome.violations.join(", ")
+                    ),
+                );
+            }
+            info!(
+                "Solved {name} with {algorithm}: makespan {}, {runtime_ms} ms",
+                schedule.makespan()
+            );
+            entries.push(Entry {
+                instance: name.clone(),
+                algorithm: algorithm.to_string(),
+                instance_hash: instance_hash.clone(),
+                makespan: schedule.makespan(),
+                runtime_ms,
+                valid: outcome.violations.is_empty(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                git_hash: option_env!("GIT_HASH").map(str::to_string),
+                recorded_at,
+                parallelism: Some(outcome.parallelism),
+            });
+        }
+    }
+    entries
+}
+
+/// Reads the baseline database at `path`. If `path` does not exist yet, the
+/// database is empty.
+pub fn read(path: &str) -> Baseline {
+    if !Path::new(path).exists() {
+        return Baseline {
+            schema_version: BASELINE_VERSION.to_string(),
+            entries: vec![],
+        };
+    }
+    let json: serde_json::Value = files::read_json(path);
+    schema::check_version(path, Document::Baseline, &json);
+    serde_json::from_value(json).unwrap_or_else(|e| panic!("cannot parse {path}: {e}"))
+}
+
+/// Writes the baseline database to `path` in the current format
+pub fn write(path: &str, baseline: &Baseline) {
+    files::write_json(
+        path,
+        &Baseline {
+            schema_version: BASELINE_VERSION.to_string(),
+            entries: baseline.entries.clone(),
+        },
+    );
+}
+
+/// Stores `entries` in `baseline`, replacing the entries of the same
+/// instance and algorithm and keeping all others, e.g. of algorithms that
+/// were not run this time. Returns how many entries were added and how many
+/// were replaced.
+pub fn record(baseline: &mut Baseline, entries: Vec<Entry>) -> (usize, usize) {
+    let mut added = 0;
+    let mut replaced = 0;
+    for entry in entries {
+        match baseline
+            .entries
+            .iter_mut()
+            .find(|old| old.instance == entry.instance && old.algorithm == entry.algorithm)
+        {
+            Some(old) => {
+                *old = entry;
+                replaced += 1;
+            }
+            None => {
+                baseline.entries.push(entry);
+                added += 1;
+            }
+        }
+    }
+    baseline
+        .entries
+        .sort_by(|a, b| (&a.instance, &a.algorithm).cmp(&(&b.instance, &b.algorithm)));
+    (added, replaced)
+}
+
+/// Compares the `entries` of the current build to `baseline`. Returns one
+/// delta per entry, sorted by status and then by instance and algorithm, so
+/// that the most severe changes come first.
+pub fn compare(baseline: &Baseline, entries: Vec<Entry>, thresholds: Thresholds) -> Vec<Delta> {
+    let mut deltas = entries
+        .into_iter()
+        .map(|current| {
+            let baseline = baseline
+                .entries
+                .iter()
+                .find(|old| old.instance == current.instance && old.algorithm == current.algorithm)
+                .cloned();
+            let status = status(&current, baseline.as_ref(), thresholds);
+            Delta {
+                current,
+                baseline,
+                status,
+            }
+        })
+        .collect::<Vec<_>>();
+    deltas.sort_by(|a, b| {
+        (a.status, &a.current.instance, &a.current.algorithm).cmp(&(
+            b.status,
+            &b.current.instance,
+            &b.current.algorithm,
+        ))
+    });
+    deltas
+}
+
+/// Classifies a result of the current build. A worse makespan takes
+/// precedence over a longer runtime, and an improved makespan over a shorter
+/// runtime.
+fn status(current: &Entry, baseline: Option<&Entry>, thresholds: Thresholds) -> Status {
+    let Some(baseline) = baseline else {
+        return if current.valid {
+            Status::New
+        } else {
+            Status::Invalid
+        };
+    };
+    let makespan = relative_change(f64::from(current.makespan), f64::from(baseline.makespan));
+    let runtime = relative_change(current.runtime_ms as f64, baseline.runtime_ms as f64);
+    let noticeable = current.runtime_ms.abs_diff(baseline.runtime_ms) >= RUNTIME_NOISE_MS;
+    if !current.valid {
+        Status::Invalid
+    } else if current.instance_hash != baseline.instance_hash {
+        Status::Changed
+    } else if makespan > thresholds.makespan {
+        Status::Regression
+    } else if noticeable && runtime > thresholds.runtime {
+        Status::Slower
+    } else if current.makespan < baseline.makespan {
+        Status::Improved
+    } else if noticeable && -runtime > thresholds.runtime {
+        Status::Faster
+    } else {
+        Status::Unchanged
+    }
+}
+
+/// Change from `baseline` to `current` relative to `baseline`, where a
+/// baseline of 0 counts as 1 to avoid dividing by zero
+fn relative_change(current: f64, baseline: f64) -> f64 {
+    (current - baseline) / baseline.max(1.0)
+}
@@ -0,0 +1,336 @@
+// Builds instances programmatically from jobs and constraints that refer to
+// each other by id, which is the primary way of creating instances without
+// reading them from files.
+
+use std::{collections::HashMap, fmt};
+
+use itertools::Itertools;
+
+use crate::algo::{topo_order, Constraint, Instance, Job, Lag};
+
+/// Reason why an `InstanceBuilder` cannot build its instance
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ValidationError {
+    /// The number of processors is zero or was never set
+    NoProcessors,
+    /// Two jobs share the same id
+    DuplicateId(i32),
+    /// A constraint refers to a job that does not exist
+    UnknownId(i32),
+    /// A job has not exactly one processing time per processor
+    WrongTimeCount {
+        id: i32,
+        count: usize,
+        processors: usize,
+    },
+    /// A job has a negative processing time. Jobs without processing time are
+    /// fine and run instantaneously.
+    NegativeTime(i32),
+    /// A job takes longer with more processors, see
+    /// `InstanceBuilder::allow_non_monotone`
+    NonMonotone(i32),
+    /// The constraints contain a cycle through the jobs with these ids, where
+    /// every job is a predecessor of the next one and the last job is a
+    /// predecessor of the first one
+    Cycle(Vec<i32>),
+    /// The processors were named, but not exactly one name per processor
+    /// was given
+    MachineNameCount { names: usize, processors: usize },
+}
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::NoProcessors => f.write_str("the instance has no processors"),
+            ValidationError::DuplicateId(id) => write!(f, "job id {id} is used twice"),
+            ValidationError::UnknownId(id) => {
+                write!(f, "a constraint refers to the unknown job id {id}")
+            }
+            ValidationError::WrongTimeCount {
+                id,
+                count,
+                processors,
+            } => write!(
+                f,
+                "job {id} has {count} processing times, but there are {processors} processors"
+            ),
+            ValidationError::NegativeTime(id) => {
+                write!(f, "job {id} has a negative processing time")
+            }
+            ValidationError::NonMonotone(id) => {
+                write!(f, "job {id} takes longer with more processors")
+            }
+            ValidationError::Cycle(ids) => write!(
+                f,
+                "the constraints contain the cycle {} -> {}",
+                ids.iter().join(" -> "),
+                ids[0]
+            ),
+            ValidationError::MachineNameCount { names, processors } => write!(
+                f,
+                "{names} machine names are given, but there are {processors} processors"
+            ),
+        }
+    }
+}
+
+/// Collects jobs and constraints by id and builds a validated `Instance` from
+/// them, e.g.
+///
+/// ```ignore
+/// let instance = InstanceBuilder::new()
+///     .processors(2)
+///     .job(7).times([4, 2])
+///     .job(8).times_fn(|l| 6 / l as i32)
+///     .constraint(7, 8)
+///     .build()?;
+/// ```
+#[derive(Debug, Default)]
+pub struct InstanceBuilder {
+    /// Number of processors
+    processors: usize,
+    /// Ids and processing times of the jobs, in the order in which they were
+    /// added
+    jobs: Vec<(i32, Vec<i32>)>,
+    /// Ids of the jobs of every constraint along with its lag
+    constraints: Vec<(i32, i32, Lag)>,
+    /// Whether jobs may take longer with more processors
+    allow_non_monotone: bool,
+    /// Names of the processors, empty if they are not named
+    machine_names: Vec<String>,
+}
+impl InstanceBuilder {
+    /// Creates a builder without processors, jobs, and constraints
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Sets the number of processors
+    pub fn processors(mut self, processors: usize) -> Self {
+        self.processors = processors;
+        self
+    }
+    /// Starts adding the job with the given `id`, whose processing times are
+    /// set next
+    pub fn job(self, id: i32) -> JobBuilder {
+        JobBuilder { builder: self, id }
+    }
+    /// Requires the job with id `left` to complete before the job with id
+    /// `right` starts
+    pub fn constraint(mut self, left: i32, right: i32) -> Self {
+        self.constraints.push((left, right, Lag::default()));
+        self
+    }
+    /// Names the processors, one name per processor
+    #[cfg_attr(
+        not(test),
+        expect(dead_code, reason = "used by code that builds named instances")
+    )]
+    pub fn machine_names(mut self, names: impl IntoIterator<Item = String>) -> Self {
+        self.machine_names = names.into_iter().collect();
+        self
+    }
+    /// Accepts jobs that take longer with more processors, which are rejected
+    /// by default
+    pub fn allow_non_monotone(mut self) -> Self {
+        self.allow_non_monotone = true;
+        self
+    }
+    /// Builds the instance. The jobs get dense indices in the order in which
+    /// they were added, the constraints are resolved to these indices, and
+    /// the horizon is computed.
+    pub fn build(self) -> Result<Instance, ValidationError> {
+        if self.processors == 0 {
+            return Err(ValidationError::NoProcessors);
+        }
+        if !self.machine_names.is_empty() && self.machine_names.len() != self.processors {
+            return Err(ValidationError::MachineNameCount {
+                names: self.machine_names.len(),
+                processors: self.processors,
+            });
+        }
+        let mut positions = HashMap::new();
+        for (index, (id, times)) in self.jobs.iter().enumerate() {
+            if positions.insert(*id, index).is_some() {
+                return Err(ValidationError::DuplicateId(*id));
+            }
+            if times.len() != self.processors {
+                return Err(ValidationError::WrongTimeCount {
+                    id: *id,
+                    count: times.len(),
+                    processors: self.processors,
+                });
+            }
+            if times.iter().any(|&p| p < 0) {
+                return Err(ValidationError::NegativeTime(*id));
+            }
+            if !self.allow_non_monotone && times.iter().tuple_windows().any(|(a, b)| b > a) {
+                return Err(ValidationError::NonMonotone(*id));
+            }
+        }
+        let position = |id| {
+            positions
+                .get(&id)
+                .copied()
+                .ok_or(ValidationError::UnknownId(id))
+        };
+        let constraints = self
+            .constraints
+            .iter()
+            .map(|&(left, right, lag)| Ok(Constraint(position(left)?, position(right)?, lag)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let ids = self.jobs.iter().map(|&(id, _)| id).collect::<Vec<_>>();
+        let jobs = self
+            .jobs
+            .into_iter()
+            .enumerate()
+            .map(|(index, (_, processing_times))| Job {
+                index,
+                processing_times,
+                synthesized: 0,
+                affinity: None,
+                note: None,
+            })
+            .collect::<Vec<_>>();
+        let instance = Instance::new(self.processors, jobs, constraints)
+            .with_machine_names(self.machine_names);
+        if let Err(e) = topo_order(&instance) {
+            return Err(ValidationError::Cycle(
+                e.jobs.into_iter().map(|j| ids[j]).collect(),
+            ));
+        }
+        instance.horizon();
+        Ok(instance)
+    }
+}
+
+/// A job of an `InstanceBuilder` whose processing times are set next
+#[derive(Debug)]
+pub struct JobBuilder {
+    builder: InstanceBuilder,
+    id: i32,
+}
+impl JobBuilder {
+    /// Sets the processing times of the job, starting with the one on a
+    /// single processor
+    pub fn times(mut self, times: impl IntoIterator<Item = i32>) -> InstanceBuilder {
+        self.builder
+            .jobs
+            .push((self.id, times.into_iter().collect()));
+        self.builder
+    }
+    /// Sets the processing time of the job on `l` processors to `time(l)` for
+    /// every `l` from 1 up to the number of processors, which must be set
+    /// before
+    pub fn times_fn(self, time: impl Fn(usize) -> i32) -> InstanceBuilder {
+        let processors = self.builder.processors;
+        self.times((1..=processors).map(time))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{InstanceBuilder, ValidationError};
+
+    /// Builds two jobs with ids 1 and 2 on two processors
+    fn two_jobs() -> InstanceBuilder {
+        InstanceBuilder::new()
+            .processors(2)
+            .job(1)
+            .times([4, 2])
+            .job(2)
+            .times([3, 2])
+    }
+
+    #[test]
+    fn valid_instance_is_built() {
+        let instance = two_jobs().constraint(1, 2).build().expect("valid instance");
+        assert_eq!(instance.processor_count, 2);
+        assert_eq!(instance.jobs.len(), 2);
+        assert_eq!(instance.jobs[1].processing_times, [3, 2]);
+        assert_eq!(instance.predecessor_indices(1), [0]);
+    }
+
+    #[test]
+    fn rejects_missing_processors() {
+        let error = InstanceBuilder::new().job(1).times([]).build().unwrap_err();
+        assert_eq!(error, ValidationError::NoProcessors);
+    }
+
+    #[test]
+    fn rejects_duplicate_id() {
+        let error = two_jobs().job(1).times([2, 1]).build().unwrap_err();
+        assert_eq!(error, ValidationError::DuplicateId(1));
+    }
+
+    #[test]
+    fn rejects_unknown_id() {
+        let error = two_jobs().constraint(1, 9).build().unwrap_err();
+        assert_eq!(error, ValidationError::UnknownId(9));
+    }
+
+    #[test]
+    fn rejects_wrong_time_count() {
+        let error = two_jobs().job(3).times([5]).build().unwrap_err();
+        assert_eq!(
+            error,
+            ValidationError::WrongTimeCount {
+                id: 3,
+                count: 1,
+                processors: 2
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_negative_time() {
+        let error = two_jobs().job(3).times([2, -1]).build().unwrap_err();
+        assert_eq!(error, ValidationError::NegativeTime(3));
+    }
+
+    #[test]
+    fn rejects_non_monotone_times_unless_allowed() {
+        let error = two_jobs().job(3).times([2, 3]).build().unwrap_err();
+        assert_eq!(error, ValidationError::NonMonotone(3));
+        let instance = two_jobs()
+            .allow_non_monotone()
+            .job(3)
+            .times([2, 3])
+            .build()
+            .expect("non-monotone times are allowed");
+        assert_eq!(instance.jobs[2].processing_times, [2, 3]);
+    }
+
+    #[test]
+    fn rejects_cycle() {
+        let error = two_jobs()
+            .constraint(1, 2)
+            .constraint(2, 1)
+            .build()
+            .unwrap_err();
+        assert_eq!(error, ValidationError::Cycle(vec![2, 1]));
+        assert_eq!(
+            error.to_string(),
+            "the constraints contain the cycle 2 -> 1 -> 2"
+        );
+    }
+
+    #[test]
+    fn rejects_self_loop() {
+        let error = two_jobs().constraint(2, 2).build().unwrap_err();
+        assert_eq!(error, ValidationError::Cycle(vec![2]));
+    }
+
+    #[test]
+    fn rejects_wrong_machine_name_count() {
+        let error = two_jobs()
+            .machine_names(["left".to_string()])
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            error,
+            ValidationError::MachineNameCount {
+                names: 1,
+                processors: 2
+            }
+        );
+    }
+}
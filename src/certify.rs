@@ -0,0 +1,325 @@
+// Certifies that the makespan of a schedule of the dynamic program is
+// optimal, by showing that no schedule completes one unit of time earlier.
+// The certificate records the schedule along with the results of the checks,
+// so that reviewers can re-run them with the `certify` command instead of
+// trusting the solver.
+
+use std::fmt;
+
+use log::info;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    algo::{Instance, Schedule},
+    dp, files,
+    milp::{self, Formulation},
+    schema::{self, Document, CERTIFICATE_VERSION},
+};
+
+/// Instances whose time-indexed formulation has more variables than this are
+/// too large for the MILP check
+const MAX_MILP_VARIABLES: usize = 50_000;
+
+/// How a check decides whether a schedule exists
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Method {
+    /// The exhaustive search of the dynamic program
+    Dp,
+    /// The time-indexed formulation of the mixed integer linear program,
+    /// which shares no code with the dynamic program
+    Milp,
+}
+impl fmt::Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Method::Dp => "dynamic program",
+            Method::Milp => "MILP",
+        })
+    }
+}
+
+/// The result of a check
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case", tag = "result")]
+pub enum Outcome {
+    /// No schedule completes by the horizon of the check
+    Infeasible,
+    /// Some schedule completes by the horizon of the check, so the claimed
+    /// optimum is wrong
+    Feasible,
+    /// The check cannot be applied to the instance
+    Skipped {
+        /// Why the check was skipped
+        reason: String,
+    },
+}
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Infeasible => f.write_str("infeasible"),
+            Outcome::Feasible => f.write_str("feasible"),
+            Outcome::Skipped { reason } => write!(f, "skipped, {reason}"),
+        }
+    }
+}
+
+/// A check whether some schedule completes by `horizon`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Check {
+    /// How the check decides it
+    pub method: Method,
+    /// Time by which all jobs must complete, one less than the claimed optimum
+    pub horizon: i32,
+    /// The result of the check
+    pub outcome: Outcome,
+}
+
+/// A claim that the makespan of a schedule is optimal, backed by checks that
+/// no schedule completes one unit of time earlier
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct Certificate {
+    /// Version of the certificate format, see `schema::CERTIFICATE_VERSION`
+    pub schema_version: String,
+    /// FNV-1a hash of the job file followed by the constraint file of the
+    /// instance
+    pub instance_hash: String,
+    /// The makespan that is claimed to be optimal
+    pub claimed_optimum: i32,
+    /// A schedule with the claimed makespan
+    pub schedule: Schedule,
+    /// The checks that no schedule completes before the claimed optimum
+    pub checks: Vec<Check>,
+}
+
+/// Certifies the makespan of `schedule` as optimal for `instance`, whose
+/// files have the hash `instance_hash`. The checks are run, but not judged,
+/// see `problems`.
+pub fn certify(instance: &Instance, schedule: &Schedule, instance_hash: String) -> Certificate {
+    let claimed_optimum = schedule.makespan();
+    Certificate {
+        schema_version: CERTIFICATE_VERSION.to_string(),
+        instance_hash,
+        claimed_optimum,
+        schedule: schedule.clone(),
+        checks: check(instance, claimed_optimum - 1),
+    }
+}
+
+/// Checks with the dynamic program and the MILP whether some schedule of
+/// `instance` completes by `horizon`. Both ignore some requirements of the
+/// instance, such as processor affinity, so neither can wrongly find that no
+/// schedule exists. The dynamic program is skipped for instances with
+/// downtime, and the MILP for large instances.
+pub fn check(instance: &Instance, horizon: i32) -> Vec<Check> {
+    let outcome = |feasible: bool| {
+        if feasible {
+            Outcome::Feasible
+        } else {
+            Outcome::Infeasible
+        }
+    };
+    let dp = if instance.downtime.is_empty() {
+        outcome(dp::feasible_within(instance, horizon))
+    } else {
+        Outcome::Skipped {
+            reason: "the dynamic program does not support processor downtime".to_string(),
+        }
+    };
+    info!("The dynamic program finds the instance {dp} by {horizon}");
+    let size = milp::model_size(
+        &instance
+            .with_processor_count(instance.processor_count)
+            .with_horizon(horizon.max(0)),
+        Formulation::TimeIndexed,
+    );
+    let milp = if size.variables <= MAX_MILP_VARIABLES {
+        outcome(milp::feasible_within(instance, horizon))
+    } else {
+        Outcome::Skipped {
+            reason: format!(
+                "the MILP would have {} variables, more than {MAX_MILP_VARIABLES}",
+                size.variables
+            ),
+        }
+    };
+    info!("The MILP finds the instance {milp} by {horizon}");
+    vec![
+        Check {
+            method: Method::Dp,
+            horizon,
+            outcome: dp,
+        },
+        Check {
+            method: Method::Milp,
+            horizon,
+            outcome: milp,
+        },
+    ]
+}
+
+/// Describes every reason to reject `certificate` for `instance`, whose files
+/// have the hash `instance_hash`, given the `rerun` checks. The certificate
+/// holds if its schedule satisfies the instance with the claimed makespan,
+/// and at least one rerun check, but none of the recorded ones, finds that
+/// no schedule completes earlier. Rerun checks that disagree with the
+/// recorded ones are reported, too.
+pub fn problems(
+    instance: &Instance,
+    certificate: &Certificate,
+    instance_hash: &str,
+    rerun: &[Check],
+) -> Vec<String> {
+    let mut problems = vec![];
+    if certificate.instance_hash != instance_hash {
+        problems.push(format!(
+            "the certificate belongs to the instance with hash {}, but the files have hash {instance_hash}",
+            certificate.instance_hash
+        ));
+    }
+    let schedule = &certificate.schedule;
+    problems.extend(
+        schedule
+            .violations(instance)
+            .into_iter()
+            .map(|violation| format!("the schedule violates the instance: {violation}")),
+    );
+    if schedule.makespan() != certificate.claimed_optimum {
+        problems.push(format!(
+            "the schedule has makespan {}, but the claimed optimum is {}",
+            schedule.makespan(),
+            certificate.claimed_optimum
+        ));
+    }
+    for check in certificate.checks.iter().chain(rerun) {
+        if check.outcome == Outcome::Feasible {
+            problems.push(format!(
+                "the {} finds a schedule that completes by {}",
+                check.method, check.horizon
+            ));
+        }
+    }
+    for check in rerun {
+        let recorded = certificate
+            .checks
+            .iter()
+            .find(|recorded| recorded.method == check.method);
+        if let Some(recorded) = recorded.filter(|recorded| recorded != &check) {
+            problems.push(format!(
+                "the {} check was recorded as {} by {}, but is now {} by {}",
+                check.method, recorded.outcome, recorded.horizon, check.outcome, check.horizon
+            ));
+        }
+    }
+    if rerun
+        .iter()
+        .all(|check| check.outcome != Outcome::Infeasible)
+    {
+        problems.push(format!(
+            "no check shows that no schedule completes by {}",
+            certificate.claimed_optimum - 1
+        ));
+    }
+    problems
+}
+
+/// Reads a certificate written by `write`
+pub fn read(path: &str) -> Certificate {
+    let json: serde_json::Value = files::read_json(path);
+    schema::check_version(path, Document::Certificate, &json);
+    serde_json::from_value(json).unwrap_or_else(|e| panic!("cannot parse {path}: {e}"))
+}
+
+/// Writes a certificate as JSON to `path`
+pub fn write(path: &str, certificate: &Certificate) {
+    files::write_json(path, certificate);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{certify, check, problems, Outcome};
+    use crate::{
+        algo::{Instance, Schedule, ScheduledJob},
+        builder::InstanceBuilder,
+    };
+
+    /// Hash of the instance files, which the tests do not read
+    const HASH: &str = "0000000000000000";
+
+    /// Two independent jobs on two processors, which have the optimal
+    /// makespan 4 because their work is at least 8
+    fn instance() -> Instance {
+        InstanceBuilder::new()
+            .processors(2)
+            .job(1)
+            .times([4, 2])
+            .job(2)
+            .times([4, 2])
+            .build()
+            .expect("valid instance")
+    }
+
+    /// Schedules the jobs of `instance` one after another on both processors
+    /// if `sequential`, and next to each other on one processor each otherwise
+    fn schedule(instance: &Instance, sequential: bool) -> Schedule {
+        let jobs = if sequential {
+            vec![
+                ScheduledJob::new(instance.jobs[0].clone(), 2, 0, vec![0, 1]),
+                ScheduledJob::new(instance.jobs[1].clone(), 2, 2, vec![0, 1]),
+            ]
+        } else {
+            vec![
+                ScheduledJob::new(instance.jobs[0].clone(), 1, 0, vec![0]),
+                ScheduledJob::new(instance.jobs[1].clone(), 1, 0, vec![1]),
+            ]
+        };
+        Schedule {
+            processor_count: 2,
+            jobs,
+        }
+    }
+
+    #[test]
+    fn optimal_makespan_is_certified() {
+        let instance = instance();
+        let certificate = certify(&instance, &schedule(&instance, false), HASH.to_string());
+        assert_eq!(certificate.claimed_optimum, 4);
+        assert!(certificate
+            .checks
+            .iter()
+            .all(|check| check.outcome == Outcome::Infeasible));
+        let rerun = check(&instance, certificate.claimed_optimum - 1);
+        assert_eq!(
+            problems(&instance, &certificate, HASH, &rerun),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn suboptimal_makespan_is_rejected() {
+        let instance = instance();
+        let mut late = schedule(&instance, true);
+        late.jobs[1].start_time += 1;
+        let certificate = certify(&instance, &late, HASH.to_string());
+        assert_eq!(certificate.claimed_optimum, 5);
+        assert!(certificate
+            .checks
+            .iter()
+            .all(|check| check.outcome == Outcome::Feasible));
+        let rerun = check(&instance, certificate.claimed_optimum - 1);
+        assert!(!problems(&instance, &certificate, HASH, &rerun).is_empty());
+    }
+
+    #[test]
+    fn lowered_claim_is_rejected() {
+        let instance = instance();
+        let mut certificate = certify(&instance, &schedule(&instance, true), HASH.to_string());
+        certificate.claimed_optimum -= 1;
+        let rerun = check(&instance, certificate.claimed_optimum - 1);
+        let problems = problems(&instance, &certificate, HASH, &rerun);
+        assert!(problems
+            .iter()
+            .any(|problem| problem.contains("but the claimed optimum is 3")));
+    }
+}
@@ -0,0 +1,445 @@
+// Plain text output for the terminal, such as a summary table of a schedule,
+// which also backs the `Display` impls of instances and schedules.
+
+use std::{collections::HashMap, env, fmt};
+
+use itertools::Itertools;
+
+use crate::{
+    algo::{format_processors, Instance, Schedule},
+    baseline::Delta,
+    metrics::ChainBreakdown,
+    render::{assign_processors, render_ascii, RenderOptions},
+};
+
+/// Number of rows shown at the start and at the end of a truncated table
+const SHOWN_ROWS: usize = 10;
+
+/// Separator between two columns of a table
+const SEPARATOR: &str = "  ";
+
+/// Number of characters of a note shown in the summary table, including the
+/// ellipsis of truncated notes
+const NOTE_WIDTH: usize = 30;
+
+/// Width of the terminal if the shell does not announce it
+const DEFAULT_WIDTH: usize = 80;
+
+/// A column of a text table
+struct Column {
+    header: String,
+    /// Whether the cells are aligned to the right, which is used for numbers
+    right_align: bool,
+}
+
+/// A text table with aligned columns. If the table is too wide, the columns
+/// on the right are dropped.
+struct Table {
+    columns: Vec<Column>,
+    rows: Vec<Vec<String>>,
+}
+impl Table {
+    fn new(columns: Vec<Column>) -> Self {
+        Table {
+            columns,
+            rows: vec![],
+        }
+    }
+    fn push(&mut self, row: Vec<String>) {
+        debug_assert_eq!(row.len(), self.columns.len(), "wrong number of cells");
+        self.rows.push(row);
+    }
+    /// Renders the table to lines of text. If `max_width` is given, trailing
+    /// columns are dropped until the table fits, but the first column is
+    /// always kept. Cells of the last column are cut off if they do not fit.
+    /// Only the first and last `SHOWN_ROWS` rows of long tables are rendered.
+    fn render(&self, max_width: Option<usize>) -> Vec<String> {
+        let widths = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                self.rows
+                    .iter()
+                    .map(|row| row[i].chars().count())
+                    .chain([column.header.chars().count()])
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect::<Vec<_>>();
+        let mut count = widths.len();
+        if let Some(max_width) = max_width {
+            while count > 1 && width_of(&widths[..count]) > max_width {
+                count -= 1;
+            }
+        }
+        let line = |cells: &mut dyn Iterator<Item = &str>| {
+            let line = cells
+                .zip(&self.columns)
+                .zip(&widths)
+                .take(count)
+                .map(|((cell, column), width)| {
+                    if column.right_align {
+                        format!("{cell:>width$}")
+                    } else {
+                        format!("{cell:<width$}")
+                    }
+                })
+                .join(SEPARATOR);
+            let line = line.trim_end();
+            match max_width {
+                Some(max_width) => line.chars().take(max_width).collect(),
+                None => line.to_string(),
+            }
+        };
+
+        let mut lines = vec![line(&mut self.columns.iter().map(|c| c.header.as_str()))];
+        if self.rows.len() > 2 * SHOWN_ROWS + 1 {
+            let head = &self.rows[..SHOWN_ROWS];
+            let tail = &self.rows[self.rows.len() - SHOWN_ROWS..];
+            lines.extend(
+                head.iter()
+                    .map(|row| line(&mut row.iter().map(String::as_str))),
+            );
+            lines.push(format!(
+                "... {} rows omitted ...",
+                self.rows.len() - 2 * SHOWN_ROWS
+            ));
+            lines.extend(
+                tail.iter()
+                    .map(|row| line(&mut row.iter().map(String::as_str))),
+            );
+        } else {
+            lines.extend(
+                self.rows
+                    .iter()
+                    .map(|row| line(&mut row.iter().map(String::as_str))),
+            );
+        }
+        if count < self.columns.len() {
+            lines.push(format!(
+                "({} columns hidden, the terminal is too narrow)",
+                self.columns.len() - count
+            ));
+        }
+        lines
+    }
+}
+
+/// Total width of a table with columns of the given widths
+fn width_of(widths: &[usize]) -> usize {
+    widths.iter().sum::<usize>() + SEPARATOR.len() * widths.len().saturating_sub(1)
+}
+
+/// Width of the terminal, as announced by the shell in `COLUMNS`
+fn terminal_width() -> Option<usize> {
+    env::var("COLUMNS").ok()?.trim().parse().ok()
+}
+
+/// Summarizes a schedule as a table of its jobs sorted by start time, along
+/// with a footer line about the makespan, the idle time, and the utilization.
+/// The table fits into the width of the terminal, if it is known.
+pub fn summary(schedule: &Schedule) -> Vec<String> {
+    summary_lines(schedule, terminal_width())
+}
+
+/// Summarizes a schedule like `summary`, dropping columns that do not fit
+/// into `max_width`
+fn summary_lines(schedule: &Schedule, max_width: Option<usize>) -> Vec<String> {
+    let (processors, _, _) = assign_processors(
+        schedule.processor_count,
+        &schedule.jobs,
+        &[],
+        &HashMap::new(),
+    );
+    // only show the note column if any job has a note
+    let notes = schedule.jobs.iter().any(|job| job.job.note.is_some());
+    let mut table = Table::new(
+        [
+            ("id", true),
+            ("start", true),
+            ("duration", true),
+            ("completion", true),
+            ("allotment", true),
+            ("processors", false),
+        ]
+        .into_iter()
+        .chain(notes.then_some(("note", false)))
+        .map(|(header, right_align)| Column {
+            header: header.to_string(),
+            right_align,
+        })
+        .collect(),
+    );
+    for (job, processors) in schedule
+        .jobs
+        .iter()
+        .zip(&processors)
+        .sorted_by_key(|(job, _)| (job.start_time, job.job.index))
+    {
+        let mut row = vec![
+            job.job.index.to_string(),
+            job.start_time.to_string(),
+            job.processing_time().to_string(),
+            job.completion_time().to_string(),
+            job.allotment.to_string(),
+            format_processors(processors),
+        ];
+        if notes {
+            row.push(truncate_note(job.job.note.as_deref().unwrap_or_default()));
+        }
+        table.push(row);
+    }
+
+    let makespan = schedule.makespan();
+    let capacity = i64::from(makespan) * schedule.processor_count as i64;
+    let work = schedule
+        .jobs
+        .iter()
+        .map(|job| i64::from(job.processing_time()) * job.allotment as i64)
+        .sum::<i64>();
+    let utilization = if capacity == 0 {
+        0.0
+    } else {
+        work as f64 / capacity as f64
+    };
+
+    let mut lines = table.render(max_width);
+    lines.push(format!(
+        "makespan {makespan}, idle {}, utilization {:.1}%",
+        capacity - work,
+        100.0 * utilization
+    ));
+    lines
+}
+
+/// Shortens a note to at most `NOTE_WIDTH` characters on a single line
+fn truncate_note(note: &str) -> String {
+    let line = note.split_whitespace().join(" ");
+    if line.chars().count() <= NOTE_WIDTH {
+        line
+    } else {
+        let mut short = line.chars().take(NOTE_WIDTH - 1).collect::<String>();
+        short.push('…');
+        short
+    }
+}
+
+/// Summarizes the chains of a schedule as a table of their processing times,
+/// completion times, and slack to the makespan, fitting into the width of
+/// the terminal like `summary`
+pub fn chain_summary(breakdown: &[ChainBreakdown]) -> Vec<String> {
+    let mut table = Table::new(
+        [
+            ("chain", true),
+            ("processing", true),
+            ("completion", true),
+            ("slack", true),
+            ("jobs", false),
+        ]
+        .into_iter()
+        .map(|(header, right_align)| Column {
+            header: header.to_string(),
+            right_align,
+        })
+        .collect(),
+    );
+    for chain in breakdown {
+        table.push(vec![
+            chain.chain.to_string(),
+            chain.processing_time.to_string(),
+            chain.completion.to_string(),
+            chain.slack.to_string(),
+            chain.jobs.iter().join(" "),
+        ]);
+    }
+    table.render(terminal_width())
+}
+
+/// Draws the schedule as a text Gantt chart that fits into the width of the
+/// terminal, see `render_ascii`
+pub fn gantt(schedule: &Schedule, options: &RenderOptions) -> Vec<String> {
+    render_ascii(schedule, options, terminal_width().unwrap_or(DEFAULT_WIDTH))
+}
+
+/// Lists the results of a baseline comparison as a table of the makespans
+/// and runtimes next to their baselines and the relative changes, followed by
+/// the mean allotment, the work relative to the least work, and the idle
+/// fraction of the current schedules, fitting into the width of the terminal
+/// like `summary`
+pub fn baseline_table(deltas: &[Delta]) -> Vec<String> {
+    let mut table = Table::new(
+        [
+            ("instance", false),
+            ("algorithm", false),
+            ("baseline", true),
+            ("makespan", true),
+            ("change", true),
+            ("baseline ms", true),
+            ("ms", true),
+            ("change", true),
+            ("allot", true),
+            ("work", true),
+            ("idle", true),
+            ("status", false),
+        ]
+        .into_iter()
+        .map(|(header, right_align)| Column {
+            header: header.to_string(),
+            right_align,
+        })
+        .collect(),
+    );
+    let percent =
+        |change: Option<f64>| change.map_or(String::new(), |c| format!("{:+.1}%", 100.0 * c));
+    for delta in deltas {
+        let baseline = delta.baseline.as_ref();
+        let parallelism = delta.current.parallelism.as_ref();
+        table.push(vec![
+            delta.current.instance.clone(),
+            delta.current.algorithm.clone(),
+            baseline.map_or(String::new(), |b| b.makespan.to_string()),
+            delta.current.makespan.to_string(),
+            percent(delta.makespan_change()),
+            baseline.map_or(String::new(), |b| b.runtime_ms.to_string()),
+            delta.current.runtime_ms.to_string(),
+            percent(delta.runtime_change()),
+            parallelism.map_or(String::new(), |p| format!("{:.2}", p.mean_allotment)),
+            parallelism.map_or(String::new(), |p| format!("{:.2}x", p.work_ratio())),
+            parallelism.map_or(String::new(), |p| {
+                format!("{:.1}%", 100.0 * p.idle_fraction)
+            }),
+            delta.status.to_string(),
+        ]);
+    }
+    table.render(terminal_width())
+}
+
+/// Prints the summary table of the schedule regardless of the width of the
+/// terminal
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&summary_lines(self, None).join("\n"))
+    }
+}
+
+/// Prints a line about the size of the instance, followed by a table of its
+/// jobs with their processing times on one and on all processors and their
+/// number of direct predecessors and successors
+impl fmt::Display for Instance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let m = self.processor_count;
+        let p_m = format!("p({m})");
+        let mut columns = vec![
+            ("id", true),
+            ("p(1)", true),
+            (p_m.as_str(), true),
+            ("predecessors", true),
+            ("successors", true),
+        ];
+        if self.has_affinity() {
+            columns.push(("affinity", false));
+        }
+        let mut table = Table::new(
+            columns
+                .into_iter()
+                .map(|(header, right_align)| Column {
+                    header: header.to_string(),
+                    right_align,
+                })
+                .collect(),
+        );
+        for (j, job) in self.jobs.iter().enumerate() {
+            let mut row = vec![
+                job.index.to_string(),
+                job.processing_time(1).to_string(),
+                job.processing_times
+                    .get(m - 1)
+                    .map(ToString::to_string)
+                    .unwrap_or_default(),
+                self.predecessor_indices(j).len().to_string(),
+                self.successor_indices(j).len().to_string(),
+            ];
+            if self.has_affinity() {
+                row.push(
+                    job.affinity
+                        .as_deref()
+                        .map(format_processors)
+                        .unwrap_or_default(),
+                );
+            }
+            table.push(row);
+        }
+        writeln!(
+            f,
+            "{} jobs on {m} processors, {} constraints, {} soft constraints, width {}",
+            self.jobs.len(),
+            self.constraints.len(),
+            self.soft_constraints.len(),
+            self.chains().len()
+        )?;
+        f.write_str(&table.render(None).join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        algo::{Instance, Schedule, ScheduledJob},
+        builder::InstanceBuilder,
+    };
+
+    /// Three jobs on two processors, where the first one precedes the last
+    fn instance() -> Instance {
+        InstanceBuilder::new()
+            .processors(2)
+            .job(1)
+            .times([4, 2])
+            .job(2)
+            .times([3, 2])
+            .job(3)
+            .times([2, 1])
+            .constraint(1, 3)
+            .build()
+            .expect("valid instance")
+    }
+
+    #[test]
+    fn instance_display() {
+        let expected = [
+            "3 jobs on 2 processors, 1 constraints, 0 soft constraints, width 2",
+            "id  p(1)  p(2)  predecessors  successors",
+            " 0     4     2             0           1",
+            " 1     3     2             0           0",
+            " 2     2     1             1           0",
+        ];
+        assert_eq!(instance().to_string(), expected.join("\n"));
+    }
+
+    #[test]
+    fn schedule_display() {
+        let instance = instance();
+        let job = |index, allotment, start_time, processors| ScheduledJob {
+            job: instance.shared_job(index),
+            allotment,
+            start_time,
+            processors,
+        };
+        let schedule = Schedule {
+            processor_count: 2,
+            jobs: vec![
+                job(0, 2, 0, vec![0, 1]),
+                job(2, 1, 2, vec![1]),
+                job(1, 1, 2, vec![0]),
+            ],
+        };
+        let expected = [
+            "id  start  duration  completion  allotment  processors",
+            " 0      0         2           2          2  0-1",
+            " 1      2         3           5          1  0",
+            " 2      2         2           4          1  1",
+            "makespan 5, idle 1, utilization 90.0%",
+        ];
+        assert_eq!(schedule.to_string(), expected.join("\n"));
+    }
+}
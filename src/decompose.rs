@@ -0,0 +1,297 @@
+// Solves the weakly connected components of an instance separately and packs
+// their schedules onto the shared processors.
+
+use std::cmp::Reverse;
+
+use log::{debug, info};
+
+use crate::{
+    algo::{Instance, RoundingDecision, Schedule, ScheduledJob, SolverStats},
+    diagnostics::{self, Category},
+    improve,
+    list::{self, ListOptions},
+};
+
+/// Solves every component of `instance` with `solve` and merges the schedules
+/// of the components via `merge`. Instances with a single component are
+/// passed to `solve` as they are, and so are instances with pinned jobs,
+/// which merging could move. The bound of the linear program is the
+/// largest bound of all components, since each component alone takes at
+/// least that long.
+pub fn solve(
+    instance: Instance,
+    mut solve: impl FnMut(Instance) -> (Schedule, SolverStats),
+) -> (Schedule, SolverStats) {
+    let component_jobs = instance.component_jobs();
+    if component_jobs.len() == 1 {
+        info!("The instance has a single component, solving it as a whole");
+        return solve(instance);
+    }
+    if !instance.pinned.is_empty() {
+        diagnostics::report(
+            Category::Model,
+            "Merging components would move pinned jobs, so the instance is solved as a whole",
+        );
+        return solve(instance);
+    }
+    info!(
+        "Solving {} components with up to {} jobs separately",
+        component_jobs.len(),
+        component_jobs.iter().map(Vec::len).max().unwrap_or(0)
+    );
+    let mut stats = SolverStats::default();
+    let mut schedules = vec![];
+    for (c, (component, jobs)) in instance
+        .components()
+        .into_iter()
+        .zip(&component_jobs)
+        .enumerate()
+    {
+        let (schedule, component_stats) = solve(component);
+        debug!(
+            "Component {c} with {} jobs has makespan {}",
+            jobs.len(),
+            schedule.makespan()
+        );
+        stats.lp_bound = match (stats.lp_bound, component_stats.lp_bound) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        };
+        stats.rho = stats.rho.or(component_stats.rho);
+        stats.mu = stats.mu.or(component_stats.mu);
+        stats.max_target_deviation = stats
+            .max_target_deviation
+            .max(component_stats.max_target_deviation);
+        stats
+            .rounding
+            .extend(
+                component_stats
+                    .rounding
+                    .into_iter()
+                    .map(|decision| RoundingDecision {
+                        job: jobs[decision.job],
+                        ..decision
+                    }),
+            );
+        schedules.push(schedule);
+    }
+    stats.rounding.sort_by_key(|decision| decision.job);
+    (merge(&instance, &component_jobs, schedules), stats)
+}
+
+/// Packs the schedules of the components, given by the positions of their
+/// jobs in `instance`, onto the processors of `instance`. Each schedule is
+/// treated as a rectangle that is as wide as the processors it uses and as
+/// high as its makespan. The rectangles are placed on shelves by decreasing
+/// height, putting each one on the first shelf that still has enough free
+/// processors or on a new shelf above the others. Since there are no
+/// constraints between components, this is feasible unless the capacity
+/// profile forbids it, in which case the merged schedule is repaired.
+/// Alternatively, all components are overlaid from time 0, and LIST keeps
+/// their allotments but delays jobs until enough processors are free, with
+/// the start times within the components as targets. The overlay is returned
+/// instead if it satisfies the instance and is shorter.
+pub fn merge(
+    instance: &Instance,
+    component_jobs: &[Vec<usize>],
+    schedules: Vec<Schedule>,
+) -> Schedule {
+    let m = instance.processor_count;
+    let mut blocks = schedules
+        .into_iter()
+        .zip(component_jobs)
+        .map(|(mut schedule, jobs)| {
+            let width = if let Some(highest) = highest_processor(&schedule) {
+                highest + 1
+            } else {
+                // the processors of the other jobs may overlap after
+                // shifting, so they are dropped
+                for job in &mut schedule.jobs {
+                    job.processors.clear();
+                }
+                max_busy_processors(&schedule)
+            };
+            (schedule.makespan(), width.min(m), schedule, jobs)
+        })
+        .collect::<Vec<_>>();
+    blocks.sort_by_key(|&(height, width, _, jobs)| (Reverse(height), Reverse(width), jobs[0]));
+
+    // start time, height, and number of used processors of every shelf
+    let mut shelves: Vec<(i32, i32, usize)> = vec![];
+    let mut jobs = vec![];
+    let mut overlay = vec![];
+    for (height, width, schedule, positions) in blocks {
+        let shelf = if let Some(shelf) = shelves.iter().position(|&(_, _, used)| used + width <= m)
+        {
+            shelf
+        } else {
+            let start = shelves
+                .last()
+                .map_or(0, |&(start, height, _)| start + height);
+            shelves.push((start, height, 0));
+            shelves.len() - 1
+        };
+        let (offset, _, used) = shelves[shelf];
+        debug!(
+            "Placing the component of job {} on shelf {shelf} at {offset} on processors {used} to {}",
+            positions[0],
+            used + width - 1
+        );
+        shelves[shelf].2 += width;
+        overlay.extend(schedule.jobs.iter().map(|scheduled| ScheduledJob {
+            job: instance.shared_job(positions[scheduled.job.index]),
+            processors: vec![],
+            ..scheduled.clone()
+        }));
+        jobs.extend(schedule.jobs.into_iter().map(|scheduled| ScheduledJob {
+            job: instance.shared_job(positions[scheduled.job.index]),
+            allotment: scheduled.allotment,
+            start_time: offset + scheduled.start_time,
+            processors: scheduled.processors.iter().map(|&p| used + p).collect(),
+        }));
+    }
+    jobs.sort_by_key(|job| (job.start_time, job.job.index));
+    let packed = Schedule {
+        processor_count: m,
+        jobs,
+    };
+    info!(
+        "Packing the components onto {} shelves yields makespan {}",
+        shelves.len(),
+        packed.makespan()
+    );
+
+    // all components start at once, and jobs only wait if they would exceed
+    // the processors
+    let mut allotments = vec![0; instance.jobs.len()];
+    let mut targets = vec![0; instance.jobs.len()];
+    for job in &overlay {
+        allotments[job.job.index] = job.allotment;
+        targets[job.job.index] = job.start_time;
+    }
+    let overlaid = Schedule {
+        processor_count: m,
+        jobs: list::schedule(instance, &allotments, &targets, &ListOptions::default()).jobs,
+    };
+    let overlay_violations = overlaid.violations(instance);
+    info!(
+        "Overlaying the components yields makespan {}{}",
+        overlaid.makespan(),
+        if overlay_violations.is_empty() {
+            ""
+        } else {
+            " but violates the instance"
+        }
+    );
+    if overlay_violations.is_empty() && overlaid.makespan() < packed.makespan() {
+        return overlaid;
+    }
+
+    let violations = packed.violations(instance);
+    if violations.is_empty() {
+        return packed;
+    }
+    diagnostics::report(
+        Category::Repair,
+        format!(
+            "Merged schedule violates the instance, repairing it: {}",
+            violations.join(", ")
+        ),
+    );
+    improve::repair(instance, packed).0
+}
+
+/// Returns the highest processor of a schedule if every job is placed on
+/// processors, so that shifting all processors by the same amount keeps the
+/// jobs apart
+fn highest_processor(schedule: &Schedule) -> Option<usize> {
+    if schedule.jobs.iter().any(|job| job.processors.is_empty()) {
+        return None;
+    }
+    schedule
+        .jobs
+        .iter()
+        .flat_map(|job| job.processors.iter().copied())
+        .max()
+}
+
+/// Returns the largest number of processors that are busy at the same time
+fn max_busy_processors(schedule: &Schedule) -> usize {
+    schedule
+        .resource_profile()
+        .iter()
+        .map(|point| point.busy_processors)
+        .max()
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::solve;
+    use crate::{
+        algo::{Instance, Schedule, SolverStats},
+        dp,
+        generate::{self, GenerateConfig},
+    };
+
+    /// Three independent chains of eight jobs in total on three processors
+    fn instance(seed: u64) -> Instance {
+        let config = GenerateConfig {
+            n: 8,
+            m: 3,
+            min_p: 1,
+            max_p: 6,
+            omega: 3,
+            min_chain: 2,
+            max_chain: 3,
+            concave: true,
+        };
+        generate::instance(&config, &mut StdRng::seed_from_u64(seed))
+    }
+
+    fn solve_dp(instance: Instance) -> Schedule {
+        dp::schedule(
+            instance,
+            &dp::CheckpointOptions::default(),
+            &dp::TraceOptions::default(),
+        )
+    }
+
+    /// Finds the smallest makespan of any schedule of `instance`
+    fn optimum(instance: &Instance) -> i32 {
+        (instance.min_critical_path_length()..=instance.horizon())
+            .find(|&horizon| dp::feasible_within(instance, horizon))
+            .expect("every instance has a schedule")
+    }
+
+    #[test]
+    fn merged_components_are_feasible_and_close_to_monolithic() {
+        for seed in 0..4 {
+            let components = instance(seed).components();
+            assert_eq!(components.len(), 3, "seed {seed}");
+            let separately = components
+                .into_iter()
+                .map(|component| solve_dp(component).makespan())
+                .collect::<Vec<_>>();
+            let (merged, _) = solve(instance(seed), |component| {
+                (solve_dp(component), SolverStats::default())
+            });
+            let monolithic = solve_dp(instance(seed));
+
+            assert_eq!(merged.violations(&instance(seed)), Vec::<String>::new());
+            assert_eq!(merged.jobs.len(), 8);
+            assert_eq!(monolithic.violations(&instance(seed)), Vec::<String>::new());
+            let optimum = optimum(&instance(seed));
+            assert!(merged.makespan() >= optimum, "seed {seed}");
+            assert!(monolithic.makespan() >= optimum, "seed {seed}");
+            // stacking the schedules of the components is always possible
+            assert!(
+                merged.makespan() <= separately.iter().sum::<i32>(),
+                "seed {seed}: {} exceeds the stacked components {separately:?}",
+                merged.makespan()
+            );
+        }
+    }
+}
@@ -0,0 +1,104 @@
+// Collects the warnings of a run as structured records, so that --strict can
+// fail on them and the schedule JSON can list them.
+
+use std::{fmt, sync::Mutex};
+
+use itertools::Itertools;
+use log::{error, warn};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// Diagnostics reported so far, in the order in which they were reported
+static DIAGNOSTICS: Mutex<Vec<Diagnostic>> = Mutex::new(vec![]);
+
+/// What a diagnostic is about
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum Category {
+    /// The input files are suspicious, e.g. because of non-monotone
+    /// processing times
+    Input,
+    /// The solver ignores part of the instance
+    Model,
+    /// Rounding the fractional solution clamped an allotment or broke a
+    /// precedence constraint
+    Rounding,
+    /// The schedule does not satisfy the instance
+    Verification,
+    /// The schedule had to be repaired or could not be exported as requested
+    Repair,
+    /// The rendered schedule may be misleading
+    Render,
+    /// A result is worse than its recorded baseline
+    Regression,
+}
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Category::Input => "input",
+            Category::Model => "model",
+            Category::Rounding => "rounding",
+            Category::Verification => "verification",
+            Category::Repair => "repair",
+            Category::Render => "render",
+            Category::Regression => "regression",
+        })
+    }
+}
+
+/// A warning that came up while reading, solving, or exporting an instance
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct Diagnostic {
+    /// What the warning is about
+    pub category: Category,
+    /// Description of the problem
+    pub message: String,
+}
+
+/// Logs `message` as a warning and records it. Reporting the same message
+/// again, e.g. because the instance is read twice, only logs it.
+pub fn report(category: Category, message: impl Into<String>) {
+    let message = message.into();
+    warn!("{message}");
+    record(Diagnostic { category, message });
+}
+
+/// Logs `message` as an error and records it like `report`
+pub fn report_error(category: Category, message: impl Into<String>) {
+    let message = message.into();
+    error!("{message}");
+    record(Diagnostic { category, message });
+}
+
+/// Records `diagnostic` unless the same one was recorded before
+fn record(diagnostic: Diagnostic) {
+    let mut diagnostics = DIAGNOSTICS.lock().expect("diagnostics are poisoned");
+    if !diagnostics.contains(&diagnostic) {
+        diagnostics.push(diagnostic);
+    }
+}
+
+/// Returns all diagnostics reported so far
+pub fn all() -> Vec<Diagnostic> {
+    DIAGNOSTICS
+        .lock()
+        .expect("diagnostics are poisoned")
+        .clone()
+}
+
+/// Groups the diagnostics reported so far by their category, in the order of
+/// the categories
+pub fn by_category() -> Vec<(Category, Vec<String>)> {
+    all()
+        .into_iter()
+        .sorted_by_key(|diagnostic| diagnostic.category)
+        .chunk_by(|diagnostic| diagnostic.category)
+        .into_iter()
+        .map(|(category, diagnostics)| {
+            (
+                category,
+                diagnostics.map(|diagnostic| diagnostic.message).collect(),
+            )
+        })
+        .collect()
+}
@@ -1,13 +1,24 @@
 // This file contains the implementation of the dynamic programming algorithm.
 
+use log::{info, trace, warn};
+use serde::{Deserialize, Serialize};
 
-use log::debug;
+use std::{
+    collections::HashSet,
+    fmt::Write as _,
+    hash::Hash,
+    time::{Duration, Instant},
+};
 
-use std::{cmp::Ordering, collections::HashSet, hash::Hash};
+use crate::{
+    algo::{Constraint, Instance, Lag, Schedule, ScheduledJob},
+    diagnostics::{self, Category},
+    files, interrupt, list,
+    provenance::hash_contents,
+    trace::{Pruned, TraceEvent, Tracer},
+};
 
-use crate::algo::{Instance, PartialRelation, Schedule, ScheduledJob};
-
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 /// A state in our dynamic program
 struct State {
     /// For each chain, how far have we advanced this chain
@@ -51,160 +62,751 @@ impl Hash for State {
     }
 }
 
-#[expect(clippy::needless_pass_by_value)]
+/// How the dynamic program chooses its horizon, i.e. the latest completion
+/// time up to which it enumerates states
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum HorizonPolicy {
+    /// The makespan of the greedy schedule of `list::greedy`, which is an
+    /// upper bound on the optimum if the greedy schedule satisfies the
+    /// instance, and `Instance::horizon` otherwise
+    #[default]
+    Auto,
+    /// The given time
+    Fixed(i32),
+    /// The given multiple of the critical path length with the fastest
+    /// allotments, rounded up, which is a lower bound on the optimum
+    LowerBoundMultiple(f64),
+}
+impl HorizonPolicy {
+    /// Computes the horizon that the policy chooses for `instance`
+    pub fn resolve(self, instance: &Instance) -> i32 {
+        match self {
+            HorizonPolicy::Auto => {
+                let greedy = list::greedy(instance);
+                if greedy.violations(instance).is_empty() {
+                    greedy.makespan()
+                } else {
+                    instance.horizon()
+                }
+            }
+            HorizonPolicy::Fixed(horizon) => horizon,
+            HorizonPolicy::LowerBoundMultiple(factor) => {
+                (factor * f64::from(instance.min_critical_path_length())).ceil() as i32
+            }
+        }
+    }
+}
+impl std::str::FromStr for HorizonPolicy {
+    type Err = String;
+
+    /// Parses "auto", a time such as "120", or a multiple of the lower bound
+    /// such as "1.5x"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "auto" {
+            Ok(HorizonPolicy::Auto)
+        } else if let Some(factor) = s.strip_suffix('x') {
+            match factor.parse::<f64>() {
+                Ok(factor) if factor >= 1.0 => Ok(HorizonPolicy::LowerBoundMultiple(factor)),
+                _ => Err(format!(
+                    "the multiple of the lower bound must be a number of at least 1, not {factor}"
+                )),
+            }
+        } else {
+            s.parse().map(HorizonPolicy::Fixed).map_err(|_| {
+                format!("expected \"auto\", a time, or a multiple such as \"1.5x\", not {s}")
+            })
+        }
+    }
+}
+impl std::fmt::Display for HorizonPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HorizonPolicy::Auto => f.write_str("auto"),
+            HorizonPolicy::Fixed(horizon) => write!(f, "{horizon}"),
+            HorizonPolicy::LowerBoundMultiple(factor) => write!(f, "{factor}x"),
+        }
+    }
+}
+
+/// Where and how often the search of the dynamic program is saved to disk
+#[derive(Debug, Default)]
+pub struct CheckpointOptions {
+    /// File to periodically write the search state to
+    pub path: Option<String>,
+    /// Time between two checkpoints
+    pub interval: Duration,
+    /// File to restore the search state from before searching
+    pub resume: Option<String>,
+}
+
+/// Where the candidate states of the search are traced to
+#[derive(Debug, Default)]
+pub struct TraceOptions {
+    /// File to write the sampled candidates to, one JSON object per line
+    pub path: Option<String>,
+    /// Probability with which each candidate is written
+    pub sample: f64,
+}
+
+/// Version of the checkpoint file format, increased on incompatible changes
+const CHECKPOINT_VERSION: u32 = 1;
+
+/// The state of an interrupted search as it is written to disk
+#[derive(Serialize, Deserialize)]
+struct Checkpoint {
+    version: u32,
+    /// Hash of the instance the search belongs to
+    instance_hash: String,
+    stack: Vec<Frame>,
+    known: Vec<State>,
+}
+
+/// A state on the current search path, along with the next candidate to try
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Frame {
+    state: State,
+    cursor: Cursor,
+    /// The job that was scheduled to reach this state, `None` for the root
+    placement: Option<Placement>,
+}
+
+/// Position of the search among the successors of a state
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+struct Cursor {
+    chain: usize,
+    /// Index into the processing times of the front job of the chain
+    allotment: usize,
+    compl: i32,
+}
+
+/// A job that was scheduled to move from one state to the next
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+struct Placement {
+    job: usize,
+    allotment: usize,
+    compl: i32,
+}
+
 /// Given a problem `instance`, find a schedule that satisfies the constraints.
-/// The main function of the DP-Algorithm.
-pub fn schedule(instance: Instance) -> Schedule {
-    let chains = preprocess(&instance);
+/// The main function of the DP-Algorithm. If the search finds no schedule
+/// with completion times up to the horizon, but the greedy schedule of
+/// `list::greedy` is feasible and takes longer, the horizon was too small and
+/// the search is retried with the makespan of the greedy schedule as horizon.
+pub fn schedule(
+    mut instance: Instance,
+    checkpoint: &CheckpointOptions,
+    trace: &TraceOptions,
+) -> Schedule {
+    assert!(
+        instance.downtime.is_empty(),
+        "the dynamic program does not support processor downtime"
+    );
+    if !instance.capacity.is_empty() {
+        diagnostics::report(
+            Category::Model,
+            "The dynamic program ignores the capacity profile, so the schedule may exceed it",
+        );
+    }
+    if instance.has_affinity() {
+        diagnostics::report(
+            Category::Model,
+            "The dynamic program ignores processor affinity, so jobs may run on processors they are not allowed on",
+        );
+    }
+    if !instance.soft_constraints.is_empty() {
+        diagnostics::report(
+            Category::Model,
+            "The dynamic program ignores soft constraints and only minimizes the makespan",
+        );
+    }
+    trace!("Solving instance\n{instance}");
+    let horizon = instance.horizon();
+    let lower_bound = instance.min_critical_path_length();
+    assert!(
+        horizon >= lower_bound,
+        "horizon {horizon} is below the critical path length {lower_bound}, no schedule can be found"
+    );
+    let chains = instance.chains();
     let omega = chains.len();
-    let initial_state = State::empty(omega);
-    let jobs = search(&instance, &chains, &initial_state, &mut HashSet::new()).expect("no solution found");
-    debug!("jobs are {jobs:#?}");
-    Schedule {
+    let mut tracer = trace
+        .path
+        .as_deref()
+        .map(|path| Tracer::create(path, trace.sample));
+    let mut resume = checkpoint.resume.as_deref();
+    let mut retries = 0;
+    let jobs = loop {
+        let instance_hash = instance_hash(&instance);
+        let (stack, known) = match resume.take() {
+            Some(path) => {
+                let Checkpoint {
+                    version,
+                    instance_hash: hash,
+                    stack,
+                    known,
+                } = files::read_json(path);
+                assert_eq!(
+                    version, CHECKPOINT_VERSION,
+                    "checkpoint {path} has unsupported version {version}"
+                );
+                assert_eq!(
+                    hash, instance_hash,
+                    "checkpoint {path} belongs to a different instance"
+                );
+                info!(
+                    "Resuming search from {path} at depth {} with {} known states",
+                    stack.len(),
+                    known.len()
+                );
+                (stack, known.into_iter().collect())
+            }
+            None => (
+                vec![Frame {
+                    state: State::empty(omega),
+                    cursor: Cursor::default(),
+                    placement: None,
+                }],
+                HashSet::new(),
+            ),
+        };
+        let jobs = search(
+            &instance,
+            &chains,
+            stack,
+            known,
+            checkpoint,
+            &instance_hash,
+            tracer.as_mut(),
+        );
+        if let Some(jobs) = jobs {
+            break jobs;
+        }
+        let horizon = instance.horizon();
+        let greedy = list::greedy(&instance);
+        let makespan = greedy.makespan();
+        assert!(
+            makespan > horizon && greedy.violations(&instance).is_empty(),
+            "no solution found with completion times up to the horizon {horizon}, and the greedy schedule with makespan {makespan} does not show that the horizon is too small"
+        );
+        warn!(
+            "No solution found with completion times up to the horizon {horizon}, but the greedy schedule takes {makespan}, so the horizon truncated the search"
+        );
+        instance = instance.with_horizon(makespan);
+        retries += 1;
+    };
+    if retries > 0 {
+        info!("Retried the dynamic program {retries} times with a larger horizon");
+    }
+    if let Some(tracer) = tracer {
+        tracer.finish();
+    }
+    let schedule = Schedule {
         processor_count: instance.processor_count,
         jobs,
+    };
+    trace!("Found schedule\n{schedule}");
+    schedule
+}
+
+/// Decides whether the instance has a schedule in which all jobs complete
+/// by `horizon`, by searching all states of the dynamic program up to it
+/// without retrying with a larger horizon. Like `schedule`, the search
+/// ignores the capacity profile, processor affinity, and soft constraints,
+/// so an instance without such a schedule has no schedule by `horizon` with
+/// them either.
+pub fn feasible_within(instance: &Instance, horizon: i32) -> bool {
+    assert!(
+        instance.downtime.is_empty(),
+        "the dynamic program does not support processor downtime"
+    );
+    if horizon < instance.min_critical_path_length() {
+        return false;
     }
+    let instance = instance
+        .with_processor_count(instance.processor_count)
+        .with_horizon(horizon);
+    let chains = instance.chains();
+    let root = Frame {
+        state: State::empty(chains.len()),
+        cursor: Cursor::default(),
+        placement: None,
+    };
+    search(
+        &instance,
+        &chains,
+        vec![root],
+        HashSet::new(),
+        &CheckpointOptions::default(),
+        &instance_hash(&instance),
+        None,
+    )
+    .is_some()
 }
 
-/// Recursive function that searches for the next jobs to be scheduled
+/// Depth-first search for the next jobs to be scheduled, starting from the
+/// path in `stack`. Every candidate state is passed to the `tracer`.
 fn search(
     instance: &Instance,
-    chains: &Vec<Vec<usize>>,
-    state: &State,
-    known: &mut HashSet<State>,
+    chains: &[Vec<usize>],
+    mut stack: Vec<Frame>,
+    mut known: HashSet<State>,
+    checkpoint: &CheckpointOptions,
+    instance_hash: &str,
+    mut tracer: Option<&mut Tracer>,
 ) -> Option<Vec<ScheduledJob>> {
-    // Terminate when all jobs are scheduled.
-    if state.ideal.iter().sum::<usize>() == instance.jobs.len() {
-        return Some(vec![]);
-    }
+    let mut last_checkpoint = Instant::now();
+    loop {
+        let depth = stack.len();
+        let Some(frame) = stack.last_mut() else {
+            break;
+        };
+        // Terminate when all jobs are scheduled.
+        if frame.state.ideal.iter().sum::<usize>() == instance.jobs.len() {
+            return Some(
+                stack
+                    .iter()
+                    .filter_map(|frame| frame.placement)
+                    .map(
+                        |Placement {
+                             job,
+                             allotment,
+                             compl,
+                         }| {
+                            let job = instance.shared_job(job);
+                            let start_time = compl - job.processing_time(allotment);
+                            ScheduledJob {
+                                job,
+                                allotment,
+                                start_time,
+                                processors: vec![],
+                            }
+                        },
+                    )
+                    .collect(),
+            );
+        }
 
-    // Search for the new job to be scheduled for every chain.
-    for (chain_index, chain) in chains.iter().enumerate() {
-        let ideal = state.ideal[chain_index];
-        if ideal == chain.len() {
-            continue;
-        }
-        let new_job_index = chain[ideal];
-        let new_job = &instance.jobs[new_job_index];
-        // Check all possible allotments for the new job and determine if it can be scheduled.
-        for (&processing_time, allotment) in new_job.processing_times.iter().zip(1..) {
-            for compl in 0..instance.max_time {
-                let new_start_time = compl - processing_time;
-                if new_start_time < 0 {
-                    continue;
-                }
+        match next_state(
+            instance,
+            chains,
+            &frame.state,
+            &mut frame.cursor,
+            &mut known,
+            depth,
+            tracer.as_deref_mut(),
+        ) {
+            Some((state, placement)) => stack.push(Frame {
+                state,
+                cursor: Cursor::default(),
+                placement: Some(placement),
+            }),
+            None => {
+                stack.pop();
+            }
+        }
 
-                let mut can_insert = true;
-                for (chain_index, &ideal) in
-                    state.ideal.iter().filter(|&&ideal| ideal != 0).enumerate()
-                {
-                    let completion_time = state.completion_times[chain_index];
-                    let front_job_index = chains[chain_index][ideal - 1];
-                    let front_job = &instance.jobs[front_job_index];
-
-                    // Condition 2
-                    if front_job.less_than(&instance.constraints, new_job)
-                        && new_start_time < completion_time
-                    {
-                        can_insert = false;
-                        break;
-                    }
-                    // Condition 3
-                    let processing_time = front_job.processing_time(state.allotment[chain_index]);
-                    if new_start_time < completion_time - processing_time {
-                        can_insert = false;
-                        break;
-                    }
-                }
-                if !can_insert {
-                    continue;
-                }
+        if let Some(path) = &checkpoint.path {
+            if last_checkpoint.elapsed() >= checkpoint.interval || interrupt::requested() {
+                save_checkpoint(path, instance_hash, &stack, &known);
+                last_checkpoint = Instant::now();
+            }
+        }
+        // the search has no schedule before it completes, only its state
+        if interrupt::requested() {
+            interrupt::finish(&checkpoint.path.iter().cloned().collect::<Vec<_>>());
+        }
+    }
+    None
+}
 
-                // Check if processor count exceeded
-                let mut pairs = state
-                    .ideal
-                    .iter()
-                    .filter(|&&ideal| ideal != 0)
-                    .enumerate()
-                    .flat_map(|(chain_index, &ideal)| {
-                        let front_job_index = chains[chain_index][ideal - 1];
-                        let front_job = if new_job_index == front_job_index {
-                            new_job
+/// Finds the next state after `cursor` that can be reached from `state` by
+/// scheduling the front job of a chain, and advances the cursor past it. The
+/// candidates on the way are passed to the `tracer` at the given `depth`.
+fn next_state(
+    instance: &Instance,
+    chains: &[Vec<usize>],
+    state: &State,
+    cursor: &mut Cursor,
+    known: &mut HashSet<State>,
+    depth: usize,
+    mut tracer: Option<&mut Tracer>,
+) -> Option<(State, Placement)> {
+    // Search for the new job to be scheduled for every chain.
+    while cursor.chain < chains.len() {
+        let chain = &chains[cursor.chain];
+        let ideal = state.ideal[cursor.chain];
+        if ideal < chain.len() {
+            let new_job_index = chain[ideal];
+            let new_job = &instance.jobs[new_job_index];
+            // Check all possible allotments for the new job and determine if it can be scheduled.
+            while cursor.allotment < new_job.processing_times.len() {
+                let processing_time = new_job.processing_times[cursor.allotment];
+                let allotment = cursor.allotment + 1;
+                while cursor.compl <= instance.horizon() {
+                    let compl = cursor.compl;
+                    cursor.compl += 1;
+                    let outcome = can_insert(
+                        instance,
+                        chains,
+                        state,
+                        new_job_index,
+                        processing_time,
+                        allotment,
+                        compl,
+                    )
+                    .and_then(|()| {
+                        // It is feasable to schedule this job in this chain. So we progress to the next state
+                        // and search for the next job to schedule.
+                        let new_state = state.add_job(cursor.chain, allotment, compl);
+                        if known.insert(new_state.clone()) {
+                            Ok(new_state)
                         } else {
-                            &instance.jobs[front_job_index]
+                            Err(Pruned::Known)
+                        }
+                    });
+                    if let Some(tracer) = tracer.as_deref_mut() {
+                        tracer.record(|| TraceEvent {
+                            depth,
+                            ideal: state.ideal.clone(),
+                            job: new_job_index,
+                            allotment,
+                            completion_time: compl,
+                            pruned: outcome.as_ref().err().copied(),
+                        });
+                    }
+                    if let Ok(new_state) = outcome {
+                        let placement = Placement {
+                            job: new_job_index,
+                            allotment,
+                            compl,
                         };
-                        let completion_time = state.completion_times[chain_index];
-                        let start_time = completion_time
-                            - front_job.processing_time(state.allotment[chain_index]);
-                        let a = allotment as i32;
-                        vec![(start_time, a), (completion_time, -a)]
-                    })
-                    .collect::<Vec<_>>();
-                pairs.sort_by_key(|p| p.0);
-                let limit = instance.processor_count as i32;
-                let mut utilisation = 0;
-                for (_, diff) in pairs {
-                    utilisation += diff;
-                    if utilisation > limit {
-                        can_insert = false;
-                        break;
+                        return Some((new_state, placement));
                     }
                 }
-                if !can_insert {
-                    continue;
-                }
-
-                // It is feasable to schedule this job in this chain. So we progress to the next state
-                // and search for the next job to schedule.
-                let new_state = state.add_job(chain_index, allotment, compl);
-                let is_new = known.insert(new_state.clone());
-                if !is_new {
-                    continue;
-                }
-
-                let tail = search(instance, chains, &new_state, known);
-                if let Some(tail) = tail {
-                    let mut path = Vec::with_capacity(tail.len() + 1);
-                    let job = instance.jobs[new_job_index].clone();
-                    let start_time = compl - job.processing_time(allotment);
-                    path.push(ScheduledJob {
-                        job,
-                        allotment,
-                        start_time,
-                    });
-                    path.extend(tail);
-                    return Some(path);
-                }
+                cursor.allotment += 1;
+                cursor.compl = 0;
             }
         }
+        cursor.chain += 1;
+        cursor.allotment = 0;
+        cursor.compl = 0;
     }
     None
 }
 
-/// Given a problem `instance`, which contains only a list of constraints,
-/// compute a list of chains of jobs that are comparable to each other.
-fn preprocess(instance: &Instance) -> Vec<Vec<usize>> {
-    let mut chains: Vec<Vec<usize>> = vec![];
-    for (job_index, job) in instance.jobs.iter().enumerate() {
-        if let Some(chain) = chains.iter_mut().find(|chain| {
-            // Check if the job is comparable to all jobs in the chain
-            chain
+/// Checks if the job with index `new_job_index` can be scheduled with
+/// `allotment` processors, completing at time `compl`, and tells why not
+/// otherwise. Pinned jobs can only be scheduled as they are pinned, and all
+/// other jobs must fit next to them.
+fn can_insert(
+    instance: &Instance,
+    chains: &[Vec<usize>],
+    state: &State,
+    new_job_index: usize,
+    processing_time: i32,
+    allotment: usize,
+    compl: i32,
+) -> Result<(), Pruned> {
+    let new_job = &instance.jobs[new_job_index];
+    let new_start_time = compl - processing_time;
+    if let Some(pin) = instance.pin(new_job_index) {
+        if allotment != pin.allotment || compl != pin.completion_time() {
+            return Err(Pruned::Pinned);
+        }
+    } else {
+        // jobs are added in the order of their start times, so no job that is
+        // still missing, including the new one, may have to start earlier to
+        // let the pinned jobs start on time
+        let deadlines = instance.pin_deadlines();
+        let misses_pin = chains
+            .iter()
+            .zip(&state.ideal)
+            .flat_map(|(chain, &ideal)| &chain[ideal..])
+            .any(|&j| deadlines[j].is_some_and(|deadline| deadline < new_start_time));
+        // the pinned jobs are known in advance, so the new job must fit next
+        // to all of them while it runs, unless it takes no time
+        let overlaps_pins = processing_time > 0
+            && instance
+                .pinned
                 .iter()
-                .all(|&i| instance.jobs[i].is_comparable(&instance.constraints, job))
-        }) {
-            chain.push(job_index);
+                .map(|pin| pin.start_time)
+                .filter(|&t| new_start_time < t && t < compl)
+                .chain([new_start_time])
+                .any(|t| {
+                    let pinned = instance
+                        .pinned
+                        .iter()
+                        .filter(|pin| pin.start_time <= t && t < pin.completion_time())
+                        .map(|pin| pin.allotment)
+                        .sum::<usize>();
+                    pinned + allotment > instance.processor_count
+                });
+        if misses_pin || overlaps_pins {
+            return Err(Pruned::Pinned);
+        }
+    }
+    if new_start_time < 0 {
+        return Err(Pruned::NegativeStart);
+    }
+
+    for (chain_index, &ideal) in state
+        .ideal
+        .iter()
+        .enumerate()
+        .filter(|&(_, &ideal)| ideal != 0)
+    {
+        let completion_time = state.completion_times[chain_index];
+        let front_job_index = chains[chain_index][ideal - 1];
+        let front_job = &instance.jobs[front_job_index];
+
+        // Condition 2, extended by the minimum lag between the two jobs
+        if instance.compare(front_job, new_job) == Some(true)
+            && new_start_time < completion_time + instance.lag(front_job_index, new_job_index).min
+        {
+            return Err(Pruned::Precedence);
+        }
+        // Condition 3
+        let processing_time = front_job.processing_time(state.allotment[chain_index]);
+        if new_start_time < completion_time - processing_time {
+            return Err(Pruned::StartOrder);
+        }
+    }
+
+    // Check if processor count exceeded. Every chain runs its jobs one after
+    // another, and condition 3 keeps the front jobs from starting after the
+    // new job, so only the front jobs that still run when the new job starts
+    // share the processors with it, each with its own allotment.
+    let busy = state
+        .ideal
+        .iter()
+        .enumerate()
+        .filter(|&(_, &ideal)| ideal != 0)
+        .filter_map(|(chain_index, &ideal)| {
+            let front_job = &instance.jobs[chains[chain_index][ideal - 1]];
+            let front_allotment = state.allotment[chain_index];
+            let completion_time = state.completion_times[chain_index];
+            let start_time = completion_time - front_job.processing_time(front_allotment);
+            // jobs without processing time occupy no processors
+            (start_time < completion_time && new_start_time < completion_time)
+                .then_some(front_allotment)
+        })
+        .sum::<usize>();
+    let new_allotment = if processing_time > 0 { allotment } else { 0 };
+    if busy + new_allotment > instance.processor_count {
+        return Err(Pruned::ProcessorCount);
+    }
+    Ok(())
+}
+
+/// Writes the search state to `path`, replacing an older checkpoint only once
+/// the new one is complete
+fn save_checkpoint(path: &str, instance_hash: &str, stack: &[Frame], known: &HashSet<State>) {
+    files::write_json(
+        path,
+        &Checkpoint {
+            version: CHECKPOINT_VERSION,
+            instance_hash: instance_hash.to_string(),
+            stack: stack.to_vec(),
+            known: known.iter().cloned().collect(),
+        },
+    );
+    info!(
+        "Saved checkpoint at depth {} with {} known states to {path}",
+        stack.len(),
+        known.len()
+    );
+}
+
+/// Hashes everything about the instance that influences the search
+fn instance_hash(instance: &Instance) -> String {
+    let mut description = format!("{} {}\n", instance.processor_count, instance.horizon());
+    for job in &instance.jobs {
+        let _ = writeln!(description, "{:?}", job.processing_times);
+    }
+    for Constraint(left, right, lag) in &instance.constraints {
+        if *lag == Lag::default() {
+            let _ = writeln!(description, "{left} {right}");
         } else {
-            chains.push(vec![job_index]);
+            let _ = writeln!(description, "{left} {right} {} {:?}", lag.min, lag.max);
         }
     }
-    for chain in &mut chains {
-        chain.sort_by(|&left, &right| {
-            match instance.jobs[left].compare(&instance.constraints, &instance.jobs[right]) {
-                Some(true) => Ordering::Less,
-                Some(false) => Ordering::Greater,
-                _ => panic!("chain contains two non-comparable jobs"),
+    for pin in &instance.pinned {
+        let _ = writeln!(
+            description,
+            "pin {} {} {}",
+            pin.job.index, pin.start_time, pin.allotment
+        );
+    }
+    hash_contents(description.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+    use crate::builder::InstanceBuilder;
+
+    /// Four jobs on three processors, two of which form a chain
+    fn instance() -> Instance {
+        InstanceBuilder::new()
+            .processors(3)
+            .job(1)
+            .times([6, 4, 3])
+            .job(2)
+            .times([4, 3, 3])
+            .job(3)
+            .times([5, 3, 2])
+            .job(4)
+            .times([2, 2, 2])
+            .constraint(1, 3)
+            .build()
+            .expect("valid instance")
+    }
+
+    /// The job, allotment, and start time of every scheduled job
+    fn placements(schedule: &Schedule) -> Vec<(usize, usize, i32)> {
+        schedule
+            .jobs
+            .iter()
+            .map(|job| (job.job.index, job.allotment, job.start_time))
+            .collect()
+    }
+
+    /// Takes the first `steps` steps of the search like `search` does and
+    /// writes a checkpoint of where it stopped to `path`
+    fn interrupt_after(instance: &Instance, steps: usize, path: &str) {
+        let chains = instance.chains();
+        let mut stack = vec![Frame {
+            state: State::empty(chains.len()),
+            cursor: Cursor::default(),
+            placement: None,
+        }];
+        let mut known = HashSet::new();
+        for depth in 1..=steps {
+            let frame = stack.last_mut().expect("the search ended early");
+            match next_state(
+                instance,
+                &chains,
+                &frame.state,
+                &mut frame.cursor,
+                &mut known,
+                depth,
+                None,
+            ) {
+                Some((state, placement)) => stack.push(Frame {
+                    state,
+                    cursor: Cursor::default(),
+                    placement: Some(placement),
+                }),
+                None => {
+                    stack.pop();
+                }
             }
-        });
+        }
+        save_checkpoint(path, &instance_hash(instance), &stack, &known);
+    }
+
+    #[test]
+    fn horizon_policies_resolve() {
+        let instance = instance();
+        // job 1 and job 3 take 3 and 2 with all processors
+        assert_eq!(instance.min_critical_path_length(), 5);
+        let greedy = list::greedy(&instance);
+        assert!(greedy.violations(&instance).is_empty());
+        assert_eq!(HorizonPolicy::Auto.resolve(&instance), greedy.makespan());
+        assert_eq!(HorizonPolicy::Fixed(42).resolve(&instance), 42);
+        assert_eq!(HorizonPolicy::LowerBoundMultiple(1.0).resolve(&instance), 5);
+        assert_eq!(HorizonPolicy::LowerBoundMultiple(1.5).resolve(&instance), 8);
+    }
+
+    #[test]
+    fn horizon_policies_parse() {
+        for (text, policy) in [
+            ("auto", HorizonPolicy::Auto),
+            ("120", HorizonPolicy::Fixed(120)),
+            ("1.5x", HorizonPolicy::LowerBoundMultiple(1.5)),
+        ] {
+            assert_eq!(text.parse(), Ok(policy));
+            assert_eq!(policy.to_string(), text);
+        }
+        for text in ["0.5x", "twicex", "1.5", "", "Auto"] {
+            assert!(text.parse::<HorizonPolicy>().is_err(), "{text} was parsed");
+        }
+    }
+
+    #[test]
+    fn resumed_search_finds_the_same_schedule() {
+        let uninterrupted = schedule(
+            instance(),
+            &CheckpointOptions::default(),
+            &TraceOptions::default(),
+        );
+        assert_eq!(uninterrupted.violations(&instance()), Vec::<String>::new());
+        for steps in 1..=3 {
+            let path = env::temp_dir()
+                .join(format!("dp-checkpoint-{steps}-{}.json", process::id()))
+                .to_str()
+                .expect("UTF-8 path")
+                .to_string();
+            interrupt_after(&instance(), steps, &path);
+            let resumed = schedule(
+                instance(),
+                &CheckpointOptions {
+                    resume: Some(path),
+                    ..CheckpointOptions::default()
+                },
+                &TraceOptions::default(),
+            );
+            assert_eq!(
+                placements(&resumed),
+                placements(&uninterrupted),
+                "resuming after {steps} steps"
+            );
+        }
+    }
+
+    #[test]
+    fn default_horizon_runs_jobs_one_after_another() {
+        // the slowest allotments take 6, 4, 5, and 2
+        assert_eq!(instance().horizon(), 17);
+        assert_eq!(instance().with_horizon(9).horizon(), 9);
+        // job 1 and job 3 take at least 3 and 2 one after another
+        assert_eq!(instance().min_critical_path_length(), 5);
+    }
+
+    #[test]
+    fn schedule_completes_within_horizon() {
+        let schedule = schedule(
+            instance().with_horizon(8),
+            &CheckpointOptions::default(),
+            &TraceOptions::default(),
+        );
+        assert_eq!(schedule.violations(&instance()), Vec::<String>::new());
+        assert!(schedule.makespan() <= 8);
+    }
+
+    #[test]
+    #[should_panic(expected = "horizon 4 is below the critical path length 5")]
+    fn horizon_below_critical_path_is_rejected() {
+        schedule(
+            instance().with_horizon(4),
+            &CheckpointOptions::default(),
+            &TraceOptions::default(),
+        );
+    }
+
+    #[test]
+    fn truncated_horizon_is_retried_with_greedy_makespan() {
+        // the horizon equals the critical path length, but the jobs need at
+        // least 17 units of work, which do not fit on three processors in 5
+        let greedy = list::greedy(&instance()).makespan();
+        assert!(greedy > 5);
+        let schedule = schedule(
+            instance().with_horizon(5),
+            &CheckpointOptions::default(),
+            &TraceOptions::default(),
+        );
+        assert_eq!(schedule.violations(&instance()), Vec::<String>::new());
+        assert!((6..=greedy).contains(&schedule.makespan()));
     }
-    chains
 }
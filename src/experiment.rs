@@ -0,0 +1,258 @@
+// Compares algorithms on random instances of several families. Every
+// algorithm solves the same instances, so the runs are paired by the seed
+// from which the generator produced the instance. The instances are solved
+// and verified in parallel.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use itertools::Itertools;
+use log::info;
+use rand::{rngs::StdRng, SeedableRng};
+use rayon::prelude::*;
+use serde::Serialize;
+
+use crate::{
+    algo::Instance,
+    diagnostics::{self, Category},
+    generate::{self, GenerateConfig},
+    interrupt,
+    pareto::Algorithm,
+    stats_util,
+};
+
+/// Number of bins of the histograms of the makespan ratios
+const HISTOGRAM_BINS: usize = 10;
+
+/// Number of times the progress of a run is logged
+const PROGRESS_STEPS: usize = 10;
+
+/// The makespan ratios of one algorithm on all instances of one family
+#[derive(Debug)]
+pub struct Cell {
+    /// Name of the family
+    pub family: String,
+    /// Algorithm that solved the instances
+    pub algorithm: Algorithm,
+    /// Seed of every instance along with the makespan divided by the lower
+    /// bound of the instance, in the order of the seeds
+    pub ratios: Vec<(u64, f64)>,
+}
+
+/// Outcome of testing whether two algorithms differ on one family
+#[derive(Debug, Serialize)]
+pub struct Comparison {
+    /// Name of the family
+    pub family: String,
+    /// First algorithm of the pair
+    pub algorithm_a: String,
+    /// Second algorithm of the pair
+    pub algorithm_b: String,
+    /// Median makespan ratio of the first algorithm
+    pub median_a: f64,
+    /// Median makespan ratio of the second algorithm
+    pub median_b: f64,
+    /// Number of instances on which the ratios differ
+    pub pairs: usize,
+    /// Sum of the ranks of the instances on which the first algorithm has
+    /// the larger ratio
+    pub statistic: f64,
+    /// Two-sided p-value of the Wilcoxon signed-rank test
+    pub p_value: f64,
+    /// Whether the p-value is below the significance level
+    pub significant: bool,
+}
+
+/// Solves `seeds` instances of every family, generated from consecutive seeds
+/// starting at `first_seed`, with every algorithm. The instances of a family
+/// are solved in parallel on the global thread pool. Returns one cell per
+/// family and algorithm, in that order. On Ctrl-C, no further instances are
+/// solved, so the cells only hold the instances solved so far and the
+/// remaining cells are left out.
+pub fn run(
+    families: &[(String, GenerateConfig)],
+    algorithms: &[Algorithm],
+    first_seed: u64,
+    seeds: usize,
+) -> Vec<Cell> {
+    let total = families.len() * algorithms.len() * seeds;
+    let solved = AtomicUsize::new(0);
+    let mut cells = vec![];
+    for (family, config) in families {
+        let instance = |seed| generate::instance(config, &mut StdRng::seed_from_u64(seed));
+        let bounds = (0..seeds)
+            .into_par_iter()
+            .map(|i| first_seed.wrapping_add(i as u64))
+            .map(|seed| (seed, lower_bound(&instance(seed))))
+            .collect::<Vec<_>>();
+        for &algorithm in algorithms {
+            // collecting keeps the order of the seeds
+            let ratios = bounds
+                .par_iter()
+                .filter(|_| !interrupt::requested())
+                .map(|&(seed, bound)| {
+                    let schedule = algorithm.solve(instance(seed));
+                    let violations = schedule.violations(&instance(seed));
+                    if !violations.is_empty() {
+                        diagnostics::report(
+                            Category::Verification,
+                            format!(
+                                "{algorithm} on {family} from seed {seed} violates the instance: {}",
+                                violations.join(", ")
+                            ),
+                        );
+                    }
+                    log_progress(solved.fetch_add(1, Ordering::Relaxed) + 1, total);
+                    (seed, f64::from(schedule.makespan()) / f64::from(bound))
+                })
+                .collect::<Vec<_>>();
+            info!(
+                "Solved {} instances of {family} with {algorithm}",
+                ratios.len()
+            );
+            cells.push(Cell {
+                family: family.clone(),
+                algorithm,
+                ratios,
+            });
+            if interrupt::requested() {
+                return cells;
+            }
+        }
+    }
+    cells
+}
+
+/// Logs how many of the `total` instances are solved, in steps of a tenth
+fn log_progress(solved: usize, total: usize) {
+    let step = total.div_ceil(PROGRESS_STEPS).max(1);
+    if solved % step == 0 || solved == total {
+        info!("Solved {solved} of {total} instances");
+    }
+}
+
+/// Computes a lower bound on the makespan of every schedule of `instance`,
+/// which is the longest path if every job runs with its fastest allotment or
+/// the smallest total work spread evenly over all processors, whichever is
+/// larger
+fn lower_bound(instance: &Instance) -> i32 {
+    let work = instance
+        .jobs
+        .iter()
+        .map(|job| {
+            job.processing_times
+                .iter()
+                .zip(1..)
+                .map(|(&p, l)| l * p)
+                .min()
+                .unwrap_or(0)
+        })
+        .sum::<i32>();
+    let spread = (f64::from(work) / instance.processor_count as f64).ceil() as i32;
+    instance.min_critical_path_length().max(spread).max(1)
+}
+
+/// Logs the distribution of the makespan ratios of every cell as quantiles
+/// and as a histogram
+pub fn log_distributions(cells: &[Cell]) {
+    for cell in cells {
+        let ratios = sorted_ratios(cell);
+        if ratios.is_empty() {
+            continue;
+        }
+        info!(
+            "{} on {}: makespan ratio is {:.3} at minimum, {:.3} at the first quartile, {:.3} at median, {:.3} at the third quartile, and {:.3} at maximum",
+            cell.algorithm,
+            cell.family,
+            ratios[0],
+            stats_util::quantile(&ratios, 0.25),
+            stats_util::quantile(&ratios, 0.5),
+            stats_util::quantile(&ratios, 0.75),
+            ratios[ratios.len() - 1]
+        );
+        for (from, count) in stats_util::histogram(&ratios, HISTOGRAM_BINS) {
+            info!("  {from:>8.3} | {}", "#".repeat(count));
+        }
+    }
+}
+
+/// Tests every pair of algorithms on every family for a difference in their
+/// makespan ratios, pairing the ratios by seed, and marks the differences
+/// with p-values below `alpha` as significant
+pub fn compare(cells: &[Cell], alpha: f64) -> Vec<Comparison> {
+    cells
+        .iter()
+        .chunk_by(|cell| &cell.family)
+        .into_iter()
+        .flat_map(|(_, family)| {
+            family
+                .collect::<Vec<_>>()
+                .into_iter()
+                .tuple_combinations()
+                .map(|(a, b)| {
+                    let test = stats_util::wilcoxon_signed_rank(&ratios(a), &ratios(b));
+                    Comparison {
+                        family: a.family.clone(),
+                        algorithm_a: a.algorithm.to_string(),
+                        algorithm_b: b.algorithm.to_string(),
+                        median_a: median(a),
+                        median_b: median(b),
+                        pairs: test.pairs,
+                        statistic: test.statistic,
+                        p_value: test.p_value,
+                        significant: test.p_value < alpha,
+                    }
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+impl Comparison {
+    /// Describes the outcome of the test in a sentence
+    pub fn verdict(&self, alpha: f64) -> String {
+        let Comparison {
+            family,
+            algorithm_a,
+            algorithm_b,
+            median_a,
+            median_b,
+            p_value,
+            ..
+        } = self;
+        if !self.significant {
+            return format!(
+                "{family}: no significant difference between {algorithm_a} and {algorithm_b} (p = {p_value:.4}, alpha = {alpha})"
+            );
+        }
+        let (better, worse) = if median_a <= median_b {
+            (algorithm_a, algorithm_b)
+        } else {
+            (algorithm_b, algorithm_a)
+        };
+        format!(
+            "{family}: {better} produces shorter schedules than {worse}, the median ratio is {median_a:.3} for {algorithm_a} and {median_b:.3} for {algorithm_b} (p = {p_value:.4}, alpha = {alpha})"
+        )
+    }
+}
+
+/// Computes the median makespan ratio of a cell
+fn median(cell: &Cell) -> f64 {
+    let ratios = sorted_ratios(cell);
+    if ratios.is_empty() {
+        f64::NAN
+    } else {
+        stats_util::quantile(&ratios, 0.5)
+    }
+}
+
+/// Returns the makespan ratios of a cell in the order of the seeds
+fn ratios(cell: &Cell) -> Vec<f64> {
+    cell.ratios.iter().map(|&(_, ratio)| ratio).collect()
+}
+
+/// Returns the makespan ratios of a cell in ascending order
+fn sorted_ratios(cell: &Cell) -> Vec<f64> {
+    let mut ratios = ratios(cell);
+    ratios.sort_by(f64::total_cmp);
+    ratios
+}
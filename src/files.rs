@@ -1,136 +1,1956 @@
 // CSV file handling implementations.
 
-use crate::algo::{Constraint, Instance, Job};
-use csv::{ReaderBuilder, Writer};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::{self, Write as _},
+    fs,
+    io::{self, BufRead, BufReader},
+};
+
+use crate::{
+    algo::{
+        format_processors, machine_name, CapacityWindow, Constraint, Downtime, Instance, Job, Lag,
+        ProfilePoint, RoundingDecision, Schedule, ScheduledJob, SoftConstraint,
+    },
+    diagnostics::{self, Category, Diagnostic},
+    experiment::{Cell, Comparison},
+    fit::Amdahl,
+    generate::GenerateConfig,
+    metrics::{Parallelism, Structure, Waiting},
+    output::{atomic_write, atomic_write_with},
+    preprocess::SplitMap,
+    provenance::Provenance,
+    replan::Progress,
+    schema::{self, Document, SCHEDULE_VERSION},
+};
+use clap::ValueEnum;
+use csv::{ReaderBuilder, StringRecord, Trim, Writer};
+use itertools::Itertools;
+use log::info;
+use schemars::JsonSchema;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+mod clean;
+
+/// How to handle jobs that share the same id in the job file
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Dedupe {
+    /// Abort with an error that lists all duplicates
+    #[default]
+    Fail,
+    /// Keep the last job with a given id and warn about the others
+    Last,
+}
+
+/// How to fill in processing times for allotments that were not measured,
+/// i.e. blank trailing cells or processors beyond the last column
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ExtendTimes {
+    /// Abort if a processing time is missing
+    #[default]
+    Error,
+    /// Repeat the last measured processing time
+    RepeatLast,
+    /// Fit Amdahl's law to the measured processing times and extrapolate
+    AmdahlFit,
+}
+
+/// How schedules and their utilization are exported
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    /// The schedule as one JSON document and the utilization as CSV, unless
+    /// the file name ends in ".ndjson"
+    #[default]
+    Standard,
+    /// One JSON object per line, which is written and read line by line
+    /// without holding the whole file in memory
+    Ndjson,
+}
+impl ExportFormat {
+    /// The format in which a file is written to `path`, which is always
+    /// NDJSON if its name ends in ".ndjson"
+    fn resolve(self, path: &str) -> ExportFormat {
+        if path.ends_with(".ndjson") {
+            ExportFormat::Ndjson
+        } else {
+            self
+        }
+    }
+}
+
+/// Options that control how instance files are read
+#[derive(Debug, Default)]
+pub struct ReadOptions {
+    /// How to handle jobs that share the same id
+    pub dedupe: Dedupe,
+    /// How to fill in missing processing times
+    pub extend_times: ExtendTimes,
+    /// Number of processors, defaults to the number of processing time columns
+    pub machines: Option<usize>,
+    /// CSV file of jobs that are pinned to fixed start times and processors
+    pub pinned_file: Option<String>,
+    /// Reject job and constraint files with formatting issues instead of
+    /// fixing them, see `read_contents`
+    pub strict_parse: bool,
+    /// Drop the last record of the job and constraint file if it was cut off
+    /// mid-line, see `check_last_record`
+    pub ignore_trailing_garbage: bool,
+}
+
+/// A job, constraint, or pinned-jobs file that cannot be read, along with the
+/// row of the record that is at fault
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReadError {
+    /// Path of the file
+    pub path: String,
+    /// Number of the record, counting from 1 after the header, or `None` if
+    /// the file as a whole is at fault
+    pub row: Option<usize>,
+    /// What is wrong with the record or the file
+    pub reason: String,
+}
+impl ReadError {
+    fn in_row(path: &str, row: usize, reason: impl Into<String>) -> Self {
+        Self {
+            path: path.to_string(),
+            row: Some(row),
+            reason: reason.into(),
+        }
+    }
+    fn in_file(path: &str, reason: impl Into<String>) -> Self {
+        Self {
+            path: path.to_string(),
+            row: None,
+            reason: reason.into(),
+        }
+    }
+}
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.row {
+            Some(row) => write!(f, "row {row} of {}: {}", self.path, self.reason),
+            None => write!(f, "{}: {}", self.path, self.reason),
+        }
+    }
+}
+
+/// A row of the job file: id, row number, processing times along with the
+/// number of synthesized processing times, the affinity, and the note
+type JobRow = (
+    i32,
+    usize,
+    (Vec<i32>, usize),
+    Option<Vec<usize>>,
+    Option<String>,
+);
+
+/// A row of the constraint file: row number, left id, right id, the lag, and
+/// the penalty of a soft constraint or `None` for a hard one
+type ConstraintRow = (usize, i32, i32, Lag, Option<f64>);
 
 /// Reads a job and constraint CSV file and returns an `Instance`.
-pub fn read(job_file: &str, constraint_file: &str) -> Instance {
+pub fn read(
+    job_file: &str,
+    constraint_file: &str,
+    options: &ReadOptions,
+) -> Result<Instance, ReadError> {
+    Ok(read_with_positions(job_file, constraint_file, options)?.0)
+}
+
+/// Reads an instance like `read`, along with the position of every job by
+/// its id in the job file, e.g. to read other files that refer to the ids
+pub fn read_with_positions(
+    job_file: &str,
+    constraint_file: &str,
+    options: &ReadOptions,
+) -> Result<(Instance, HashMap<i32, usize>), ReadError> {
+    let (processor_count, jobs, dropped_job) = read_job_rows(job_file, options)?;
+    let constraint_ids = read_constraint_rows(constraint_file, options)?;
+
+    let jobs = remove_duplicates(job_file, jobs, &constraint_ids, options.dedupe)?;
+    let positions = jobs
+        .iter()
+        .enumerate()
+        .map(|(index, (id, ..))| (*id, index))
+        .collect::<HashMap<_, _>>();
+    let constraint_ids = if dropped_job {
+        drop_unknown_ids(constraint_ids, &positions)
+    } else {
+        constraint_ids
+    };
+    let position = |row: usize, id: i32| {
+        positions.get(&id).copied().ok_or_else(|| {
+            ReadError::in_row(
+                constraint_file,
+                row,
+                format!("the constraint refers to job {id}, which is not in the job file"),
+            )
+        })
+    };
+    let (hard, soft): (Vec<_>, Vec<_>) = constraint_ids
+        .into_iter()
+        .partition(|(.., penalty)| penalty.is_none());
+    let constraints = hard
+        .into_iter()
+        .map(|(row, left, right, lag, _)| {
+            Ok(Constraint(position(row, left)?, position(row, right)?, lag))
+        })
+        .collect::<Result<Vec<_>, ReadError>>()?;
+    let soft_constraints = soft
+        .into_iter()
+        .map(|(row, left, right, lag, penalty)| {
+            Ok(SoftConstraint {
+                left: position(row, left)?,
+                right: position(row, right)?,
+                min_lag: lag.min,
+                penalty: penalty.unwrap_or_default(),
+            })
+        })
+        .collect::<Result<Vec<_>, ReadError>>()?;
+    if !soft_constraints.is_empty() {
+        info!("Read {} soft constraints", soft_constraints.len());
+    }
+
+    let jobs = jobs
+        .into_iter()
+        .enumerate()
+        .map(
+            |(index, (_, _, (processing_times, synthesized), affinity, note))| Job {
+                index,
+                processing_times,
+                synthesized,
+                affinity,
+                note,
+            },
+        )
+        .collect::<Vec<_>>();
+    let synthesized = jobs.iter().map(|job| job.synthesized).sum::<usize>();
+    if synthesized > 0 {
+        info!(
+            "Synthesized {synthesized} processing times for {} jobs using {:?}",
+            jobs.iter().filter(|job| job.synthesized > 0).count(),
+            options.extend_times
+        );
+    }
+    let non_monotone = jobs
+        .iter()
+        .filter(|job| {
+            job.processing_times
+                .iter()
+                .tuple_windows()
+                .any(|(fewer, more)| more > fewer)
+        })
+        .map(|job| job.index)
+        .collect::<Vec<_>>();
+    if !non_monotone.is_empty() {
+        diagnostics::report(
+            Category::Input,
+            format!(
+                "Jobs {} take longer with more processors for some allotments",
+                non_monotone.iter().join(", ")
+            ),
+        );
+    }
+    let instantaneous = jobs
+        .iter()
+        .filter(|job| job.is_instantaneous())
+        .map(|job| job.index)
+        .collect::<Vec<_>>();
+    if !instantaneous.is_empty() {
+        diagnostics::report(
+            Category::Input,
+            format!(
+                "Jobs {} take no time and are scheduled as instantaneous",
+                instantaneous.iter().join(", ")
+            ),
+        );
+    }
+
+    let pinned = options
+        .pinned_file
+        .as_deref()
+        .map(|path| read_pinned(path, &positions, &jobs))
+        .transpose()?
+        .unwrap_or_default();
+
+    let instance = Instance::new(processor_count, jobs, constraints)
+        .with_soft_constraints(soft_constraints)
+        .with_pinned(pinned);
+    Ok((instance, positions))
+}
+
+/// A row of the pinned-jobs file
+#[derive(Debug, Serialize, Deserialize)]
+struct PinRow {
+    id: i32,
+    start: i32,
+    allotment: usize,
+    first_processor: usize,
+}
+
+/// Reads pinned jobs from a CSV file with the columns `id`, `start`,
+/// `allotment`, and `first_processor`, where each line pins the job with the
+/// given id to start at `start` on `allotment` consecutive processors from
+/// `first_processor` on. The ids refer to the job file, whose jobs are at the
+/// given `positions`.
+fn read_pinned(
+    pinned_file: &str,
+    positions: &HashMap<i32, usize>,
+    jobs: &[Job],
+) -> Result<Vec<ScheduledJob>, ReadError> {
+    let mut rdr = ReaderBuilder::new()
+        .trim(Trim::All)
+        .from_path(pinned_file)
+        .map_err(|e| ReadError::in_file(pinned_file, format!("could not read the file: {e}")))?;
+    let mut pinned: Vec<ScheduledJob> = vec![];
+    for (index, pin) in rdr.deserialize::<PinRow>().enumerate() {
+        let row = index + 1;
+        let pin = pin.map_err(|e| {
+            ReadError::in_row(pinned_file, row, format!("cannot parse pinned job: {e}"))
+        })?;
+        let position = *positions.get(&pin.id).ok_or_else(|| {
+            ReadError::in_row(
+                pinned_file,
+                row,
+                format!("pinned job {} is not in the job file", pin.id),
+            )
+        })?;
+        if pinned.iter().any(|other| other.job.index == position) {
+            return Err(ReadError::in_row(
+                pinned_file,
+                row,
+                format!("job {} is pinned again", pin.id),
+            ));
+        }
+        pinned.push(ScheduledJob::new(
+            jobs[position].clone(),
+            pin.allotment,
+            pin.start,
+            (pin.first_processor..pin.first_processor + pin.allotment).collect(),
+        ));
+    }
+    info!("Pinned {} jobs", pinned.len());
+    Ok(pinned)
+}
+
+/// The status of a job in the progress file
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Status {
+    Done,
+    Running,
+    Pending,
+}
+
+/// A row of the progress file
+#[derive(Debug, Deserialize)]
+struct ProgressRow {
+    id: i32,
+    status: Status,
+    #[serde(default)]
+    remaining_fraction: Option<f64>,
+    #[serde(default)]
+    allotment: Option<usize>,
+    #[serde(default)]
+    first_processor: Option<usize>,
+}
+
+/// Reads the progress of the execution of a schedule from a CSV file with the
+/// columns `id`, `status`, `remaining_fraction`, `allotment`, and
+/// `first_processor`, where the status is `done`, `running`, or `pending`.
+/// Running jobs need the last three columns, which are blank for the others.
+/// The ids refer to the job file, whose jobs are at the given `positions`,
+/// and the progress is returned by position.
+pub fn read_progress(
+    progress_file: &str,
+    positions: &HashMap<i32, usize>,
+    jobs: &[Job],
+) -> HashMap<usize, Progress> {
+    let mut rdr = ReaderBuilder::new()
+        .trim(Trim::All)
+        .from_path(progress_file)
+        .expect("could not read progress CSV");
+    let mut progress = HashMap::new();
+    for (index, record) in rdr.deserialize::<ProgressRow>().enumerate() {
+        let row = index + 1;
+        let record = record.unwrap_or_else(|e| panic!("cannot parse progress in row {row}: {e}"));
+        let position = *positions.get(&record.id).unwrap_or_else(|| {
+            panic!(
+                "job {} in row {row} of the progress is not in the job file",
+                record.id
+            )
+        });
+        let status = match record.status {
+            Status::Done => Progress::Done,
+            Status::Pending => Progress::Pending,
+            Status::Running => {
+                let (Some(remaining_fraction), Some(allotment), Some(first_processor)) = (
+                    record.remaining_fraction,
+                    record.allotment,
+                    record.first_processor,
+                ) else {
+                    panic!("running job {} in row {row} needs a remaining_fraction, an allotment, and a first_processor", record.id);
+                };
+                assert!(
+                    (0.0..=1.0).contains(&remaining_fraction),
+                    "running job {} in row {row} has remaining fraction {remaining_fraction}, which is not between 0 and 1",
+                    record.id
+                );
+                assert!(
+                    (1..=jobs[position].processing_times.len()).contains(&allotment),
+                    "running job {} in row {row} has invalid allotment {allotment}",
+                    record.id
+                );
+                Progress::Running {
+                    remaining_fraction,
+                    allotment,
+                    first_processor,
+                }
+            }
+        };
+        assert!(
+            progress.insert(position, status).is_none(),
+            "job {} has progress again in row {row}",
+            record.id
+        );
+    }
+    info!("Read the progress of {} jobs", progress.len());
+    progress
+}
+
+/// Writes the given jobs as a pinned-jobs file that `read` accepts, using
+/// their indices as ids like `write`. Every job must run on consecutive
+/// processors.
+pub fn write_pinned(path: &str, jobs: &[&ScheduledJob]) {
+    let mut wtr = Writer::from_writer(vec![]);
+    for job in jobs {
+        let first_processor = job.processors.first().copied().unwrap_or_default();
+        assert!(
+            job.processors.len() == job.allotment
+                && job
+                    .processors
+                    .iter()
+                    .enumerate()
+                    .all(|(i, &p)| p == first_processor + i),
+            "job {} does not run on consecutive processors and cannot be pinned",
+            job.job.index
+        );
+        wtr.serialize(PinRow {
+            id: i32::try_from(job.job.index).expect("job index does not fit into an id"),
+            start: job.start_time,
+            allotment: job.allotment,
+            first_processor,
+        })
+        .expect("could not write pinned job");
+    }
+    let bytes = wtr.into_inner().expect("could not flush pinned jobs CSV");
+    atomic_write(path, &bytes)
+        .unwrap_or_else(|e| panic!("could not write pinned jobs CSV {path}: {e}"));
+}
+
+/// Reads a capacity profile from a CSV file with the columns `from`, `to`,
+/// and `capacity`. Windows may overlap, in which case the smaller capacity
+/// applies.
+pub fn read_capacity(capacity_file: &str) -> Vec<CapacityWindow> {
+    let mut rdr = ReaderBuilder::new()
+        .trim(Trim::All)
+        .from_path(capacity_file)
+        .expect("could not read capacity CSV");
+    rdr.deserialize::<CapacityWindow>()
+        .enumerate()
+        .map(|(index, window)| {
+            let row = index + 1;
+            let window =
+                window.unwrap_or_else(|e| panic!("cannot parse capacity in row {row}: {e}"));
+            assert!(
+                window.from < window.to,
+                "capacity window in row {row} ends at {} before it starts at {}",
+                window.to,
+                window.from
+            );
+            window
+        })
+        .collect()
+}
+
+/// Reads the names of the processors from a text file with one name per
+/// line, starting with processor 0. Surrounding whitespace and blank lines
+/// are ignored.
+pub fn read_machine_names(path: &str) -> Vec<String> {
+    fs::read_to_string(path)
+        .unwrap_or_else(|e| panic!("cannot read machine names {path}: {e}"))
+        .lines()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Reads the downtime of the processors from a CSV file with the columns
+/// `processor`, `from`, and `to`, where processors are numbered from 0 up to
+/// `processor_count`. Windows of the same processor may overlap.
+pub fn read_downtime(downtime_file: &str, processor_count: usize) -> Vec<Downtime> {
+    let mut rdr = ReaderBuilder::new()
+        .trim(Trim::All)
+        .from_path(downtime_file)
+        .expect("could not read downtime CSV");
+    rdr.deserialize::<Downtime>()
+        .enumerate()
+        .map(|(index, down)| {
+            let row = index + 1;
+            let down = down.unwrap_or_else(|e| panic!("cannot parse downtime in row {row}: {e}"));
+            assert!(
+                down.processor < processor_count,
+                "downtime in row {row} refers to processor {}, but there are only {processor_count} processors",
+                down.processor
+            );
+            assert!(
+                down.from < down.to,
+                "downtime in row {row} ends at {} before it starts at {}",
+                down.to,
+                down.from
+            );
+            down
+        })
+        .collect()
+}
+
+/// A row of the family file
+#[derive(Debug, Deserialize)]
+struct FamilyRow {
+    family: String,
+    n: usize,
+    m: usize,
+    min: i32,
+    max: i32,
+    omega: usize,
+    min_chain: usize,
+    max_chain: usize,
+    #[serde(default)]
+    concave: bool,
+}
+
+/// Reads instance families from a CSV file with the columns `family`, `n`,
+/// `m`, `min`, `max`, `omega`, `min_chain`, `max_chain`, and optionally
+/// `concave`, where each line names a family and the parameters of the
+/// instance generator that produces it
+pub fn read_families(family_file: &str) -> Vec<(String, GenerateConfig)> {
+    let mut rdr = ReaderBuilder::new()
+        .trim(Trim::All)
+        .from_path(family_file)
+        .expect("could not read family CSV");
+    rdr.deserialize::<FamilyRow>()
+        .enumerate()
+        .map(|(index, family)| {
+            let row = index + 1;
+            let family = family.unwrap_or_else(|e| panic!("cannot parse family in row {row}: {e}"));
+            let config = GenerateConfig {
+                n: family.n,
+                m: family.m,
+                min_p: family.min,
+                max_p: family.max,
+                omega: family.omega,
+                min_chain: family.min_chain,
+                max_chain: family.max_chain,
+                concave: family.concave,
+            };
+            if let Err(errors) = config.validate() {
+                panic!(
+                    "no instance exists for the family in row {row}: {}",
+                    errors.iter().join(", ")
+                );
+            }
+            (family.family, config)
+        })
+        .collect()
+}
+
+/// Reads the contents of a job or constraint file along with whether they end
+/// with a line break, i.e. whether the last line was written completely. Stray
+/// whitespace, quoted numbers, Windows line endings, and an empty trailing
+/// column are fixed and every fix is reported as a diagnostic with the rows it
+/// applied to, unless `strict` is set, in which case any of them is an error.
+fn read_contents(path: &str, strict: bool) -> Result<(Vec<u8>, bool), ReadError> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| ReadError::in_file(path, format!("could not read the file: {e}")))?;
+    let complete = contents.is_empty() || contents.ends_with('\n');
+    let (cleaned, fixes) = clean::clean(&contents);
+    if fixes.is_empty() {
+        return Ok((contents.into_bytes(), complete));
+    }
+    let recovery = clean::describe(path, &fixes);
+    if strict {
+        return Err(ReadError::in_file(
+            path,
+            format!(
+                "strict parsing rejects formatting issues, which tolerant parsing would fix:\n  {}",
+                recovery.join("\n  ")
+            ),
+        ));
+    }
+    for line in recovery {
+        diagnostics::report(
+            Category::Input,
+            format!("Recovered from formatting issues: {line}"),
+        );
+    }
+    Ok((cleaned.into_bytes(), complete))
+}
+
+/// Reads all records of a job or constraint file and checks the last one if
+/// the file does not end with a line break, because a file that is still
+/// being written, e.g. in watch mode or on a networked filesystem, may end in
+/// the middle of a record. The last record counts as cut off if it cannot be
+/// parsed, has fewer cells than the header, or has a cell in one of the
+/// `numeric` columns that is not an integer. Such a record is reported and
+/// dropped if `ignore_trailing_garbage` is set, and is an error otherwise. A
+/// last record that looks complete is kept with a warning, since its last cell
+/// may still be shorter than intended. Returns the records and whether the
+/// last one was dropped.
+fn read_records(
+    path: &str,
+    rdr: &mut csv::Reader<&[u8]>,
+    complete: bool,
+    header_count: usize,
+    numeric: &[usize],
+    ignore_trailing_garbage: bool,
+) -> Result<(Vec<StringRecord>, bool), ReadError> {
+    let mut records = rdr.records().collect::<Vec<_>>();
+    let problem = if complete {
+        None
+    } else {
+        records
+            .last()
+            .and_then(|record| check_last_record(record, header_count, numeric))
+    };
+    let dropped = match problem {
+        Some(problem) => {
+            let row = records.len();
+            let reason =
+                format!("the last record is cut off, the file may still be written: {problem}");
+            if !ignore_trailing_garbage {
+                return Err(ReadError::in_row(
+                    path,
+                    row,
+                    format!("{reason}, pass --ignore-trailing-garbage to drop it and read the other records"),
+                ));
+            }
+            diagnostics::report(
+                Category::Input,
+                format!("Dropped record {row} of {path}, {reason}"),
+            );
+            records.pop();
+            true
+        }
+        None => {
+            if !complete && !records.is_empty() {
+                diagnostics::report(
+                    Category::Input,
+                    format!(
+                        "The last record {} of {path} ends without a line break, so its last cell may be cut off",
+                        records.len()
+                    ),
+                );
+            }
+            false
+        }
+    };
+    let records = records
+        .into_iter()
+        .enumerate()
+        .map(|(index, record)| {
+            record.map_err(|e| {
+                ReadError::in_row(path, index + 1, format!("cannot parse the record: {e}"))
+            })
+        })
+        .collect::<Result<_, _>>()?;
+    Ok((records, dropped))
+}
+
+/// Describes why the last record of a file that ends without a line break is
+/// cut off, or returns `None` if it looks complete
+fn check_last_record(
+    record: &csv::Result<StringRecord>,
+    header_count: usize,
+    numeric: &[usize],
+) -> Option<String> {
+    let record = match record {
+        Ok(record) => record,
+        Err(e) => return Some(e.to_string()),
+    };
+    if record.len() < header_count {
+        return Some(format!("it has {} of {header_count} columns", record.len()));
+    }
+    numeric
+        .iter()
+        .filter_map(|&column| Some((column, record.get(column)?.trim())))
+        .find(|(_, cell)| !cell.is_empty() && cell.parse::<i32>().is_err())
+        .map(|(column, cell)| format!("{cell} in column {column} is not an integer"))
+}
+
+/// Removes the constraints that refer to ids without a job at the given
+/// `positions`, which happens if the job file was cut off and its last record
+/// dropped. Every removed constraint is reported.
+fn drop_unknown_ids(
+    constraints: Vec<ConstraintRow>,
+    positions: &HashMap<i32, usize>,
+) -> Vec<ConstraintRow> {
+    let (known, unknown): (Vec<_>, Vec<_>) = constraints
+        .into_iter()
+        .partition(|(_, l, r, ..)| positions.contains_key(l) && positions.contains_key(r));
+    for (row, left, right, ..) in unknown {
+        let id = if positions.contains_key(&left) {
+            right
+        } else {
+            left
+        };
+        diagnostics::report(
+            Category::Input,
+            format!("Dropped constraint in row {row}, which refers to unknown job {id}"),
+        );
+    }
+    known
+}
+
+/// Reads the job file into the processor count and the rows of the file. All
+/// columns after the id contain processing times, except for the optional
+/// `affinity` and `note` columns. Also returns whether the last record was
+/// dropped because it was cut off, see `read_records`.
+fn read_job_rows(
+    job_file: &str,
+    options: &ReadOptions,
+) -> Result<(usize, Vec<JobRow>, bool), ReadError> {
+    let (contents, complete) = read_contents(job_file, options.strict_parse)?;
     let mut rdr = ReaderBuilder::new()
-        .from_path(job_file)
-        .expect("could not read job CSV");
-    let headers = rdr.headers().expect("no headers in job file");
+        .flexible(true)
+        .from_reader(contents.as_slice());
+    let headers = rdr
+        .headers()
+        .map_err(|e| ReadError::in_file(job_file, format!("no headers: {e}")))?;
     let header_count = headers.len();
-    assert!((header_count > 1), "too few columns!");
-    assert!(
-        headers.iter().next().is_some_and(|name| name == "id"),
-        "first column is not id"
+    if header_count <= 1 {
+        return Err(ReadError::in_file(job_file, "too few columns"));
+    }
+    if headers.iter().next() != Some("id") {
+        return Err(ReadError::in_file(job_file, "first column is not id"));
+    }
+    let affinity_column = headers.iter().position(|name| name == "affinity");
+    let note_column = headers.iter().position(|name| name == "note");
+    let processor_count = options.machines.unwrap_or(
+        header_count
+            - 1
+            - usize::from(affinity_column.is_some())
+            - usize::from(note_column.is_some()),
     );
-    let processor_count = header_count - 1;
-    let jobs = rdr
-        .records()
+    if processor_count == 0 {
+        return Err(ReadError::in_file(
+            job_file,
+            "at least one processor is needed",
+        ));
+    }
+    let numeric = (0..header_count)
+        .filter(|&column| Some(column) != affinity_column && Some(column) != note_column)
+        .collect::<Vec<_>>();
+    let (records, dropped) = read_records(
+        job_file,
+        &mut rdr,
+        complete,
+        header_count,
+        &numeric,
+        options.ignore_trailing_garbage,
+    )?;
+    let jobs = records
+        .into_iter()
         .enumerate()
         .map(|(index, record)| {
             let row = index + 1;
-            let record = record.unwrap_or_else(|e| panic!("cannot parse record {row}: {e:#?}"));
+            let error = |reason: String| ReadError::in_row(job_file, row, reason);
             let id: i32 = record
                 .get(0)
-                .unwrap_or_else(|| panic!("missing id in row {row}"))
+                .ok_or_else(|| error("missing id".to_string()))?
                 .parse()
-                .unwrap_or_else(|e| panic!("bad id in row {row}: {e:#?}"));
-            (
-                id,
-                Job {
-                    index,
-                    processing_times: record
-                        .iter()
-                        .enumerate()
-                        .skip(1)
-                        .map(|(column, cell)| {
-                            cell.parse().unwrap_or_else(|e| {
-                                panic!("bad processing time in cell at {row}:{column}: {e:#?}")
+                .map_err(|e| error(format!("bad id: {e}")))?;
+            let measured = record
+                .iter()
+                .enumerate()
+                .skip(1)
+                .filter(|&(column, _)| {
+                    Some(column) != affinity_column && Some(column) != note_column
+                })
+                .map(|(column, cell)| {
+                    (!cell.trim().is_empty())
+                        .then(|| {
+                            cell.parse().map_err(|e| {
+                                error(format!("bad processing time in column {column}: {e}"))
                             })
                         })
-                        .collect(),
-                },
-            )
+                        .transpose()
+                })
+                .collect::<Result<Vec<Option<i32>>, _>>()?;
+            let processing_times =
+                extend_times(&measured, processor_count, options.extend_times).map_err(error)?;
+            let affinity = affinity_column
+                .and_then(|column| record.get(column))
+                .filter(|cell| !cell.trim().is_empty())
+                .map(parse_affinity)
+                .transpose()
+                .map_err(error)?;
+            let note = note_column
+                .and_then(|column| record.get(column))
+                .filter(|cell| !cell.is_empty())
+                .map(str::to_string);
+            Ok((id, row, processing_times, affinity, note))
         })
-        .collect::<Vec<_>>();
-
-    let n = jobs.len();
+        .collect::<Result<Vec<_>, ReadError>>()?;
+    Ok((processor_count, jobs, dropped))
+}
 
-    let mut rdr = ReaderBuilder::new()
-        .from_path(constraint_file)
-        .expect("cound not read constraints CSV");
-    assert_eq!(
-        rdr.headers()
-            .expect("no headers in constraint file")
-            .iter()
-            .collect::<Vec<&str>>(),
-        vec!["id0", "id1"]
-    );
-    let constraints = rdr
-        .records()
+/// Reads the constraint file into rows. Besides the ids, the file may contain
+/// the columns `min_lag` and `max_lag`, where blank cells mean no lag, and the
+/// column `kind`, which is either `hard` or `soft:<penalty>` for a constraint
+/// that schedules may break at the given penalty. Blank kinds are hard.
+fn read_constraint_rows(
+    constraint_file: &str,
+    options: &ReadOptions,
+) -> Result<Vec<ConstraintRow>, ReadError> {
+    let (contents, complete) = read_contents(constraint_file, options.strict_parse)?;
+    let mut rdr = ReaderBuilder::new().from_reader(contents.as_slice());
+    let headers = rdr
+        .headers()
+        .map_err(|e| ReadError::in_file(constraint_file, format!("no headers: {e}")))?
+        .iter()
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    if headers.len() < 2 || headers[..2] != ["id0", "id1"] {
+        return Err(ReadError::in_file(
+            constraint_file,
+            "the first two columns are not id0 and id1",
+        ));
+    }
+    if let Some(header) = headers[2..]
+        .iter()
+        .find(|&header| header != "min_lag" && header != "max_lag" && header != "kind")
+    {
+        return Err(ReadError::in_file(
+            constraint_file,
+            format!("unknown column {header}"),
+        ));
+    }
+    let column = |name: &str| headers.iter().position(|header| header == name);
+    let (min_column, max_column) = (column("min_lag"), column("max_lag"));
+    let kind_column = column("kind");
+    let numeric = [Some(0), Some(1), min_column, max_column]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+    let (records, _) = read_records(
+        constraint_file,
+        &mut rdr,
+        complete,
+        headers.len(),
+        &numeric,
+        options.ignore_trailing_garbage,
+    )?;
+    records
+        .into_iter()
         .enumerate()
         .map(|(index, record)| {
             let row = index + 1;
-            let record = record.unwrap_or_else(|e| panic!("cannot parse record {row}: {e:#?}"));
-            let left: i32 = record
-                .get(0)
-                .unwrap_or_else(|| panic!("missing left side of constraint in row {row}"))
-                .parse()
-                .unwrap_or_else(|e| {
-                    panic!("bad id in left side of constraint in row {row}: {e:#?}")
-                });
-            let right: i32 = record
-                .get(1)
-                .unwrap_or_else(|| panic!("missing right side of constraint in row {row}"))
-                .parse()
-                .unwrap_or_else(|e| {
-                    panic!("bad id in right side of constraint in row {row}: {e:#?}")
-                });
-
-            Constraint(
-                jobs.iter()
-                    .find(|(id, _)| *id == left)
-                    .expect("bad left side")
-                    .1
-                    .index,
-                jobs.iter()
-                    .find(|(id, _)| *id == right)
-                    .expect("bad right side")
-                    .1
-                    .index,
-            )
+            let error = |reason: String| ReadError::in_row(constraint_file, row, reason);
+            let id = |column: usize, side: &str| {
+                record
+                    .get(column)
+                    .ok_or_else(|| error(format!("missing {side} side of constraint")))
+                    .and_then(|cell| {
+                        cell.parse::<i32>()
+                            .map_err(|e| error(format!("bad id in {side} side of constraint: {e}")))
+                    })
+            };
+            let (left, right) = (id(0, "left")?, id(1, "right")?);
+            let lag_cell = |column: Option<usize>| {
+                column
+                    .and_then(|column| record.get(column))
+                    .filter(|cell| !cell.trim().is_empty())
+                    .map(|cell| {
+                        cell.trim()
+                            .parse::<i32>()
+                            .map_err(|e| error(format!("bad lag: {e}")))
+                    })
+                    .transpose()
+            };
+            let lag = Lag {
+                min: lag_cell(min_column)?.unwrap_or(0),
+                max: lag_cell(max_column)?,
+            };
+            if lag.min < 0 {
+                return Err(error("negative minimum lag".to_string()));
+            }
+            let penalty = kind_column
+                .and_then(|column| record.get(column))
+                .map(parse_kind)
+                .transpose()
+                .map_err(error)?
+                .flatten();
+            if penalty.is_some() && lag.max.is_some() {
+                return Err(error(
+                    "a soft constraint cannot have a maximum lag".to_string(),
+                ));
+            }
+            if penalty.is_some() && left == right {
+                return Err(error(format!(
+                    "the soft constraint relates job {left} to itself"
+                )));
+            }
+            Ok((row, left, right, lag, penalty))
         })
-        .take_while(|Constraint(l, r)| *l < n && *r < n)
-        .collect();
+        .collect()
+}
 
-    let max_time = jobs.len() as i32
-        * jobs
-            .iter()
-            .map(|job| job.1.processing_times.iter().max().copied().unwrap_or(0))
-            .max()
-            .unwrap_or(0);
+/// Parses the kind of a constraint, returning the penalty of a soft constraint
+/// or `None` for a hard one
+fn parse_kind(cell: &str) -> Result<Option<f64>, String> {
+    match cell.trim() {
+        "" | "hard" => Ok(None),
+        kind => {
+            let penalty = kind
+                .strip_prefix("soft:")
+                .ok_or_else(|| format!("bad kind {kind}, expected hard or soft:<penalty>"))?
+                .trim()
+                .parse::<f64>()
+                .map_err(|e| format!("bad penalty: {e}"))?;
+            if !penalty.is_finite() || penalty < 0.0 {
+                return Err(format!("penalty {penalty} must be a nonnegative number"));
+            }
+            Ok(Some(penalty))
+        }
+    }
+}
 
-    Instance {
-        processor_count,
-        jobs: jobs.into_iter().map(|pair| pair.1).collect(),
-        constraints,
-        max_time,
+/// Turns the measured processing times of a row into exactly
+/// `processor_count` processing times. Missing values are filled in
+/// according to `policy`. Returns the processing times along with the number
+/// of values that were synthesized.
+fn extend_times(
+    measured: &[Option<i32>],
+    processor_count: usize,
+    policy: ExtendTimes,
+) -> Result<(Vec<i32>, usize), String> {
+    let measured = &measured[..measured.len().min(processor_count)];
+    let count = measured.iter().take_while(|p| p.is_some()).count();
+    if measured[count..].iter().any(Option::is_some) {
+        return Err("gap in the processing times".to_string());
+    }
+    let measured = measured.iter().flatten().copied().collect::<Vec<i32>>();
+    if measured.is_empty() {
+        return Err("no processing times".to_string());
+    }
+    if measured.iter().any(|&p| p < 0) {
+        return Err("negative processing time".to_string());
+    }
+    let missing = processor_count - measured.len();
+    let times = measured.iter().copied();
+    let processing_times = match policy {
+        _ if missing == 0 => times.collect(),
+        ExtendTimes::Error => return Err(format!(
+            "the row contains {} processing times but {processor_count} processors are used, pass --extend-times to fill in the rest",
+            measured.len()
+        )),
+        ExtendTimes::RepeatLast => {
+            let last = measured[measured.len() - 1];
+            times.chain(std::iter::repeat_n(last, missing)).collect()
+        }
+        ExtendTimes::AmdahlFit => {
+            let points = (1..).zip(measured.iter().copied()).collect::<Vec<_>>();
+            let curve = Amdahl::fit(&points);
+            times
+                .chain((measured.len() + 1..=processor_count).map(|allotment| {
+                    (curve.processing_time(allotment).round() as i32).max(1)
+                }))
+                .collect()
+        }
+    };
+    Ok((processing_times, missing))
+}
+
+/// Parses the affinity of a job, which lists processors and ranges of
+/// processors separated by commas, e.g. `0-3` or `0,2,5`. Returns the sorted
+/// processors without duplicates.
+fn parse_affinity(cell: &str) -> Result<Vec<usize>, String> {
+    let processor = |value: &str| {
+        value
+            .trim()
+            .parse::<usize>()
+            .map_err(|e| format!("bad processor {value} in affinity: {e}"))
+    };
+    let mut processors = vec![];
+    for part in cell.split(',') {
+        match part.split_once('-') {
+            Some((first, last)) => {
+                let (first, last) = (processor(first)?, processor(last)?);
+                if first > last {
+                    return Err(format!(
+                        "processor range {first}-{last} in affinity is empty"
+                    ));
+                }
+                processors.extend(first..=last);
+            }
+            None => processors.push(processor(part)?),
+        }
+    }
+    processors.sort_unstable();
+    processors.dedup();
+    Ok(processors)
+}
+
+/// Finds jobs that share an id. Depending on `dedupe`, either fails with a
+/// list of all duplicates and the constraint rows that refer to them, or
+/// keeps only the last job for each id.
+fn remove_duplicates(
+    job_file: &str,
+    jobs: Vec<JobRow>,
+    constraint_ids: &[ConstraintRow],
+    dedupe: Dedupe,
+) -> Result<Vec<JobRow>, ReadError> {
+    let mut rows_by_id: HashMap<i32, Vec<usize>> = HashMap::new();
+    for (id, row, ..) in &jobs {
+        rows_by_id.entry(*id).or_default().push(*row);
+    }
+    let duplicates = jobs
+        .iter()
+        .filter_map(|(id, ..)| rows_by_id.get_key_value(id))
+        .filter(|(_, rows)| rows.len() > 1)
+        .unique_by(|(id, _)| **id)
+        .collect::<Vec<_>>();
+    if duplicates.is_empty() {
+        return Ok(jobs);
+    }
+
+    let mut message = String::new();
+    for (id, rows) in &duplicates {
+        let referencing = constraint_ids
+            .iter()
+            .filter(|(_, left, right, ..)| left == *id || right == *id)
+            .map(|(row, ..)| row)
+            .join(", ");
+        let _ = write!(message, "\n  id {id} in rows {}", rows.iter().join(", "));
+        if !referencing.is_empty() {
+            let _ = write!(message, ", referenced by constraint rows {referencing}");
+        }
+    }
+    match dedupe {
+        Dedupe::Fail => Err(ReadError::in_file(
+            job_file,
+            format!("duplicate job ids:{message}"),
+        )),
+        Dedupe::Last => {
+            diagnostics::report(
+                Category::Input,
+                format!("Keeping the last job of each duplicate id:{message}"),
+            );
+            Ok(jobs
+                .into_iter()
+                .filter(|(id, row, ..)| rows_by_id[id].last() == Some(row))
+                .collect())
+        }
     }
 }
 
 /// Writes an `Instance` to job and constraint CSV files.
 pub fn write(job_file: &str, constraint_file: &str, instance: Instance) {
-    let mut wtr = Writer::from_path(job_file).expect("could not write job CSV");
+    let mut wtr = Writer::from_writer(vec![]);
+    // only write the affinity and note columns if any job has an affinity or
+    // a note, respectively
+    let affinity = instance.has_affinity();
+    let notes = instance.jobs.iter().any(|job| job.note.is_some());
     let headers = std::iter::once("id".to_string())
-        .chain((0..instance.processor_count).map(|i| format!("p{i}")));
+        .chain((0..instance.processor_count).map(|i| format!("p{i}")))
+        .chain(affinity.then(|| "affinity".to_string()))
+        .chain(notes.then(|| "note".to_string()));
     wtr.write_record(headers).expect("could not write headers");
     for job in instance.jobs {
+        let allowed = affinity.then(|| {
+            job.affinity
+                .as_deref()
+                .map(format_processors)
+                .unwrap_or_default()
+        });
+        let note = notes.then(|| job.note.unwrap_or_default());
         wtr.write_record(
             std::iter::once(job.index.to_string())
-                .chain(job.processing_times.into_iter().map(|p| p.to_string())),
+                .chain(job.processing_times.into_iter().map(|p| p.to_string()))
+                .chain(allowed)
+                .chain(note),
         )
         .expect("could not write job");
     }
-    wtr.flush().expect("could not flush job CSV");
+    let bytes = wtr.into_inner().expect("could not flush job CSV");
+    atomic_write(job_file, &bytes)
+        .unwrap_or_else(|e| panic!("could not write job CSV {job_file}: {e}"));
+    write_constraints(
+        constraint_file,
+        instance.constraints,
+        instance.soft_constraints,
+    );
+}
 
-    let mut wtr = Writer::from_path(constraint_file).expect("could not write constraint CSV");
-    wtr.write_record(["id0", "id1"])
-        .expect("could not write headers");
-    for Constraint(l, r) in instance.constraints {
-        wtr.write_record(std::iter::once(l.to_string()).chain(std::iter::once(r.to_string())))
+/// Writes hard and soft constraints to a constraint CSV file
+pub fn write_constraints(
+    constraint_file: &str,
+    constraints: Vec<Constraint>,
+    soft_constraints: Vec<SoftConstraint>,
+) {
+    let mut wtr = Writer::from_writer(vec![]);
+    // only write the lag columns if there are any lags, and the kind column if
+    // there are soft constraints
+    let lags = constraints
+        .iter()
+        .any(|Constraint(_, _, lag)| *lag != Lag::default())
+        || soft_constraints.iter().any(|soft| soft.min_lag != 0);
+    let kinds = !soft_constraints.is_empty();
+    let headers = ["id0", "id1"]
+        .into_iter()
+        .chain(lags.then_some(["min_lag", "max_lag"]).into_iter().flatten())
+        .chain(kinds.then_some("kind"));
+    wtr.write_record(headers).expect("could not write headers");
+    let hard = constraints
+        .into_iter()
+        .map(|Constraint(l, r, lag)| (l, r, lag, "hard".to_string()));
+    let soft = soft_constraints.into_iter().map(|soft| {
+        let lag = Lag {
+            min: soft.min_lag,
+            max: None,
+        };
+        (soft.left, soft.right, lag, format!("soft:{}", soft.penalty))
+    });
+    for (l, r, lag, kind) in hard.chain(soft) {
+        let mut record = vec![l.to_string(), r.to_string()];
+        if lags {
+            record.push(lag.min.to_string());
+            record.push(lag.max.map(|max| max.to_string()).unwrap_or_default());
+        }
+        if kinds {
+            record.push(kind);
+        }
+        wtr.write_record(record)
             .expect("could not write constraint");
     }
-    wtr.flush().expect("could not flush constraint CSV");
+    let bytes = wtr.into_inner().expect("could not flush constraint CSV");
+    atomic_write(constraint_file, &bytes)
+        .unwrap_or_else(|e| panic!("could not write constraint CSV {constraint_file}: {e}"));
+}
+
+/// Writes any serializable `value` as pretty-printed JSON to `path`.
+pub fn write_json<T: Serialize>(path: &str, value: &T) {
+    let json = serde_json::to_string_pretty(value)
+        .unwrap_or_else(|e| panic!("cannot serialize {path}: {e}"));
+    atomic_write(path, (json + "\n").as_bytes())
+        .unwrap_or_else(|e| panic!("cannot write to file {path}: {e}"));
+}
+
+/// Reads a value from the JSON file at `path`.
+pub fn read_json<T: DeserializeOwned>(path: &str) -> T {
+    let json = fs::read_to_string(path).unwrap_or_else(|e| panic!("cannot read file {path}: {e}"));
+    serde_json::from_str(&json).unwrap_or_else(|e| panic!("cannot parse {path}: {e}"))
+}
+
+/// Writes `value` as one line of JSON to `w`
+fn write_ndjson_line(w: &mut dyn io::Write, value: &impl Serialize) -> io::Result<()> {
+    serde_json::to_writer(&mut *w, value)?;
+    w.write_all(b"\n")
+}
+
+/// A point of the resource profile as it is written to an NDJSON file
+#[derive(Serialize)]
+struct UtilizationLine<'a> {
+    #[serde(flatten)]
+    point: &'a ProfilePoint,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    busy_machines: Option<&'a [String]>,
+}
+
+/// Writes the resource profile of a schedule to a CSV file. If
+/// `busy_machines` is not empty, it holds the names of the busy processors
+/// at every point of the profile, which are added as the column
+/// `busy_machines`, separated by semicolons. In the NDJSON `format`, every
+/// point is written as an object on its own line instead, with the busy
+/// processors as an array.
+pub fn write_utilization(
+    path: &str,
+    profile: &[ProfilePoint],
+    busy_machines: &[Vec<String>],
+    format: ExportFormat,
+) {
+    if format.resolve(path) == ExportFormat::Ndjson {
+        atomic_write_with(path, |w| {
+            for (k, point) in profile.iter().enumerate() {
+                let busy_machines = busy_machines.get(k).map(Vec::as_slice);
+                write_ndjson_line(
+                    w,
+                    &UtilizationLine {
+                        point,
+                        busy_machines,
+                    },
+                )?;
+            }
+            Ok(())
+        })
+        .unwrap_or_else(|e| panic!("could not write utilization NDJSON {path}: {e}"));
+        return;
+    }
+    let mut wtr = Writer::from_writer(vec![]);
+    let headers = ["time", "busy_processors", "active_jobs", "cumulative_work"]
+        .into_iter()
+        .chain((!busy_machines.is_empty()).then_some("busy_machines"));
+    wtr.write_record(headers)
+        .expect("could not write utilization");
+    for (k, point) in profile.iter().enumerate() {
+        let mut record = vec![
+            point.time.to_string(),
+            point.busy_processors.to_string(),
+            point.active_jobs.to_string(),
+            point.cumulative_work.to_string(),
+        ];
+        if let Some(names) = busy_machines.get(k) {
+            record.push(names.join(";"));
+        }
+        wtr.write_record(record)
+            .expect("could not write utilization");
+    }
+    let bytes = wtr.into_inner().expect("could not flush utilization CSV");
+    atomic_write(path, &bytes)
+        .unwrap_or_else(|e| panic!("could not write utilization CSV {path}: {e}"));
+}
+
+/// Writes one CSV file per processor of `schedule` to `dir`, named after the
+/// processor, containing the jobs on it ordered by start time. The
+/// `assignment` holds the processors of every job in the order of the jobs of
+/// the schedule, and processors beyond the processor count are left out. The
+/// columns are `id`, `start`, `end`, `allotment`, and `peer_processors`,
+/// which lists the other processors of the job like the affinity column of
+/// the job file. Jobs use their indices as ids like `write`. If the
+/// processors are named by `machine_names`, the columns `machine` and
+/// `peer_machines` hold the name of the processor and the names of the other
+/// processors, separated by semicolons.
+pub fn write_timelines(
+    dir: &str,
+    schedule: &Schedule,
+    assignment: &[Vec<usize>],
+    machine_names: &[String],
+) {
+    fs::create_dir_all(dir).unwrap_or_else(|e| panic!("cannot create directory {dir}: {e}"));
+    let named = !machine_names.is_empty();
+    for processor in 0..schedule.processor_count {
+        let mut wtr = Writer::from_writer(vec![]);
+        let headers = ["id", "start", "end", "allotment", "peer_processors"]
+            .into_iter()
+            .chain(
+                named
+                    .then_some(["machine", "peer_machines"])
+                    .into_iter()
+                    .flatten(),
+            );
+        wtr.write_record(headers).expect("could not write timeline");
+        let jobs = schedule
+            .jobs
+            .iter()
+            .zip(assignment)
+            .filter(|(_, processors)| processors.contains(&processor))
+            .sorted_by_key(|(job, _)| (job.start_time, job.job.index));
+        for (job, processors) in jobs {
+            let peers = processors
+                .iter()
+                .copied()
+                .filter(|&p| p != processor)
+                .collect::<Vec<_>>();
+            let mut record = vec![
+                job.job.index.to_string(),
+                job.start_time.to_string(),
+                job.completion_time().to_string(),
+                job.allotment.to_string(),
+                format_processors(&peers),
+            ];
+            if named {
+                record.push(machine_name(machine_names, processor));
+                record.push(
+                    peers
+                        .iter()
+                        .map(|&peer| machine_name(machine_names, peer))
+                        .join(";"),
+                );
+            }
+            wtr.write_record(record).expect("could not write timeline");
+        }
+        let path = format!("{dir}/processor-{processor}.csv");
+        let bytes = wtr.into_inner().expect("could not flush timeline CSV");
+        atomic_write(&path, &bytes)
+            .unwrap_or_else(|e| panic!("could not write timeline CSV {path}: {e}"));
+    }
+}
+
+/// Writes how the allotment of every job was rounded as CSV to `path`. The
+/// values of the linear program are joined by semicolons, and the
+/// criticality is left empty if the solver does not score it.
+pub fn write_rounding(path: &str, rounding: &[RoundingDecision]) {
+    let mut wtr = Writer::from_writer(vec![]);
+    wtr.write_record([
+        "job",
+        "allotment",
+        "reason",
+        "criticality",
+        "fractional_values",
+    ])
+    .expect("could not write rounding");
+    for decision in rounding {
+        wtr.write_record([
+            decision.job.to_string(),
+            decision.chosen_allotment.to_string(),
+            decision.reason.to_string(),
+            decision
+                .criticality
+                .map(|score| score.to_string())
+                .unwrap_or_default(),
+            decision.fractional_values.iter().join(";"),
+        ])
+        .expect("could not write rounding");
+    }
+    let bytes = wtr.into_inner().expect("could not flush rounding CSV");
+    atomic_write(path, &bytes)
+        .unwrap_or_else(|e| panic!("could not write rounding CSV {path}: {e}"));
+}
+
+/// Writes the makespan ratio of every algorithm on every instance of an
+/// experiment as CSV to `path`
+pub fn write_ratios(path: &str, cells: &[Cell]) {
+    let mut wtr = Writer::from_writer(vec![]);
+    wtr.write_record(["family", "algorithm", "seed", "ratio"])
+        .expect("could not write ratios");
+    for cell in cells {
+        for (seed, ratio) in &cell.ratios {
+            wtr.write_record([
+                cell.family.clone(),
+                cell.algorithm.to_string(),
+                seed.to_string(),
+                ratio.to_string(),
+            ])
+            .expect("could not write ratios");
+        }
+    }
+    let bytes = wtr.into_inner().expect("could not flush ratios CSV");
+    atomic_write(path, &bytes).unwrap_or_else(|e| panic!("could not write ratios CSV {path}: {e}"));
+}
+
+/// Writes the outcome of every statistical test of an experiment as CSV to
+/// `path`
+pub fn write_comparisons(path: &str, comparisons: &[Comparison]) {
+    let mut wtr = Writer::from_writer(vec![]);
+    for comparison in comparisons {
+        wtr.serialize(comparison).expect("could not write p-values");
+    }
+    let bytes = wtr.into_inner().expect("could not flush p-values CSV");
+    atomic_write(path, &bytes)
+        .unwrap_or_else(|e| panic!("could not write p-values CSV {path}: {e}"));
+}
+
+/// A schedule as it is written to a JSON file, see `schema::SCHEDULE_VERSION`
+#[derive(Serialize, JsonSchema)]
+pub struct ScheduleFile<'a> {
+    schema_version: &'static str,
+    provenance: &'a Provenance,
+    makespan: i32,
+    #[serde(flatten)]
+    schedule: &'a Schedule,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    structure: Option<&'a Structure>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    split: Option<&'a SplitMap>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    soft_violations: &'a [&'a SoftConstraint],
+    /// Indices of the jobs that a partial schedule leaves out
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    deferred: &'a [usize],
+    objectives: &'a BTreeMap<&'static str, f64>,
+    parallelism: &'a Parallelism,
+    waiting: &'a Waiting,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// The first line of a schedule written as NDJSON, which holds everything of
+/// a `ScheduleFile` except for the jobs, which follow on one line each
+#[derive(Serialize)]
+struct ScheduleHeader<'a> {
+    schema_version: &'static str,
+    provenance: &'a Provenance,
+    makespan: i32,
+    processor_count: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    structure: Option<&'a Structure>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    split: Option<&'a SplitMap>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    soft_violations: &'a [&'a SoftConstraint],
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    deferred: &'a [usize],
+    objectives: &'a BTreeMap<&'static str, f64>,
+    parallelism: &'a Parallelism,
+    waiting: &'a Waiting,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// A schedule that a search writes whenever it found a better one, which can
+/// be read like a schedule JSON, see `write_checkpoint`
+#[derive(Serialize)]
+struct CheckpointFile<'a> {
+    schema_version: &'static str,
+    makespan: i32,
+    #[serde(flatten)]
+    schedule: &'a Schedule,
+}
+
+/// Writes the best schedule that a search found so far to `path`, without
+/// provenance, so that --improve-from can continue from it if the search is
+/// interrupted
+pub fn write_checkpoint(path: &str, schedule: &Schedule) {
+    write_json(
+        path,
+        &CheckpointFile {
+            schema_version: SCHEDULE_VERSION,
+            makespan: schedule.makespan(),
+            schedule,
+        },
+    );
+}
+
+/// Reads a schedule from a JSON file that was written by `write_schedule`.
+/// The provenance and the makespan in the file are ignored. Files of older
+/// schema versions are read if possible, see `schema::check_version`.
+/// Schedules written as NDJSON are recognized by the header on their first
+/// line and read line by line.
+pub fn read_schedule(path: &str) -> Schedule {
+    let file = fs::File::open(path).unwrap_or_else(|e| panic!("cannot read file {path}: {e}"));
+    let mut lines = BufReader::new(file).lines();
+    let first = lines
+        .next()
+        .transpose()
+        .unwrap_or_else(|e| panic!("cannot read file {path}: {e}"))
+        .unwrap_or_default();
+    // the first line of a pretty-printed schedule is not a complete object
+    if let Ok(header) = serde_json::from_str::<serde_json::Value>(&first) {
+        if header.is_object() && header.get("jobs").is_none() {
+            return read_schedule_lines(path, &header, lines);
+        }
+    }
+    let json: serde_json::Value = read_json(path);
+    schema::check_version(path, Document::Schedule, &json);
+    serde_json::from_value(json).unwrap_or_else(|e| panic!("cannot parse {path}: {e}"))
+}
+
+/// Reads the jobs of a schedule written as NDJSON, one per line, after its
+/// `header`
+fn read_schedule_lines(
+    path: &str,
+    header: &serde_json::Value,
+    lines: impl Iterator<Item = io::Result<String>>,
+) -> Schedule {
+    schema::check_version(path, Document::Schedule, header);
+    let processor_count = header
+        .get("processor_count")
+        .and_then(serde_json::Value::as_u64)
+        .unwrap_or_else(|| panic!("cannot parse {path}: no processor_count in the first line"))
+        as usize;
+    let mut jobs = vec![];
+    for (k, line) in lines.enumerate() {
+        let line = line.unwrap_or_else(|e| panic!("cannot read file {path}: {e}"));
+        if line.trim().is_empty() {
+            continue;
+        }
+        jobs.push(
+            serde_json::from_str(&line)
+                .unwrap_or_else(|e| panic!("cannot parse line {} of {path}: {e}", k + 2)),
+        );
+    }
+    Schedule {
+        processor_count,
+        jobs,
+    }
+}
+
+/// Writes a schedule together with its provenance and, optionally, its
+/// structure as JSON to `path`. If the schedule contains the segments of split
+/// jobs, `split` maps them to the original jobs. The soft constraints that the
+/// schedule breaks, the jobs that a partial schedule leaves out, the values
+/// of all `objectives`, its `parallelism`, how long its jobs were `waiting`,
+/// and all diagnostics reported so far are included. In the NDJSON `format`, all of these are written to the
+/// first line, followed by one line per job, without building the whole file
+/// in memory.
+#[expect(clippy::too_many_arguments)]
+pub fn write_schedule(
+    path: &str,
+    schedule: &Schedule,
+    provenance: &Provenance,
+    structure: Option<&Structure>,
+    split: Option<&SplitMap>,
+    soft_violations: &[&SoftConstraint],
+    deferred: &[usize],
+    objectives: &BTreeMap<&'static str, f64>,
+    parallelism: &Parallelism,
+    waiting: &Waiting,
+    format: ExportFormat,
+) {
+    if format.resolve(path) == ExportFormat::Ndjson {
+        let header = ScheduleHeader {
+            schema_version: SCHEDULE_VERSION,
+            provenance,
+            makespan: schedule.makespan(),
+            processor_count: schedule.processor_count,
+            structure,
+            split,
+            soft_violations,
+            deferred,
+            objectives,
+            parallelism,
+            waiting,
+            diagnostics: diagnostics::all(),
+        };
+        atomic_write_with(path, |w| {
+            write_ndjson_line(w, &header)?;
+            for job in &schedule.jobs {
+                write_ndjson_line(w, job)?;
+            }
+            Ok(())
+        })
+        .unwrap_or_else(|e| panic!("cannot write to file {path}: {e}"));
+        return;
+    }
+    write_json(
+        path,
+        &ScheduleFile {
+            schema_version: SCHEDULE_VERSION,
+            provenance,
+            makespan: schedule.makespan(),
+            schedule,
+            structure,
+            split,
+            soft_violations,
+            deferred,
+            objectives,
+            parallelism,
+            waiting,
+            diagnostics: diagnostics::all(),
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use csv::ReaderBuilder;
+
+    use super::{
+        parse_affinity, read, write, write_timelines, write_utilization, ExportFormat, Instance,
+        ReadError, ReadOptions,
+    };
+    use crate::{
+        algo::{Constraint, Job, Schedule, ScheduledJob},
+        builder::InstanceBuilder,
+        list,
+    };
+
+    /// Writes the contents of a job file and a constraint file to a fresh
+    /// directory, and reads them with the given `options`
+    fn read_files(
+        name: &str,
+        jobs: &str,
+        constraints: &str,
+        options: &ReadOptions,
+    ) -> Result<Instance, ReadError> {
+        let dir = env::temp_dir().join(format!("read-{name}-{}", process::id()));
+        fs::create_dir_all(&dir).expect("could not create test directory");
+        let job_file = dir.join("jobs.csv");
+        let constraint_file = dir.join("constraints.csv");
+        fs::write(&job_file, jobs).expect("could not write job file");
+        fs::write(&constraint_file, constraints).expect("could not write constraint file");
+        read(
+            job_file.to_str().expect("UTF-8 path"),
+            constraint_file.to_str().expect("UTF-8 path"),
+            options,
+        )
+    }
+
+    /// Writes a job file with the given rows and a constraint file with the
+    /// given constraints to a fresh directory, and reads them
+    fn read_jobs(name: &str, jobs: &[&str], constraints: &[&str]) -> Result<Instance, ReadError> {
+        read_files(
+            name,
+            &format!("id,p0,p1\n{}\n", jobs.join("\n")),
+            &format!(
+                "id0,id1\n{}",
+                constraints
+                    .iter()
+                    .map(|c| format!("{c}\n"))
+                    .collect::<String>()
+            ),
+            &ReadOptions::default(),
+        )
+    }
+
+    #[test]
+    fn rejects_adjacent_duplicates() {
+        let e = read_jobs("adjacent", &["1,4,2", "2,6,3", "2,5,3", "3,2,1"], &[])
+            .expect_err("the ids are not unique");
+        assert!(e.path.ends_with("jobs.csv"), "{e}");
+        assert_eq!(e.row, None);
+        assert_eq!(e.reason, "duplicate job ids:\n  id 2 in rows 2, 3");
+    }
+
+    #[test]
+    fn rejects_duplicates_far_apart() {
+        let e = read_jobs(
+            "far-apart",
+            &["1,4,2", "2,6,3", "3,5,3", "4,2,1", "5,3,2", "1,7,4"],
+            &["2,3", "1,4"],
+        )
+        .expect_err("the ids are not unique");
+        assert_eq!(e.row, None);
+        assert_eq!(
+            e.reason,
+            "duplicate job ids:\n  id 1 in rows 1, 6, referenced by constraint rows 2"
+        );
+    }
+
+    #[test]
+    fn reports_the_row_of_a_bad_record() {
+        let e = read_jobs("bad-time", &["1,4,2", "2,x,3"], &[]).expect_err("x is no time");
+        assert!(e.path.ends_with("jobs.csv"), "{e}");
+        assert_eq!(e.row, Some(2));
+        assert!(
+            e.reason.starts_with("bad processing time in column 1"),
+            "{e}"
+        );
+
+        let e = read_jobs("unknown-id", &["1,4,2", "2,6,3"], &["1,2", "2,9"])
+            .expect_err("job 9 does not exist");
+        assert!(e.path.ends_with("constraints.csv"), "{e}");
+        assert_eq!(e.row, Some(2));
+        assert_eq!(
+            e.reason,
+            "the constraint refers to job 9, which is not in the job file"
+        );
+    }
+
+    #[test]
+    fn tolerant_parsing_fixes_messy_files() {
+        let jobs = "id , p0,p1,\r\n1,\"4\", 2 ,\r\n2,6,3,\r\n";
+        let constraints = "id0,id1\r\n 1 ,2\r\n";
+        let messy = read_files("messy", jobs, constraints, &ReadOptions::default())
+            .expect("tolerant parsing fixes the files");
+        let clean = read_jobs("clean", &["1,4,2", "2,6,3"], &["1,2"]).expect("valid files");
+        assert_eq!(messy.processor_count, clean.processor_count);
+        assert_eq!(
+            messy
+                .jobs
+                .iter()
+                .map(|job| (job.index, job.processing_times.clone()))
+                .collect::<Vec<_>>(),
+            [(0, vec![4, 2]), (1, vec![6, 3])]
+        );
+        assert_eq!(messy.predecessor_indices(1), clean.predecessor_indices(1));
+
+        let e = read_files(
+            "messy-strict",
+            jobs,
+            constraints,
+            &ReadOptions {
+                strict_parse: true,
+                ..ReadOptions::default()
+            },
+        )
+        .expect_err("strict parsing rejects the files");
+        assert!(e.path.ends_with("jobs.csv"), "{e}");
+        assert!(
+            e.reason
+                .starts_with("strict parsing rejects formatting issues"),
+            "{e}"
+        );
+        assert!(
+            e.reason.contains("trimmed whitespace in rows header, 1"),
+            "{e}"
+        );
+    }
+
+    #[test]
+    fn utilization_csv_integrates_to_total_work() {
+        let instance = InstanceBuilder::new()
+            .processors(3)
+            .job(1)
+            .times([6, 4, 3])
+            .job(2)
+            .times([4, 3, 3])
+            .job(3)
+            .times([5, 3, 2])
+            .job(4)
+            .times([2, 2, 2])
+            .constraint(1, 3)
+            .build()
+            .expect("valid instance");
+        let schedule = list::greedy(&instance);
+        let work = schedule
+            .jobs
+            .iter()
+            .map(|job| job.allotment as i64 * i64::from(job.processing_time()))
+            .sum::<i64>();
+
+        let dir = env::temp_dir().join(format!("utilization-{}", process::id()));
+        fs::create_dir_all(&dir).expect("could not create test directory");
+        let path = dir.join("utilization.csv");
+        let path = path.to_str().expect("UTF-8 path");
+        write_utilization(
+            path,
+            &schedule.resource_profile(),
+            &[],
+            ExportFormat::Standard,
+        );
+        let contents = fs::read_to_string(path).expect("could not read utilization CSV");
+        let mut lines = contents.lines();
+        assert_eq!(
+            lines.next(),
+            Some("time,busy_processors,active_jobs,cumulative_work")
+        );
+        let rows = lines
+            .map(|line| {
+                let cells = line
+                    .split(',')
+                    .map(|cell| cell.parse::<i64>().expect("numeric cell"))
+                    .collect::<Vec<_>>();
+                (cells[0], cells[1], cells[3])
+            })
+            .collect::<Vec<_>>();
+        let integral = rows
+            .windows(2)
+            .map(|pair| pair[0].1 * (pair[1].0 - pair[0].0))
+            .sum::<i64>();
+        assert_eq!(integral, work);
+        let &(makespan, busy, cumulative) = rows.last().expect("at least one row");
+        assert_eq!(makespan, i64::from(schedule.makespan()));
+        assert_eq!((busy, cumulative), (0, work));
+    }
+
+    #[test]
+    fn timelines_reassemble_to_the_schedule() {
+        let instance = InstanceBuilder::new()
+            .processors(4)
+            .job(1)
+            .times([9, 5, 3, 3])
+            .job(2)
+            .times([4, 4, 4, 4])
+            .job(3)
+            .times([4, 2, 2, 2])
+            .job(4)
+            .times([1, 1, 1, 1])
+            .build()
+            .expect("valid instance");
+        let placed = [
+            (3, 0, vec![0, 1, 3]),
+            (1, 0, vec![2]),
+            (2, 4, vec![0, 2]),
+            (1, 3, vec![3]),
+        ];
+        let schedule = Schedule {
+            processor_count: 4,
+            jobs: placed
+                .into_iter()
+                .enumerate()
+                .map(
+                    |(index, (allotment, start_time, processors))| ScheduledJob {
+                        job: instance.shared_job(index),
+                        allotment,
+                        start_time,
+                        processors,
+                    },
+                )
+                .collect(),
+        };
+        assert_eq!(schedule.violations(&instance), Vec::<String>::new());
+        let assignment = schedule
+            .jobs
+            .iter()
+            .map(|job| job.processors.clone())
+            .collect::<Vec<_>>();
+        let dir = env::temp_dir().join(format!("timelines-{}", process::id()));
+        write_timelines(dir.to_str().expect("UTF-8 path"), &schedule, &assignment);
+
+        // the id, start, end, and allotment of every row along with the
+        // processor of its file and the processors of the job it lists
+        let mut rows = vec![];
+        for processor in 0..4 {
+            let path = dir.join(format!("processor-{processor}.csv"));
+            let mut rdr = ReaderBuilder::new()
+                .from_path(&path)
+                .expect("the timeline exists");
+            assert_eq!(
+                rdr.headers().expect("the timeline has a header"),
+                vec!["id", "start", "end", "allotment", "peer_processors"]
+            );
+            let mut last_start = 0;
+            for record in rdr.records() {
+                let record = record.expect("valid record");
+                let number = |column: usize| record[column].parse::<i32>().expect("numeric cell");
+                let row = (number(0) as usize, number(1), number(2), number(3) as usize);
+                assert!(row.1 >= last_start, "processor {processor} is not sorted");
+                last_start = row.1;
+                let mut processors = if record[4].is_empty() {
+                    vec![]
+                } else {
+                    parse_affinity(&record[4]).expect("valid peers")
+                };
+                processors.push(processor);
+                processors.sort_unstable();
+                rows.push((row, processor, processors));
+            }
+        }
+        assert_eq!(rows.len(), 3 + 1 + 2 + 1);
+        for job in &schedule.jobs {
+            let listed = rows
+                .iter()
+                .filter(|((id, ..), _, _)| *id == job.job.index)
+                .collect::<Vec<_>>();
+            // a job with allotment 3 is in exactly 3 files, which agree on
+            // its times and all of its processors
+            assert_eq!(
+                listed
+                    .iter()
+                    .map(|(_, processor, _)| *processor)
+                    .collect::<Vec<_>>(),
+                job.processors
+            );
+            for (row, _, processors) in listed {
+                assert_eq!(
+                    *row,
+                    (
+                        job.job.index,
+                        job.start_time,
+                        job.completion_time(),
+                        job.allotment
+                    )
+                );
+                assert_eq!(processors, &job.processors);
+            }
+        }
+    }
+
+    #[test]
+    fn notes_survive_writing_and_reading() {
+        let notes = [
+            Some("waiting on customer approval, see ticket"),
+            None,
+            Some(r#"says "hi" & <bye>"#),
+            Some("two\nlines, and 'quotes' – ünïcode"),
+        ];
+        let jobs = notes
+            .iter()
+            .enumerate()
+            .map(|(index, note)| Job {
+                index,
+                processing_times: vec![2, 1],
+                synthesized: 0,
+                affinity: None,
+                note: note.map(str::to_string),
+            })
+            .collect();
+        let dir = env::temp_dir().join(format!("notes-{}", process::id()));
+        fs::create_dir_all(&dir).expect("could not create test directory");
+        let job_file = dir.join("jobs.csv");
+        let constraint_file = dir.join("constraints.csv");
+        let job_file = job_file.to_str().expect("UTF-8 path");
+        let constraint_file = constraint_file.to_str().expect("UTF-8 path");
+        write(job_file, constraint_file, Instance::new(2, jobs, vec![]));
+        let instance = read(job_file, constraint_file, &ReadOptions::default())
+            .expect("the written files can be read");
+        assert_eq!(instance.processor_count, 2);
+        assert_eq!(
+            instance
+                .jobs
+                .iter()
+                .map(|job| (job.processing_times.clone(), job.note.as_deref()))
+                .collect::<Vec<_>>(),
+            notes.map(|note| (vec![2, 1], note))
+        );
+    }
+
+    /// The processing times of all jobs and the constraints by job positions
+    fn contents(instance: &Instance) -> (Vec<Vec<i32>>, Vec<(usize, usize)>) {
+        (
+            instance
+                .jobs
+                .iter()
+                .map(|job| job.processing_times.clone())
+                .collect(),
+            instance
+                .constraints
+                .iter()
+                .map(|&Constraint(left, right, _)| (left, right))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn truncated_files_are_read_or_rejected_precisely() {
+        let jobs = "id,p1,p2\n1,4,2\n2,6,3\n3,5,3\n";
+        let constraints = "id0,id1\n1,2\n2,3\n";
+        let (full_jobs, full_constraints) = contents(
+            &read_files("full", jobs, constraints, &ReadOptions::default()).expect("valid files"),
+        );
+        for ignore_trailing_garbage in [false, true] {
+            let options = ReadOptions {
+                ignore_trailing_garbage,
+                ..ReadOptions::default()
+            };
+            let cuts = (0..=jobs.len())
+                .map(|cut| (&jobs[..cut], constraints, "jobs.csv"))
+                .chain(
+                    (0..=constraints.len())
+                        .map(|cut| (jobs, &constraints[..cut], "constraints.csv")),
+                );
+            for (jobs, constraints, cut_file) in cuts {
+                let name = format!(
+                    "truncated-{ignore_trailing_garbage}-{cut_file}-{}",
+                    jobs.len() + constraints.len()
+                );
+                match read_files(&name, jobs, constraints, &options) {
+                    // whatever is read must be a part of the full instance
+                    // and keep every constraint between the jobs it has
+                    Ok(instance) => {
+                        let (jobs_read, constraints_read) = contents(&instance);
+                        assert_eq!(
+                            jobs_read,
+                            full_jobs[..jobs_read.len()],
+                            "{jobs:?} and {constraints:?} are read as other jobs"
+                        );
+                        if cut_file == "jobs.csv" {
+                            let expected = full_constraints
+                                .iter()
+                                .copied()
+                                .filter(|&(left, right)| left.max(right) < jobs_read.len())
+                                .collect::<Vec<_>>();
+                            assert_eq!(constraints_read, expected, "{jobs:?}");
+                        } else {
+                            assert_eq!(jobs_read.len(), full_jobs.len());
+                            assert!(
+                                full_constraints.starts_with(&constraints_read),
+                                "{constraints:?} is read as other constraints"
+                            );
+                        }
+                    }
+                    Err(e) => {
+                        assert!(!e.reason.is_empty());
+                        assert!(
+                            e.path.ends_with("jobs.csv") || e.path.ends_with("constraints.csv"),
+                            "{e}"
+                        );
+                        assert!(e.row.is_none_or(|row| row <= 3), "{e}");
+                    }
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,202 @@
+// Fixes minor formatting issues of CSV files before they are parsed, and
+// records every fix so that tolerant parsing can report it and strict parsing
+// can reject the file.
+
+use std::fmt;
+
+use itertools::Itertools;
+
+/// A formatting issue that tolerant parsing fixes
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Fix {
+    /// A line ends with a carriage return, as with Windows line endings
+    CarriageReturn,
+    /// A cell has whitespace around its value
+    Whitespace,
+    /// A number is enclosed in quotes
+    QuotedNumber,
+    /// The last column has neither a header nor values, e.g. because every
+    /// line ends with a comma
+    EmptyTrailingColumn,
+}
+impl fmt::Display for Fix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Fix::CarriageReturn => "removed carriage returns",
+            Fix::Whitespace => "trimmed whitespace",
+            Fix::QuotedNumber => "unquoted numbers",
+            Fix::EmptyTrailingColumn => "dropped an empty trailing column",
+        })
+    }
+}
+
+/// Cleans the `contents` of a CSV file. Returns the cleaned contents along
+/// with every fix and the row it was applied to, where row 0 is the header
+/// and blank lines are not counted.
+pub fn clean(contents: &str) -> (String, Vec<(Fix, usize)>) {
+    let mut fixes = vec![];
+    let mut rows = vec![];
+    for line in split_lines(contents) {
+        let row = rows.len();
+        let line = match line.strip_suffix('\r') {
+            Some(line) => {
+                fixes.push((Fix::CarriageReturn, row));
+                line
+            }
+            None => line,
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let cells = split_cells(line)
+            .into_iter()
+            .map(|cell| {
+                let (cell, fix) = clean_cell(&cell);
+                if let Some(fix) = fix {
+                    fixes.push((fix, row));
+                }
+                cell
+            })
+            .collect::<Vec<_>>();
+        rows.push(cells);
+    }
+    let width = rows.first().map_or(0, Vec::len);
+    let trailing_empty = width > 1
+        && rows
+            .iter()
+            .all(|cells| cells.get(width - 1).is_none_or(String::is_empty));
+    if trailing_empty {
+        fixes.push((Fix::EmptyTrailingColumn, 0));
+        for cells in &mut rows {
+            cells.truncate(width - 1);
+        }
+    }
+    let mut cleaned = rows.iter().map(|cells| cells.join(",")).join("\n");
+    cleaned.push('\n');
+    (cleaned, fixes.into_iter().dedup().collect())
+}
+
+/// Describes the `fixes` of the file at `path`, one line per kind of fix
+pub fn describe(path: &str, fixes: &[(Fix, usize)]) -> Vec<String> {
+    fixes
+        .iter()
+        .sorted()
+        .chunk_by(|(fix, _)| *fix)
+        .into_iter()
+        .map(|(fix, rows)| {
+            let rows = rows
+                .map(|&(_, row)| {
+                    if row == 0 {
+                        "header".to_string()
+                    } else {
+                        row.to_string()
+                    }
+                })
+                .dedup()
+                .join(", ");
+            format!("{path}: {fix} in rows {rows}")
+        })
+        .collect()
+}
+
+/// Removes whitespace around the value of a cell and the quotes around a
+/// number, returning the cell along with the fix that was needed, if any.
+/// Other quoted values keep their quotes.
+fn clean_cell(cell: &str) -> (String, Option<Fix>) {
+    let trimmed = cell.trim();
+    let unquoted = trimmed
+        .strip_prefix('"')
+        .and_then(|inner| inner.strip_suffix('"'))
+        .map(str::trim)
+        .filter(|inner| inner.parse::<f64>().is_ok());
+    match unquoted {
+        Some(number) => (number.to_string(), Some(Fix::QuotedNumber)),
+        None if trimmed.len() < cell.len() => (trimmed.to_string(), Some(Fix::Whitespace)),
+        None => (cell.to_string(), None),
+    }
+}
+
+/// Splits the contents of a CSV file into lines at the line breaks outside of
+/// quotes, so that quoted cells such as notes may span several lines
+fn split_lines(contents: &str) -> Vec<&str> {
+    let mut lines = vec![];
+    let mut quoted = false;
+    let mut start = 0;
+    for (i, c) in contents.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '\n' if !quoted => {
+                lines.push(&contents[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    lines.push(&contents[start..]);
+    lines
+}
+
+/// Splits a line of a CSV file at the commas outside of quotes. The cells
+/// keep their quotes.
+fn split_cells(line: &str) -> Vec<String> {
+    let mut cells = vec![String::new()];
+    let mut quoted = false;
+    for c in line.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                cells.last_mut().expect("at least one cell").push(c);
+            }
+            ',' if !quoted => cells.push(String::new()),
+            c => cells.last_mut().expect("at least one cell").push(c),
+        }
+    }
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{clean, describe, Fix};
+
+    #[test]
+    fn messy_file_is_cleaned_and_reported() {
+        let (cleaned, fixes) = clean("id , p0,p1,\r\n1,\"4\", 2 ,\r\n\r\n2,6,3,\r\n");
+        assert_eq!(cleaned, "id,p0,p1\n1,4,2\n2,6,3\n");
+        assert_eq!(
+            fixes,
+            [
+                (Fix::CarriageReturn, 0),
+                (Fix::Whitespace, 0),
+                (Fix::CarriageReturn, 1),
+                (Fix::QuotedNumber, 1),
+                (Fix::Whitespace, 1),
+                (Fix::CarriageReturn, 2),
+                (Fix::EmptyTrailingColumn, 0),
+            ]
+        );
+        assert_eq!(
+            describe("jobs.csv", &fixes),
+            [
+                "jobs.csv: removed carriage returns in rows header, 1, 2",
+                "jobs.csv: trimmed whitespace in rows header, 1",
+                "jobs.csv: unquoted numbers in rows 1",
+                "jobs.csv: dropped an empty trailing column in rows header",
+            ]
+        );
+    }
+
+    #[test]
+    fn clean_file_needs_no_fixes() {
+        let contents = "id,p0,note\n1,4,\"a, b\"\n2,6,\"two\nlines\"\n";
+        let (cleaned, fixes) = clean(contents);
+        assert_eq!(cleaned, contents);
+        assert!(fixes.is_empty());
+    }
+
+    #[test]
+    fn quoted_text_keeps_its_quotes() {
+        let (cleaned, fixes) = clean("id,note\n1,\" a note \"\n");
+        assert_eq!(cleaned, "id,note\n1,\" a note \"\n");
+        assert!(fixes.is_empty());
+    }
+}
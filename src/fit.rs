@@ -0,0 +1,121 @@
+// Small numeric helpers to extrapolate processing times that were not measured.
+
+/// Processing times that follow Amdahl's law, p(l) = serial + parallel / l
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Amdahl {
+    /// The part of the work that does not benefit from more processors
+    pub serial: f64,
+    /// The part of the work that is split evenly among the processors
+    pub parallel: f64,
+}
+impl Amdahl {
+    /// Fits the curve to measured `(allotment, processing_time)` points by
+    /// least squares, keeping both parts non-negative. A single point is
+    /// treated as purely serial.
+    pub fn fit(points: &[(usize, i32)]) -> Self {
+        assert!(!points.is_empty(), "cannot fit a curve to zero points");
+        let n = points.len() as f64;
+        let xs = points.iter().map(|&(l, _)| 1.0 / l as f64);
+        let ys = points.iter().map(|&(_, p)| f64::from(p));
+        let mean_x = xs.clone().sum::<f64>() / n;
+        let mean_y = ys.clone().sum::<f64>() / n;
+        let sxx = xs.clone().map(|x| (x - mean_x).powi(2)).sum::<f64>();
+        if sxx == 0.0 {
+            return Amdahl {
+                serial: mean_y,
+                parallel: 0.0,
+            };
+        }
+        let sxy = xs
+            .clone()
+            .zip(ys.clone())
+            .map(|(x, y)| (x - mean_x) * (y - mean_y))
+            .sum::<f64>();
+        let parallel = sxy / sxx;
+        let serial = mean_y - parallel * mean_x;
+        if parallel < 0.0 {
+            // processing times grow with more processors, nothing to split
+            Amdahl {
+                serial: mean_y,
+                parallel: 0.0,
+            }
+        } else if serial < 0.0 {
+            // refit through the origin
+            let parallel = xs.clone().zip(ys).map(|(x, y)| x * y).sum::<f64>()
+                / xs.map(|x| x * x).sum::<f64>();
+            Amdahl {
+                serial: 0.0,
+                parallel,
+            }
+        } else {
+            Amdahl { serial, parallel }
+        }
+    }
+    /// Evaluates the curve for the given allotment
+    pub fn processing_time(&self, allotment: usize) -> f64 {
+        self.serial + self.parallel / allotment as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Amdahl;
+
+    /// Part of the work on a single processor that is serial
+    fn serial_fraction(amdahl: Amdahl) -> f64 {
+        amdahl.serial / (amdahl.serial + amdahl.parallel)
+    }
+
+    /// Measures the processing times of a curve on the given allotments,
+    /// rounded to whole time units like the job files
+    fn measure(serial: f64, parallel: f64, allotments: &[usize]) -> Vec<(usize, i32)> {
+        allotments
+            .iter()
+            .map(|&l| (l, (serial + parallel / l as f64).round() as i32))
+            .collect()
+    }
+
+    #[test]
+    fn recovers_exact_serial_fraction() {
+        let amdahl = Amdahl::fit(&measure(20.0, 80.0, &[1, 2, 4, 8]));
+        assert!((amdahl.serial - 20.0).abs() < 1e-9, "{amdahl:?}");
+        assert!((amdahl.parallel - 80.0).abs() < 1e-9, "{amdahl:?}");
+    }
+
+    #[test]
+    fn recovers_serial_fractions_despite_rounding() {
+        for (serial, parallel) in [(10.0, 90.0), (50.0, 50.0), (3.0, 97.0), (75.0, 25.0)] {
+            let amdahl = Amdahl::fit(&measure(serial, parallel, &[1, 2, 3, 4, 5, 6]));
+            let expected = serial / (serial + parallel);
+            assert!(
+                (serial_fraction(amdahl) - expected).abs() < 0.02,
+                "expected a serial fraction of {expected}, got {amdahl:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn purely_parallel_work_has_no_serial_part() {
+        let amdahl = Amdahl::fit(&measure(0.0, 120.0, &[1, 2, 3, 4]));
+        assert!(serial_fraction(amdahl) < 1e-9, "{amdahl:?}");
+    }
+
+    #[test]
+    fn single_point_is_serial() {
+        let amdahl = Amdahl::fit(&[(2, 7)]);
+        assert_eq!(
+            amdahl,
+            Amdahl {
+                serial: 7.0,
+                parallel: 0.0
+            }
+        );
+    }
+
+    #[test]
+    fn growing_times_are_serial() {
+        let amdahl = Amdahl::fit(&[(1, 10), (2, 12), (4, 14)]);
+        assert!(amdahl.parallel.abs() < 1e-9, "{amdahl:?}");
+        assert!((amdahl.serial - 12.0).abs() < 1e-9, "{amdahl:?}");
+    }
+}
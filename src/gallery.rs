@@ -0,0 +1,437 @@
+// Builds an HTML index of a directory of rendered schedules, e.g. the
+// directory "schedules" after a series of experiments. Every rendered
+// schedule gets a row with the instance, the solver, the makespan, and the
+// date, which are read from the provenance of a schedule JSON next to it or
+// else guessed from the file itself.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    io::{BufRead, BufReader},
+    path::Path,
+    time::UNIX_EPOCH,
+};
+
+use itertools::Itertools;
+use log::{debug, info};
+use serde::Deserialize;
+
+use crate::{output, render::escape_html};
+
+/// Name of the index file in the indexed directory
+pub const INDEX_FILE: &str = "index.html";
+
+/// Height of the thumbnails in pixels
+const THUMBNAIL_HEIGHT: usize = 80;
+
+/// A rendered schedule or schedule JSON in the index
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// File name of the rendered schedule, or of the HTML index of its pages,
+    /// if there is one
+    pub link: Option<String>,
+    /// File name of the SVG shown as a thumbnail
+    pub thumbnail: Option<String>,
+    /// File name of the schedule JSON holding the provenance, if any
+    pub json: Option<String>,
+    /// Name of the instance, i.e. the names of its input files
+    pub instance: String,
+    /// Name of the subcommand that computed the schedule, if known
+    pub algorithm: Option<String>,
+    /// Makespan of the schedule, if known
+    pub makespan: Option<i32>,
+    /// Seconds since the Unix epoch at which the schedule was computed, or
+    /// at which the file was last modified if the provenance is missing
+    pub date: u64,
+}
+
+/// The parts of a schedule JSON that the index shows, see
+/// `files::write_schedule`
+#[derive(Deserialize)]
+struct ScheduleJson {
+    makespan: i32,
+    provenance: Option<ProvenanceJson>,
+}
+
+/// The parts of the provenance that the index shows, see
+/// `provenance::Provenance`
+#[derive(Deserialize)]
+struct ProvenanceJson {
+    solver: String,
+    started_at: u64,
+    job_file: Option<InputFileJson>,
+    constraint_file: Option<InputFileJson>,
+    generator: Option<GeneratorJson>,
+}
+
+/// The path of an input file in the provenance
+#[derive(Deserialize)]
+struct InputFileJson {
+    path: String,
+}
+
+/// The seed of a generator run in the provenance
+#[derive(Deserialize)]
+struct GeneratorJson {
+    seed: u64,
+}
+
+/// Lists the rendered schedules and schedule JSON or NDJSON files in `dir`,
+/// ordered by date, newest first. A schedule JSON belongs to the SVG that its input
+/// files are rendered to by default, or else to the SVG with the same name.
+/// The pages of a paginated schedule form one entry that links their HTML
+/// index. Files that cannot be read are skipped.
+pub fn scan(dir: &Path) -> Vec<Entry> {
+    let names = fs::read_dir(dir)
+        .unwrap_or_else(|e| panic!("cannot read directory {}: {e}", dir.display()))
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_file()))
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect::<HashSet<_>>();
+    // rendered schedules by the stem that a schedule JSON refers to them by
+    let mut svgs = BTreeMap::new();
+    for name in &names {
+        let Some(stem) = name.strip_suffix(".svg") else {
+            continue;
+        };
+        match stem.rsplit_once("_page") {
+            Some((stem, "1")) => svgs.insert(stem.to_string(), name.clone()),
+            Some((_, page)) if page.parse::<usize>().is_ok() => continue,
+            _ => svgs.insert(stem.to_string(), name.clone()),
+        };
+    }
+    let link = |stem: &str, svg: &str| {
+        let index = format!("{stem}.html");
+        if svg.ends_with("_page1.svg") && names.contains(&index) {
+            index
+        } else {
+            svg.to_string()
+        }
+    };
+
+    let mut entries = vec![];
+    let schedule_files = names
+        .iter()
+        .filter_map(|name| {
+            let stem = name
+                .strip_suffix(".json")
+                .or_else(|| name.strip_suffix(".ndjson"))?;
+            Some((name, stem))
+        })
+        .sorted();
+    for (name, stem) in schedule_files {
+        let path = dir.join(name);
+        let Some(json) = read_schedule_json(&path) else {
+            debug!("Not indexing {name}, which is no schedule JSON");
+            continue;
+        };
+        let rendered_stem = json
+            .provenance
+            .as_ref()
+            .and_then(ProvenanceJson::rendered_stem)
+            .filter(|stem| svgs.contains_key(stem))
+            .unwrap_or_else(|| stem.to_string());
+        let svg = svgs.remove(&rendered_stem);
+        let (instance, algorithm, date) = match &json.provenance {
+            Some(provenance) => (
+                provenance.instance(),
+                Some(provenance.solver.clone()),
+                provenance.started_at,
+            ),
+            None => (instance_name(stem), None, modified(&path)),
+        };
+        entries.push(Entry {
+            link: svg.as_ref().map(|svg| link(&rendered_stem, svg)),
+            thumbnail: svg,
+            json: Some(name.clone()),
+            instance,
+            algorithm,
+            makespan: Some(json.makespan),
+            date,
+        });
+    }
+    // rendered schedules without a schedule JSON
+    for (stem, svg) in svgs {
+        entries.push(Entry {
+            link: Some(link(&stem, &svg)),
+            date: modified(&dir.join(&svg)),
+            thumbnail: Some(svg),
+            json: None,
+            instance: instance_name(&stem),
+            algorithm: None,
+            makespan: None,
+        });
+    }
+    entries.sort_by(|a, b| {
+        b.date
+            .cmp(&a.date)
+            .then_with(|| a.instance.cmp(&b.instance))
+    });
+    entries
+}
+
+impl ProvenanceJson {
+    /// The stem of the SVG file that a solver renders the schedule to by
+    /// default, see `generate_filename`
+    fn rendered_stem(&self) -> Option<String> {
+        let (job_file, constraint_file) = (self.job_file.as_ref()?, self.constraint_file.as_ref()?);
+        Some(format!(
+            "{}_{}_schedule",
+            file_stem(&job_file.path),
+            file_stem(&constraint_file.path)
+        ))
+    }
+
+    /// Names the instance after its input files, or after the generator run
+    /// that produced it
+    fn instance(&self) -> String {
+        match (&self.job_file, &self.constraint_file, &self.generator) {
+            (Some(job_file), Some(constraint_file), _) => format!(
+                "{}_{}",
+                file_stem(&job_file.path),
+                file_stem(&constraint_file.path)
+            ),
+            (_, _, Some(generator)) => format!("generated with seed {}", generator.seed),
+            _ => "unknown".to_string(),
+        }
+    }
+}
+
+/// Reads the parts of a schedule JSON that the index shows, which are on
+/// the first line of a schedule written as NDJSON
+fn read_schedule_json(path: &Path) -> Option<ScheduleJson> {
+    if path
+        .extension()
+        .is_some_and(|extension| extension == "ndjson")
+    {
+        let file = fs::File::open(path).ok()?;
+        let first = BufReader::new(file).lines().next()?.ok()?;
+        serde_json::from_str(&first).ok()
+    } else {
+        serde_json::from_str(&fs::read_to_string(path).ok()?).ok()
+    }
+}
+
+/// The file name of `path` without its extension
+fn file_stem(path: &str) -> String {
+    Path::new(path).file_stem().map_or_else(
+        || path.to_string(),
+        |stem| stem.to_string_lossy().into_owned(),
+    )
+}
+
+/// Guesses the name of the instance from the stem of a file name
+fn instance_name(stem: &str) -> String {
+    stem.strip_suffix("_schedule").unwrap_or(stem).to_string()
+}
+
+/// Seconds since the Unix epoch at which the file at `path` was last
+/// modified, or 0 if that is unknown
+fn modified(path: &Path) -> u64 {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |duration| duration.as_secs())
+}
+
+/// Formats seconds since the Unix epoch as a date and time in UTC
+fn format_date(seconds: u64) -> String {
+    let days = (seconds / 86_400) as i64;
+    let (hours, minutes) = (seconds % 86_400 / 3600, seconds % 3600 / 60);
+    // civil date from days since 1970-01-01, after Howard Hinnant
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02} {hours:02}:{minutes:02} UTC")
+}
+
+/// Renders the index of `entries` as an HTML page with the given `title`.
+/// Clicking a column header sorts the table by it.
+pub fn render(title: &str, entries: &[Entry]) -> String {
+    let title = escape_html(title);
+    let rows = entries
+        .iter()
+        .map(|entry| {
+            let thumbnail = match (&entry.link, &entry.thumbnail) {
+                (Some(link), Some(thumbnail)) => format!(
+                    r#"<a href="{}"><img src="{}" height="{THUMBNAIL_HEIGHT}" loading="lazy" alt=""></a>"#,
+                    escape_html(link),
+                    escape_html(thumbnail)
+                ),
+                _ => String::new(),
+            };
+            let json = entry.json.as_ref().map_or_else(String::new, |json| {
+                format!(r#"<a href="{0}">{0}</a>"#, escape_html(json))
+            });
+            let makespan = entry.makespan.map_or_else(String::new, |m| m.to_string());
+            format!(
+                r#"    <tr><td>{thumbnail}</td><td>{}</td><td>{}</td><td data-sort="{}">{makespan}</td><td data-sort="{}">{}</td><td>{json}</td></tr>"#,
+                escape_html(&entry.instance),
+                escape_html(entry.algorithm.as_deref().unwrap_or("")),
+                entry.makespan.unwrap_or(-1),
+                entry.date,
+                format_date(entry.date)
+            )
+        })
+        .join("\n");
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+  <meta charset="utf-8">
+  <title>{title}</title>
+  <style>
+    table {{ border-collapse: collapse; }}
+    th, td {{ padding: 4px 8px; border-bottom: 1px solid #ddd; text-align: left; }}
+    th {{ cursor: pointer; }}
+  </style>
+</head>
+<body>
+  <h1>{title}</h1>
+  <table>
+    <thead><tr><th>Schedule</th><th>Instance</th><th>Algorithm</th><th>Makespan</th><th>Date</th><th>JSON</th></tr></thead>
+    <tbody>
+{rows}
+    </tbody>
+  </table>
+  <script>
+    document.querySelectorAll("th").forEach((th, column) => th.addEventListener("click", () => {{
+      const body = th.closest("table").tBodies[0];
+      const key = row => row.cells[column].dataset.sort ?? row.cells[column].textContent;
+      const ascending = th.dataset.order !== "asc";
+      th.dataset.order = ascending ? "asc" : "desc";
+      const rows = [...body.rows].sort((a, b) => {{
+        const [x, y] = [key(a), key(b)];
+        const order = isNaN(x) || isNaN(y) ? x.localeCompare(y) : x - y;
+        return ascending ? order : -order;
+      }});
+      body.append(...rows);
+    }}));
+  </script>
+</body>
+</html>
+"#
+    )
+}
+
+/// Writes the index of `dir` to the file `INDEX_FILE` in it and returns its
+/// path
+pub fn write(dir: &Path) -> String {
+    let entries = scan(dir);
+    let title = dir.file_name().map_or_else(
+        || dir.display().to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    );
+    let path = dir.join(INDEX_FILE).to_string_lossy().into_owned();
+    output::atomic_write(&path, render(&title, &entries).as_bytes())
+        .unwrap_or_else(|e| panic!("cannot write to file {path}: {e}"));
+    info!("Index of {} schedules is written to {path}", entries.len());
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use super::{scan, write, Entry};
+
+    #[test]
+    fn index_lists_every_schedule() {
+        let dir = env::temp_dir().join(format!("gallery-{}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("cannot create the test directory");
+        let file = |name: &str, contents: &str| {
+            fs::write(dir.join(name), contents).expect("cannot write the test file");
+        };
+        // a schedule JSON with provenance, which names the SVG of its inputs
+        file("jobs_constraints_schedule.svg", "<svg/>");
+        file(
+            "result.json",
+            r#"{"makespan": 12, "provenance": {"solver": "solve-ilp", "started_at": 1700000000,
+                "job_file": {"path": "in/jobs.csv"}, "constraint_file": {"path": "in/constraints.csv"}}}"#,
+        );
+        // a paginated schedule without provenance
+        file("big_schedule_page1.svg", "<svg/>");
+        file("big_schedule_page2.svg", "<svg/>");
+        file("big_schedule.html", "<html></html>");
+        // a generated instance that was not rendered
+        file(
+            "generated.ndjson",
+            "{\"makespan\": 9, \"provenance\": {\"solver\": \"gen-solve\", \"started_at\": 1600000000, \"generator\": {\"seed\": 7}}}\n{}\n",
+        );
+        // other JSON files are skipped
+        file("notes.json", r#"{"note": "not a schedule"}"#);
+
+        let entries = scan(&dir);
+        assert_eq!(entries.len(), 3, "{entries:?}");
+        assert!(entries[0].date > 1_700_000_000);
+        assert_eq!(
+            entries[0],
+            Entry {
+                link: Some("big_schedule.html".to_string()),
+                thumbnail: Some("big_schedule_page1.svg".to_string()),
+                json: None,
+                instance: "big".to_string(),
+                algorithm: None,
+                makespan: None,
+                date: entries[0].date,
+            }
+        );
+        assert_eq!(
+            entries[1..],
+            [
+                Entry {
+                    link: Some("jobs_constraints_schedule.svg".to_string()),
+                    thumbnail: Some("jobs_constraints_schedule.svg".to_string()),
+                    json: Some("result.json".to_string()),
+                    instance: "jobs_constraints".to_string(),
+                    algorithm: Some("solve-ilp".to_string()),
+                    makespan: Some(12),
+                    date: 1_700_000_000,
+                },
+                Entry {
+                    link: None,
+                    thumbnail: None,
+                    json: Some("generated.ndjson".to_string()),
+                    instance: "generated with seed 7".to_string(),
+                    algorithm: Some("gen-solve".to_string()),
+                    makespan: Some(9),
+                    date: 1_600_000_000,
+                },
+            ]
+        );
+
+        let path = write(&dir);
+        let html = fs::read_to_string(path).expect("no index written");
+        let rows = html
+            .lines()
+            .filter(|line| line.trim_start().starts_with("<tr><td"))
+            .collect::<Vec<_>>();
+        assert_eq!(rows.len(), 3, "{html}");
+        assert!(rows[0].starts_with(
+            r#"    <tr><td><a href="big_schedule.html"><img src="big_schedule_page1.svg" height="80" loading="lazy" alt=""></a></td><td>big</td><td></td><td data-sort="-1"></td>"#
+        ));
+        assert_eq!(
+            rows[1],
+            r#"    <tr><td><a href="jobs_constraints_schedule.svg"><img src="jobs_constraints_schedule.svg" height="80" loading="lazy" alt=""></a></td><td>jobs_constraints</td><td>solve-ilp</td><td data-sort="12">12</td><td data-sort="1700000000">2023-11-14 22:13 UTC</td><td><a href="result.json">result.json</a></td></tr>"#
+        );
+        assert_eq!(
+            rows[2],
+            r#"    <tr><td></td><td>generated with seed 7</td><td>gen-solve</td><td data-sort="9">9</td><td data-sort="1600000000">2020-09-13 12:26 UTC</td><td><a href="generated.ndjson">generated.ndjson</a></td></tr>"#
+        );
+        // the index does not index itself
+        assert_eq!(scan(&dir).len(), 3);
+    }
+}
@@ -1,72 +1,362 @@
 // Small helper functions to generate random instances of the scheduling problem.
 
-use crate::algo::{Constraint, Instance, Job};
+use crate::{algo::Instance, builder::InstanceBuilder, schema::METADATA_VERSION};
 use itertools::Itertools;
+use log::info;
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
-use rand::Rng;
-use std::cmp;
+use rand::{Rng, SeedableRng};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::{cmp, fmt};
+
+/// Parameters of the instance generator
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct GenerateConfig {
+    /// Number of jobs
+    pub n: usize,
+    /// Number of processors
+    pub m: usize,
+    /// Minimum processing time of a job
+    pub min_p: i32,
+    /// Maximum processing time of a job
+    pub max_p: i32,
+    /// Number of chains
+    pub omega: usize,
+    /// Minimum length of a chain
+    pub min_chain: usize,
+    /// Maximum length of a chain
+    pub max_chain: usize,
+    /// Use the concave 1/n function for processing times
+    pub concave: bool,
+}
+impl GenerateConfig {
+    /// Checks that instances with these parameters exist, and returns every
+    /// relation between the parameters that does not hold otherwise
+    pub fn validate(&self) -> Result<(), Vec<ConfigError>> {
+        let &GenerateConfig {
+            n,
+            m,
+            min_p,
+            max_p,
+            omega,
+            min_chain,
+            max_chain,
+            ..
+        } = self;
+        let relations = [
+            (n >= 1, ConfigError::NoJobs),
+            (m >= 1, ConfigError::NoProcessors),
+            (min_p >= 1, ConfigError::MinTimeBelowOne),
+            (max_p >= min_p, ConfigError::MaxTimeBelowMin),
+            (omega >= 1, ConfigError::NoChains),
+            (omega <= n, ConfigError::MoreChainsThanJobs),
+            (min_chain >= 1, ConfigError::MinChainBelowOne),
+            (max_chain >= min_chain, ConfigError::MaxChainBelowMin),
+            (max_chain <= n, ConfigError::MaxChainAboveJobs),
+            (
+                min_chain.saturating_mul(omega) <= n,
+                ConfigError::ChainsTooLong,
+            ),
+            (
+                max_chain.saturating_mul(omega) >= n,
+                ConfigError::ChainsTooShort,
+            ),
+        ];
+        let errors = relations
+            .into_iter()
+            .filter(|&(holds, _)| !holds)
+            .map(|(_, error)| error)
+            .collect::<Vec<_>>();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// A relation between the parameters of the generator that does not hold, so
+/// that no instance with these parameters exists
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfigError {
+    /// There are no jobs
+    NoJobs,
+    /// There are no processors
+    NoProcessors,
+    /// The shortest processing time is below 1
+    MinTimeBelowOne,
+    /// The longest processing time is below the shortest one
+    MaxTimeBelowMin,
+    /// There are no chains
+    NoChains,
+    /// There are more chains than jobs
+    MoreChainsThanJobs,
+    /// The shortest chain is empty
+    MinChainBelowOne,
+    /// The longest chain is shorter than the shortest one
+    MaxChainBelowMin,
+    /// The longest chain has more jobs than the instance
+    MaxChainAboveJobs,
+    /// All chains at their shortest need more jobs than the instance has
+    ChainsTooLong,
+    /// All chains at their longest hold fewer jobs than the instance has
+    ChainsTooShort,
+}
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ConfigError::NoJobs => "n must be at least 1",
+            ConfigError::NoProcessors => "m must be at least 1",
+            ConfigError::MinTimeBelowOne => "min_p must be at least 1",
+            ConfigError::MaxTimeBelowMin => "max_p must be at least min_p",
+            ConfigError::NoChains => "omega must be at least 1",
+            ConfigError::MoreChainsThanJobs => "omega must be at most n",
+            ConfigError::MinChainBelowOne => "min_chain must be at least 1",
+            ConfigError::MaxChainBelowMin => "max_chain must be at least min_chain",
+            ConfigError::MaxChainAboveJobs => "max_chain must be at most n",
+            ConfigError::ChainsTooLong => "min_chain * omega must be at most n",
+            ConfigError::ChainsTooShort => "max_chain * omega must be at least n",
+        })
+    }
+}
 
-#[expect(clippy::too_many_arguments)]
 /// Generates a randomized instance of the scheduling problem with `n` jobs and `m` processors.
 /// The processing times of the jobs are randomly generated within the range of `min_p` to `max_p`.
 /// `omega` many chains are created, with each chain having a length between `min_chain` and `max_chain`.
 /// If `concave` is true, the processing times follow the concave 1/n function, otherwise they are randomly distributed.
-pub fn instance(
-    n: usize,
-    m: usize,
-    min_p: i32,
-    max_p: i32,
-    omega: usize,
-    min_chain: usize,
-    max_chain: usize,
-    concave: bool,
-) -> Instance {
-    Instance {
-        processor_count: m,
-        jobs: if concave {
-            jobs_concave(n, m as i32, min_p, max_p)
+pub fn instance(config: &GenerateConfig, rng: &mut impl Rng) -> Instance {
+    let GenerateConfig {
+        n,
+        m,
+        min_p,
+        max_p,
+        omega,
+        min_chain,
+        max_chain,
+        concave,
+    } = *config;
+    let builder = InstanceBuilder::new().processors(m);
+    let builder = if concave {
+        jobs_concave(builder, rng, n, m as i32, min_p, max_p)
+    } else {
+        jobs(builder.allow_non_monotone(), rng, n, m, min_p, max_p)
+    };
+    constraints(rng, n, omega, min_chain, max_chain)
+        .into_iter()
+        .fold(builder, |builder, (left, right)| {
+            builder.constraint(left as i32, right as i32)
+        })
+        .build()
+        .unwrap_or_else(|e| panic!("generated an invalid instance: {e}"))
+}
+
+/// Basic statistics of an instance that tell whether it can differentiate algorithms
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Analysis {
+    /// Number of chains in the chain decomposition of the instance
+    pub width: usize,
+    /// Total work divided by the number of processors times the critical path
+    /// length, where values below 1 mean that the instance is effectively
+    /// sequential
+    pub parallelism: f64,
+    /// Fraction of jobs whose processing times are identical to those of
+    /// another job
+    pub identical_fraction: f64,
+    /// Number of distinct processing times across all jobs and allotments
+    pub distinct_processing_times: usize,
+}
+
+/// Limits beyond which a generated instance is considered uninteresting
+#[derive(Debug)]
+pub struct Thresholds {
+    /// Minimum width of the instance
+    pub min_width: usize,
+    /// Minimum parallelism of the instance
+    pub min_parallelism: f64,
+    /// Maximum fraction of identical jobs
+    pub max_identical: f64,
+}
+
+/// Computes basic statistics of the given instance
+pub fn analyse(instance: &Instance) -> Analysis {
+    let n = instance.jobs.len();
+    let total_work = instance
+        .jobs
+        .iter()
+        .map(|job| f64::from(job.processing_time(1)))
+        .sum::<f64>();
+    let critical_path = f64::from(instance.critical_path_length());
+    let identical = instance
+        .jobs
+        .iter()
+        .filter(|job| {
+            instance.jobs.iter().any(|other| {
+                other.index != job.index && other.processing_times == job.processing_times
+            })
+        })
+        .count();
+    Analysis {
+        width: instance.chains().len(),
+        parallelism: if critical_path > 0.0 {
+            total_work / (instance.processor_count as f64 * critical_path)
+        } else {
+            0.0
+        },
+        identical_fraction: if n == 0 {
+            0.0
         } else {
-            jobs(n, m, min_p, max_p)
+            identical as f64 / n as f64
         },
-        constraints: constraints(n, omega, min_chain, max_chain),
-        max_time: n as i32 * max_p,
+        distinct_processing_times: instance
+            .jobs
+            .iter()
+            .flat_map(|job| job.processing_times.iter())
+            .unique()
+            .count(),
     }
 }
 
-/// Generates jobs following a concave 1/n processing time function.
-fn jobs_concave(n: usize, m: i32, min_p: i32, max_p: i32) -> Vec<Job> {
-    (0..n)
-        .map(|index| {
-            let p = rand::rng().random_range(min_p..max_p);
-            let cutoff = rand::rng().random_range(1..=m);
-            Job {
-                index,
-                processing_times: (1..=m).map(|i| p / cmp::min(i, cutoff)).collect(),
-            }
-        })
-        .collect()
+impl Analysis {
+    /// Lists the reasons why the analysed instance is unlikely to differentiate
+    /// algorithms, based on the given thresholds
+    pub fn warnings(&self, thresholds: &Thresholds) -> Vec<String> {
+        let mut warnings = vec![];
+        if self.distinct_processing_times <= 1 {
+            warnings.push("all processing times are equal".to_string());
+        }
+        if self.width < thresholds.min_width {
+            warnings.push(format!(
+                "width is {} but should be at least {}",
+                self.width, thresholds.min_width
+            ));
+        }
+        if self.parallelism < thresholds.min_parallelism {
+            warnings.push(format!(
+                "parallelism is {:.3} but should be at least {}, the instance is effectively sequential",
+                self.parallelism, thresholds.min_parallelism
+            ));
+        }
+        if self.identical_fraction > thresholds.max_identical {
+            warnings.push(format!(
+                "{:.1}% of the jobs are identical, but at most {:.1}% should be",
+                100.0 * self.identical_fraction,
+                100.0 * thresholds.max_identical
+            ));
+        }
+        warnings
+    }
 }
 
-/// Generates jobs with random processing times for each allotment.
-fn jobs(n: usize, m: usize, min_p: i32, max_p: i32) -> Vec<Job> {
-    (0..n)
-        .map(|index| Job {
-            index,
-            processing_times: (1..=m)
-                .map(|_| rand::rng().random_range(min_p..max_p))
-                .collect(),
-        })
-        .collect()
+/// Result of sampling an instance, possibly after several resamples
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Metadata {
+    /// Version of the metadata, see `schema::METADATA_VERSION`
+    pub schema_version: &'static str,
+    /// The seed from which the instance was generated
+    pub seed: u64,
+    /// How often the instance was resampled because it was not interesting
+    pub resamples: u64,
+    /// Statistics of the generated instance
+    pub analysis: Analysis,
+    /// Reasons why the instance is unlikely to differentiate algorithms
+    pub warnings: Vec<String>,
+}
+
+/// Generates an instance from `seed`. If `max_resamples` is given, the
+/// instance is resampled with the next seeds until it meets the thresholds or
+/// the number of resamples is exhausted.
+pub fn sample(
+    config: &GenerateConfig,
+    seed: u64,
+    thresholds: &Thresholds,
+    max_resamples: Option<u64>,
+) -> (Instance, Metadata) {
+    let mut resamples = 0;
+    loop {
+        let seed = seed.wrapping_add(resamples);
+        let instance = instance(config, &mut StdRng::seed_from_u64(seed));
+        let analysis = analyse(&instance);
+        let warnings = analysis.warnings(thresholds);
+        if warnings.is_empty() || max_resamples.is_none_or(|max| resamples >= max) {
+            let metadata = Metadata {
+                schema_version: METADATA_VERSION,
+                seed,
+                resamples,
+                analysis,
+                warnings,
+            };
+            return (instance, metadata);
+        }
+        info!(
+            "Resampling uninteresting instance from seed {seed}: {}",
+            warnings.join(", ")
+        );
+        resamples += 1;
+    }
+}
+
+/// Adds jobs following a concave 1/n processing time function, using their
+/// indices as ids.
+fn jobs_concave(
+    builder: InstanceBuilder,
+    rng: &mut impl Rng,
+    n: usize,
+    m: i32,
+    min_p: i32,
+    max_p: i32,
+) -> InstanceBuilder {
+    (0..n as i32).fold(builder, |builder, id| {
+        let p = processing_time(rng, min_p, max_p);
+        let cutoff = rng.random_range(1..=m);
+        builder.job(id).times_fn(|i| p / cmp::min(i as i32, cutoff))
+    })
+}
+
+/// Adds jobs with random processing times for each allotment, using their
+/// indices as ids.
+fn jobs(
+    builder: InstanceBuilder,
+    rng: &mut impl Rng,
+    n: usize,
+    m: usize,
+    min_p: i32,
+    max_p: i32,
+) -> InstanceBuilder {
+    (0..n as i32).fold(builder, |builder, id| {
+        builder
+            .job(id)
+            .times((1..=m).map(|_| processing_time(rng, min_p, max_p)))
+    })
+}
+
+/// Draws a processing time from `min_p` up to, but excluding, `max_p`, or
+/// returns `min_p` if both are equal
+fn processing_time(rng: &mut impl Rng, min_p: i32, max_p: i32) -> i32 {
+    if min_p == max_p {
+        min_p
+    } else {
+        rng.random_range(min_p..max_p)
+    }
 }
 
-/// Generates constraints for the scheduling problem.
-fn constraints(n: usize, omega: usize, min_chain: usize, max_chain: usize) -> Vec<Constraint> {
+/// Generates constraints for the scheduling problem as pairs of job indices.
+fn constraints(
+    rng: &mut impl Rng,
+    n: usize,
+    omega: usize,
+    min_chain: usize,
+    max_chain: usize,
+) -> Vec<(usize, usize)> {
     let mut indices = (1..n).collect::<Vec<_>>();
-    indices.shuffle(&mut rand::rng());
+    indices.shuffle(rng);
 
     let mut cuts = indices[0..omega - 1].to_vec();
     cuts.sort_unstable();
-    cuts.ensure_slice_size(min_chain, max_chain);
+    // the adjusted cuts may still run past the last job
+    if !cuts.ensure_slice_size(min_chain, max_chain) || cuts.last().is_some_and(|&last| last >= n) {
+        cuts = chain_cuts(rng, n, omega, min_chain, max_chain);
+    }
 
     [0].iter()
         .chain(cuts.iter())
@@ -75,18 +365,48 @@ fn constraints(n: usize, omega: usize, min_chain: usize, max_chain: usize) -> Ve
         .fold(vec![], |constraints, (&l, &r)| {
             constraints
                 .into_iter()
-                .chain(
-                    (l..r)
-                        .flat_map(|job0| (job0..r).map(move |job1| (job0, job1)))
-                        .map(|(left, right)| Constraint(left, right)),
-                )
+                .chain((l..r).flat_map(|job0| (job0 + 1..r).map(move |job1| (job0, job1))))
                 .collect()
         })
 }
 
+/// Cuts the jobs `0..n` into `omega` chains of `min_chain` to `max_chain`
+/// jobs each, by growing chains at random from their shortest length until
+/// they hold all jobs, and returns the first job of every chain but the first
+fn chain_cuts(
+    rng: &mut impl Rng,
+    n: usize,
+    omega: usize,
+    min_chain: usize,
+    max_chain: usize,
+) -> Vec<usize> {
+    let mut lengths = vec![min_chain; omega];
+    // chains that may still grow
+    let mut open = if min_chain < max_chain {
+        (0..omega).collect::<Vec<_>>()
+    } else {
+        vec![]
+    };
+    for _ in omega * min_chain..n {
+        let k = rng.random_range(0..open.len());
+        lengths[open[k]] += 1;
+        if lengths[open[k]] == max_chain {
+            open.swap_remove(k);
+        }
+    }
+    lengths
+        .iter()
+        .scan(0, |first, length| {
+            *first += length;
+            Some(*first)
+        })
+        .take(omega - 1)
+        .collect()
+}
+
 trait SlicesWithSize {
     type T;
-    fn ensure_slice_size(&mut self, min: Self::T, max: Self::T);
+    fn ensure_slice_size(&mut self, min: Self::T, max: Self::T) -> bool;
 }
 
 /// Small helper implementation to ensure that all elements are at least min and at most
@@ -101,14 +421,19 @@ where
 {
     type T = E;
     /// iterate over self and check if two consecutive value are within min and max apart
-    /// if not, increase or decrease the second value to fit the bounds
-    fn ensure_slice_size(&mut self, min: E, max: E) {
+    /// if not, increase or decrease the second value to fit the bounds. Returns
+    /// false if there are fewer than two values or the bounds cannot be met.
+    fn ensure_slice_size(&mut self, min: E, max: E) -> bool {
+        if self.len() < 2 {
+            return false;
+        }
         for i in 0..self.len() - 1 {
             let max_remaining = cmp::max(max, min * (self.len() - i));
-            let diff = self[i + 1] - self[i];
-            if diff < min {
+            // raising a value may move it past the next one, so the values
+            // are compared without subtracting them
+            if self[i + 1] < self[i] + min {
                 self[i + 1] = self[i] + min;
-            } else if diff > max_remaining {
+            } else if self[i + 1] > self[i] + max_remaining {
                 self[i + 1] = self[i] + max_remaining;
             }
         }
@@ -116,9 +441,141 @@ where
         // just make sure we did not mess up
         let last = self.len() - 1;
         let diff = self[last] - self[last - 1];
-        assert!(
-            min <= diff && diff <= max,
-            "unfortunate random values, cannot handle"
+        min <= diff && diff <= max
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::{instance, ConfigError, GenerateConfig};
+
+    /// Parameters with processing times of `min_p` to `max_p` and `omega`
+    /// chains of `min_chain` to `max_chain` jobs
+    fn config(
+        n: usize,
+        m: usize,
+        (min_p, max_p): (i32, i32),
+        omega: usize,
+        (min_chain, max_chain): (usize, usize),
+    ) -> GenerateConfig {
+        GenerateConfig {
+            n,
+            m,
+            min_p,
+            max_p,
+            omega,
+            min_chain,
+            max_chain,
+            concave: false,
+        }
+    }
+
+    #[test]
+    fn every_violated_relation_is_reported() {
+        use ConfigError::{
+            ChainsTooLong, ChainsTooShort, MaxChainAboveJobs, MaxChainBelowMin, MaxTimeBelowMin,
+            MinChainBelowOne, MinTimeBelowOne, MoreChainsThanJobs, NoChains, NoJobs, NoProcessors,
+        };
+
+        let cases = [
+            (config(6, 2, (1, 10), 2, (1, 5)), vec![]),
+            // equal bounds mean constant processing times or chain lengths
+            (config(6, 2, (4, 4), 2, (3, 3)), vec![]),
+            (config(6, 1, (1, 2), 6, (1, 1)), vec![]),
+            (config(1, 1, (1, 1), 1, (1, 1)), vec![]),
+            (
+                config(0, 0, (0, 0), 0, (0, 0)),
+                vec![
+                    NoJobs,
+                    NoProcessors,
+                    MinTimeBelowOne,
+                    NoChains,
+                    MinChainBelowOne,
+                ],
+            ),
+            (config(6, 2, (0, 10), 2, (1, 5)), vec![MinTimeBelowOne]),
+            (config(6, 2, (5, 4), 2, (1, 5)), vec![MaxTimeBelowMin]),
+            // as many chains as jobs leave one job per chain
+            (config(3, 2, (1, 10), 3, (2, 3)), vec![ChainsTooLong]),
+            (
+                config(2, 2, (1, 10), 3, (1, 2)),
+                vec![MoreChainsThanJobs, ChainsTooLong],
+            ),
+            (config(4, 2, (1, 10), 1, (1, 5)), vec![MaxChainAboveJobs]),
+            (
+                config(4, 2, (1, 10), 2, (3, 2)),
+                vec![MaxChainBelowMin, ChainsTooLong],
+            ),
+            (config(10, 2, (1, 10), 2, (1, 4)), vec![ChainsTooShort]),
+            // products of the chain lengths must not overflow
+            (
+                config(5, 2, (1, 10), 2, (usize::MAX, usize::MAX)),
+                vec![MaxChainAboveJobs, ChainsTooLong],
+            ),
+        ];
+        for (config, errors) in cases {
+            let expected = if errors.is_empty() {
+                Ok(())
+            } else {
+                Err(errors)
+            };
+            assert_eq!(config.validate(), expected, "{config:?}");
+        }
+    }
+
+    #[test]
+    fn chain_lengths_at_their_bounds_are_met() {
+        // chains of exactly 3 jobs each, and chains of single jobs
+        for (config, constraints) in [
+            (config(6, 2, (4, 4), 2, (3, 3)), 6),
+            (config(6, 1, (1, 2), 6, (1, 1)), 0),
+        ] {
+            for seed in 0..50 {
+                let instance = instance(&config, &mut StdRng::seed_from_u64(seed));
+                assert_eq!(instance.jobs.len(), 6, "seed {seed}");
+                assert_eq!(instance.constraints.len(), constraints, "seed {seed}");
+            }
+        }
+        // constant processing times
+        let instance = instance(
+            &config(6, 2, (4, 4), 2, (3, 3)),
+            &mut StdRng::seed_from_u64(0),
         );
+        assert!(instance
+            .jobs
+            .iter()
+            .all(|job| job.processing_times == [4, 4]));
+    }
+
+    #[test]
+    fn valid_configs_never_panic() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut valid = 0;
+        for _ in 0..20_000 {
+            let min_p = rng.random_range(0..4);
+            let min_chain = rng.random_range(0..5);
+            let config = GenerateConfig {
+                concave: rng.random(),
+                ..config(
+                    rng.random_range(0..12),
+                    rng.random_range(0..4),
+                    (min_p, min_p + rng.random_range(-1..4)),
+                    rng.random_range(0..6),
+                    (min_chain, min_chain + rng.random_range(0..5)),
+                )
+            };
+            if config.validate().is_err() {
+                continue;
+            }
+            valid += 1;
+            for seed in 0..5 {
+                let instance = instance(&config, &mut StdRng::seed_from_u64(seed));
+                assert_eq!(instance.jobs.len(), config.n, "{config:?}");
+                assert_eq!(instance.processor_count, config.m, "{config:?}");
+            }
+        }
+        assert!(valid > 1000, "only {valid} configs were valid");
     }
 }
@@ -1,71 +1,375 @@
 // This file contains the "ILP" implementation from the newer Jansen, Zhang Paper, but it transformed to a relaxed LP implementation.
 
-use log::debug;
+use itertools::Itertools;
+use log::{debug, info};
 
 use cpm_rs::{CustomTask, Scheduler};
-use good_lp::{constraint, default_solver, variable, variables, Expression, Solution, SolverModel};
+use good_lp::{
+    constraint, default_solver, variable, variables, Expression, Solution, SolverModel, Variable,
+};
 
-use crate::algo::{Instance, Schedule, ScheduledJob};
+use crate::{
+    algo::{
+        to_integer, Instance, ModelSize, Rounding, RoundingDecision, RoundingReason, Schedule,
+        ScheduledJob, SolverStats,
+    },
+    diagnostics::{self, Category},
+    list::{self, ListOptions},
+    preprocess::{self, Groups},
+};
 
 #[expect(clippy::too_many_lines, clippy::needless_pass_by_value)]
 /// Computes a schedule for the given `instance` using a linear programming approach.
-pub fn schedule(instance: Instance, compress: bool) -> Schedule {
+/// Values of the linear program within `int_tolerance` of an integer are
+/// treated as integers, and all other processing times are rounded in
+/// `processing_time_rounding`. The allotments are turned into a schedule by
+/// LIST with the given `list_options`. With `group_identical`, interchangeable
+/// jobs share their variables, see `preprocess::identical_jobs`, and LIST
+/// aims to start them in waves that fit next to each other.
+pub fn schedule(
+    instance: Instance,
+    compress: bool,
+    int_tolerance: f64,
+    processing_time_rounding: Rounding,
+    group_identical: bool,
+    list_options: &ListOptions,
+) -> (Schedule, SolverStats) {
     // initialization step
+    if !instance.capacity.is_empty() {
+        diagnostics::report(
+            Category::Model,
+            "The integer linear program does not model the capacity profile, only the list scheduling respects it",
+        );
+    }
+    if !instance.downtime.is_empty() {
+        diagnostics::report(
+            Category::Model,
+            "The integer linear program does not model the downtime, only the list scheduling respects it",
+        );
+    }
+    if instance.has_affinity() {
+        diagnostics::report(
+            Category::Model,
+            "The integer linear program does not model processor affinity, only the list scheduling respects it",
+        );
+    }
     let m = instance.jobs.len() as i32;
+    let groups = group_identical.then(|| preprocess::identical_jobs(&instance));
 
     // PHASE 1: linear program
     // - define linear program
-    let cpl = critical_path_length(&instance);
+    let Model {
+        problem,
+        makespan,
+        processing_times,
+        completion_times,
+        size: _,
+    } = build_model(&instance, groups.as_ref());
+
+    // - obtain fractional solution
+    let solution = problem
+        .solve()
+        .unwrap_or_else(|e| panic!("no solution: {e}"));
+    // LIST respects the soft constraints that the integer linear program keeps
+    let enforced = instance.enforce_soft(|soft| {
+        let kept = solution.value(completion_times[soft.left])
+            + solution.value(processing_times[soft.right])
+            + f64::from(soft.min_lag)
+            <= solution.value(completion_times[soft.right]) + int_tolerance;
+        if !kept {
+            debug!(
+                "Integer linear program breaks soft constraint {} -> {}",
+                soft.left, soft.right
+            );
+        }
+        kept
+    });
+    let lp_bound = solution.value(makespan);
+    debug!("Believe makespan to be {lp_bound}");
+    let fractional_processing_times = processing_times
+        .iter()
+        .map(|&v| solution.value(v))
+        .collect::<Vec<_>>();
+    let processing_times = fractional_processing_times
+        .iter()
+        .map(|&x_j| to_integer(x_j, int_tolerance, processing_time_rounding))
+        .collect::<Vec<_>>();
+    let completion_times = completion_times
+        .into_iter()
+        .map(|v| to_integer(solution.value(v), int_tolerance, Rounding::Up))
+        .collect::<Vec<_>>();
+
+    // rounding completion times up and processing times down preserves the
+    // precedence constraints, except for values that were snapped, while
+    // rounding processing times otherwise may break them
+    for i in 0..instance.jobs.len() {
+        for &j in instance.predecessor_indices(i) {
+            let lag = instance.lag(j, i).min;
+            if completion_times[j] + processing_times[i] + lag > completion_times[i] {
+                diagnostics::report(
+                    Category::Rounding,
+                    format!(
+                        "Rounding violates C_{j} + x_{i} + {lag} <= C_{i} with C_{j} = {}, x_{i} = {}, C_{i} = {}",
+                        completion_times[j], processing_times[i], completion_times[i]
+                    ),
+                );
+            }
+        }
+    }
+
+    for (i, x_j) in processing_times.iter().copied().enumerate() {
+        // print solution
+        debug!("x_{i} = {x_j}");
+    }
+    for (i, c_j) in completion_times.iter().copied().enumerate() {
+        debug!("C_{i} = {c_j}");
+    }
+    // - round it to a feasible allotment
+    // - compute allotment parameter µ
+    let my = compute_my(m).floor() as usize;
+    let rounding = processing_times
+        .iter()
+        .copied()
+        .zip(&fractional_processing_times)
+        .enumerate()
+        .map(|(j, (x_j, &fractional))| round_to_closest(&instance, j, x_j, fractional, my))
+        .collect::<Vec<_>>();
+    let capped = rounding
+        .iter()
+        .filter(|decision| matches!(decision.reason, RoundingReason::CappedByMu { .. }))
+        .map(|decision| decision.job)
+        .collect::<Vec<_>>();
+    if !capped.is_empty() {
+        diagnostics::report(
+            Category::Rounding,
+            format!(
+                "Rounding capped the allotments of jobs {} at µ = {my}",
+                capped.iter().join(", ")
+            ),
+        );
+    }
+    let allotments = rounding
+        .iter()
+        .map(|decision| decision.chosen_allotment)
+        .collect::<Vec<_>>();
+    for (i, l_j) in allotments.iter().copied().enumerate() {
+        debug!("l_{i} = {l_j}");
+    }
+    // the chosen allotments rarely realize the processing times of the LP
+    let discrepancies = allotments
+        .iter()
+        .zip(&instance.jobs)
+        .zip(&fractional_processing_times)
+        .map(|((&l_j, job), &x_j)| f64::from(job.processing_time(l_j)) - x_j)
+        .collect::<Vec<_>>();
+    for (i, discrepancy) in discrepancies.iter().enumerate() {
+        debug!("p_{i}(l_{i}) - x_{i} = {discrepancy}");
+    }
+    if let Some((job, discrepancy)) = discrepancies
+        .iter()
+        .enumerate()
+        .max_by(|(_, a), (_, b)| a.abs().total_cmp(&b.abs()))
+    {
+        info!("The allotments change processing times of the LP by up to {discrepancy:.3}, for job {job}");
+    }
+
+    // PHASE 2: list schedule
+    // - run LIST to generate feasible schedule
+    let targets = if compress {
+        vec![0; instance.jobs.len()]
+    } else {
+        start_targets(
+            &instance,
+            &completion_times,
+            &processing_times,
+            &allotments,
+            groups.as_ref(),
+        )
+    };
+    let list = list::schedule(&enforced, &allotments, &targets, list_options);
+    let max_target_deviation =
+        (!compress).then(|| list::max_target_deviation(&list.target_deviations));
+    (
+        Schedule {
+            processor_count: instance.processor_count,
+            jobs: list.jobs,
+        },
+        SolverStats {
+            lp_bound: Some(lp_bound),
+            rho: None,
+            mu: Some(my),
+            max_target_deviation,
+            rounding,
+            seed: list.seed,
+        },
+    )
+}
+
+/// Computes the target start times of the jobs for LIST from the completion
+/// times `completion_times` and the processing times `processing_times` of
+/// the linear program. A job completes at C_j - x_j + p_j(l_j) with the
+/// processing time of its allotment, so that it still starts where the LP
+/// starts it, which can be negative if the processing time was rounded up.
+/// With `groups`, the jobs of a group share their completion time in the LP,
+/// but only as many of them as fit next to each other can run at the same
+/// time, so the others start in later waves.
+fn start_targets(
+    instance: &Instance,
+    completion_times: &[i32],
+    processing_times: &[i32],
+    allotments: &[usize],
+    groups: Option<&Groups>,
+) -> Vec<i32> {
+    allotments
+        .iter()
+        .copied()
+        .enumerate()
+        .map(|(job, allotment)| {
+            let p = instance.jobs[job].processing_time(allotment);
+            let completion = completion_times[job] - processing_times[job] + p;
+            let waves = groups.map_or(0, |groups| {
+                groups.waves_before(job, instance.processor_count / allotment.max(1))
+            });
+            (completion - p).max(0) + waves as i32 * p
+        })
+        .collect()
+}
+
+/// The linear program of an instance along with the variables that the
+/// rounding reads from its solution
+pub struct Model<P> {
+    problem: P,
+    makespan: Variable,
+    processing_times: Vec<Variable>,
+    completion_times: Vec<Variable>,
+    /// Size of the linear program
+    pub size: ModelSize,
+}
+
+/// Builds the linear program for the given `instance` without solving it.
+/// With `groups`, the linear program only has variables for the first job of
+/// every group, which the other jobs of the group share, and counts the work
+/// of every group once per job in it.
+pub fn build_model(instance: &Instance, groups: Option<&Groups>) -> Model<impl SolverModel> {
+    let Some(groups) = groups else {
+        return build_weighted_model(instance, &vec![1; instance.jobs.len()]);
+    };
+    let model = build_weighted_model(&groups.quotient(instance), &groups.multiplicities());
+    info!(
+        "Grouping {} jobs into {} groups of identical jobs shrinks the linear program by a factor of {:.1}",
+        groups.of.len(),
+        groups.members.len(),
+        model_size(instance).total() as f64 / model.size.total() as f64
+    );
+    Model {
+        problem: model.problem,
+        makespan: model.makespan,
+        processing_times: groups.expand(&model.processing_times),
+        completion_times: groups.expand(&model.completion_times),
+        size: model.size,
+    }
+}
+
+/// Builds the linear program for the given `instance`, in which the job at
+/// every position stands for as many jobs as its entry in `multiplicities`
+fn build_weighted_model(instance: &Instance, multiplicities: &[usize]) -> Model<impl SolverModel> {
+    // the average work is taken over all jobs that the positions stand for
+    let m = multiplicities.iter().sum::<usize>() as i32;
+    let size = model_size(instance);
+    debug!("Building linear program with {size}");
+    let cpl = critical_path_length(instance);
     let mut vars = variables!();
     let makespan = vars.add(variable().min(0));
     let processing_times = instance
         .jobs
         .iter()
-        .map(|job| {
-            vars.add(variable().clamp(
-                job.processing_time(instance.processor_count),
-                job.processing_time(1),
-            ))
+        .enumerate()
+        .map(|(j, job)| {
+            vars.add(match instance.pin(j) {
+                Some(pin) => variable().clamp(pin.processing_time(), pin.processing_time()),
+                None => variable().clamp(
+                    job.processing_time(instance.processor_count),
+                    job.processing_time(1),
+                ),
+            })
         })
         .collect::<Vec<_>>();
-    let completion_times = instance
-        .jobs
-        .iter()
-        .map(|_| vars.add(variable().clamp(0, cpl)))
+    // pinned jobs are fixed to their completion times, which may lie after
+    // the critical path, and so may their successors
+    let latest = cpl
+        + instance
+            .pinned
+            .iter()
+            .map(ScheduledJob::completion_time)
+            .max()
+            .unwrap_or(0);
+    let completion_times = (0..instance.jobs.len())
+        .map(|j| {
+            vars.add(match instance.pin(j) {
+                Some(pin) => variable().clamp(pin.completion_time(), pin.completion_time()),
+                None => variable().clamp(0, latest),
+            })
+        })
         .collect::<Vec<_>>();
     let work = instance
         .jobs
         .iter()
         .map(|_| vars.add(variable()))
         .collect::<Vec<_>>();
-    // minimize makespan
-    let problem = vars.minimise(makespan).using(default_solver);
+    let soft_violations = instance
+        .soft_constraints
+        .iter()
+        .map(|_| vars.add(variable().binary()))
+        .collect::<Vec<_>>();
+    // minimize makespan plus the penalties of the broken soft constraints
+    let objective = makespan
+        + instance
+            .soft_constraints
+            .iter()
+            .zip(&soft_violations)
+            .map(|(soft, &y)| soft.penalty * y)
+            .sum::<Expression>();
+    let problem = vars.minimise(objective).using(default_solver);
     // set the makespan as the maximum completion time
     let problem = completion_times.iter().fold(problem, |prob, &c_j| {
         prob.with(constraint!(makespan >= c_j))
     });
     // ensure the order of jobs
-    let problem = instance
-        .jobs
-        .iter()
-        .enumerate()
-        .fold(problem, |prob, (i, job)| {
-            instance
-                .predecessors(job)
-                .into_iter()
-                .fold(prob, |p, (j, _)| {
-                    p.with(constraint!(
-                        completion_times[i] + processing_times[j] <= completion_times[j]
-                    ))
-                })
-        });
+    let problem = (0..instance.jobs.len()).fold(problem, |prob, i| {
+        instance.predecessor_indices(i).iter().fold(prob, |p, &j| {
+            let lag = instance.lag(j, i);
+            let p = p.with(constraint!(
+                completion_times[j] + processing_times[i] + lag.min <= completion_times[i]
+            ));
+            match lag.max {
+                Some(max) => p.with(constraint!(
+                    completion_times[i] - processing_times[i] <= completion_times[j] + max
+                )),
+                None => p,
+            }
+        })
+    });
+    // a broken soft constraint is relaxed by enough to let its right job start
+    // at 0 while its left job completes at the latest time
+    let problem =
+        instance
+            .soft_constraints
+            .iter()
+            .zip(&soft_violations)
+            .fold(problem, |prob, (soft, &y)| {
+                let big_m = latest + instance.jobs[soft.right].processing_time(1) + soft.min_lag;
+                prob.with(constraint!(
+                    completion_times[soft.left] + processing_times[soft.right] + soft.min_lag
+                        <= completion_times[soft.right] + big_m * y
+                ))
+            });
     // LP (9) from the paper
     #[expect(
         clippy::range_minus_one,
         reason = "drop last element of a 1-indexed vector, stay close to notation in paper"
     )]
     let problem = (1..=instance.processor_count - 1).fold(problem, |prob, l| {
-        (0..m as usize).fold(prob, |p, j| {
+        (0..instance.jobs.len()).fold(prob, |p, j| {
             let job = &instance.jobs[j];
             let p_j_l = job.processing_time(l);
             let p_j_lp1 = job.processing_time(l + 1);
@@ -81,108 +385,87 @@ pub fn schedule(instance: Instance, compress: bool) -> Schedule {
             p.with(constraint!(r * processing_times[j] - s <= work[j]))
         })
     });
-    let problem = problem.with(constraint!(work.iter().sum::<Expression>() / m <= makespan));
-
-    // - obtain fractional solution
-    let solution = problem
-        .solve()
-        .unwrap_or_else(|e| panic!("no solution: {e}"));
-    let processing_times = processing_times
-        .into_iter()
-        .map(|v| solution.value(v).round() as i32)
-        .collect::<Vec<_>>();
-    let completion_times = completion_times
-        .into_iter()
-        .map(|v| solution.value(v).round() as i32)
-        .collect::<Vec<_>>();
-
-    for (i, x_j) in processing_times.iter().copied().enumerate() {
-        // print solution
-        debug!("x_{i} = {x_j}");
-    }
-    for (i, c_j) in completion_times.iter().copied().enumerate() {
-        debug!("C_{i} = {c_j}");
-    }
-    // - round it to a feasible allotment
-    // - compute allotment parameter µ
-    let my = compute_my(m).floor() as usize;
-    let allotments = processing_times
-        .iter()
-        .copied()
-        .zip(instance.jobs.iter())
-        .map(|(x_j, job)| job.closest_allotment(x_j).min(my))
-        .collect::<Vec<_>>();
-    for (i, l_j) in allotments.iter().copied().enumerate() {
-        debug!("l_{i} = {l_j}");
+    let problem = problem.with(constraint!(
+        work.iter()
+            .zip(multiplicities)
+            .map(|(&w_j, &count)| count as f64 * w_j)
+            .sum::<Expression>()
+            / m
+            <= makespan
+    ));
+    Model {
+        problem,
+        makespan,
+        processing_times,
+        completion_times,
+        size,
     }
+}
 
-    // PHASE 2: list schedule
-
-    // - run LIST to generate feasible schedule
-    let mut jobs = (0..instance.jobs.len())
-        .map(|i| (i, true))
-        .collect::<Vec<_>>();
-    let mut scheduled_jobs: Vec<ScheduledJob> = vec![];
-    let mut occupation = vec![0; instance.processor_count];
-    for _ in 0..jobs.len() {
-        // find READY jobs
-        let (pick, start_time) = jobs
-            .iter()
-            .filter(|(_, available)| *available)
-            .filter_map(|&(job, _)| {
-                instance
-                    .predecessors(&instance.jobs[job])
-                    .iter()
-                    .map(|(_, p)| scheduled_jobs.iter().find(|s| s.job.index == p.index))
-                    .collect::<Option<Vec<_>>>()
-                    .map(|s| (job, s))
-            })
-            .map(|(job, scheduled_predecessors)| {
-                let allotment = allotments[job];
-                let starting_time = if compress {
-                    0
-                } else {
-                    completion_times[job] - instance.jobs[job].processing_time(allotment)
-                };
-
-                let predecessors_finished_at = scheduled_predecessors
-                    .iter()
-                    .map(|s| s.completion_time())
-                    .max()
-                    .unwrap_or(0);
-
-                let fit = occupation[occupation.len() - allotment];
+/// Computes the number of variables, constraints, and nonzeros of the linear
+/// program for the given `instance` without building it.
+pub fn model_size(instance: &Instance) -> ModelSize {
+    let n = instance.jobs.len();
+    let m = instance.processor_count;
+    let edges = (0..n)
+        .map(|j| instance.predecessor_indices(j).len())
+        .sum::<usize>();
+    let max_lags = max_lags(instance);
+    let soft = instance.soft_constraints.len();
+    ModelSize {
+        variables: 1 + 3 * n + soft,
+        constraints: n + edges + max_lags + soft + n * m.saturating_sub(1) + 1,
+        // the makespan bounds have two variables each, precedence and maximum
+        // lags three, soft constraints four, the work bounds of LP (9) two,
+        // and the average work refers to the makespan and the work of every
+        // job
+        nonzeros: 2 * n + 3 * (edges + max_lags) + 4 * soft + 2 * n * m.saturating_sub(1) + n + 1,
+    }
+}
 
-                let earliest = starting_time.max(predecessors_finished_at).max(fit);
+/// Counts the precedence constraints with a maximum lag, each of which adds a
+/// constraint to the linear program
+fn max_lags(instance: &Instance) -> usize {
+    (0..instance.jobs.len())
+        .flat_map(|j| instance.successor_indices(j).iter().map(move |&k| (j, k)))
+        .filter(|&(j, k)| instance.lag(j, k).max.is_some())
+        .count()
+}
 
-                (job, earliest)
-            })
-            // take min by starting time
-            .min_by_key(|&(_, alpha)| alpha)
-            .expect("no job ready");
-        jobs[pick].1 = false;
-        let allotment = allotments[pick];
-        let job = ScheduledJob {
-            job: instance.jobs[pick].clone(),
-            allotment,
-            start_time,
+/// Rounds the processing time `x_j` of the job at position `j` to the
+/// allotment with the closest processing time, but to at most `my`
+/// processors. Pinned jobs keep their allotment.
+fn round_to_closest(
+    instance: &Instance,
+    j: usize,
+    x_j: i32,
+    fractional: f64,
+    my: usize,
+) -> RoundingDecision {
+    if let Some(pin) = instance.pin(j) {
+        return RoundingDecision {
+            job: j,
+            fractional_values: vec![fractional],
+            chosen_allotment: pin.allotment,
+            reason: RoundingReason::Pinned,
+            criticality: None,
         };
-        // update occupation
-        let machine = occupation
-            .iter()
-            .enumerate()
-            .find(|(_, o)| **o <= start_time)
-            .expect("bad start time")
-            .0;
-        let done = job.completion_time();
-        for occ in occupation.iter_mut().skip(machine).take(allotment) {
-            *occ = done;
-        }
-        scheduled_jobs.push(job);
     }
-    Schedule {
-        processor_count: instance.processor_count,
-        jobs: scheduled_jobs,
+    let job = &instance.jobs[j];
+    let closest = job.closest_allotment(x_j);
+    let reason = if closest > my {
+        RoundingReason::CappedByMu { closest }
+    } else {
+        RoundingReason::Closest {
+            distance: (f64::from(job.processing_time(closest)) - fractional).abs(),
+        }
+    };
+    RoundingDecision {
+        job: j,
+        fractional_values: vec![fractional],
+        chosen_allotment: closest.min(my),
+        reason,
+        criticality: None,
     }
 }
 
@@ -195,16 +478,31 @@ fn compute_my(m: i32) -> f64 {
 /// Computes the critical path length CPL for the given `instance`.
 fn critical_path_length(instance: &Instance) -> i32 {
     let mut scheduler = Scheduler::<i32>::new();
-    for job in &instance.jobs {
+    for (j, job) in instance.jobs.iter().enumerate() {
+        let mut successors = vec![];
+        for &k in instance.successor_indices(j) {
+            let successor = &instance.jobs[k];
+            // minimum lags are modeled as tasks between the two jobs
+            let lag = instance.lag(j, k).min;
+            if lag > 0 {
+                let name = format!("{}-{}", job.index, successor.index);
+                scheduler
+                    .add_task(CustomTask::new(
+                        name.clone(),
+                        lag,
+                        vec![successor.index.to_string()],
+                    ))
+                    .expect("duplicate task");
+                successors.push(name);
+            } else {
+                successors.push(successor.index.to_string());
+            }
+        }
         scheduler
             .add_task(CustomTask::new(
                 job.index.to_string(),
                 job.processing_time(1),
-                instance
-                    .successors(job)
-                    .iter()
-                    .map(|(_, job)| job.index.to_string())
-                    .collect(),
+                successors,
             ))
             .expect("duplicate task");
     }
@@ -218,3 +516,177 @@ fn critical_path_length(instance: &Instance) -> i32 {
         Err(e) => panic!("{e}"),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use good_lp::{Solution, SolverModel};
+
+    use super::{build_model, round_to_closest, schedule, start_targets, Model};
+    use crate::{
+        algo::{Instance, Rounding, RoundingReason, ScheduledJob, SoftConstraint, INT_TOLERANCE},
+        builder::InstanceBuilder,
+        list::{self, ListOptions},
+    };
+
+    /// Two jobs on two processors, where the second job waits for the first
+    fn chain() -> Instance {
+        InstanceBuilder::new()
+            .processors(2)
+            .job(1)
+            .times([4, 2])
+            .job(2)
+            .times([6, 3])
+            .constraint(1, 2)
+            .build()
+            .expect("valid instance")
+    }
+
+    #[test]
+    fn linear_program_completes_predecessor_first() {
+        let Model {
+            problem,
+            processing_times,
+            completion_times,
+            ..
+        } = build_model(&chain(), None);
+        let solution = problem.solve().expect("feasible linear program");
+        let value = |variable| solution.value(variable);
+        assert!(
+            value(completion_times[0]) + value(processing_times[1])
+                <= value(completion_times[1]) + INT_TOLERANCE
+        );
+    }
+
+    /// Solves the chain and checks that the successor never starts before its
+    /// predecessor completes
+    fn check_chain(compress: bool) {
+        let (schedule, _) = schedule(
+            chain(),
+            compress,
+            INT_TOLERANCE,
+            Rounding::default(),
+            false,
+            &ListOptions::default(),
+        );
+        let job = |index| {
+            schedule
+                .jobs
+                .iter()
+                .find(|job| job.job.index == index)
+                .expect("job is scheduled")
+        };
+        assert!(job(0).completion_time() <= job(1).start_time);
+        assert!(schedule.violations(&chain()).is_empty());
+    }
+
+    #[test]
+    fn chain_without_compression() {
+        check_chain(false);
+    }
+
+    #[test]
+    fn chain_with_compression() {
+        check_chain(true);
+    }
+
+    #[test]
+    fn closest_rounding_records_its_reasons() {
+        let instance = InstanceBuilder::new()
+            .processors(3)
+            .job(1)
+            .times([6, 3, 2])
+            .build()
+            .expect("valid instance");
+        let closest = round_to_closest(&instance, 0, 3, 3.4, 2);
+        assert_eq!(closest.chosen_allotment, 2);
+        match closest.reason {
+            RoundingReason::Closest { distance } => assert!((distance - 0.4).abs() < 1e-9),
+            reason => panic!("rounded to the closest allotment with {reason:?}"),
+        }
+        let capped = round_to_closest(&instance, 0, 2, 2.0, 2);
+        assert_eq!(capped.chosen_allotment, 2);
+        assert_eq!(capped.reason, RoundingReason::CappedByMu { closest: 3 });
+
+        let job = instance.jobs[0].clone();
+        let pinned = instance.with_pinned(vec![ScheduledJob::new(job, 1, 0, vec![0])]);
+        let pin = round_to_closest(&pinned, 0, 2, 2.0, 2);
+        assert_eq!(pin.chosen_allotment, 1);
+        assert_eq!(pin.reason, RoundingReason::Pinned);
+    }
+
+    /// Solves the linear program for two jobs that take 4 time units on any
+    /// number of processors, where the second job should wait for the first
+    /// one at the given `penalty`, and returns the makespan
+    fn soft_makespan(penalty: f64) -> f64 {
+        let instance = InstanceBuilder::new()
+            .processors(2)
+            .job(1)
+            .times([4, 4])
+            .job(2)
+            .times([4, 4])
+            .build()
+            .expect("valid instance")
+            .with_soft_constraints(vec![SoftConstraint {
+                left: 0,
+                right: 1,
+                min_lag: 0,
+                penalty,
+            }]);
+        let Model {
+            problem, makespan, ..
+        } = build_model(&instance, None);
+        let solution = problem.solve().expect("feasible linear program");
+        solution.value(makespan)
+    }
+
+    #[test]
+    fn cheap_soft_constraint_is_broken() {
+        // running both jobs next to each other saves 4 time units, which is
+        // worth breaking the soft constraint only if it costs less than that
+        assert!((soft_makespan(1.0) - 4.0).abs() < INT_TOLERANCE);
+        assert!((soft_makespan(10.0) - 8.0).abs() < INT_TOLERANCE);
+    }
+
+    #[test]
+    fn targets_keep_start_times_of_linear_program() {
+        // the second job takes 6 in the linear program, between its times
+        // with one and two processors, and gets both processors
+        let instance = InstanceBuilder::new()
+            .processors(2)
+            .job(1)
+            .times([2, 2])
+            .job(2)
+            .times([8, 4])
+            .constraint(1, 2)
+            .build()
+            .expect("valid instance");
+        let (completion_times, processing_times, allotments) = ([2, 8], [2, 6], [1, 2]);
+        let targets = start_targets(
+            &instance,
+            &completion_times,
+            &processing_times,
+            &allotments,
+            None,
+        );
+        assert_eq!(targets, [0, 2]);
+        // deriving the start from the completion time of the LP and the
+        // realized processing time delays the job by the difference
+        let unadjusted = allotments
+            .iter()
+            .enumerate()
+            .map(|(j, &l)| completion_times[j] - instance.jobs[j].processing_time(l))
+            .collect::<Vec<_>>();
+        assert_eq!(unadjusted, [0, 4]);
+
+        let makespan = |targets: &[i32]| {
+            let list = list::schedule(&instance, &allotments, targets, &ListOptions::default());
+            list.jobs
+                .iter()
+                .map(ScheduledJob::completion_time)
+                .max()
+                .expect("jobs are scheduled")
+        };
+        assert_eq!(makespan(&targets), 6);
+        assert_eq!(makespan(&unadjusted), 8);
+    }
+}
@@ -0,0 +1,643 @@
+// Repairs existing schedules after the instance changed and improves them by
+// local search instead of solving the instance from scratch.
+
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    fmt,
+    time::{Duration, Instant},
+};
+
+use itertools::Itertools;
+use log::{debug, info};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    algo::{Instance, Schedule, ScheduledJob},
+    files, interrupt,
+    objective::ScheduleObjective,
+    placement::{earliest_start, Placement},
+    reproducible,
+};
+
+/// Limits of the local search and where it reports its progress
+#[derive(Debug, Default)]
+pub struct SearchOptions {
+    /// Time after which the search stops with the best schedule so far, none
+    /// for no limit
+    pub time_limit: Option<Duration>,
+    /// Path to which the best schedule so far is written whenever a pass of
+    /// the search improved it
+    pub checkpoint_best: Option<String>,
+}
+
+/// The best schedule of the local search at some point in time, for plotting
+/// how the search improves over time
+#[derive(Clone, Copy, Debug, Serialize, JsonSchema)]
+pub struct Improvement {
+    /// Milliseconds since the search started, 0 in reproducible mode
+    pub elapsed_ms: u128,
+    /// Makespan of the best schedule
+    pub makespan: i32,
+    /// Objective value of the best schedule
+    pub value: f64,
+}
+
+/// Counts the changes that were needed to make a schedule feasible again
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Repairs {
+    /// Jobs that are no longer part of the instance, or were scheduled twice
+    pub dropped: usize,
+    /// Jobs of the instance that were missing from the schedule
+    pub added: usize,
+    /// Jobs whose allotment exceeded the number of processors
+    pub reallotted: usize,
+    /// Jobs whose processing times differ from the ones in the instance
+    pub changed: usize,
+    /// Jobs that had to start later to respect their predecessors or the
+    /// number of processors
+    pub shifted: usize,
+}
+impl Repairs {
+    /// Number of jobs that were dropped, added, or moved
+    pub fn total(self) -> usize {
+        self.dropped + self.added + self.reallotted + self.shifted
+    }
+}
+impl fmt::Display for Repairs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} dropped, {} added, {} reallotted, and {} shifted jobs, {} jobs have changed processing times",
+            self.dropped, self.added, self.reallotted, self.shifted, self.changed
+        )
+    }
+}
+
+/// Turns `schedule` into a feasible schedule for `instance`. Jobs that no
+/// longer exist are dropped, and all other jobs keep their allotment and are
+/// only ever moved to later start times until their predecessors completed,
+/// their minimum lags passed, and enough processors are free. Jobs that are missing from the schedule
+/// are placed greedily after the other jobs. Pinned jobs are moved to where
+/// they are pinned, and the other jobs are placed around them. Returns the
+/// repaired schedule along with the repairs that were needed.
+pub fn repair(instance: &Instance, schedule: Schedule) -> (Schedule, Repairs) {
+    let n = instance.jobs.len();
+    let mut repairs = Repairs::default();
+    let desired = desired_placements(instance, schedule, &mut repairs);
+
+    // place the jobs in topological order, preferring early start times, so
+    // that new jobs come last unless a scheduled job depends on them
+    let mut remaining = (0..n)
+        .map(|j| instance.predecessor_indices(j).len())
+        .collect::<Vec<_>>();
+    let priority = |index: usize| desired[index].map_or(i32::MAX, |(_, start)| start);
+    let mut ready = (0..n)
+        .filter(|&j| remaining[j] == 0)
+        .map(|j| Reverse((priority(j), j)))
+        .collect::<BinaryHeap<_>>();
+    // pinned jobs never move, so all other jobs are placed around them
+    let mut placements: Vec<Option<Placement>> = vec![None; n];
+    for pin in &instance.pinned {
+        placements[pin.job.index] = Some(Placement {
+            start: pin.start_time,
+            end: pin.completion_time(),
+            allotment: pin.allotment,
+        });
+    }
+    while let Some(Reverse((_, index))) = ready.pop() {
+        let job = &instance.jobs[index];
+        let released = instance
+            .predecessor_indices(index)
+            .iter()
+            .filter_map(|&p| placements[p].map(|placed| placed.end + instance.lag(p, index).min))
+            .max()
+            .unwrap_or(0);
+        let others = placements.iter().flatten().copied().collect::<Vec<_>>();
+        let placement = if let Some(pin) = instance.pin(index) {
+            match desired[index] {
+                Some(desired) if desired == (pin.allotment, pin.start_time) => {}
+                Some(_) => {
+                    debug!("Moving job {index} to where it is pinned");
+                    repairs.shifted += 1;
+                }
+                None => repairs.added += 1,
+            }
+            placements[index].expect("pinned jobs are placed first")
+        } else if let Some((allotment, start)) = desired[index] {
+            let duration = job.processing_time(allotment);
+            let from = start.max(released);
+            let new_start = earliest_start(instance, &others, from, duration, allotment);
+            if new_start != start {
+                debug!("Shifting job {index} from {start} to {new_start}");
+                repairs.shifted += 1;
+            }
+            Placement {
+                start: new_start,
+                end: new_start + duration,
+                allotment,
+            }
+        } else {
+            repairs.added += 1;
+            let makespan = others.iter().map(|p| p.end).max().unwrap_or(0);
+            best_placement(instance, index, &others, released.max(makespan))
+        };
+        placements[index] = Some(placement);
+        for &s in instance.successor_indices(index) {
+            remaining[s] -= 1;
+            if remaining[s] == 0 {
+                ready.push(Reverse((priority(s), s)));
+            }
+        }
+    }
+
+    let schedule = Schedule {
+        processor_count: instance.processor_count,
+        jobs: placements
+            .into_iter()
+            .enumerate()
+            .map(|(index, placement)| {
+                let placement = placement.expect("precedence constraints contain a cycle");
+                ScheduledJob {
+                    job: instance.shared_job(index),
+                    allotment: placement.allotment,
+                    start_time: placement.start,
+                    processors: vec![],
+                }
+            })
+            .collect(),
+    };
+    (schedule, repairs)
+}
+
+/// Returns the allotment and start time that each job of `instance` has in
+/// `schedule`, counting the jobs that are dropped, changed, or reallotted
+fn desired_placements(
+    instance: &Instance,
+    schedule: Schedule,
+    repairs: &mut Repairs,
+) -> Vec<Option<(usize, i32)>> {
+    let mut desired: Vec<Option<(usize, i32)>> = vec![None; instance.jobs.len()];
+    for scheduled in schedule.jobs {
+        let index = scheduled.job.index;
+        let Some(job) = instance.jobs.get(index) else {
+            repairs.dropped += 1;
+            continue;
+        };
+        if desired[index].is_some() {
+            repairs.dropped += 1;
+            continue;
+        }
+        if scheduled.job.processing_times != job.processing_times {
+            repairs.changed += 1;
+        }
+        let max_allotment = instance.processor_count.min(job.max_allotment());
+        let allotment = scheduled.allotment.clamp(1, max_allotment);
+        if allotment != scheduled.allotment {
+            repairs.reallotted += 1;
+        }
+        desired[index] = Some((allotment, scheduled.start_time));
+    }
+    desired
+}
+
+/// Improves a feasible schedule by local search. Each job is moved to the
+/// allotment and start time that let it complete earliest while all other
+/// jobs stay in place, unless that makes the `objective` worse, until no job
+/// can complete any earlier. Pinned jobs are never moved. Since no job ever
+/// completes later, the schedule stays feasible and its makespan never grows,
+/// so the search can stop at any time with the best schedule so far, e.g.
+/// when it reaches the time limit of `options` or on Ctrl-C. Returns the schedule along
+/// with the trace of its improvements, which starts with the given schedule
+/// and gets a point after every pass over the jobs that improved it.
+pub fn improve(
+    instance: &Instance,
+    schedule: Schedule,
+    objective: &dyn ScheduleObjective,
+    options: &SearchOptions,
+) -> (Schedule, Vec<Improvement>) {
+    let started = Instant::now();
+    let deadline = options.time_limit.map(|limit| started + limit);
+    let mut jobs = schedule.jobs;
+    jobs.sort_by_key(|job| job.job.index);
+    let mut placements = jobs
+        .iter()
+        .map(|job| Placement {
+            start: job.start_time,
+            end: job.completion_time(),
+            allotment: job.allotment,
+        })
+        .collect::<Vec<_>>();
+
+    let best = assemble(instance, &jobs, &placements);
+    let mut value = objective.evaluate(instance, &best);
+    let mut trace = vec![Improvement {
+        elapsed_ms: 0,
+        makespan: best.makespan(),
+        value,
+    }];
+    let mut moves = 0;
+    let mut improved = true;
+    let mut timed_out = false;
+    while improved && !timed_out {
+        improved = false;
+        let mut order = (0..jobs.len())
+            .filter(|&i| instance.pin(i).is_none())
+            .collect::<Vec<_>>();
+        order.sort_by_key(|&i| (placements[i].start, i));
+        for index in order {
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) || interrupt::requested()
+            {
+                timed_out = true;
+                break;
+            }
+            let released = instance
+                .predecessor_indices(index)
+                .iter()
+                .map(|&p| placements[p].end + instance.lag(p, index).min)
+                .max()
+                .unwrap_or(0);
+            let others = placements
+                .iter()
+                .enumerate()
+                .filter(|&(i, _)| i != index)
+                .map(|(_, p)| *p)
+                .collect::<Vec<_>>();
+            let candidate = best_placement(instance, index, &others, released);
+            if candidate.end >= placements[index].end {
+                continue;
+            }
+            let previous = placements[index];
+            placements[index] = candidate;
+            let moved = objective.evaluate(instance, &assemble(instance, &jobs, &placements));
+            if moved <= value {
+                value = moved;
+                moves += 1;
+                improved = true;
+            } else {
+                placements[index] = previous;
+            }
+        }
+        if improved {
+            let best = assemble(instance, &jobs, &placements);
+            trace.push(Improvement {
+                elapsed_ms: reproducible::duration_ms(started.elapsed()),
+                makespan: best.makespan(),
+                value,
+            });
+            if let Some(path) = &options.checkpoint_best {
+                files::write_checkpoint(path, &best);
+            }
+        }
+    }
+    if timed_out && interrupt::requested() {
+        info!("Local search was interrupted after {moves} moves");
+    } else if timed_out {
+        info!(
+            "Local search reached its time limit of {:?} after {moves} moves",
+            options.time_limit.unwrap_or_default()
+        );
+    }
+    debug!(
+        "Local search moved jobs {moves} times, reaching {} {value}",
+        objective.name()
+    );
+
+    (assemble(instance, &jobs, &placements), trace)
+}
+
+/// Builds the schedule in which the `jobs` run as given by their `placements`
+/// on any free processors
+fn assemble(instance: &Instance, jobs: &[ScheduledJob], placements: &[Placement]) -> Schedule {
+    Schedule {
+        processor_count: instance.processor_count,
+        jobs: jobs
+            .iter()
+            .zip(placements)
+            .map(|(scheduled, placement)| ScheduledJob {
+                job: scheduled.job.clone(),
+                allotment: placement.allotment,
+                start_time: placement.start,
+                processors: vec![],
+            })
+            .collect(),
+    }
+}
+
+/// Finds the allotment and start time, not before `from`, that let the job
+/// with the given index complete earliest next to the `others`. Prefers
+/// fewer processors among equally good placements.
+fn best_placement(instance: &Instance, index: usize, others: &[Placement], from: i32) -> Placement {
+    let job = &instance.jobs[index];
+    let max_allotment = instance.processor_count.min(job.processing_times.len());
+    (1..=max_allotment)
+        .map(|allotment| {
+            let duration = job.processing_time(allotment);
+            let start = earliest_start(instance, others, from, duration, allotment);
+            Placement {
+                start,
+                end: start + duration,
+                allotment,
+            }
+        })
+        .min_by_key(|p| (p.end, p.allotment))
+        .expect("job has no processing times")
+}
+
+/// Gives jobs more processors where that shortens them without moving any
+/// other job. Jobs are visited in the order of their completion times, and
+/// each job keeps its start time but gets the allotment with the shortest
+/// processing time for which enough processors are idle while it runs and
+/// its successors still start within their maximum lags. Pinned jobs keep
+/// their allotments. If the jobs are placed on explicit processors, a job on
+/// consecutive processors only grows onto idle processors next to them. This
+/// repeats until no job can grow, so the schedule stays feasible and its
+/// makespan never grows.
+pub fn grow_allotments(instance: &Instance, schedule: Schedule) -> Schedule {
+    let mut jobs = schedule.jobs;
+    let placed = jobs.iter().all(|job| !job.processors.is_empty());
+    let mut position = vec![None; instance.jobs.len()];
+    for (i, job) in jobs.iter().enumerate() {
+        position[job.job.index] = Some(i);
+    }
+    let mut grown = true;
+    while grown {
+        grown = false;
+        let mut order = (0..jobs.len()).collect::<Vec<_>>();
+        order.sort_by_key(|&i| (jobs[i].completion_time(), jobs[i].job.index));
+        for i in order {
+            if let Some((allotment, processors)) = grow(instance, &jobs, &position, i, placed) {
+                let job = &mut jobs[i];
+                debug!(
+                    "Growing job {} from {} to {allotment} processors",
+                    job.job.index, job.allotment
+                );
+                job.allotment = allotment;
+                job.processors = processors;
+                grown = true;
+            }
+        }
+    }
+    Schedule {
+        processor_count: schedule.processor_count,
+        jobs,
+    }
+}
+
+/// Finds the allotment with the shortest processing time that the job at
+/// position `i` can grow to without moving, along with its processors if the
+/// jobs are `placed` on explicit processors
+fn grow(
+    instance: &Instance,
+    jobs: &[ScheduledJob],
+    position: &[Option<usize>],
+    i: usize,
+    placed: bool,
+) -> Option<(usize, Vec<usize>)> {
+    let scheduled = &jobs[i];
+    let job = &scheduled.job;
+    if instance.pin(job.index).is_some() {
+        return None;
+    }
+    let start = scheduled.start_time;
+    // successors with a maximum lag must not start too long after the job
+    let min_end = instance
+        .successor_indices(job.index)
+        .iter()
+        .filter_map(|&k| {
+            let max = instance.lag(job.index, k).max?;
+            position[k].map(|k| jobs[k].start_time - max)
+        })
+        .max()
+        .unwrap_or(i32::MIN);
+    let others = jobs
+        .iter()
+        .enumerate()
+        .filter(|&(k, _)| k != i)
+        .map(|(_, other)| Placement {
+            start: other.start_time,
+            end: other.completion_time(),
+            allotment: other.allotment,
+        })
+        .collect::<Vec<_>>();
+    let max_allotment = instance.processor_count.min(job.max_allotment());
+    (scheduled.allotment + 1..=max_allotment)
+        .filter(|&allotment| {
+            let duration = job.processing_time(allotment);
+            duration < scheduled.processing_time() && start + duration >= min_end
+        })
+        .sorted_by_key(|&allotment| (job.processing_time(allotment), allotment))
+        .find_map(|allotment| {
+            let duration = job.processing_time(allotment);
+            if earliest_start(instance, &others, start, duration, allotment) != start {
+                return None;
+            }
+            if !placed {
+                return Some((allotment, vec![]));
+            }
+            let end = start + duration;
+            let idle = |p: usize| {
+                p < instance.processor_count
+                    && job.allows(p)
+                    && !scheduled.processors.contains(&p)
+                    && jobs.iter().all(|other| {
+                        !other.processors.contains(&p)
+                            || other.completion_time() <= start
+                            || end <= other.start_time
+                    })
+            };
+            let extra = allotment - scheduled.allotment;
+            let first = scheduled.processors.iter().copied().min()?;
+            let last = scheduled.processors.iter().copied().max()?;
+            let processors = if last - first + 1 == scheduled.allotment {
+                // extend the run of processors to the left and to the right
+                (0..=extra.min(first)).find_map(|left| {
+                    let range = first - left..=last + extra - left;
+                    range
+                        .clone()
+                        .filter(|p| !(first..=last).contains(p))
+                        .all(idle)
+                        .then(|| range.collect::<Vec<_>>())
+                })?
+            } else {
+                let added = (0..instance.processor_count)
+                    .filter(|&p| idle(p))
+                    .take(extra)
+                    .collect::<Vec<_>>();
+                if added.len() < extra {
+                    return None;
+                }
+                scheduled
+                    .processors
+                    .iter()
+                    .copied()
+                    .chain(added)
+                    .sorted_unstable()
+                    .collect()
+            };
+            Some((allotment, processors))
+        })
+}
+
+/// Counts the jobs that have a larger allotment in `after` than in `before`
+pub fn grown_jobs(before: &Schedule, after: &Schedule) -> usize {
+    let mut allotments = vec![0; before.jobs.len().max(after.jobs.len())];
+    for job in &before.jobs {
+        if let Some(allotment) = allotments.get_mut(job.job.index) {
+            *allotment = job.allotment;
+        }
+    }
+    after
+        .jobs
+        .iter()
+        .filter(|job| {
+            allotments
+                .get(job.job.index)
+                .is_some_and(|&allotment| job.allotment > allotment)
+        })
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::{grow_allotments, grown_jobs, repair};
+    use crate::{
+        algo::{Instance, Schedule, ScheduledJob},
+        builder::InstanceBuilder,
+        generate::{self, GenerateConfig},
+        list,
+    };
+
+    #[test]
+    fn job_grows_onto_idle_neighbor() {
+        let instance = InstanceBuilder::new()
+            .processors(3)
+            .job(1)
+            .times([6, 3, 2])
+            .job(2)
+            .times([2, 2, 2])
+            .build()
+            .expect("valid instance");
+        let job = |index, start_time, processors: Vec<usize>| ScheduledJob {
+            job: instance.shared_job(index),
+            allotment: processors.len(),
+            start_time,
+            processors,
+        };
+        // the first job cannot take the third processor while the second job
+        // runs on it, but it can take the idle processor next to it
+        let schedule = Schedule {
+            processor_count: 3,
+            jobs: vec![job(0, 0, vec![0]), job(1, 0, vec![2])],
+        };
+        let grown = grow_allotments(&instance, schedule.clone());
+        assert_eq!(grown.violations(&instance), Vec::<String>::new());
+        assert_eq!(grown_jobs(&schedule, &grown), 1);
+        assert_eq!(grown.jobs[0].allotment, 2);
+        assert_eq!(grown.jobs[0].processors, [0, 1]);
+        assert_eq!(grown.makespan(), 3);
+        assert!(grown.makespan() < schedule.makespan());
+    }
+
+    #[test]
+    fn growing_keeps_generated_schedules_feasible() {
+        let config = GenerateConfig {
+            n: 12,
+            m: 4,
+            min_p: 1,
+            max_p: 10,
+            omega: 4,
+            min_chain: 1,
+            max_chain: 5,
+            concave: false,
+        };
+        for seed in 0..20 {
+            let instance = generate::instance(&config, &mut StdRng::seed_from_u64(seed));
+            let schedule = list::greedy(&instance);
+            assert_eq!(schedule.violations(&instance), Vec::<String>::new());
+            let grown = grow_allotments(&instance, schedule.clone());
+            assert_eq!(
+                grown.violations(&instance),
+                Vec::<String>::new(),
+                "seed {seed}"
+            );
+            assert!(grown.makespan() <= schedule.makespan(), "seed {seed}");
+        }
+    }
+
+    /// Jobs with the given processing times on two processors, where the
+    /// first job comes before the second one
+    fn chain(times: &[[i32; 2]]) -> Instance {
+        let builder = times.iter().zip(1..).fold(
+            InstanceBuilder::new().processors(2),
+            |builder, (&times, id)| builder.job(id).times(times),
+        );
+        builder.constraint(1, 2).build().expect("valid instance")
+    }
+
+    /// Runs the three jobs of `instance` one after another on both processors
+    fn sequential(instance: &Instance) -> Schedule {
+        let job = |index, start_time| ScheduledJob {
+            job: instance.shared_job(index),
+            allotment: 2,
+            start_time,
+            processors: vec![],
+        };
+        Schedule {
+            processor_count: 2,
+            jobs: vec![job(0, 0), job(1, 2), job(2, 4)],
+        }
+    }
+
+    #[test]
+    fn repair_places_added_jobs_at_the_end() {
+        let before = chain(&[[4, 2], [3, 2], [2, 2]]);
+        let after = chain(&[[4, 2], [3, 2], [2, 2], [2, 1]]);
+        let (repaired, repairs) = repair(&after, sequential(&before));
+        assert_eq!(repaired.violations(&after), Vec::<String>::new());
+        assert_eq!((repairs.added, repairs.dropped, repairs.shifted), (1, 0, 0));
+        assert_eq!(
+            repaired
+                .jobs
+                .iter()
+                .map(|job| job.start_time)
+                .collect::<Vec<_>>()[..3],
+            [0, 2, 4]
+        );
+        assert!(repaired.jobs[3].start_time >= 6);
+    }
+
+    #[test]
+    fn repair_drops_removed_jobs() {
+        let before = chain(&[[4, 2], [3, 2], [2, 2]]);
+        let after = chain(&[[4, 2], [3, 2]]);
+        let (repaired, repairs) = repair(&after, sequential(&before));
+        assert_eq!(repaired.violations(&after), Vec::<String>::new());
+        assert_eq!((repairs.added, repairs.dropped, repairs.shifted), (0, 1, 0));
+        assert_eq!(repaired.jobs.len(), 2);
+        assert_eq!(repaired.makespan(), 4);
+    }
+
+    #[test]
+    fn repair_shifts_jobs_after_longer_predecessors() {
+        let before = chain(&[[4, 2], [3, 2], [2, 2]]);
+        let after = chain(&[[6, 3], [3, 2], [2, 2]]);
+        let (repaired, repairs) = repair(&after, sequential(&before));
+        assert_eq!(repaired.violations(&after), Vec::<String>::new());
+        assert_eq!(repairs.changed, 1);
+        // the first job now ends at 3, which pushes back both jobs after it
+        assert_eq!(repairs.shifted, 2);
+        assert_eq!(
+            repaired
+                .jobs
+                .iter()
+                .map(|job| job.start_time)
+                .collect::<Vec<_>>(),
+            [0, 3, 5]
+        );
+        assert_eq!(repaired.makespan(), 7);
+    }
+}
@@ -0,0 +1,55 @@
+// Lets long runs stop early on Ctrl-C without losing their results. The first
+// Ctrl-C only sets a flag, which the local search, the dynamic program, and
+// experiments check to stop with what they have so far. A second Ctrl-C exits
+// right away. All output files are written atomically, so an exit at any
+// time never leaves a truncated file behind.
+
+use std::{
+    process,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use itertools::Itertools;
+use log::{error, warn};
+
+/// Exit status of a run that was interrupted, like that of a shell whose
+/// command was killed by SIGINT
+pub const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+/// Whether Ctrl-C was pressed
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Installs the handler of Ctrl-C for the rest of the run
+pub fn install() {
+    let installed = ctrlc::set_handler(|| {
+        if INTERRUPTED.swap(true, Ordering::Relaxed) {
+            error!("Interrupted again, exiting right away");
+            process::exit(INTERRUPTED_EXIT_CODE);
+        }
+        warn!("Interrupted, finishing with the results so far");
+        warn!("  hint: Press Ctrl-C again to exit right away");
+    });
+    if let Err(e) = installed {
+        warn!("Cannot handle Ctrl-C, so interrupting loses all results: {e}");
+    }
+}
+
+/// Checks whether Ctrl-C was pressed, in which case long loops should stop
+/// with their results so far
+pub fn requested() -> bool {
+    INTERRUPTED.load(Ordering::Relaxed)
+}
+
+/// Exits with `INTERRUPTED_EXIT_CODE` after an interruption, naming the files
+/// to which the partial results were written
+pub fn finish(written: &[String]) -> ! {
+    if written.is_empty() {
+        error!("Interrupted, no partial results were written");
+    } else {
+        error!(
+            "Interrupted, partial results are written to {}",
+            written.iter().join(", ")
+        );
+    }
+    process::exit(INTERRUPTED_EXIT_CODE);
+}
@@ -0,0 +1,551 @@
+// The LIST phase that both linear programming algorithms use to turn
+// allotments and target start times into a feasible schedule.
+
+use std::{cmp::Reverse, collections::HashMap, fmt};
+
+use itertools::Itertools;
+use log::{debug, info};
+use rand::{rngs::StdRng, seq::IndexedRandom, SeedableRng};
+
+use crate::{
+    algo::{Instance, Schedule, ScheduledJob},
+    diagnostics::{self, Category},
+    improve,
+    objective::{Makespan, ScheduleObjective},
+    placement::{earliest_start, ProcessorPool},
+    reproducible,
+};
+
+/// Options of the list scheduler
+#[derive(Debug, Default)]
+pub struct ListOptions {
+    /// Place every job on processors with consecutive indices
+    pub contiguous: bool,
+    /// Seed of the random choice among jobs that can start at the same time.
+    /// Without a seed, the job with the smallest index is picked.
+    pub seed: Option<u64>,
+    /// Number of runs with consecutive seeds of which the best schedule is
+    /// kept. The first seed is chosen by `reproducible::seed` if no seed is
+    /// given.
+    pub multi_start: Option<usize>,
+    /// Objective by which the best of several runs is chosen, the makespan
+    /// if there is none
+    pub objective: Option<Box<dyn ScheduleObjective>>,
+    /// Give jobs more processors after every run where that shortens them
+    /// without moving other jobs, see `improve::grow_allotments`
+    pub grow_allotments: bool,
+    /// Stop as soon as no job can start before this time, which leaves all
+    /// remaining jobs out of the schedule, see `Schedule::partial_violations`
+    pub horizon_limit: Option<i32>,
+}
+
+/// The outcome of list scheduling
+#[derive(Debug)]
+pub struct ListSchedule {
+    /// The scheduled jobs in the order in which they were picked
+    pub jobs: Vec<ScheduledJob>,
+    /// Delay of every job behind its target start time
+    pub target_deviations: Vec<i32>,
+    /// Seed of the run that produced the jobs, if ties were broken randomly
+    pub seed: Option<u64>,
+}
+
+/// Schedules every job with its allotment, not before its target start time,
+/// and as soon as its predecessors are done and enough processors are free
+/// for its whole duration according to the capacity profile and the downtime
+/// of the processors. Jobs only run on the processors of their affinity,
+/// which caps their allotment. Pinned jobs keep their start times and
+/// processors, ignoring their allotments and targets.
+/// Soft constraints are respected as if they were hard ones, from the largest
+/// penalty to the smallest, unless they would close a cycle.
+/// With a horizon limit, the schedule is partial and only holds the jobs
+/// that start before the limit, along with the pinned jobs.
+/// With --multi-start, the schedule with the best objective value, by default
+/// the smallest makespan, among those that satisfy the instance is kept.
+/// Schedules that equal an earlier one up to the names of the processors are
+/// not checked against the instance again.
+pub fn schedule(
+    instance: &Instance,
+    allotments: &[usize],
+    targets: &[i32],
+    options: &ListOptions,
+) -> ListSchedule {
+    if !instance.soft_constraints.is_empty() {
+        let enforced = instance.enforce_soft(|_| true);
+        return schedule(&enforced, allotments, targets, options);
+    }
+    let Some(starts) = options.multi_start else {
+        let mut rng = options.seed.map(StdRng::seed_from_u64);
+        let (jobs, target_deviations) = run(instance, allotments, targets, options, rng.as_mut());
+        return ListSchedule {
+            jobs,
+            target_deviations,
+            seed: options.seed,
+        };
+    };
+    let first_seed = options.seed.unwrap_or_else(reproducible::seed);
+    let objective = options.objective.as_deref().unwrap_or(&Makespan);
+    let mut makespans = vec![];
+    // whether each distinct schedule satisfies the instance, by canonical hash
+    let mut seen = HashMap::new();
+    let mut best: Option<(bool, f64, ListSchedule)> = None;
+    for seed in (0..starts.max(1) as u64).map(|i| first_seed.wrapping_add(i)) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let (jobs, target_deviations) = run(instance, allotments, targets, options, Some(&mut rng));
+        let schedule = Schedule {
+            processor_count: instance.processor_count,
+            jobs,
+        };
+        let makespan = schedule.makespan();
+        makespans.push(makespan);
+        let feasible = *seen.entry(schedule.canonical_hash()).or_insert_with(|| {
+            let violations = if options.horizon_limit.is_some() {
+                schedule.partial_violations(instance)
+            } else {
+                schedule.violations(instance)
+            };
+            if !violations.is_empty() {
+                debug!(
+                    "Start from seed {seed} violates the instance: {}",
+                    violations.join(", ")
+                );
+            }
+            violations.is_empty()
+        });
+        // prefer feasible schedules, then good ones
+        let value = objective.evaluate(instance, &schedule);
+        if best.as_ref().is_none_or(|&(best_feasible, best_value, _)| {
+            (!feasible, value) < (!best_feasible, best_value)
+        }) {
+            best = Some((
+                feasible,
+                value,
+                ListSchedule {
+                    jobs: schedule.jobs,
+                    target_deviations,
+                    seed: Some(seed),
+                },
+            ));
+        }
+    }
+    let (feasible, value, best) = best.expect("at least one start");
+    makespans.sort_unstable();
+    info!(
+        "Makespans of {} starts with {} distinct schedules are {} at minimum, {} at median, and {} at maximum, keeping the schedule from seed {}",
+        makespans.len(),
+        seen.len(),
+        makespans[0],
+        makespans[makespans.len() / 2],
+        makespans[makespans.len() - 1],
+        best.seed.unwrap_or_default()
+    );
+    if objective.name() != Makespan.name() {
+        info!("Kept the schedule with {} {value}", objective.name());
+    }
+    if !feasible {
+        diagnostics::report(
+            Category::Verification,
+            "No start produced a schedule that satisfies the instance",
+        );
+    }
+    best
+}
+
+/// A READY job, i.e. a job that is not scheduled yet and whose predecessors
+/// are all scheduled
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReadyJob {
+    /// Position of the job in the instance
+    pub job: usize,
+    /// Time before which the job must not start
+    pub target: i32,
+    /// Earliest time at which the job can start
+    pub earliest: i32,
+}
+
+/// How `pick_next` chooses among the READY jobs that can start earliest
+#[derive(Debug)]
+pub enum PriorityRule<'a> {
+    /// The job with the smallest position
+    SmallestIndex,
+    /// A job chosen at random
+    Random(&'a mut StdRng),
+}
+
+/// No job is READY although some jobs are not scheduled, because all of them
+/// wait for each other
+#[derive(Debug, PartialEq, Eq)]
+pub struct Blocked {
+    /// Positions of the jobs that are not scheduled
+    pub pending: Vec<usize>,
+}
+impl fmt::Display for Blocked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "jobs {} wait for each other, e.g. because the constraints contain a cycle",
+            self.pending.iter().join(", ")
+        )
+    }
+}
+
+/// Some jobs cannot be placed at all, because none of the processors in their
+/// affinities exists
+#[derive(Debug, PartialEq, Eq)]
+pub struct Unplaceable {
+    /// Positions of the jobs that cannot be placed
+    pub jobs: Vec<usize>,
+}
+impl fmt::Display for Unplaceable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "jobs {} may not run on any of the processors",
+            self.jobs.iter().join(", ")
+        )
+    }
+}
+
+/// Finds the READY jobs among the `pending` ones, sorted by position, where
+/// the jobs that completed so far are given by `completed_at`. Every READY
+/// job can start no earlier than its target, than the minimum lags after its
+/// predecessors complete, and than the first time at which `pool` has its
+/// allotment of processors free for its whole duration and the capacity
+/// allows it. Jobs with a predecessor that is not scheduled yet are not READY,
+/// even if they could start before every READY job, so LIST may pick a job
+/// that waits for a late predecessor while processors idle. Fails if jobs are
+/// pending but none of them is READY.
+pub fn ready_jobs(
+    instance: &Instance,
+    allotments: &[usize],
+    targets: &[i32],
+    pending: &[bool],
+    completed_at: &[Option<i32>],
+    pool: &ProcessorPool,
+) -> Result<Vec<ReadyJob>, Blocked> {
+    let ready = (0..instance.jobs.len())
+        .filter(|&job| pending[job])
+        .filter_map(|job| {
+            let released = instance
+                .predecessor_indices(job)
+                .iter()
+                .map(|&p| completed_at[p].map(|c| c + instance.lag(p, job).min))
+                .try_fold(0, |latest, completion| Some(latest.max(completion?)))?;
+            let allotment = allotments[job];
+            let target = targets[job];
+            let ready = target.max(released);
+            let duration = instance.jobs[job].processing_time(allotment);
+            let affinity = instance.jobs[job].affinity.as_deref();
+
+            // earliest time at which `allotment` processors are free for the
+            // whole duration, which the capacity profile may push further
+            // back, where the processors may be busy again
+            let mut earliest = pool.earliest_fit(allotment, ready, duration, affinity);
+            while !instance.capacity.is_empty() {
+                let allowed =
+                    earliest_start(instance, pool.placed(), earliest, duration, allotment);
+                if allowed == earliest {
+                    break;
+                }
+                earliest = pool.earliest_fit(allotment, allowed, duration, affinity);
+            }
+            Some(ReadyJob {
+                job,
+                target,
+                earliest,
+            })
+        })
+        .collect::<Vec<_>>();
+    if ready.is_empty() && pending.contains(&true) {
+        return Err(Blocked {
+            pending: pending.iter().positions(|&p| p).collect(),
+        });
+    }
+    Ok(ready)
+}
+
+/// Picks the next job to schedule among the `ready` jobs, which is one of
+/// those that can start earliest, chosen by `rule`. Returns its index in
+/// `ready`, which must not be empty.
+pub fn pick_next(ready: &[ReadyJob], rule: PriorityRule) -> usize {
+    let earliest = ready
+        .iter()
+        .map(|job| job.earliest)
+        .min()
+        .expect("no job ready");
+    let candidates = ready
+        .iter()
+        .positions(|job| job.earliest == earliest)
+        .collect::<Vec<_>>();
+    *match rule {
+        PriorityRule::SmallestIndex => candidates.first(),
+        PriorityRule::Random(rng) => candidates.choose(rng),
+    }
+    .expect("some job starts earliest")
+}
+
+/// Runs LIST once. Among the jobs that can start earliest, `rng` picks one at
+/// random, or the one with the smallest index is picked if there is no `rng`.
+/// If the remaining jobs wait for each other, they are left out and reported.
+/// If a job has no processor in its affinity, only the pinned jobs are placed
+/// and the problem is reported.
+fn run(
+    instance: &Instance,
+    allotments: &[usize],
+    targets: &[i32],
+    options: &ListOptions,
+    mut rng: Option<&mut StdRng>,
+) -> (Vec<ScheduledJob>, Vec<i32>) {
+    let mut pending = vec![true; instance.jobs.len()];
+    let mut scheduled_jobs: Vec<ScheduledJob> = vec![];
+    let mut completed_at: Vec<Option<i32>> = vec![None; instance.jobs.len()];
+    let mut target_deviations = vec![0; instance.jobs.len()];
+    let mut pool = ProcessorPool::new(instance.processor_count, options.contiguous);
+    pool.block_downtime(&instance.downtime);
+    // pinned jobs are placed first, and all other jobs fit around them
+    for pin in &instance.pinned {
+        let index = pin.job.index;
+        pool.occupy_exactly(&pin.processors, pin.start_time, pin.completion_time());
+        pending[index] = false;
+        completed_at[index] = Some(pin.completion_time());
+        scheduled_jobs.push(pin.clone());
+    }
+    let allotments = match fit_affinities(instance, &pool, allotments) {
+        Ok(allotments) => allotments,
+        Err(unplaceable) => {
+            diagnostics::report_error(
+                Category::Input,
+                format!("LIST only placed the pinned jobs because {unplaceable}"),
+            );
+            return (scheduled_jobs, target_deviations);
+        }
+    };
+    for _ in instance.pinned.len()..pending.len() {
+        let ready = match ready_jobs(
+            instance,
+            &allotments,
+            targets,
+            &pending,
+            &completed_at,
+            &pool,
+        ) {
+            Ok(ready) => ready,
+            Err(blocked) => {
+                diagnostics::report(Category::Model, format!("LIST stopped because {blocked}"));
+                break;
+            }
+        };
+        let rule = match rng.as_deref_mut() {
+            Some(rng) => PriorityRule::Random(rng),
+            None => PriorityRule::SmallestIndex,
+        };
+        let next = pick_next(&ready, rule);
+        let ReadyJob {
+            job: pick,
+            target,
+            earliest: start_time,
+        } = ready[next];
+        if options
+            .horizon_limit
+            .is_some_and(|limit| start_time >= limit)
+        {
+            debug!(
+                "Stopping at time {start_time}, deferring {} jobs",
+                pending.iter().filter(|&&p| p).count()
+            );
+            break;
+        }
+        pending[pick] = false;
+        target_deviations[pick] = start_time - target;
+        let allotment = allotments[pick];
+        let done = start_time + instance.jobs[pick].processing_time(allotment);
+        let job = ScheduledJob {
+            job: instance.shared_job(pick),
+            allotment,
+            start_time,
+            processors: pool.occupy(
+                allotment,
+                start_time,
+                done,
+                instance.jobs[pick].affinity.as_deref(),
+            ),
+        };
+        completed_at[pick] = Some(done);
+        scheduled_jobs.push(job);
+    }
+    if !options.grow_allotments {
+        return (scheduled_jobs, target_deviations);
+    }
+    let listed = Schedule {
+        processor_count: instance.processor_count,
+        jobs: scheduled_jobs,
+    };
+    let grown = improve::grow_allotments(instance, listed.clone());
+    debug!(
+        "Grew the allotments of {} jobs after list scheduling",
+        improve::grown_jobs(&listed, &grown)
+    );
+    (grown.jobs, target_deviations)
+}
+
+/// Reduces the allotments of all jobs to the largest ones with which they
+/// can be placed in the `pool` on the processors of their affinities. Fails if
+/// the affinity of a job contains none of the processors in the `pool`.
+fn fit_affinities(
+    instance: &Instance,
+    pool: &ProcessorPool,
+    allotments: &[usize],
+) -> Result<Vec<usize>, Unplaceable> {
+    let fitted = instance
+        .jobs
+        .iter()
+        .zip(allotments)
+        .map(|(job, &allotment)| {
+            let max = pool.max_allotment(job.affinity.as_deref());
+            if allotment > max && max > 0 {
+                debug!(
+                    "Reducing the allotment of job {} from {allotment} to {max} to fit its affinity",
+                    job.index
+                );
+            }
+            allotment.min(max)
+        })
+        .collect::<Vec<_>>();
+    let jobs = fitted.iter().positions(|&a| a == 0).collect::<Vec<_>>();
+    if jobs.is_empty() {
+        Ok(fitted)
+    } else {
+        Err(Unplaceable { jobs })
+    }
+}
+
+/// Schedules every job with the allotment that has the least work as early as
+/// possible, which needs no linear program
+pub fn greedy(instance: &Instance) -> Schedule {
+    let allotments = instance
+        .jobs
+        .iter()
+        .map(|job| {
+            (1..=job.processing_times.len())
+                .min_by_key(|&l| l as i32 * job.processing_time(l))
+                .unwrap_or(1)
+        })
+        .collect::<Vec<_>>();
+    let targets = vec![0; instance.jobs.len()];
+    let scheduled = schedule(instance, &allotments, &targets, &ListOptions::default());
+    Schedule {
+        processor_count: instance.processor_count,
+        jobs: scheduled.jobs,
+    }
+}
+
+/// Logs the jobs that missed their target start times by the most and
+/// returns the largest delay
+pub fn max_target_deviation(target_deviations: &[i32]) -> i32 {
+    for (j, delta) in target_deviations
+        .iter()
+        .copied()
+        .enumerate()
+        .filter(|&(_, delta)| delta > 0)
+        .sorted_by_key(|&(_, delta)| Reverse(delta))
+        .take(5)
+    {
+        debug!("Job {j} missed its target start time by {delta}");
+    }
+    target_deviations.iter().copied().max().unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::InstanceBuilder;
+
+    /// Two short jobs and a long one on two processors. Picking the jobs by
+    /// index runs both short jobs first and the long one after them, while
+    /// starting the long job right away saves a time unit.
+    fn short_jobs_first() -> Instance {
+        InstanceBuilder::new()
+            .processors(2)
+            .job(1)
+            .times([1, 1])
+            .job(2)
+            .times([1, 1])
+            .job(3)
+            .times([2, 2])
+            .build()
+            .expect("valid instance")
+    }
+
+    fn makespan(instance: &Instance, options: &ListOptions) -> i32 {
+        let list = schedule(instance, &[1, 1, 1], &[0, 0, 0], options);
+        let schedule = Schedule {
+            processor_count: instance.processor_count,
+            jobs: list.jobs,
+        };
+        assert_eq!(schedule.violations(instance), Vec::<String>::new());
+        schedule.makespan()
+    }
+
+    #[test]
+    fn multi_start_improves_on_single_run() {
+        let instance = short_jobs_first();
+        let single = makespan(&instance, &ListOptions::default());
+        let multi = makespan(
+            &instance,
+            &ListOptions {
+                seed: Some(0),
+                multi_start: Some(20),
+                ..ListOptions::default()
+            },
+        );
+        assert_eq!(single, 3);
+        assert_eq!(multi, 2);
+    }
+
+    /// A long job between two short ones on three processors, followed by a
+    /// job on two processors. Once the short jobs are done, the two outer
+    /// processors are free, but they are not next to each other.
+    fn split_by_long_job() -> Instance {
+        InstanceBuilder::new()
+            .processors(3)
+            .job(1)
+            .times([1, 1, 1])
+            .job(2)
+            .times([5, 5, 5])
+            .job(3)
+            .times([1, 1, 1])
+            .job(4)
+            .times([2, 1, 1])
+            .build()
+            .expect("valid instance")
+    }
+
+    #[test]
+    fn non_contiguous_placement_packs_tighter() {
+        let instance = split_by_long_job();
+        let makespan = |contiguous| {
+            let list = schedule(
+                &instance,
+                &[1, 1, 1, 2],
+                &[0; 4],
+                &ListOptions {
+                    contiguous,
+                    ..ListOptions::default()
+                },
+            );
+            let schedule = Schedule {
+                processor_count: instance.processor_count,
+                jobs: list.jobs,
+            };
+            assert_eq!(schedule.violations(&instance), Vec::<String>::new());
+            let wide = schedule
+                .jobs
+                .iter()
+                .find(|job| job.job.index == 3)
+                .expect("job is scheduled");
+            (schedule.makespan(), wide.processors.clone())
+        };
+        assert_eq!(makespan(true), (6, vec![0, 1]));
+        assert_eq!(makespan(false), (5, vec![0, 2]));
+    }
+}
@@ -1,149 +1,174 @@
 // This file contains the implementation of the older LP algorithm Paper by Jansen and Zhang.
 
-use log::debug;
+use clap::ValueEnum;
+use log::{debug, info};
 
 use cpm_rs::{CustomTask, Scheduler};
 use good_lp::{
     constraint, default_solver, variable, variables, Expression, Solution, SolverModel, Variable,
 };
 
-use crate::algo::{Instance, Job, Schedule, ScheduledJob};
+use crate::{
+    algo::{
+        to_integer, Instance, Job, ModelSize, Rounding, RoundingDecision, RoundingReason, Schedule,
+        ScheduledJob, SolverStats,
+    },
+    diagnostics::{self, Category},
+    list::{self, ListOptions},
+    preprocess::{self, Groups},
+};
+
+/// How the allotments are derived from the solution of the linear program
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum RoundingStrategy {
+    /// The longest processing time whose virtual processing time reaches ρ
+    /// times it, as in the paper
+    #[default]
+    Threshold,
+    /// Starts from the threshold allotment, but lets jobs with slack use
+    /// fewer processors as long as the longest path of the linear program
+    /// does not grow, so that only critical jobs keep larger allotments
+    DualGuided,
+}
 
 #[expect(clippy::too_many_lines, clippy::needless_pass_by_value)]
 /// Computes a schedule for the given `instance` using a linear programming approach.
-pub fn schedule(instance: Instance, compress: bool) -> Schedule {
+/// Values of the linear program within `int_tolerance` of an integer are
+/// treated as integers. The allotments are chosen by the rounding `strategy`
+/// and turned into a schedule by LIST with the given `list_options`. With
+/// `group_identical`, interchangeable jobs share their variables, see
+/// `preprocess::identical_jobs`, and LIST aims to start them in waves that fit
+/// next to each other.
+pub fn schedule(
+    instance: Instance,
+    compress: bool,
+    int_tolerance: f64,
+    strategy: RoundingStrategy,
+    group_identical: bool,
+    list_options: &ListOptions,
+) -> (Schedule, SolverStats) {
     // initialization step
+    if !instance.capacity.is_empty() {
+        diagnostics::report(
+            Category::Model,
+            "The linear program does not model the capacity profile, only the list scheduling respects it",
+        );
+    }
+    if !instance.downtime.is_empty() {
+        diagnostics::report(
+            Category::Model,
+            "The linear program does not model the downtime, only the list scheduling respects it",
+        );
+    }
+    if instance.has_affinity() {
+        diagnostics::report(
+            Category::Model,
+            "The linear program does not model processor affinity, only the list scheduling respects it",
+        );
+    }
     let m = instance.processor_count;
     let rho = compute_rho(m);
+    let groups = group_identical.then(|| preprocess::identical_jobs(&instance));
 
     // PHASE 1: linear program
     // - define linear program
-    let cpl = critical_path_length(&instance);
-    let total_processing_time = instance
-        .jobs
-        .iter()
-        .map(|job| job.processing_time(1))
-        .sum::<i32>();
-    let mut vars = variables!();
-    let makespan = vars.add(variable().min(0));
-    let total_work = vars.add(variable().min(0));
-    let completion_times = instance
-        .jobs
-        .iter()
-        .map(|_| vars.add(variable().clamp(0, cpl)))
-        .collect::<Vec<_>>();
-    let processing_times = instance
-        .jobs
-        .iter()
-        .map(|job| vars.add(variable().clamp(0, job.processing_time(1))))
-        .collect::<Vec<_>>();
-    let virtual_processing_times = instance
-        .jobs
-        .iter()
-        .map(|_| {
-            (0..m)
-                .map(|_| vars.add(variable().min(0)))
-                .collect::<Vec<_>>()
-        })
-        .collect::<Vec<_>>();
-
-    let problem = vars.minimise(makespan).using(default_solver);
-
-    let problem = instance
-        .jobs
-        .iter()
-        .enumerate()
-        .fold(problem, |prob, (j, job)| {
-            instance
-                .successors(job)
-                .into_iter()
-                .fold(prob, |p, (k, _)| {
-                    p.with(constraint!(
-                        completion_times[j] + processing_times[k] <= completion_times[k]
-                    ))
-                })
-        });
-    let problem = instance
-        .jobs
-        .iter()
-        .enumerate()
-        .fold(problem, |prob, (j, _)| {
-            (0..m).fold(prob, |p, i| {
-                p.with(constraint!(
-                    virtual_processing_times[j][i] <= processing_times[j]
-                ))
-            })
-        });
-    let problem = instance
-        .jobs
-        .iter()
-        .enumerate()
-        .fold(problem, |prob, (j, job)| {
-            (1..m).fold(prob, |p, i| {
-                p.with(constraint!(
-                    virtual_processing_times[j][i] <= job.processing_time(i)
-                ))
-            })
-        });
-    let problem = instance
-        .jobs
-        .iter()
-        .enumerate()
-        .fold(problem, |prob, (j, job)| {
-            prob.with(constraint!(
-                virtual_processing_times[j][m - 1] == job.processing_time(m)
-            ))
-        });
-    let problem = problem
-        .with(constraint!(
-            instance
-                .jobs
-                .iter()
-                .enumerate()
-                .map(|(j, job)| w_hat_j(m, &virtual_processing_times[j], job))
-                .sum::<Expression>()
-                + total_processing_time
-                <= total_work
-        ))
-        .with(constraint!(cpl <= makespan))
-        .with(constraint!(total_work / (m as i32) <= makespan));
+    let Model {
+        problem,
+        makespan,
+        completion_times,
+        processing_times,
+        virtual_processing_times,
+        size: _,
+    } = build_model(&instance, groups.as_ref());
 
     // - obtain fractional solution
     let solution = problem
         .solve()
         .unwrap_or_else(|e| panic!("no solution: {e}"));
 
-    debug!("Believe makespan to be {}", solution.value(makespan));
+    // LIST respects the soft constraints that the linear program keeps
+    let enforced = instance.enforce_soft(|soft| {
+        let kept = solution.value(completion_times[soft.left])
+            + solution.value(processing_times[soft.right])
+            + f64::from(soft.min_lag)
+            <= solution.value(completion_times[soft.right]) + int_tolerance;
+        if !kept {
+            debug!(
+                "Linear program breaks soft constraint {} -> {}",
+                soft.left, soft.right
+            );
+        }
+        kept
+    });
+
+    let lp_bound = solution.value(makespan);
+    debug!("Believe makespan to be {lp_bound}");
+
+    let fractional_processing_times = processing_times
+        .iter()
+        .map(|&v| solution.value(v))
+        .collect::<Vec<_>>();
+    let (earliest, latest) = windows(&instance, &fractional_processing_times);
+    let criticality = criticality(
+        &fractional_processing_times,
+        &earliest,
+        &latest,
+        int_tolerance,
+    );
 
     let completion_times = completion_times
         .into_iter()
-        .map(|v| solution.value(v).round() as i32)
+        .map(|v| to_integer(solution.value(v), int_tolerance, Rounding::Up))
         .collect::<Vec<_>>();
 
     for (i, c_j) in completion_times.iter().copied().enumerate() {
         debug!("C_{i} = {c_j}");
     }
 
-    let allotments = virtual_processing_times
+    // rounding completion times up and processing times down preserves the
+    // precedence constraints, except for values that were snapped
+    let rounded_processing_times = processing_times
+        .iter()
+        .map(|&v| to_integer(solution.value(v), int_tolerance, Rounding::Down))
+        .collect::<Vec<_>>();
+    for j in 0..instance.jobs.len() {
+        for &k in instance.successor_indices(j) {
+            let lag = instance.lag(j, k).min;
+            if completion_times[j] + rounded_processing_times[k] + lag > completion_times[k] {
+                diagnostics::report(
+                    Category::Rounding,
+                    format!(
+                        "Rounding violates C_{j} + p_{k} + {lag} <= C_{k} with C_{j} = {}, p_{k} = {}, C_{k} = {}",
+                        completion_times[j], rounded_processing_times[k], completion_times[k]
+                    ),
+                );
+            }
+        }
+    }
+
+    let mut rounding = virtual_processing_times
         .into_iter()
         .enumerate()
         .map(|(j, vec)| {
-            vec.into_iter()
-                .zip(1..=m)
-                .map(|(var, i)| {
-                    let val = solution.value(var);
-                    debug!("x_{j}_{i} = {val}");
-                    let p_j_i = instance.jobs[j].processing_time(i);
-                    if val < f64::from(p_j_i) * rho {
-                        (i, 0)
-                    } else {
-                        (i, p_j_i)
-                    }
-                })
-                .max_by_key(|&(_, p)| p)
-                .map_or(0, |(i, _)| i)
+            let fractional_values = vec
+                .into_iter()
+                .map(|var| solution.value(var))
+                .collect::<Vec<_>>();
+            round_by_threshold(&instance, j, fractional_values, rho, criticality[j])
         })
         .collect::<Vec<_>>();
+    if strategy == RoundingStrategy::DualGuided {
+        relax(&instance, &mut rounding, &latest, int_tolerance);
+    }
+    for pin in &instance.pinned {
+        let decision = &mut rounding[pin.job.index];
+        decision.chosen_allotment = pin.allotment;
+        decision.reason = RoundingReason::Pinned;
+    }
+    let allotments = rounding
+        .iter()
+        .map(|decision| decision.chosen_allotment)
+        .collect::<Vec<_>>();
 
     for (i, l_j) in allotments.iter().copied().enumerate() {
         debug!("l_{i} = {l_j}");
@@ -151,70 +176,346 @@ pub fn schedule(instance: Instance, compress: bool) -> Schedule {
 
     // PHASE 2: list schedule
     // - run LIST to generate feasible schedule
-    let mut jobs = (0..instance.jobs.len())
-        .map(|i| (i, true))
+    let targets = allotments
+        .iter()
+        .copied()
+        .enumerate()
+        .map(|(job, allotment)| {
+            // rounding may have increased the processing time, so the target
+            // derived from the LP can be negative
+            if compress {
+                0
+            } else {
+                let p = instance.jobs[job].processing_time(allotment);
+                // the jobs of a group share their completion time in the LP,
+                // but only as many of them as fit next to each other can
+                // run at the same time
+                let waves = groups
+                    .as_ref()
+                    .map_or(0, |groups| groups.waves_before(job, m / allotment.max(1)));
+                (completion_times[job] - p).max(0) + waves as i32 * p
+            }
+        })
         .collect::<Vec<_>>();
-    let mut scheduled_jobs: Vec<ScheduledJob> = vec![];
-    let mut occupation = vec![0; instance.processor_count];
-    for _ in 0..jobs.len() {
-        // find READY jobs
-        let (pick, start_time) = jobs
+    let list = list::schedule(&enforced, &allotments, &targets, list_options);
+    let max_target_deviation =
+        (!compress).then(|| list::max_target_deviation(&list.target_deviations));
+    (
+        Schedule {
+            processor_count: instance.processor_count,
+            jobs: list.jobs,
+        },
+        SolverStats {
+            lp_bound: Some(lp_bound),
+            rho: Some(rho),
+            mu: None,
+            max_target_deviation,
+            rounding,
+            seed: list.seed,
+        },
+    )
+}
+
+/// The linear program of an instance along with the variables that the
+/// rounding reads from its solution
+pub struct Model<P> {
+    problem: P,
+    makespan: Variable,
+    completion_times: Vec<Variable>,
+    processing_times: Vec<Variable>,
+    virtual_processing_times: Vec<Vec<Variable>>,
+    /// Size of the linear program
+    pub size: ModelSize,
+}
+
+/// Builds the linear program for the given `instance` without solving it.
+/// With `groups`, the linear program only has variables for the first job of
+/// every group, which the other jobs of the group share, and counts the work
+/// of every group once per job in it.
+pub fn build_model(instance: &Instance, groups: Option<&Groups>) -> Model<impl SolverModel> {
+    let Some(groups) = groups else {
+        return build_weighted_model(instance, &vec![1; instance.jobs.len()]);
+    };
+    let model = build_weighted_model(&groups.quotient(instance), &groups.multiplicities());
+    info!(
+        "Grouping {} jobs into {} groups of identical jobs shrinks the linear program by a factor of {:.1}",
+        groups.of.len(),
+        groups.members.len(),
+        model_size(instance).total() as f64 / model.size.total() as f64
+    );
+    Model {
+        problem: model.problem,
+        makespan: model.makespan,
+        completion_times: groups.expand(&model.completion_times),
+        processing_times: groups.expand(&model.processing_times),
+        virtual_processing_times: groups.expand(&model.virtual_processing_times),
+        size: model.size,
+    }
+}
+
+#[expect(clippy::too_many_lines)]
+/// Builds the linear program for the given `instance`, in which the job at
+/// every position stands for as many jobs as its entry in `multiplicities`
+fn build_weighted_model(instance: &Instance, multiplicities: &[usize]) -> Model<impl SolverModel> {
+    let m = instance.processor_count;
+    let cpl = critical_path_length(instance);
+    let total_processing_time = instance
+        .jobs
+        .iter()
+        .zip(multiplicities)
+        .map(|(job, &count)| count as i32 * job.processing_time(1))
+        .sum::<i32>();
+    let mut vars = variables!();
+    let makespan = vars.add(variable().min(0));
+    let total_work = vars.add(variable().min(0));
+    // pinned jobs are fixed to their completion times, which may lie after
+    // the critical path, and so may their successors
+    let latest = cpl
+        + instance
+            .pinned
             .iter()
-            .filter(|(_, available)| *available)
-            .filter_map(|&(job, _)| {
-                instance
-                    .predecessors(&instance.jobs[job])
-                    .iter()
-                    .map(|(_, p)| scheduled_jobs.iter().find(|s| s.job.index == p.index))
-                    .collect::<Option<Vec<_>>>()
-                    .map(|s| (job, s))
+            .map(ScheduledJob::completion_time)
+            .max()
+            .unwrap_or(0);
+    let completion_times = (0..instance.jobs.len())
+        .map(|j| {
+            vars.add(match instance.pin(j) {
+                Some(pin) => variable().clamp(pin.completion_time(), pin.completion_time()),
+                None => variable().clamp(0, latest),
+            })
+        })
+        .collect::<Vec<_>>();
+    let processing_times = instance
+        .jobs
+        .iter()
+        .enumerate()
+        .map(|(j, job)| {
+            vars.add(match instance.pin(j) {
+                Some(pin) => variable().clamp(pin.processing_time(), pin.processing_time()),
+                None => variable().clamp(0, job.processing_time(1)),
             })
-            .map(|(job, scheduled_predecessors)| {
-                let allotment = allotments[job];
-                let starting_time = if compress {
-                    0
-                } else {
-                    completion_times[job] - instance.jobs[job].processing_time(allotment)
-                };
+        })
+        .collect::<Vec<_>>();
+    // The bounds p_j(i) of the virtual processing times, and fixing the last
+    // one to p_j(m), are expressed as variable bounds instead of constraints.
+    let virtual_processing_times = instance
+        .jobs
+        .iter()
+        .map(|job| {
+            (0..m)
+                .map(|i| {
+                    let lower = if i == m - 1 {
+                        job.processing_time(m)
+                    } else {
+                        0
+                    };
+                    let upper = if i >= 1 {
+                        Some(job.processing_time(i))
+                    } else if i == m - 1 {
+                        Some(job.processing_time(m))
+                    } else {
+                        None
+                    };
+                    vars.add(match upper {
+                        Some(upper) => variable().clamp(lower, upper),
+                        None => variable().min(lower),
+                    })
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
 
-                let predecessors_finished_at = scheduled_predecessors
-                    .iter()
-                    .map(|s| s.completion_time())
-                    .max()
-                    .unwrap_or(0);
+    // every soft constraint may be broken at its penalty, which relaxes it by
+    // enough to let its right job start at 0 while its left job completes at
+    // the latest time
+    let soft_violations = instance
+        .soft_constraints
+        .iter()
+        .map(|_| vars.add(variable().clamp(0, 1)))
+        .collect::<Vec<_>>();
 
-                let fit = occupation[occupation.len() - allotment];
+    let size = model_size(instance);
+    debug!("Building linear program with {size}");
+    let mut constraints = Vec::with_capacity(size.constraints);
+    for j in 0..instance.jobs.len() {
+        for &k in instance.successor_indices(j) {
+            let lag = instance.lag(j, k);
+            constraints.push(constraint!(
+                completion_times[j] + processing_times[k] + lag.min <= completion_times[k]
+            ));
+            if let Some(max) = lag.max {
+                constraints.push(constraint!(
+                    completion_times[k] - processing_times[k] <= completion_times[j] + max
+                ));
+            }
+        }
+    }
+    for (soft, &v) in instance.soft_constraints.iter().zip(&soft_violations) {
+        let big_m = latest + instance.jobs[soft.right].processing_time(1) + soft.min_lag;
+        constraints.push(constraint!(
+            completion_times[soft.left] + processing_times[soft.right] + soft.min_lag
+                <= completion_times[soft.right] + big_m * v
+        ));
+    }
+    for (j, x_j) in virtual_processing_times.iter().enumerate() {
+        for &x_j_i in x_j {
+            constraints.push(constraint!(x_j_i <= processing_times[j]));
+        }
+    }
+    constraints.push(constraint!(
+        instance
+            .jobs
+            .iter()
+            .enumerate()
+            .map(|(j, job)| {
+                w_hat_j(m, &virtual_processing_times[j], job) * multiplicities[j] as f64
+            })
+            .sum::<Expression>()
+            + total_processing_time
+            <= total_work
+    ));
+    constraints.push(constraint!(cpl <= makespan));
+    constraints.push(constraint!(total_work / (m as i32) <= makespan));
+    debug_assert_eq!(
+        constraints.len(),
+        size.constraints,
+        "miscounted constraints"
+    );
+    let objective = makespan
+        + instance
+            .soft_constraints
+            .iter()
+            .zip(&soft_violations)
+            .map(|(soft, &v)| soft.penalty * v)
+            .sum::<Expression>();
+    let problem = constraints
+        .into_iter()
+        .fold(vars.minimise(objective).using(default_solver), |p, c| {
+            p.with(c)
+        });
+    Model {
+        problem,
+        makespan,
+        completion_times,
+        processing_times,
+        virtual_processing_times,
+        size,
+    }
+}
 
-                let earliest = starting_time.max(predecessors_finished_at).max(fit);
+/// Computes the number of variables, constraints, and nonzeros of the linear
+/// program for the given `instance` without building it.
+pub fn model_size(instance: &Instance) -> ModelSize {
+    let n = instance.jobs.len();
+    let m = instance.processor_count;
+    let edges = (0..instance.jobs.len())
+        .map(|j| instance.successor_indices(j).len())
+        .sum::<usize>();
+    let max_lags = max_lags(instance);
+    let soft = instance.soft_constraints.len();
+    ModelSize {
+        variables: 2 + 2 * n + n * m + soft,
+        constraints: edges + max_lags + soft + n * m + 3,
+        // precedence and maximum lags have three variables each, soft
+        // constraints four, the bounds of the virtual processing times two,
+        // the work constraint refers to all but the first virtual processing
+        // time, and the makespan bounds have one and two variables
+        nonzeros: 3 * (edges + max_lags)
+            + 4 * soft
+            + 2 * n * m
+            + n * m.saturating_sub(1)
+            + 1
+            + 1
+            + 2,
+    }
+}
 
-                (job, earliest)
-            })
-            // take min by starting time
-            .min_by_key(|&(_, alpha)| alpha)
-            .expect("no job ready");
-        jobs[pick].1 = false;
-        let allotment = allotments[pick];
-        let job = ScheduledJob {
-            job: instance.jobs[pick].clone(),
-            allotment,
-            start_time,
-        };
-        // update occupation
-        let machine = occupation
+/// Counts the precedence constraints with a maximum lag, each of which adds a
+/// constraint to the linear program
+fn max_lags(instance: &Instance) -> usize {
+    (0..instance.jobs.len())
+        .flat_map(|j| instance.successor_indices(j).iter().map(move |&k| (j, k)))
+        .filter(|&(j, k)| instance.lag(j, k).max.is_some())
+        .count()
+}
+
+/// Computes the earliest start time and the latest completion time of every
+/// job if all jobs run for their processing times in the linear program
+/// without extending the longest path
+fn windows(instance: &Instance, processing_times: &[f64]) -> (Vec<f64>, Vec<f64>) {
+    let order = instance.topological_order();
+    let mut earliest = vec![0.0; instance.jobs.len()];
+    for &k in &order {
+        earliest[k] = instance
+            .predecessor_indices(k)
             .iter()
-            .enumerate()
-            .find(|(_, o)| **o <= start_time)
-            .expect("bad start time")
-            .0;
-        let done = job.completion_time();
-        for occ in occupation.iter_mut().skip(machine).take(allotment) {
-            *occ = done;
-        }
-        scheduled_jobs.push(job);
+            .map(|&j| earliest[j] + processing_times[j] + f64::from(instance.lag(j, k).min))
+            .fold(0.0, f64::max);
     }
-    Schedule {
-        processor_count: instance.processor_count,
-        jobs: scheduled_jobs,
+    let horizon = earliest
+        .iter()
+        .zip(processing_times)
+        .map(|(e, p)| e + p)
+        .fold(0.0, f64::max);
+    let mut latest = vec![horizon; instance.jobs.len()];
+    for &j in order.iter().rev() {
+        latest[j] = instance
+            .successor_indices(j)
+            .iter()
+            .map(|&k| latest[k] - processing_times[k] - f64::from(instance.lag(j, k).min))
+            .fold(horizon, f64::min);
+    }
+    (earliest, latest)
+}
+
+/// Scores how critical every job is by the share of its window that it runs
+/// for in the linear program, from close to 0 for jobs with a lot of slack to
+/// 1 for jobs on a critical path. The solver does not expose dual values, but
+/// by complementary slackness only the precedence constraints along critical
+/// paths can have nonzero duals.
+fn criticality(
+    processing_times: &[f64],
+    earliest: &[f64],
+    latest: &[f64],
+    tolerance: f64,
+) -> Vec<f64> {
+    (0..processing_times.len())
+        .map(|j| {
+            let window = latest[j] - earliest[j];
+            let score = if window <= processing_times[j] + tolerance {
+                1.0
+            } else {
+                (processing_times[j] / window).max(0.0)
+            };
+            debug!("criticality_{j} = {score}");
+            score
+        })
+        .collect()
+}
+
+/// Lets jobs with slack use fewer processors than their threshold allotment.
+/// In topological order, every job gets the fewest processors with which it
+/// completes by its `latest` completion time if it starts as soon as its
+/// predecessors and their lags are done. Jobs on a critical path have no room
+/// to run longer, so they keep their allotment.
+fn relax(instance: &Instance, rounding: &mut [RoundingDecision], latest: &[f64], tolerance: f64) {
+    let mut done = vec![0.0; rounding.len()];
+    for k in instance.topological_order() {
+        let job = &instance.jobs[k];
+        let ready = instance
+            .predecessor_indices(k)
+            .iter()
+            .map(|&j| done[j] + f64::from(instance.lag(j, k).min))
+            .fold(0.0, f64::max);
+        let threshold = rounding[k].chosen_allotment;
+        let allotment = (1..threshold)
+            .find(|&l| ready + f64::from(job.processing_time(l)) <= latest[k] + tolerance)
+            .unwrap_or(threshold);
+        if allotment != threshold {
+            rounding[k].chosen_allotment = allotment;
+            rounding[k].reason = RoundingReason::DualGuided { threshold };
+        }
+        done[k] = ready + f64::from(job.processing_time(allotment));
     }
 }
 
@@ -225,9 +526,10 @@ fn w_hat_j(m: usize, virtual_processing_times: &[Variable], job: &Job) -> Expres
         .sum::<Expression>()
 }
 
-/// Helper function from the paper
+/// Helper function from the paper. Allotments without processing time leave
+/// nothing to scale, so their term vanishes instead of dividing by zero.
 fn w_bar_j_i(m: usize, i: usize, virtual_processing_times: &[Variable], job: &Job) -> Expression {
-    if i == m {
+    if i == m || job.processing_time(i) == 0 {
         0.into()
     } else {
         (w_j_l(i + 1, job) - w_j_l(i, job)) * (job.processing_time(i) - virtual_processing_times[i])
@@ -252,16 +554,31 @@ fn compute_rho(_m: usize) -> f64 {
 /// Computes the critical path length CLP of the given `instance`.
 fn critical_path_length(instance: &Instance) -> i32 {
     let mut scheduler = Scheduler::<i32>::new();
-    for job in &instance.jobs {
+    for (j, job) in instance.jobs.iter().enumerate() {
+        let mut successors = vec![];
+        for &k in instance.successor_indices(j) {
+            let successor = &instance.jobs[k];
+            // minimum lags are modeled as tasks between the two jobs
+            let lag = instance.lag(j, k).min;
+            if lag > 0 {
+                let name = format!("{}-{}", job.index, successor.index);
+                scheduler
+                    .add_task(CustomTask::new(
+                        name.clone(),
+                        lag,
+                        vec![successor.index.to_string()],
+                    ))
+                    .expect("duplicate task");
+                successors.push(name);
+            } else {
+                successors.push(successor.index.to_string());
+            }
+        }
         scheduler
             .add_task(CustomTask::new(
                 job.index.to_string(),
                 job.processing_time(1),
-                instance
-                    .successors(job)
-                    .iter()
-                    .map(|(_, job)| job.index.to_string())
-                    .collect(),
+                successors,
             ))
             .expect("duplicate task");
     }
@@ -275,3 +592,111 @@ fn critical_path_length(instance: &Instance) -> i32 {
         Err(e) => panic!("{e}"),
     }
 }
+
+/// Rounds the virtual processing times `fractional_values` of the job at
+/// position `j` to the allotment with the longest processing time among those
+/// whose virtual processing time reaches `rho` times it. If there is none,
+/// all processors are used, and jobs that take no time get one processor.
+fn round_by_threshold(
+    instance: &Instance,
+    j: usize,
+    fractional_values: Vec<f64>,
+    rho: f64,
+    criticality: f64,
+) -> RoundingDecision {
+    let (chosen_allotment, p) = fractional_values
+        .iter()
+        .zip(1..=instance.processor_count)
+        .map(|(&val, i)| {
+            debug!("x_{j}_{i} = {val}");
+            let p_j_i = instance.jobs[j].processing_time(i);
+            if val < f64::from(p_j_i) * rho {
+                (i, 0)
+            } else {
+                (i, p_j_i)
+            }
+        })
+        .max_by_key(|&(_, p)| p)
+        .unwrap_or((0, 0));
+    let (chosen_allotment, reason) = if instance.jobs[j].is_instantaneous() {
+        (1, RoundingReason::Instantaneous)
+    } else if p > 0 {
+        (chosen_allotment, RoundingReason::Threshold)
+    } else {
+        (chosen_allotment, RoundingReason::BelowThreshold)
+    };
+    RoundingDecision {
+        job: j,
+        fractional_values,
+        chosen_allotment,
+        reason,
+        criticality: Some(criticality),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{round_by_threshold, schedule, RoundingStrategy};
+    use crate::{
+        algo::{RoundingReason, INT_TOLERANCE},
+        builder::InstanceBuilder,
+        list::ListOptions,
+        testdata,
+    };
+
+    /// Solves every bundled reference instance with the rounding `strategy`
+    /// and returns the total makespan
+    fn total_makespan(strategy: RoundingStrategy) -> i32 {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/testdata");
+        testdata::instance_names(dir)
+            .iter()
+            .map(|name| {
+                let (schedule, _) = schedule(
+                    testdata::read_instance(dir, name),
+                    false,
+                    INT_TOLERANCE,
+                    strategy,
+                    false,
+                    &ListOptions::default(),
+                );
+                let violations = schedule.violations(&testdata::read_instance(dir, name));
+                assert!(
+                    violations.is_empty(),
+                    "{strategy:?} on {name}: {violations:?}"
+                );
+                schedule.makespan()
+            })
+            .sum()
+    }
+
+    #[test]
+    fn dual_guided_is_no_worse_than_threshold_on_average() {
+        let threshold = total_makespan(RoundingStrategy::Threshold);
+        let dual_guided = total_makespan(RoundingStrategy::DualGuided);
+        assert!(
+            dual_guided <= threshold,
+            "dual-guided rounding takes {dual_guided} in total, threshold rounding {threshold}"
+        );
+    }
+
+    #[test]
+    fn threshold_rounding_records_its_reasons() {
+        let instance = InstanceBuilder::new()
+            .processors(2)
+            .job(1)
+            .times([4, 2])
+            .job(2)
+            .times([0, 0])
+            .build()
+            .expect("valid instance");
+        let round = |j, values: &[f64]| {
+            let decision = round_by_threshold(&instance, j, values.to_vec(), 0.5, 0.0);
+            (decision.chosen_allotment, decision.reason)
+        };
+        // with ρ = 0.5, the thresholds are 2 for one and 1 for two processors
+        assert_eq!(round(0, &[3.0, 0.5]), (1, RoundingReason::Threshold));
+        assert_eq!(round(0, &[0.0, 1.5]), (2, RoundingReason::Threshold));
+        assert_eq!(round(0, &[1.0, 0.5]), (2, RoundingReason::BelowThreshold));
+        assert_eq!(round(1, &[0.0, 0.0]), (1, RoundingReason::Instantaneous));
+    }
+}
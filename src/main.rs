@@ -1,246 +1,2380 @@
 // This is the main file of the project which gets executed when running the CLI.
 
 use env_logger::{Builder, Target::Stdout};
-use log::{error, info};
+use log::{debug, error, info, log_enabled, warn, Level};
 
-use std::{fs, io::Write, path, time::Instant};
+use std::{
+    collections::HashMap,
+    fs, io, path, process,
+    time::{Duration, Instant},
+};
 
-use algo::{Instance, Schedule, ScheduledJob};
-use render::render_schedule;
+use algo::{
+    Constraint, Instance, ModelSize, ProfilePoint, Rounding, RoundingDecision, Schedule,
+    ScheduledJob, SolverStats, INT_TOLERANCE,
+};
+use list::ListOptions;
+use lp::RoundingStrategy;
+use preprocess::SplitMap;
+use render::{
+    assign_processors, predecessors, render_page_index, render_pareto, render_schedule_pages,
+    render_size, ColorBy, Page, RenderOptions, Theme,
+};
 
-use clap::{Parser, Subcommand};
-use open::that as open_that;
+use baseline::Status;
+use builder::ValidationError;
+use clap::{Args, Command, CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+use diagnostics::Category;
+use dp::{CheckpointOptions, HorizonPolicy, TraceOptions};
+use files::{Dedupe, ExportFormat, ExtendTimes, ReadOptions};
+use generate::{GenerateConfig, Metadata, Thresholds};
+use itertools::Itertools;
+use metrics::Structure;
+use milp::Formulation;
+use objective::Objective;
+use output::{Artifact, ArtifactKind, SystemOpener};
+use provenance::{hash_files, Provenance};
+use rand::{rngs::StdRng, SeedableRng};
+use rayon::prelude::*;
+use run::AppError;
+use schema::Document;
+use trace::Pruned;
 
 mod algo;
+mod baseline;
+mod builder;
+mod certify;
+mod cli_output;
+mod decompose;
+mod diagnostics;
 mod dp;
+mod experiment;
 mod files;
+mod fit;
+mod gallery;
 mod generate;
 mod ilp;
+mod improve;
+mod interrupt;
+mod list;
 mod lp;
+mod metrics;
+mod milp;
+mod objective;
+mod output;
+mod pareto;
+mod perturb;
+mod placement;
+mod preprocess;
+mod provenance;
 mod render;
+mod replan;
+mod reproducible;
+mod run;
+mod schema;
+mod selftest;
+mod shelf;
+mod stats_util;
+mod testdata;
+mod trace;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 #[command(propagate_version = true)]
 struct Cli {
+    /// Exit with status 3 if any warnings were raised, e.g. because a
+    /// schedule violates the instance or rounding capped an allotment, and
+    /// list them by category
+    #[arg(long, global = true)]
+    strict: bool,
+
+    /// Write identical files on every run of the same command: timestamps
+    /// are fixed to SOURCE_DATE_EPOCH or 0, durations are recorded as 0, and
+    /// missing seeds default to 0. Setting SOURCE_DATE_EPOCH has the same
+    /// effect.
+    #[arg(long, global = true)]
+    reproducible: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
 
+/// Exit status of --strict if any warnings were raised
+const STRICT_EXIT_CODE: i32 = 3;
+
+/// Number of runs in the directory of --debug-dir beyond which every run warns
+/// that the directory keeps growing
+const DEBUG_RUN_LIMIT: usize = 100;
+
+/// Number of bins of the histogram of the makespans of sweep-allotments
+const SWEEP_HISTOGRAM_BINS: usize = 10;
+
+/// Seconds that the animation of --animate takes unless --speed is given
+const ANIMATION_SECONDS: f64 = 10.0;
+
+/// Input files shared by all solvers
+#[derive(Args)]
+struct InputArgs {
+    /// Input CSV file containing jobs in the format `id,p_1,...,p_m` where each
+    /// column `p_i` contains the processing time if the job were to be executed
+    /// on i machines. An optional `affinity` column limits a job to some
+    /// processors, e.g. "0-3" or "0,2,5". An optional `note` column attaches
+    /// free text to a job.
+    #[arg(short, long)]
+    job_file: String,
+
+    /// Input CSV file containing constraints between jobs in the format
+    /// "id0,id1" where each line expresses that the job with id0 is less than
+    /// the job with id1. Optional columns `min_lag` and `max_lag` limit the
+    /// time between the completion of id0 and the start of id1. An optional
+    /// `kind` column marks constraints as `hard` or as `soft:<penalty>`,
+    /// which schedules may break at the given penalty.
+    #[arg(short, long)]
+    constraint_file: String,
+
+    /// How to handle jobs that share the same id in the job file
+    #[arg(long, value_enum, default_value_t)]
+    dedupe: Dedupe,
+
+    /// How to fill in processing times that are blank in the job file or
+    /// needed for more processors than there are columns
+    #[arg(long, value_enum, default_value_t)]
+    extend_times: ExtendTimes,
+
+    /// Number of processors, defaults to the number of processing time columns
+    #[arg(long)]
+    machines: Option<usize>,
+
+    /// Input CSV file limiting the number of busy processors over time in the
+    /// format "from,to,capacity", where each line allows at most `capacity`
+    /// busy processors from `from` until right before `to`. Only the list
+    /// scheduling of solve-lp and solve-ilp and the local search of
+    /// --improve-from respect it.
+    #[arg(long)]
+    capacity_file: Option<String>,
+
+    /// Input CSV file of maintenance windows in the format
+    /// "processor,from,to", where each line takes `processor` down from
+    /// `from` until right before `to`. The list scheduling of solve-lp,
+    /// solve-ilp, and solve-milp places no job on a processor while it is
+    /// down, and solve-dp rejects instances with downtime.
+    #[arg(long)]
+    downtime_file: Option<String>,
+
+    /// Input CSV file of jobs that must run at fixed times in the format
+    /// `id,start,allotment,first_processor`, where each line pins the job
+    /// with `id` to start at `start` on `allotment` consecutive processors
+    /// from `first_processor` on. All solvers plan the other jobs around them.
+    #[arg(long)]
+    pinned_file: Option<String>,
+
+    /// Names of the processors separated by commas, starting with processor
+    /// 0, which replace "Processor <i>" in the rendered SVG, the per-processor
+    /// exports, the utilization CSV, and the reported violations. There must
+    /// be one name per processor.
+    #[arg(long, value_delimiter = ',', conflicts_with = "machine_names_file")]
+    machine_names: Vec<String>,
+
+    /// Input text file of processor names like --machine-names, with one
+    /// name per line
+    #[arg(long)]
+    machine_names_file: Option<String>,
+
+    /// Reject job and constraint files with stray whitespace, quoted numbers,
+    /// Windows line endings, or an empty trailing column instead of fixing
+    /// them and reporting the fixes
+    #[arg(long)]
+    strict_parse: bool,
+
+    /// Drop the last record of the job or constraint file if the file ends
+    /// in the middle of it, e.g. because it is still being written, instead
+    /// of aborting. Constraints that refer to a dropped job are dropped, too.
+    #[arg(long)]
+    ignore_trailing_garbage: bool,
+
+    /// Input JSON file containing a schedule written via --schedule-json.
+    /// Instead of solving the instance from scratch, the schedule is repaired
+    /// to fit the instance and then improved by local search.
+    #[arg(long)]
+    improve_from: Option<String>,
+
+    /// Stop the local search of --improve-from after this many seconds with
+    /// the best schedule so far
+    #[arg(long, requires = "improve_from")]
+    time_limit: Option<u64>,
+
+    /// Output JSON file to which the local search of --improve-from writes
+    /// the best schedule so far whenever it improves, which --improve-from
+    /// can continue from
+    #[arg(long, requires = "improve_from")]
+    checkpoint_best: Option<String>,
+
+    /// What the local search of --improve-from and the choice among the
+    /// runs of --multi-start minimize
+    #[arg(long, value_enum, default_value_t)]
+    objective: Objective,
+
+    /// Split every job that takes longer than this with one processor into a
+    /// chain of shorter segments before solving. The segments are merged back
+    /// into a single job if they run back to back with the same allotment.
+    #[arg(long, conflicts_with_all = ["improve_from", "pinned_file"])]
+    split_longer_than: Option<i32>,
+
+    /// Prune the allotments of every job from the first one whose efficiency
+    /// p(1) / (l p(l)) is below this value on, so that no job gets many
+    /// processors for little speedup. Schedules are still checked against
+    /// the original instance.
+    #[arg(long, conflicts_with = "improve_from")]
+    efficiency_floor: Option<f64>,
+}
+impl InputArgs {
+    /// Reads the instance from the input files and exits if they cannot be
+    /// read or its lags, affinities, or pinned jobs cannot be met
+    fn read(&self) -> Instance {
+        self.read_with_positions().0
+    }
+    /// Reads the instance like `read`, along with the position of every job
+    /// by its id in the job file
+    fn read_with_positions(&self) -> (Instance, HashMap<i32, usize>) {
+        let read = files::read_with_positions(
+            &self.job_file,
+            &self.constraint_file,
+            &ReadOptions {
+                dedupe: self.dedupe,
+                extend_times: self.extend_times,
+                machines: self.machines,
+                pinned_file: self.pinned_file.clone(),
+                strict_parse: self.strict_parse,
+                ignore_trailing_garbage: self.ignore_trailing_garbage,
+            },
+        );
+        let (instance, positions) = read.unwrap_or_else(|e| {
+            error!("Cannot read the instance: {e}");
+            if e.row.is_some() {
+                error!("  hint: Rows are counted from the first record after the header");
+            }
+            process::exit(1);
+        });
+        let instance = match &self.capacity_file {
+            Some(path) => instance.with_capacity(files::read_capacity(path)),
+            None => instance,
+        };
+        let instance = match &self.downtime_file {
+            Some(path) => {
+                let downtime = files::read_downtime(path, instance.processor_count);
+                instance.with_downtime(downtime)
+            }
+            None => instance,
+        };
+        let machine_names = match &self.machine_names_file {
+            Some(path) => files::read_machine_names(path),
+            None => self.machine_names.clone(),
+        };
+        let instance = if machine_names.is_empty() {
+            instance
+        } else {
+            if machine_names.len() != instance.processor_count {
+                error!(
+                    "{}",
+                    ValidationError::MachineNameCount {
+                        names: machine_names.len(),
+                        processors: instance.processor_count,
+                    }
+                );
+                error!("  hint: Name every processor, or pass --machines to use as many processors as there are names");
+                process::exit(1);
+            }
+            instance.with_machine_names(machine_names)
+        };
+        if let Err(e) = run::check(&instance) {
+            let (kind, conflicts, hint) = match &e {
+                AppError::Read(e) => {
+                    error!("Cannot read the instance: {e}");
+                    process::exit(1);
+                }
+                AppError::Cycle(cycle) => {
+                    error!("{cycle}");
+                    error!(
+                        "  hint: Remove one of the constraints of the cycle from {}",
+                        self.constraint_file
+                    );
+                    process::exit(1);
+                }
+                AppError::InfeasibleLags(conflicts) => (
+                    "lag",
+                    conflicts,
+                    format!("Relax the max_lag column of {}", self.constraint_file),
+                ),
+                AppError::InfeasibleAffinities(conflicts) => (
+                    "affinity",
+                    conflicts,
+                    format!("Fix the affinity column of {}", self.job_file),
+                ),
+                AppError::InfeasiblePins(conflicts) => (
+                    "pin",
+                    conflicts,
+                    format!(
+                        "Fix the pinned jobs in {}",
+                        self.pinned_file.as_deref().unwrap_or_default()
+                    ),
+                ),
+            };
+            for conflict in conflicts {
+                error!("Infeasible {kind}: {conflict}");
+            }
+            error!("  hint: {hint}");
+            process::exit(1);
+        }
+        (instance, positions)
+    }
+}
+
+#[expect(clippy::struct_excessive_bools)]
+/// Output options shared by all solvers
+#[derive(Args)]
+struct OutputArgs {
+    /// Render the schedule to an SVG file in the directory "schedules"
+    #[arg(long)]
+    svg: bool,
+
+    /// Open a file that the run wrote: the HTML index of a paginated
+    /// schedule, else the rendered SVG, else a PNG, else the first file
+    /// written. Pass --open=<KIND> to open the first file of that kind.
+    #[arg(long, value_enum, num_args = 0..=1, require_equals = true)]
+    open: Option<Option<ArtifactKind>>,
+
+    /// Color theme of the rendered SVG
+    #[arg(long, value_enum, default_value_t)]
+    theme: Theme,
+
+    /// What the fills of the jobs in the rendered SVG depend on
+    #[arg(long, value_enum, default_value_t)]
+    color_by: ColorBy,
+
+    /// Output CSV file containing the number of busy processors and active
+    /// jobs at every event point of the schedule
+    #[arg(long)]
+    utilization_csv: Option<String>,
+
+    /// Output JSON file containing the schedule along with the solver, its
+    /// options, and hashes of the input files that produced it
+    #[arg(long)]
+    schedule_json: Option<String>,
+
+    /// Add the chains, the critical path, and the slack of every job to the
+    /// JSON file written via --schedule-json
+    #[arg(long, requires = "schedule_json")]
+    with_structure: bool,
+
+    /// Format of the files written via --schedule-json and
+    /// --utilization-csv. NDJSON writes one JSON object per line, which
+    /// other tools can stream, and is also used for file names ending in
+    /// ".ndjson".
+    #[arg(long, value_enum, default_value_t)]
+    format: ExportFormat,
+
+    /// Print a table of the scheduled jobs along with the makespan, idle
+    /// time, and utilization. The table is also logged at info level.
+    #[arg(long)]
+    summary: bool,
+
+    /// Print the schedule as a text Gantt chart that fits into the terminal,
+    /// with one row per processor, the labels of the jobs in the cells in
+    /// which they run, and dots for idle cells
+    #[arg(long)]
+    ascii: bool,
+
+    /// Add the reason for the allotment of every job to the tooltips of the
+    /// rendered SVG
+    #[arg(long, requires = "svg")]
+    svg_rounding: bool,
+
+    /// Draw a bar for every chain of the decomposition to the right of the
+    /// rendered SVG, reaching up to the completion time of the chain
+    #[arg(long, requires = "svg")]
+    svg_chains: bool,
+
+    /// Largest height of the rendered SVG in pixels. Long schedules are
+    /// scaled down to fit, and jobs too short for their label get it next to
+    /// them. [default: 4000]
+    #[arg(long, requires = "svg")]
+    svg_max_height: Option<usize>,
+
+    /// Split the rendered SVG into pages of at most this many pixels by time
+    /// ranges if it would be taller at full scale, instead of scaling it
+    /// down. The pages get the suffix _page<N> and are linked from an HTML
+    /// index next to them.
+    #[arg(long, requires = "svg")]
+    svg_page_height: Option<usize>,
+
+    /// Draw a dashed outline above every job that did not start as soon as
+    /// it was ready in the rendered SVG, reaching back to the time at which
+    /// its last predecessor completed
+    #[arg(long, requires = "svg")]
+    svg_waiting: bool,
+
+    /// Merge jobs that are drawn shorter than this many pixels with the jobs
+    /// that run back to back with them on the same processors into one block
+    /// in the rendered SVG, whose tooltip lists the merged jobs
+    #[arg(long, requires = "svg")]
+    svg_aggregate_below: Option<usize>,
+
+    /// Animate the rendered SVG for presentations: jobs appear when they
+    /// start and grow until they complete, while a line moves along the time
+    /// axis. Viewers without animation support show the static schedule.
+    #[arg(long, requires = "svg", conflicts_with = "svg_page_height")]
+    animate: bool,
+
+    /// Seconds of playback per unit of time of the animation, by default
+    /// such that the whole schedule plays in 10 seconds
+    #[arg(long, requires = "animate")]
+    speed: Option<f64>,
+
+    /// Update the HTML index of the directory "schedules" after writing the
+    /// outputs, like the subcommand index
+    #[arg(long, requires = "svg")]
+    update_index: bool,
+
+    /// Output CSV file pinning the scheduled jobs in the format of
+    /// --pinned-file, e.g. to keep them in place when planning the remaining
+    /// jobs again. Only jobs on consecutive processors can be pinned.
+    #[arg(long)]
+    pin_csv: Option<String>,
+
+    /// Only pin the jobs that start before this time in the file written via
+    /// --pin-csv
+    #[arg(long, requires = "pin_csv")]
+    pin_before: Option<i32>,
+
+    /// Output directory for one CSV file per processor, named
+    /// "processor-<p>.csv", with the columns "id,start,end,allotment,peer_processors"
+    /// of the jobs on the processor, ordered by start time
+    #[arg(long)]
+    per_processor_dir: Option<String>,
+
+    /// Output directory for the intermediate artifacts of every run, such as
+    /// the rounding decisions along with the fractional solution, the
+    /// schedule with its provenance and structure, the utilization, and the
+    /// timelines of the processors. Every run writes to a new subdirectory
+    /// named after the solver and the time at which it started.
+    #[arg(long)]
+    debug_dir: Option<String>,
+}
+
+/// Output files of the instance generator
+#[derive(Args)]
+struct GenerateArgs {
+    #[command(flatten)]
+    generator: GeneratorArgs,
+
+    /// Output CSV file containing the jobs
+    #[arg(short, long)]
+    job_file: String,
+
+    /// Output CSV file containing constraints between jobs
+    #[arg(short, long)]
+    constraint_file: String,
+}
+
+/// Parameters of the instance generator
+#[derive(Args)]
+struct GeneratorArgs {
+    /// Number of jobs to generate
+    #[arg(short)]
+    n: usize,
+
+    /// Number of processors
+    #[arg(short)]
+    m: usize,
+
+    /// Maximum processing time for each job
+    #[arg(long)]
+    min: i32,
+
+    /// Maximum processing time for each job
+    #[arg(long)]
+    max: i32,
+
+    /// Constraint width
+    #[arg(short, long)]
+    omega: usize,
+
+    /// Minimum chain length
+    #[arg(long)]
+    min_chain: usize,
+
+    /// Maximum chain length
+    #[arg(long)]
+    max_chain: usize,
+
+    /// Monotonically decreasing processing times using the concave function 1 / l
+    #[arg(long)]
+    concave: bool,
+
+    /// Seed of the random number generator, chosen randomly if omitted
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Resample the instance until it is likely to differentiate algorithms
+    #[arg(long)]
+    require_interesting: bool,
+
+    /// Maximum number of resamples if --require-interesting is given
+    #[arg(long, default_value_t = 10)]
+    max_resamples: u64,
+
+    /// Minimum width of an interesting instance
+    #[arg(long, default_value_t = 2)]
+    min_width: usize,
+
+    /// Minimum ratio of total work to processor count times critical path
+    /// length of an interesting instance
+    #[arg(long, default_value_t = 1.0)]
+    min_parallelism: f64,
+
+    /// Maximum fraction of identical jobs in an interesting instance
+    #[arg(long, default_value_t = 0.5)]
+    max_identical: f64,
+}
+
+/// The benchmark set and the database of a baseline
+#[derive(Args)]
+struct BaselineArgs {
+    /// Directory containing the test instances
+    #[arg(long, default_value = "testdata")]
+    dir: String,
+
+    /// JSON file holding the recorded makespans and runtimes
+    #[arg(long, default_value = "baseline.json")]
+    database: String,
+
+    /// Algorithms to run, separated by commas
+    #[arg(long, value_enum, value_delimiter = ',', default_values_t = [pareto::Algorithm::Dp, pareto::Algorithm::Lp, pareto::Algorithm::Ilp])]
+    algorithms: Vec<pareto::Algorithm>,
+}
+
+#[derive(Subcommand)]
+enum BaselineCommand {
+    /// Solves the test instances with every algorithm and stores the
+    /// makespans and runtimes in the database, replacing earlier results of
+    /// the same instances and algorithms
+    Record(BaselineArgs),
+    /// Solves the test instances with every algorithm again and prints how
+    /// the makespans and runtimes changed since they were recorded.
+    /// Regressions are reported as warnings, so --strict fails on them.
+    Compare {
+        #[command(flatten)]
+        baseline: BaselineArgs,
+
+        /// Relative amount by which a makespan may exceed its baseline
+        /// before it counts as a regression
+        #[arg(long, default_value_t = 0.0)]
+        makespan_threshold: f64,
+
+        /// Relative amount by which a runtime may exceed its baseline before
+        /// it counts as a regression. Runtimes that differ by less than 50
+        /// ms are never flagged.
+        #[arg(long, default_value_t = 0.5)]
+        runtime_threshold: f64,
+    },
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Solves a given instance of the scheduling problem using a dynamic program
     SolveDp {
-        /// Input CSV file containing jobs in the format `id,p_1,...,p_m` where each
-        /// column `p_i` contains the processing time if the job were to be executed
-        /// on i machines.
-        #[arg(short, long)]
-        job_file: String,
+        #[command(flatten)]
+        input: InputArgs,
 
-        /// Input CSV file containing constraints between jobs in the format
-        /// "id0,id1" where each line expresses that the job with id0 is less than
-        /// the job with id1.
-        #[arg(short, long)]
-        constraint_file: String,
+        #[command(flatten)]
+        output: OutputArgs,
 
-        /// Render the schedule to an SVG file in the directory "schedules"
+        /// Latest completion time considered by the dynamic program: "auto"
+        /// for the makespan of a greedy schedule, a fixed time, or a multiple
+        /// of the critical path length with the fastest allotments such as
+        /// "1.5x". If the search finds nothing and the greedy schedule takes
+        /// longer than the horizon, the search is retried with its makespan.
+        #[arg(long, default_value_t)]
+        horizon: HorizonPolicy,
+
+        /// Periodically save the state of the search to this file
+        #[arg(long)]
+        checkpoint: Option<String>,
+
+        /// Seconds between two checkpoints
+        #[arg(long, default_value_t = 60)]
+        checkpoint_interval: u64,
+
+        /// Continue the search from a checkpoint of the same instance
         #[arg(long)]
-        svg: bool,
+        resume: Option<String>,
 
-        /// Open the rendered SVG if created
+        /// Output file to which the candidate states of the search are
+        /// written, one JSON object per line
         #[arg(long)]
-        open: bool,
+        trace: Option<String>,
+
+        /// Probability with which each candidate state is written to the
+        /// trace
+        #[arg(long, default_value_t = 0.01, requires = "trace")]
+        trace_sample: f64,
+
+        /// Solve the weakly connected components of the precedence graph
+        /// separately and pack their schedules onto the processors
+        #[arg(long, conflicts_with_all = ["checkpoint", "resume", "trace", "improve_from"])]
+        decompose: bool,
+
+        /// Round every processing time up to a multiple of this and solve the
+        /// coarser instance, whose horizon is this many times smaller. LIST
+        /// then places the jobs with their exact processing times, and the
+        /// largest possible gap to the optimal makespan is logged.
+        #[arg(long, conflicts_with_all = ["improve_from", "pinned_file"])]
+        granularity: Option<i32>,
+
+        /// Concatenate the shortest chains by adding constraints between them
+        /// until at most this many chains remain, which speeds up the search.
+        /// The schedule is only optimal for the instance with the added
+        /// constraints, which are logged and recorded in the provenance.
+        #[arg(long, conflicts_with_all = ["decompose", "improve_from", "split_longer_than"])]
+        merge_chains: Option<usize>,
+
+        /// Output JSON file certifying that the makespan of the schedule is
+        /// optimal, containing the schedule along with the results of
+        /// checking that no schedule completes one unit of time earlier with
+        /// the dynamic program and, for small instances, the time-indexed
+        /// MILP. The command certify checks it again.
+        #[arg(long, conflicts_with_all = ["decompose", "granularity", "merge_chains", "improve_from", "split_longer_than", "efficiency_floor"])]
+        certificate: Option<String>,
     },
     /// Solves a given instance of the scheduling problem using an integer linear program
     SolveIlp {
-        /// Input CSV file containing jobs in the format `id,p_1,...,p_m` where each
-        /// column `p_i` contains the processing time if the job were to be executed
-        /// on i machines.
-        #[arg(short, long)]
-        job_file: String,
+        #[command(flatten)]
+        input: InputArgs,
 
-        /// Input CSV file containing constraints between jobs in the format
-        /// "id0,id1" where each line expresses that the job with id0 is less than
-        /// the job with id1.
-        #[arg(short, long)]
-        constraint_file: String,
+        #[command(flatten)]
+        output: OutputArgs,
 
-        /// Render the schedule to an SVG file in the directory "schedules"
+        /// Remove idle times from schedule in a postprocessing step
         #[arg(long)]
-        svg: bool,
+        compress: bool,
 
-        /// Open the rendered SVG if created
+        /// Place every job on processors with consecutive indices instead of
+        /// any free processors, which may delay jobs until a wide enough
+        /// window of processors is free
         #[arg(long)]
-        open: bool,
+        contiguous: bool,
 
-        /// Remove idle times from schedule in a postprocessing step
+        /// Break ties between jobs that can start at the same time at random
+        /// using this seed, e.g. to reproduce the best run of --multi-start
         #[arg(long)]
-        compress: bool,
+        seed: Option<u64>,
+
+        /// Run the list scheduling this many times with consecutive seeds,
+        /// starting from --seed or a random one, and keep the best schedule
+        #[arg(long)]
+        multi_start: Option<usize>,
+
+        /// After list scheduling, give jobs more processors where idle
+        /// processors next to them let them complete earlier without moving
+        /// any other job
+        #[arg(long)]
+        grow_allotments: bool,
+
+        /// Only place the jobs that start before this time and defer all
+        /// others, which is much faster if only the first time units are
+        /// needed. The exported schedule is partial, and --improve-from
+        /// extends it to a full one later.
+        #[arg(long)]
+        horizon_limit: Option<i32>,
+
+        /// Exit with a non-zero status if the ratio between the makespan and
+        /// the lower bound of the linear program exceeds this value
+        #[arg(long)]
+        fail_if_ratio_above: Option<f64>,
+
+        /// Refuse to solve instances whose linear program has more variables
+        /// and constraints combined than this value
+        #[arg(long)]
+        max_model_size: Option<usize>,
+
+        /// Build the linear program and print its size without solving it
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Values of the linear program within this distance of an integer
+        /// are treated as integers, all others are rounded such that the
+        /// precedence constraints stay satisfied
+        #[arg(long, default_value_t = INT_TOLERANCE)]
+        int_tolerance: f64,
+
+        /// How to round the processing times of the linear program that are
+        /// not within the tolerance of an integer. Only rounding down keeps
+        /// the precedence constraints satisfied.
+        #[arg(long, value_enum, default_value_t)]
+        round_processing_times: Rounding,
+
+        /// Output CSV file containing the values of the linear program that
+        /// the allotment of every job is based on, and the reason for it
+        #[arg(long)]
+        dump_rounding: Option<String>,
+
+        /// Solve the concave closure of the processing times instead, whose
+        /// work is convex in the allotment as the analysis assumes, and map
+        /// every allotment back to the closest original processing time
+        #[arg(long, conflicts_with = "improve_from")]
+        concavify: bool,
+
+        /// Let interchangeable jobs, i.e. jobs with the same processing times,
+        /// affinity, predecessors, successors, and lags, share one set of
+        /// variables in the linear program, which shrinks it accordingly
+        #[arg(long)]
+        group_identical: bool,
+
+        /// Solve the weakly connected components of the precedence graph
+        /// separately and pack their schedules onto the processors
+        #[arg(long, conflicts_with_all = ["dry_run", "improve_from"])]
+        decompose: bool,
     },
     /// Solves a given instance of the scheduling problem using a linear program
     SolveLp {
-        /// Input CSV file containing jobs in the format `id,p_1,...,p_m` where each
-        /// column `p_i` contains the processing time if the job were to be executed
-        /// on i machines.
-        #[arg(short, long)]
-        job_file: String,
+        #[command(flatten)]
+        input: InputArgs,
 
-        /// Input CSV file containing constraints between jobs in the format
-        /// "id0,id1" where each line expresses that the job with id0 is less than
-        /// the job with id1.
-        #[arg(short, long)]
-        constraint_file: String,
+        #[command(flatten)]
+        output: OutputArgs,
 
-        /// Render the schedule to an SVG file in the directory "schedules"
+        /// Remove idle times from schedule in a postprocessing step
         #[arg(long)]
-        svg: bool,
+        compress: bool,
 
-        /// Open the rendered SVG if created
+        /// Place every job on processors with consecutive indices instead of
+        /// any free processors, which may delay jobs until a wide enough
+        /// window of processors is free
         #[arg(long)]
-        open: bool,
+        contiguous: bool,
 
-        /// Remove idle times from schedule in a postprocessing step
+        /// Break ties between jobs that can start at the same time at random
+        /// using this seed, e.g. to reproduce the best run of --multi-start
         #[arg(long)]
-        compress: bool,
+        seed: Option<u64>,
+
+        /// Run the list scheduling this many times with consecutive seeds,
+        /// starting from --seed or a random one, and keep the best schedule
+        #[arg(long)]
+        multi_start: Option<usize>,
+
+        /// After list scheduling, give jobs more processors where idle
+        /// processors next to them let them complete earlier without moving
+        /// any other job
+        #[arg(long)]
+        grow_allotments: bool,
+
+        /// Only place the jobs that start before this time and defer all
+        /// others, which is much faster if only the first time units are
+        /// needed. The exported schedule is partial, and --improve-from
+        /// extends it to a full one later.
+        #[arg(long)]
+        horizon_limit: Option<i32>,
+
+        /// Exit with a non-zero status if the ratio between the makespan and
+        /// the lower bound of the linear program exceeds this value
+        #[arg(long)]
+        fail_if_ratio_above: Option<f64>,
+
+        /// Refuse to solve instances whose linear program has more variables
+        /// and constraints combined than this value
+        #[arg(long)]
+        max_model_size: Option<usize>,
+
+        /// Build the linear program and print its size without solving it
+        #[arg(long)]
+        dry_run: bool,
+
+        /// How to derive the allotments from the solution of the linear
+        /// program
+        #[arg(long, value_enum, default_value_t)]
+        rounding_strategy: RoundingStrategy,
+
+        /// Values of the linear program within this distance of an integer
+        /// are treated as integers, all others are rounded such that the
+        /// precedence constraints stay satisfied
+        #[arg(long, default_value_t = INT_TOLERANCE)]
+        int_tolerance: f64,
+
+        /// Output CSV file containing the values of the linear program that
+        /// the allotment of every job is based on, and the reason for it
+        #[arg(long)]
+        dump_rounding: Option<String>,
+
+        /// Solve the concave closure of the processing times instead, whose
+        /// work is convex in the allotment as the analysis assumes, and map
+        /// every allotment back to the closest original processing time
+        #[arg(long, conflicts_with = "improve_from")]
+        concavify: bool,
+
+        /// Let interchangeable jobs, i.e. jobs with the same processing times,
+        /// affinity, predecessors, successors, and lags, share one set of
+        /// variables in the linear program, which shrinks it accordingly
+        #[arg(long)]
+        group_identical: bool,
+    },
+    /// Solves a given instance of the scheduling problem exactly using a
+    /// mixed integer linear program, which is only practical for small
+    /// instances
+    SolveMilp {
+        #[command(flatten)]
+        input: InputArgs,
+
+        #[command(flatten)]
+        output: OutputArgs,
+
+        /// How the mixed integer linear program models the schedule
+        #[arg(long, value_enum, default_value_t)]
+        formulation: Formulation,
+
+        /// Place every job on processors with consecutive indices instead of
+        /// any free processors, which may delay jobs until a wide enough
+        /// window of processors is free
+        #[arg(long)]
+        contiguous: bool,
+
+        /// Refuse to solve instances whose mixed integer linear program has
+        /// more variables and constraints combined than this value
+        #[arg(long)]
+        max_model_size: Option<usize>,
+
+        /// Print the size of the mixed integer linear program without solving
+        /// it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Solves a given instance of the scheduling problem by packing the jobs
+    /// into shelves level by level, which is fast enough for instances with
+    /// hundreds of thousands of jobs
+    SolveShelf {
+        #[command(flatten)]
+        input: InputArgs,
+
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// Solves a given instance with every number of processors up to the
+    /// available ones and prints the makespans that cannot be achieved with
+    /// fewer processors as CSV
+    Pareto {
+        #[command(flatten)]
+        input: InputArgs,
+
+        /// Algorithm that solves the instances
+        #[arg(long, value_enum, default_value_t)]
+        algorithm: pareto::Algorithm,
+
+        /// Output SVG file containing a chart of the makespan over the number
+        /// of processors
+        #[arg(long)]
+        svg: Option<String>,
+    },
+    /// Searches for the smallest number of processors with which a given
+    /// instance can be solved within a target makespan and prints the
+    /// makespans that the search tried as CSV. Use --machines together with
+    /// --extend-times to consider more processors than the instance has.
+    Size {
+        #[command(flatten)]
+        input: InputArgs,
+
+        /// Largest makespan that the schedule may have
+        #[arg(long)]
+        target: i32,
+
+        /// Algorithm that solves the instances
+        #[arg(long, value_enum, default_value_t)]
+        algorithm: pareto::Algorithm,
+
+        /// Output SVG file containing a chart of the makespan over the number
+        /// of processors that the search tried, along with the area and
+        /// critical path lower bounds for every number of processors
+        #[arg(long)]
+        svg: Option<String>,
+    },
+    /// Checks a certificate written via --certificate of solve-dp against a
+    /// given instance by checking its schedule and running its checks again,
+    /// and exits with status 1 if the claimed optimum does not hold
+    Certify {
+        #[command(flatten)]
+        input: InputArgs,
+
+        /// Input JSON file written via --certificate of solve-dp
+        certificate: String,
+    },
+    /// Perturbs the processing times and constraints of an instance at random
+    /// for sensitivity studies, such that the perturbed instance can still be
+    /// scheduled, and prints the changes as CSV
+    Perturb {
+        #[command(flatten)]
+        input: InputArgs,
+
+        /// Scale every processing time by a random factor of up to this many
+        /// percent more or less, keeping it positive
+        #[arg(long, default_value_t = 0.0)]
+        times: f64,
+
+        /// Percentage of the hard constraints to drop at random
+        #[arg(long, default_value_t = 0.0)]
+        drop_constraints: f64,
+
+        /// Number of hard constraints to add between jobs that do not depend
+        /// on each other yet, in percent of the hard constraints of the
+        /// instance
+        #[arg(long, default_value_t = 0.0)]
+        add_constraints: f64,
+
+        /// Seed of the random number generator, chosen randomly if omitted
+        #[arg(long)]
+        seed: Option<u64>,
+
+        /// Output CSV file containing the jobs of the perturbed instance
+        #[arg(long)]
+        out_job_file: String,
+
+        /// Output CSV file containing the constraints of the perturbed
+        /// instance
+        #[arg(long)]
+        out_constraint_file: String,
+    },
+    /// Prints the size of a given instance as CSV, including how many of its
+    /// constraints are redundant because they follow from other constraints
+    Stats {
+        #[command(flatten)]
+        input: InputArgs,
+
+        /// Output CSV file containing the transitive reduction of the
+        /// constraints, which can replace the constraint file in later runs
+        #[arg(long)]
+        write_reduced: Option<String>,
+    },
+    /// Evaluates every allotment vector of a small instance whose total work
+    /// is within a budget with LIST, logs a histogram of the makespans, and
+    /// exports the best schedule like the solve commands, e.g. to check how
+    /// far the rounding of solve-lp and solve-ilp is from the best allotments
+    SweepAllotments {
+        #[command(flatten)]
+        input: InputArgs,
+
+        #[command(flatten)]
+        output: OutputArgs,
+
+        /// Largest total work of an allotment vector, i.e. the sum of the
+        /// allotment times the processing time over all jobs, unbounded by
+        /// default
+        #[arg(long)]
+        max_total_work: Option<i64>,
+
+        /// Refuse to sweep if more than this many allotment vectors are
+        /// within the budget
+        #[arg(long, default_value_t = 100_000)]
+        max_vectors: usize,
     },
     /// Generates a random instance of the scheduling problem
-    Generate {
-        /// Number of jobs to generate
-        #[arg(short)]
-        n: usize,
+    Generate(GenerateArgs),
+    /// Generates a random instance and solves it in the same process, without
+    /// the round trip through CSV files
+    GenSolve {
+        #[command(flatten)]
+        generator: GeneratorArgs,
+
+        /// Algorithm that solves the instance
+        #[arg(long, value_enum, default_value_t)]
+        algo: pareto::Algorithm,
+
+        /// Also write the instance to `<prefix>_jobs.csv` and
+        /// `<prefix>_constraints.csv`, along with its metadata
+        #[arg(long)]
+        save_instance: Option<String>,
+
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// Plans the remainder of a schedule that is being executed, given the
+    /// progress of its jobs at some point in time, and exports the new
+    /// schedule with absolute times like the solve commands
+    Replan {
+        #[command(flatten)]
+        input: InputArgs,
 
-        /// Number of processors
-        #[arg(short)]
-        m: usize,
+        /// Input CSV file containing the progress of the jobs in the format
+        /// `id,status,remaining_fraction,allotment,first_processor`, where
+        /// the status is `done`, `running`, or `pending`. Running jobs keep
+        /// running on `allotment` processors from `first_processor` on, and
+        /// still need `remaining_fraction` of their processing time. Jobs
+        /// without progress are pending.
+        #[arg(long)]
+        progress: String,
 
-        /// Maximum processing time for each job
+        /// Time at which the progress was recorded, from which on the
+        /// remaining jobs are planned
         #[arg(long)]
-        min: i32,
+        at: i32,
+
+        /// Algorithm that plans the remaining jobs
+        #[arg(long, value_enum, default_value_t)]
+        algo: pareto::Algorithm,
 
-        /// Maximum processing time for each job
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// Solves random instances of several families with several algorithms,
+    /// logs the distribution of the makespans divided by a lower bound, and
+    /// prints whether the algorithms differ significantly on each family
+    Experiment {
+        /// Input CSV file of instance families in the format
+        /// `family,n,m,min,max,omega,min_chain,max_chain,concave`, where each
+        /// line names a family and the options of generate that produce its
+        /// instances. The `concave` column is optional.
         #[arg(long)]
-        max: i32,
+        family_file: String,
+
+        /// Algorithms to compare, separated by commas
+        #[arg(long, value_enum, value_delimiter = ',', default_values_t = [pareto::Algorithm::Lp, pareto::Algorithm::Ilp])]
+        algorithms: Vec<pareto::Algorithm>,
 
-        /// Output CSV file containing the jobs
-        #[arg(short, long)]
-        job_file: String,
+        /// Number of instances to generate for every family
+        #[arg(long, default_value_t = 50)]
+        seeds: usize,
 
-        /// Constraint width
-        #[arg(short, long)]
-        omega: usize,
+        /// Seed of the first instance of every family, the other instances
+        /// use the seeds after it
+        #[arg(long, default_value_t = 0)]
+        first_seed: u64,
 
-        /// Minimum chain length
+        /// Significance level below which the p-value of the Wilcoxon
+        /// signed-rank test marks a difference as significant
+        #[arg(long, default_value_t = 0.05)]
+        alpha: f64,
+
+        /// Output CSV file containing the test of every pair of algorithms on
+        /// every family along with its p-value
+        #[arg(long)]
+        p_values_csv: Option<String>,
+
+        /// Output CSV file containing the makespan divided by the lower bound
+        /// for every algorithm and instance
         #[arg(long)]
-        min_chain: usize,
+        ratios_csv: Option<String>,
 
-        /// Maximum chain length
+        /// Number of threads that solve and verify the instances in
+        /// parallel, defaults to the number of CPUs
         #[arg(long)]
-        max_chain: usize,
+        jobs: Option<usize>,
+    },
+    /// Solves small random instances with every solver and checks that all
+    /// schedules satisfy their instance and that no solver beats the exact
+    /// dynamic program. Failing instances are shrunk and saved, and the
+    /// command exits with status 1.
+    Selftest {
+        /// Number of instances to generate
+        #[arg(long, default_value_t = 100)]
+        instances: usize,
 
-        /// Output CSV file containing constraints between jobs
-        #[arg(short, long)]
-        constraint_file: String,
+        /// Seed of the first instance, the other instances use the seeds
+        /// after it
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+
+        /// Largest number of jobs of an instance
+        #[arg(long, default_value_t = 6)]
+        max_jobs: usize,
+
+        /// Directory to which the instances of failed checks are saved
+        #[arg(long, default_value = "selftest")]
+        dir: String,
+    },
+    /// Records the makespans and runtimes of the algorithms on the test
+    /// instances, or compares the current build against the recorded ones
+    Baseline {
+        #[command(subcommand)]
+        command: BaselineCommand,
+    },
+    /// Solves the test instances with every formulation of the exact MILP
+    /// and prints the size, the runtime, the optimal makespan, and the gap
+    /// of the linear relaxation of every formulation as CSV, to pick the
+    /// formulation for instances of a given size
+    BenchMilp {
+        /// Directory containing the test instances
+        #[arg(long, default_value = "testdata")]
+        dir: String,
+
+        /// Formulations to compare, separated by commas
+        #[arg(long, value_enum, value_delimiter = ',', default_values_t = [Formulation::TimeIndexed, Formulation::BigM, Formulation::Event])]
+        formulations: Vec<Formulation>,
+
+        /// Skip formulations whose program has more variables and
+        /// constraints combined than this value
+        #[arg(long)]
+        max_model_size: Option<usize>,
+    },
+    /// Writes an HTML table of the rendered schedules in a directory to the
+    /// file "index.html" in it, with the instance, the algorithm, the
+    /// makespan, and the date of every schedule read from the provenance of
+    /// a schedule JSON next to it, or else from the file
+    Index {
+        /// Directory containing the rendered schedules
+        #[arg(default_value = "./schedules")]
+        dir: String,
+    },
+    /// Counts the candidate states of a trace written via --trace of solve-dp
+    /// by depth and pruning reason and prints them as CSV
+    TraceStats {
+        /// Input file written via --trace
+        trace: String,
+    },
+    /// Prints the JSON Schema of a JSON document that the CLI writes, whose
+    /// version is also written to the field "schema_version" of every document
+    Schema {
+        /// The document to print the schema of
+        #[arg(value_enum)]
+        document: Document,
+    },
+    /// Prints a shell completion script to stdout
+    Completions {
+        /// The shell to generate the completion script for
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+    /// Prints a man page in roff format to stdout
+    Manpage,
+    /// Regenerates or checks the reference makespans of the bundled test instances
+    #[command(hide = true)]
+    RegenTestdata {
+        /// Directory containing the test instances and reference.csv
+        #[arg(long, default_value = "testdata")]
+        dir: String,
 
-        /// Monotonically decreasing processing times using the concave function 1 / l
+        /// Compare against the reference makespans instead of overwriting them
         #[arg(long)]
-        concave: bool,
+        check: bool,
+
+        /// Relative amount by which a makespan may exceed its reference
+        #[arg(long, default_value_t = 0.0)]
+        tolerance: f64,
     },
 }
 
+/// Builds the command line interface from the derived definitions
+fn cli() -> Command {
+    Cli::command()
+}
+
+#[expect(clippy::too_many_lines)]
 fn main() {
     Builder::from_default_env().target(Stdout).init();
 
-    match Cli::parse().command {
+    let cli = Cli::parse();
+    if cli.reproducible {
+        reproducible::enable();
+    }
+    interrupt::install();
+    match cli.command {
         Commands::SolveDp {
-            ref job_file,
-            ref constraint_file,
-            svg,
-            open,
+            input,
+            output,
+            horizon,
+            checkpoint,
+            checkpoint_interval,
+            resume,
+            trace,
+            trace_sample,
+            decompose,
+            granularity,
+            merge_chains,
+            certificate,
         } => {
-            let schedule = run_algo(
-                |inst, _| dp::schedule(inst),
-                job_file,
-                constraint_file,
+            if granularity.is_some_and(|granularity| granularity < 1) {
+                error!("--granularity must be at least 1");
+                process::exit(1);
+            }
+            if merge_chains == Some(0) {
+                error!("--merge-chains must be at least 1");
+                process::exit(1);
+            }
+            let instance = input.read();
+            if !instance.downtime.is_empty() {
+                error!("The dynamic program does not support processor downtime");
+                error!("  hint: Use solve-lp or solve-ilp, which place no job on a processor while it is down");
+                process::exit(1);
+            }
+            let resolved = horizon.resolve(&instance);
+            let lower_bound = instance.min_critical_path_length();
+            if resolved < lower_bound {
+                error!("The horizon {resolved} is below the critical path length {lower_bound}, so no schedule completes by it");
+                error!("  hint: Pass a larger --horizon, or --horizon auto");
+                process::exit(1);
+            }
+            info!("Using the horizon {resolved} for --horizon {horizon}");
+            let instance = instance.with_horizon(resolved);
+            let checkpoint = CheckpointOptions {
+                path: checkpoint,
+                interval: Duration::from_secs(checkpoint_interval),
+                resume,
+            };
+            let trace = TraceOptions {
+                path: trace,
+                sample: trace_sample,
+            };
+            let mut added = vec![];
+            let (schedule, _, provenance) = solve(
+                |inst, _| {
+                    let dp = |inst: Instance| {
+                        let inst = match merge_chains {
+                            Some(max_chains) => {
+                                let (merged, constraints) =
+                                    preprocess::merge_chains(&inst, max_chains);
+                                for Constraint(left, right, _) in &constraints {
+                                    info!("Added constraint {left} -> {right} to merge chains");
+                                }
+                                added = constraints;
+                                merged
+                            }
+                            None => inst,
+                        };
+                        (
+                            dp::schedule(inst, &checkpoint, &trace),
+                            SolverStats::default(),
+                        )
+                    };
+                    let dp = |inst| match granularity {
+                        Some(granularity) => {
+                            preprocess::solve_coarse(inst, granularity, dp, &ListOptions::default())
+                        }
+                        None => dp(inst),
+                    };
+                    if decompose {
+                        decompose::solve(inst, dp)
+                    } else {
+                        dp(inst)
+                    }
+                },
+                "solve-dp",
+                instance,
+                &input,
                 false,
             );
-            process_schedule(schedule, job_file, constraint_file, svg, open);
+            if !added.is_empty() {
+                diagnostics::report(
+                    Category::Model,
+                    format!(
+                        "The schedule is only optimal for the instance with the {} constraints added by --merge-chains",
+                        added.len()
+                    ),
+                );
+            }
+            let provenance = provenance.with_added_constraints(&added);
+            if let Some(path) = &certificate {
+                write_certificate(path, &schedule, &input);
+            }
+            process_schedule(schedule, &provenance, &[], &input, &output);
         }
         Commands::SolveIlp {
-            ref job_file,
-            ref constraint_file,
-            svg,
-            open,
+            input,
+            output,
             compress,
+            contiguous,
+            seed,
+            multi_start,
+            grow_allotments,
+            horizon_limit,
+            fail_if_ratio_above,
+            max_model_size,
+            dry_run,
+            int_tolerance,
+            round_processing_times,
+            dump_rounding,
+            concavify,
+            group_identical,
+            decompose,
         } => {
-            let schedule = run_algo(ilp::schedule, job_file, constraint_file, compress);
-            process_schedule(schedule, job_file, constraint_file, svg, open);
+            let instance = input.read();
+            check_horizon_limit(horizon_limit, &input);
+            let groups = group_identical.then(|| preprocess::identical_jobs(&instance));
+            if dry_run {
+                println!("{}", ilp::build_model(&instance, groups.as_ref()).size);
+                return;
+            }
+            let quotient = groups.as_ref().map(|groups| groups.quotient(&instance));
+            check_model_size(
+                ilp::model_size(quotient.as_ref().unwrap_or(&instance)),
+                max_model_size,
+            );
+            let list_options = ListOptions {
+                contiguous,
+                seed,
+                multi_start,
+                objective: Some(input.objective.boxed()),
+                grow_allotments,
+                horizon_limit,
+            };
+            let (schedule, stats, provenance) = solve(
+                |inst, compress| {
+                    let ilp = |inst| {
+                        ilp::schedule(
+                            inst,
+                            compress,
+                            int_tolerance,
+                            round_processing_times,
+                            group_identical,
+                            &list_options,
+                        )
+                    };
+                    let ilp = |inst| {
+                        if concavify {
+                            preprocess::solve_concave(inst, ilp, &list_options)
+                        } else {
+                            ilp(inst)
+                        }
+                    };
+                    if decompose {
+                        decompose::solve(inst, ilp)
+                    } else {
+                        ilp(inst)
+                    }
+                },
+                "solve-ilp",
+                instance,
+                &input,
+                compress,
+            );
+            let provenance = provenance.with_horizon_limit(horizon_limit);
+            let ratio = stats.rounding_ratio(schedule.makespan());
+            dump_rounding_decisions(dump_rounding.as_deref(), &stats.rounding);
+            process_schedule(schedule, &provenance, &stats.rounding, &input, &output);
+            check_rounding_ratio(ratio, fail_if_ratio_above);
         }
         Commands::SolveLp {
-            ref job_file,
-            ref constraint_file,
-            svg,
-            open,
+            input,
+            output,
             compress,
+            contiguous,
+            seed,
+            multi_start,
+            grow_allotments,
+            horizon_limit,
+            fail_if_ratio_above,
+            max_model_size,
+            dry_run,
+            rounding_strategy,
+            int_tolerance,
+            dump_rounding,
+            concavify,
+            group_identical,
         } => {
-            let schedule = run_algo(lp::schedule, job_file, constraint_file, compress);
-            process_schedule(schedule, job_file, constraint_file, svg, open);
-        }
-        Commands::Generate {
-            n,
-            m,
-            min: min_p,
-            max: max_p,
-            omega,
-            min_chain,
-            max_chain,
-            ref job_file,
-            ref constraint_file,
-            concave,
+            let instance = input.read();
+            check_horizon_limit(horizon_limit, &input);
+            let groups = group_identical.then(|| preprocess::identical_jobs(&instance));
+            if dry_run {
+                println!("{}", lp::build_model(&instance, groups.as_ref()).size);
+                return;
+            }
+            let quotient = groups.as_ref().map(|groups| groups.quotient(&instance));
+            check_model_size(
+                lp::model_size(quotient.as_ref().unwrap_or(&instance)),
+                max_model_size,
+            );
+            let list_options = ListOptions {
+                contiguous,
+                seed,
+                multi_start,
+                objective: Some(input.objective.boxed()),
+                grow_allotments,
+                horizon_limit,
+            };
+            let (schedule, stats, provenance) = solve(
+                |inst, compress| {
+                    let lp = |inst| {
+                        lp::schedule(
+                            inst,
+                            compress,
+                            int_tolerance,
+                            rounding_strategy,
+                            group_identical,
+                            &list_options,
+                        )
+                    };
+                    if concavify {
+                        preprocess::solve_concave(inst, lp, &list_options)
+                    } else {
+                        lp(inst)
+                    }
+                },
+                "solve-lp",
+                instance,
+                &input,
+                compress,
+            );
+            let provenance = provenance.with_horizon_limit(horizon_limit);
+            let ratio = stats.rounding_ratio(schedule.makespan());
+            dump_rounding_decisions(dump_rounding.as_deref(), &stats.rounding);
+            process_schedule(schedule, &provenance, &stats.rounding, &input, &output);
+            check_rounding_ratio(ratio, fail_if_ratio_above);
+        }
+        Commands::SolveMilp {
+            input,
+            output,
+            formulation,
+            contiguous,
+            max_model_size,
+            dry_run,
         } => {
-            assert!(n >= 1, "n must be at least 1");
-            assert!(min_p >= 1, "min_p must be at least 1");
-            assert!(max_p >= min_p, "max_p must be at least min_p");
-            assert!(omega >= 1, "omega must be at least 1");
-            assert!(omega <= n, "omega must be at most n");
-            assert!(min_chain >= 1, "min_chain must be at least 1");
-            assert!(
-                max_chain >= min_chain,
-                "max_chain must be at least min_chain"
+            let instance = input.read();
+            if dry_run {
+                println!("{}", milp::model_size(&instance, formulation));
+                return;
+            }
+            check_model_size(milp::model_size(&instance, formulation), max_model_size);
+            let list_options = ListOptions {
+                contiguous,
+                objective: Some(input.objective.boxed()),
+                ..ListOptions::default()
+            };
+            let (schedule, _, provenance) = solve(
+                |inst, _| milp::schedule(inst, formulation, &list_options),
+                "solve-milp",
+                instance,
+                &input,
+                false,
             );
-            assert!(max_chain <= n, "max_chain must be at most n");
-            assert!(
-                min_chain * omega <= n,
-                "min_chain * omega must be at at most n"
+            process_schedule(schedule, &provenance, &[], &input, &output);
+        }
+        Commands::SolveShelf { input, output } => {
+            let instance = input.read();
+            let (schedule, _, provenance) = solve(
+                |inst, _| (shelf::schedule(inst), SolverStats::default()),
+                "solve-shelf",
+                instance,
+                &input,
+                false,
             );
-            assert!(
-                max_chain * omega >= n,
-                "max_chain * omega must be at at least n"
+            process_schedule(schedule, &provenance, &[], &input, &output);
+        }
+        Commands::Pareto {
+            input,
+            algorithm,
+            svg,
+        } => explore_pareto(&input, algorithm, svg.as_deref()),
+        Commands::Size {
+            input,
+            target,
+            algorithm,
+            svg,
+        } => size_machines(&input, target, algorithm, svg.as_deref()),
+        Commands::Certify { input, certificate } => check_certificate(&input, &certificate),
+        Commands::Perturb {
+            input,
+            times,
+            drop_constraints,
+            add_constraints,
+            seed,
+            out_job_file,
+            out_constraint_file,
+        } => perturb_instance(
+            &input,
+            &perturb::Noise {
+                times,
+                drop_constraints,
+                add_constraints,
+            },
+            seed,
+            &out_job_file,
+            &out_constraint_file,
+        ),
+        Commands::Stats {
+            input,
+            write_reduced,
+        } => print_stats(&input, write_reduced.as_deref()),
+        Commands::SweepAllotments {
+            input,
+            output,
+            max_total_work,
+            max_vectors,
+        } => sweep_allotments(&input, &output, max_total_work, max_vectors),
+        Commands::Generate(args) => generate_instance(&args),
+        Commands::Replan {
+            input,
+            progress,
+            at,
+            algo,
+            output,
+        } => replan_schedule(&input, &progress, at, algo, &output),
+        Commands::GenSolve {
+            generator,
+            algo,
+            save_instance,
+            output,
+        } => generate_and_solve(&generator, algo, save_instance.as_deref(), &output),
+        Commands::Experiment {
+            family_file,
+            algorithms,
+            seeds,
+            first_seed,
+            alpha,
+            p_values_csv,
+            ratios_csv,
+            jobs,
+        } => {
+            configure_threads(jobs);
+            run_experiment(
+                &family_file,
+                &algorithms,
+                seeds,
+                first_seed,
+                alpha,
+                p_values_csv.as_deref(),
+                ratios_csv.as_deref(),
             );
+        }
+        Commands::Selftest {
+            instances,
+            seed,
+            max_jobs,
+            dir,
+        } => run_selftest(instances, seed, max_jobs, &dir),
+        Commands::Baseline { command } => run_baseline(command),
+        Commands::BenchMilp {
+            dir,
+            formulations,
+            max_model_size,
+        } => run_bench_milp(&dir, &formulations, max_model_size),
+        Commands::Index { dir } => {
+            gallery::write(path::Path::new(&dir));
+        }
+        Commands::TraceStats { trace } => print_trace_stats(&trace),
+        Commands::Schema { document } => println!(
+            "{}",
+            serde_json::to_string_pretty(&document.schema()).expect("cannot serialize schema")
+        ),
+        Commands::Completions { shell } => {
+            let mut command = cli();
+            let name = command.get_name().to_string();
+            clap_complete::generate(shell, &mut command, name, &mut io::stdout());
+        }
+        Commands::Manpage => clap_mangen::Man::new(cli())
+            .render(&mut io::stdout())
+            .expect("cannot write man page to stdout"),
+        Commands::RegenTestdata {
+            dir,
+            check,
+            tolerance,
+        } => {
+            if !testdata::run(&dir, check, tolerance) {
+                process::exit(1);
+            }
+        }
+    }
+    if cli.strict {
+        check_strict();
+    }
+}
 
-            let instance =
-                generate::instance(n, m, min_p, max_p, omega, min_chain, max_chain, concave);
-            files::write(job_file, constraint_file, instance);
+/// Exits with `STRICT_EXIT_CODE` if any diagnostics were reported, listing
+/// them by category
+fn check_strict() {
+    let diagnostics = diagnostics::by_category();
+    if diagnostics.is_empty() {
+        return;
+    }
+    error!(
+        "Strict mode fails because of {} warnings",
+        diagnostics
+            .iter()
+            .map(|(_, messages)| messages.len())
+            .sum::<usize>()
+    );
+    for (category, messages) in &diagnostics {
+        error!("  {category}:");
+        for message in messages {
+            error!("    {message}");
         }
     }
+    process::exit(STRICT_EXIT_CODE);
 }
 
-fn run_algo<T: FnOnce(Instance, bool) -> Schedule>(
-    algo: T,
+fn run_experiment(
+    family_file: &str,
+    algorithms: &[pareto::Algorithm],
+    seeds: usize,
+    first_seed: u64,
+    alpha: f64,
+    p_values_csv: Option<&str>,
+    ratios_csv: Option<&str>,
+) {
+    let families = files::read_families(family_file);
+    let before = Instant::now();
+    let cells = experiment::run(&families, algorithms, first_seed, seeds);
+    info!(
+        "Needed {:?} to solve {} instances of {} families with {} algorithms",
+        before.elapsed(),
+        seeds * families.len(),
+        families.len(),
+        algorithms.len()
+    );
+    experiment::log_distributions(&cells);
+    if let Some(path) = ratios_csv {
+        files::write_ratios(path, &cells);
+        info!("Makespan ratios are written to {path}");
+    }
+    // the tests would pair the ratios of instances that not all algorithms
+    // solved
+    if interrupt::requested() {
+        interrupt::finish(
+            &ratios_csv
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+        );
+    }
+    let comparisons = experiment::compare(&cells, alpha);
+    if let Some(path) = p_values_csv {
+        files::write_comparisons(path, &comparisons);
+        info!("P-values are written to {path}");
+    }
+    for comparison in &comparisons {
+        println!("{}", comparison.verdict(alpha));
+    }
+}
+
+/// Sets the number of threads of the pool that parallel work runs on, or
+/// keeps one thread per CPU if `jobs` is `None`
+fn configure_threads(jobs: Option<usize>) {
+    let Some(jobs) = jobs else {
+        return;
+    };
+    if jobs == 0 {
+        error!("--jobs must be at least 1");
+        process::exit(1);
+    }
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(jobs)
+        .build_global()
+        .expect("thread pool is configured twice");
+}
+
+fn run_selftest(instances: usize, seed: u64, max_jobs: usize, dir: &str) {
+    if max_jobs == 0 {
+        error!("--max-jobs must be at least 1");
+        process::exit(1);
+    }
+    let before = Instant::now();
+    let report = selftest::run(instances, seed, max_jobs, dir);
+    info!("Needed {:?} for the self test", before.elapsed());
+    for line in report.lines() {
+        println!("{line}");
+    }
+    if !report.failures.is_empty() {
+        for failure in &report.failures {
+            error!("Failed check at {failure}");
+        }
+        error!(
+            "  hint: Run the solvers on the saved instances to reproduce the failures, e.g. solve-dp --job-file {} --constraint-file {}",
+            report.failures[0].job_file, report.failures[0].constraint_file
+        );
+        process::exit(1);
+    }
+}
+
+/// Prints how the formulations of the exact MILP fare on the test instances
+/// in `dir` as CSV, skipping programs with more variables and constraints
+/// combined than `max_model_size`
+fn run_bench_milp(dir: &str, formulations: &[Formulation], max_model_size: Option<usize>) {
+    println!(
+        "instance,formulation,variables,constraints,nonzeros,runtime_ms,makespan,relaxation,gap"
+    );
+    for name in testdata::instance_names(dir) {
+        let instance = testdata::read_instance(dir, &name);
+        for &formulation in formulations {
+            let size = milp::model_size(&instance, formulation);
+            if max_model_size.is_some_and(|limit| size.total() > limit) {
+                info!("Skipping the {formulation} formulation of {name} with {size}");
+                continue;
+            }
+            let comparison = milp::compare(&instance, formulation);
+            info!(
+                "Solved the {formulation} formulation of {name} in {:?}",
+                comparison.runtime
+            );
+            println!(
+                "{name},{formulation},{},{},{},{},{},{:.3},{:.4}",
+                comparison.size.variables,
+                comparison.size.constraints,
+                comparison.size.nonzeros,
+                comparison.runtime.as_millis(),
+                comparison.makespan,
+                comparison.relaxation,
+                comparison.gap()
+            );
+        }
+    }
+}
+
+fn run_baseline(command: BaselineCommand) {
+    match command {
+        BaselineCommand::Record(args) => {
+            let entries = baseline::run(&args.dir, &args.algorithms);
+            let mut database = baseline::read(&args.database);
+            let (added, replaced) = baseline::record(&mut database, entries);
+            baseline::write(&args.database, &database);
+            info!(
+                "Recorded {added} new and {replaced} updated results in {}",
+                args.database
+            );
+        }
+        BaselineCommand::Compare {
+            baseline: args,
+            makespan_threshold,
+            runtime_threshold,
+        } => {
+            if !path::Path::new(&args.database).exists() {
+                error!("Baseline database {} does not exist", args.database);
+                error!("  hint: Run baseline record to create it");
+                process::exit(1);
+            }
+            let database = baseline::read(&args.database);
+            let entries = baseline::run(&args.dir, &args.algorithms);
+            let deltas = baseline::compare(
+                &database,
+                entries,
+                baseline::Thresholds {
+                    makespan: makespan_threshold,
+                    runtime: runtime_threshold,
+                },
+            );
+            for line in cli_output::baseline_table(&deltas) {
+                println!("{line}");
+            }
+            for delta in &deltas {
+                let current = &delta.current;
+                let Some(recorded) = &delta.baseline else {
+                    continue;
+                };
+                let name = format!("{} on {}", current.algorithm, current.instance);
+                match delta.status {
+                    Status::Regression => diagnostics::report(
+                        Category::Regression,
+                        format!(
+                            "{name} has makespan {}, baseline is {}",
+                            current.makespan, recorded.makespan
+                        ),
+                    ),
+                    Status::Slower => diagnostics::report(
+                        Category::Regression,
+                        format!(
+                            "{name} needs {} ms, baseline is {} ms",
+                            current.runtime_ms, recorded.runtime_ms
+                        ),
+                    ),
+                    Status::Changed => diagnostics::report(
+                        Category::Input,
+                        format!(
+                            "{} changed since the baseline of {name} was recorded by version {}",
+                            current.instance, recorded.version
+                        ),
+                    ),
+                    _ => {}
+                }
+            }
+            let count = |status| deltas.iter().filter(|delta| delta.status == status).count();
+            info!(
+                "{} regressions, {} slower, {} improved, {} faster, {} new of {} results",
+                count(Status::Regression),
+                count(Status::Slower),
+                count(Status::Improved),
+                count(Status::Faster),
+                count(Status::New),
+                deltas.len()
+            );
+            if count(Status::Changed) > 0 {
+                warn!("  hint: Run baseline record to record the changed instances again");
+            }
+        }
+    }
+}
+
+fn print_trace_stats(path: &str) {
+    let counts = trace::stats(path);
+    println!("depth,explored,accepted,{}", Pruned::ALL.iter().join(","));
+    let mut total = trace::Counts::default();
+    for (depth, counts) in &counts {
+        println!(
+            "{depth},{},{},{}",
+            counts.explored(),
+            counts.accepted,
+            counts.pruned.iter().join(",")
+        );
+        total.accepted += counts.accepted;
+        for (sum, count) in total.pruned.iter_mut().zip(counts.pruned) {
+            *sum += count;
+        }
+    }
+    println!(
+        "total,{},{},{}",
+        total.explored(),
+        total.accepted,
+        total.pruned.iter().join(",")
+    );
+}
+
+/// Writes a certificate that the makespan of `schedule` is optimal for the
+/// instance of `input`, and warns if a check finds an earlier schedule
+fn write_certificate(path: &str, schedule: &Schedule, input: &InputArgs) {
+    let instance_hash = hash_files(&[&input.job_file, &input.constraint_file]);
+    let certificate = certify::certify(&input.read(), schedule, instance_hash);
+    for check in &certificate.checks {
+        if check.outcome == certify::Outcome::Feasible {
+            diagnostics::report(
+                Category::Verification,
+                format!(
+                    "The {} finds a schedule that completes by {}, so the makespan {} is not optimal",
+                    check.method, check.horizon, certificate.claimed_optimum
+                ),
+            );
+        }
+    }
+    certify::write(path, &certificate);
+    info!("Certificate is written to {path}");
+}
+
+/// Checks a certificate against the instance of `input`, prints the recorded
+/// and the rerun checks, and exits with status 1 if it does not hold
+fn check_certificate(input: &InputArgs, path: &str) {
+    let instance = input.read();
+    let certificate = certify::read(path);
+    let instance_hash = hash_files(&[&input.job_file, &input.constraint_file]);
+    let rerun = certify::check(&instance, certificate.claimed_optimum - 1);
+    for check in &rerun {
+        let recorded = certificate
+            .checks
+            .iter()
+            .find(|recorded| recorded.method == check.method)
+            .map_or("missing".to_string(), |recorded| {
+                recorded.outcome.to_string()
+            });
+        println!(
+            "{} by {}: recorded {recorded}, rerun {}",
+            check.method, check.horizon, check.outcome
+        );
+    }
+    let problems = certify::problems(&instance, &certificate, &instance_hash, &rerun);
+    if problems.is_empty() {
+        info!(
+            "Certified that the makespan {} is optimal",
+            certificate.claimed_optimum
+        );
+        return;
+    }
+    for problem in &problems {
+        error!("Rejected certificate: {problem}");
+    }
+    error!("  hint: Run solve-dp --certificate again to write a new certificate");
+    process::exit(1);
+}
+
+fn perturb_instance(
+    input: &InputArgs,
+    noise: &perturb::Noise,
+    seed: Option<u64>,
     job_file: &str,
     constraint_file: &str,
+) {
+    if input.improve_from.is_some() || input.pinned_file.is_some() {
+        error!("--improve-from and --pinned-file cannot be used to perturb an instance");
+        process::exit(1);
+    }
+    if !(0.0..100.0).contains(&noise.times) {
+        error!(
+            "--times must be at least 0 and below 100, not {}",
+            noise.times
+        );
+        process::exit(1);
+    }
+    if !(0.0..=100.0).contains(&noise.drop_constraints) || noise.add_constraints < 0.0 {
+        error!("--drop-constraints must be between 0 and 100, and --add-constraints at least 0");
+        process::exit(1);
+    }
+    // reading the instance already checks that it can be scheduled
+    let instance = input.read();
+    let seed = seed.unwrap_or_else(reproducible::seed);
+    let (perturbed, changes) = match perturb::perturb(&instance, noise, seed) {
+        Ok(perturbed) => perturbed,
+        Err(e) => {
+            error!("Every perturbation from seed {seed} is infeasible, the last one has {e}");
+            error!("  hint: Perturb less, or pick another seed");
+            process::exit(1);
+        }
+    };
+    println!("{}", perturb::Change::HEADER);
+    for change in &changes {
+        debug!("{change}");
+        println!("{}", change.record());
+    }
+    files::write(job_file, constraint_file, perturbed);
+    info!("Wrote the instance perturbed from seed {seed} to {job_file} and {constraint_file}");
+}
+
+fn print_stats(input: &InputArgs, write_reduced: Option<&str>) {
+    let instance = input.read();
+    let before = Instant::now();
+    let reduced = instance.transitive_reduction();
+    info!(
+        "Reduced {} constraints to {} in {:?}",
+        instance.constraints.len(),
+        reduced.len(),
+        before.elapsed()
+    );
+    let redundancy = if instance.constraints.is_empty() {
+        0.0
+    } else {
+        100.0 * (instance.constraints.len() - reduced.len()) as f64
+            / instance.constraints.len() as f64
+    };
+    println!("jobs,processors,constraints,soft_constraints,reduced_constraints,redundancy_percent");
+    println!(
+        "{},{},{},{},{},{redundancy:.1}",
+        instance.jobs.len(),
+        instance.processor_count,
+        instance.constraints.len(),
+        instance.soft_constraints.len(),
+        reduced.len()
+    );
+    if let Some(path) = write_reduced {
+        files::write_constraints(path, reduced.to_vec(), instance.soft_constraints.clone());
+        info!("Wrote the reduced constraints to {path}");
+    }
+}
+
+fn explore_pareto(input: &InputArgs, algorithm: pareto::Algorithm, svg: Option<&str>) {
+    if input.improve_from.is_some() || input.pinned_file.is_some() {
+        error!("--improve-from and --pinned-file cannot be used to explore the Pareto frontier");
+        process::exit(1);
+    }
+    let instance = input.read();
+    let before = Instant::now();
+    let points = pareto::explore(&instance, algorithm);
+    let frontier = pareto::frontier(points);
+    info!(
+        "Needed {:?} to find {} Pareto optimal schedules",
+        before.elapsed(),
+        frontier.len()
+    );
+    println!("budget,machines,makespan");
+    for point in &frontier {
+        println!("{},{},{}", point.budget, point.machines, point.makespan);
+    }
+    if let Some(path) = svg {
+        output::atomic_write(path, render_pareto(&frontier).as_bytes())
+            .unwrap_or_else(|e| panic!("cannot write to file {path}: {e}"));
+        info!("Chart is written to {path}");
+    }
+}
+
+/// Evaluates every allotment vector within the work budget with LIST, logs
+/// the distribution of their makespans, and exports the schedule of the first
+/// vector with the smallest makespan
+fn sweep_allotments(
+    input: &InputArgs,
+    output: &OutputArgs,
+    max_total_work: Option<i64>,
+    max_vectors: usize,
+) {
+    if input.improve_from.is_some() {
+        error!("--improve-from cannot be used to sweep allotments");
+        process::exit(1);
+    }
+    let instance = input.read();
+    let budget = max_total_work.unwrap_or(i64::MAX);
+    let sweep = |instance: Instance, _| {
+        let before = Instant::now();
+        let vectors = algo::allotment_space(&instance, budget)
+            .take(max_vectors.saturating_add(1))
+            .collect::<Vec<_>>();
+        if vectors.len() > max_vectors {
+            error!("More than {max_vectors} allotment vectors are within the budget");
+            error!("  hint: Pass a smaller --max-total-work or a larger --max-vectors");
+            process::exit(1);
+        }
+        if vectors.is_empty() {
+            error!("No allotment vector has a total work of at most {budget}");
+            error!("  hint: Pass a larger --max-total-work");
+            process::exit(1);
+        }
+        let targets = vec![0; instance.jobs.len()];
+        let evaluate = |allotments: &[usize]| Schedule {
+            processor_count: instance.processor_count,
+            jobs: list::schedule(&instance, allotments, &targets, &ListOptions::default()).jobs,
+        };
+        let makespans = vectors
+            .par_iter()
+            .map(|allotments| evaluate(allotments).makespan())
+            .collect::<Vec<_>>();
+        let (best, &makespan) = makespans
+            .iter()
+            .enumerate()
+            .min_by_key(|&(i, &makespan)| (makespan, i))
+            .expect("at least one vector");
+        info!(
+            "Evaluated {} allotment vectors in {:?}, {} of them reach the smallest makespan {makespan}, first with the allotments {}",
+            vectors.len(),
+            before.elapsed(),
+            makespans.iter().filter(|&&m| m == makespan).count(),
+            vectors[best].iter().join(" ")
+        );
+        let makespans = makespans.iter().map(|&m| f64::from(m)).collect::<Vec<_>>();
+        for (from, count) in stats_util::histogram(&makespans, SWEEP_HISTOGRAM_BINS) {
+            info!("  {from:>8.1} | {count}");
+        }
+        (evaluate(&vectors[best]), SolverStats::default())
+    };
+    let (schedule, _, provenance) = solve(sweep, "sweep-allotments", instance, input, false);
+    process_schedule(schedule, &provenance, &[], input, output);
+}
+
+fn size_machines(input: &InputArgs, target: i32, algorithm: pareto::Algorithm, svg: Option<&str>) {
+    if input.improve_from.is_some() || input.pinned_file.is_some() {
+        error!(
+            "--improve-from and --pinned-file cannot be used to search for the number of machines"
+        );
+        process::exit(1);
+    }
+    let instance = input.read();
+    let before = Instant::now();
+    let sizing = pareto::size(&instance, algorithm, target);
+    info!(
+        "Needed {:?} to try {} numbers of machines",
+        before.elapsed(),
+        sizing.curve.len()
+    );
+    println!("machines,makespan");
+    for (machines, makespan) in &sizing.curve {
+        println!("{machines},{makespan}");
+    }
+    if let Some(path) = svg {
+        output::atomic_write(
+            path,
+            render_size(&sizing.curve, &sizing.bounds, target, sizing.machines).as_bytes(),
+        )
+        .unwrap_or_else(|e| panic!("cannot write to file {path}: {e}"));
+        info!("Chart is written to {path}");
+    }
+    if let Some(machines) = sizing.machines {
+        info!("{machines} machines suffice for a makespan of {target}");
+    } else {
+        let (machines, best) = sizing.curve[sizing.curve.len() - 1];
+        error!(
+            "Even {machines} machines cannot meet a makespan of {target}, the best makespan is {best}"
+        );
+        process::exit(1);
+    }
+}
+
+fn generate_instance(args: &GenerateArgs) {
+    let (instance, metadata, _) = sample_instance(&args.generator);
+    save_instance(&args.job_file, &args.constraint_file, instance, &metadata);
+}
+
+/// Generates an instance with the parameters of the generator, resampling it
+/// if requested, and reports why it is not interesting
+fn sample_instance(args: &GeneratorArgs) -> (Instance, Metadata, GenerateConfig) {
+    let GeneratorArgs {
+        n,
+        m,
+        min: min_p,
+        max: max_p,
+        omega,
+        min_chain,
+        max_chain,
+        concave,
+        seed,
+        require_interesting,
+        max_resamples,
+        min_width,
+        min_parallelism,
+        max_identical,
+    } = *args;
+    let config = GenerateConfig {
+        n,
+        m,
+        min_p,
+        max_p,
+        omega,
+        min_chain,
+        max_chain,
+        concave,
+    };
+    if let Err(errors) = config.validate() {
+        error!("No instance exists with these parameters:");
+        for error in errors {
+            error!("  {error}");
+        }
+        process::exit(1);
+    }
+    let thresholds = Thresholds {
+        min_width,
+        min_parallelism,
+        max_identical,
+    };
+    let seed = seed.unwrap_or_else(reproducible::seed);
+    let (instance, metadata) = generate::sample(
+        &config,
+        seed,
+        &thresholds,
+        require_interesting.then_some(max_resamples),
+    );
+    info!(
+        "Generated instance from seed {} after {} resamples",
+        metadata.seed, metadata.resamples
+    );
+    for warning in &metadata.warnings {
+        diagnostics::report(
+            Category::Input,
+            format!("Generated instance is not interesting: {warning}"),
+        );
+    }
+    (instance, metadata, config)
+}
+
+/// Writes a generated instance to the job and constraint files, and its
+/// metadata next to the job file
+fn save_instance(job_file: &str, constraint_file: &str, instance: Instance, metadata: &Metadata) {
+    files::write(job_file, constraint_file, instance);
+    let metadata_file = path::Path::new(job_file).with_extension("meta.json");
+    files::write_json(
+        metadata_file
+            .to_str()
+            .expect("invalid UTF-8 in job file name"),
+        metadata,
+    );
+}
+
+/// Plans the jobs of the instance that remain at time `at` according to the
+/// progress file, on a copy of the instance that starts at `at`, and exports
+/// the schedule with absolute times like the solve commands
+fn replan_schedule(
+    input: &InputArgs,
+    progress: &str,
+    at: i32,
+    algorithm: pareto::Algorithm,
+    output: &OutputArgs,
+) {
+    if input.improve_from.is_some()
+        || input.pinned_file.is_some()
+        || input.split_longer_than.is_some()
+    {
+        error!("--improve-from, --pinned-file, and --split-longer-than cannot be used to replan");
+        error!("  hint: Mark the jobs that must not move as running in {progress}");
+        process::exit(1);
+    }
+    if at < 0 {
+        error!("--at must not be negative");
+        process::exit(1);
+    }
+    let (instance, positions) = input.read_with_positions();
+    let progress_by_job = files::read_progress(progress, &positions, &instance.jobs);
+    let residual = replan::residual(&instance, &progress_by_job, at);
+    let rebased = replan::rebase(&residual, at);
+    if let Err(e) = run::check(&rebased) {
+        error!("The progress cannot be continued: {e}");
+        error!("  hint: Fix {progress}, e.g. jobs cannot run or be done before their predecessors are done");
+        process::exit(1);
+    }
+    let (schedule, stats, provenance) = run_algo(
+        |inst, _| algorithm.solve_with_stats(inst),
+        algorithm.command(),
+        rebased,
+        false,
+    );
+    info!("Replanned the remaining jobs from {at} on");
+    let provenance = provenance.with_input_files(&input.job_file, &input.constraint_file);
+    export_schedule(
+        replan::to_absolute(schedule, at),
+        residual,
+        None,
+        &generate_filename(&input.job_file, &input.constraint_file),
+        &provenance,
+        &stats.rounding,
+        output,
+    );
+}
+
+/// Generates an instance, optionally saves it with the given prefix, and
+/// solves, checks, and exports it like the solve commands. Since the instance
+/// never has to be read, the provenance records the generator run instead of
+/// the input files, unless the instance was saved.
+fn generate_and_solve(
+    generator: &GeneratorArgs,
+    algorithm: pareto::Algorithm,
+    prefix: Option<&str>,
+    output: &OutputArgs,
+) {
+    let (instance, metadata, config) = sample_instance(generator);
+    let seed = metadata.seed;
+    // the generator is deterministic, so the instance is generated again
+    // whenever another copy is needed
+    let regenerate = || generate::instance(&config, &mut StdRng::seed_from_u64(seed));
+    let files = prefix.map(|prefix| {
+        let job_file = format!("{prefix}_jobs.csv");
+        let constraint_file = format!("{prefix}_constraints.csv");
+        save_instance(&job_file, &constraint_file, regenerate(), &metadata);
+        info!("Instance is written to {job_file} and {constraint_file}");
+        (job_file, constraint_file)
+    });
+    let (schedule, stats, provenance) = run_algo(
+        |inst, _| algorithm.solve_with_stats(inst),
+        algorithm.command(),
+        instance,
+        false,
+    );
+    let provenance = provenance.with_generator(config.clone(), seed);
+    let (provenance, svg_path) = match &files {
+        Some((job_file, constraint_file)) => (
+            provenance.with_input_files(job_file, constraint_file),
+            generate_filename(job_file, constraint_file),
+        ),
+        None => (
+            provenance,
+            format!("./schedules/generated_{seed}_schedule.svg"),
+        ),
+    };
+    export_schedule(
+        schedule,
+        regenerate(),
+        None,
+        &svg_path,
+        &provenance,
+        &stats.rounding,
+        output,
+    );
+}
+
+/// Runs `algo` on the instance, or repairs and improves the schedule given via
+/// --improve-from instead if there is one. Long jobs are split beforehand if
+/// requested.
+fn solve<T: FnOnce(Instance, bool) -> (Schedule, SolverStats)>(
+    algo: T,
+    solver: &'static str,
+    instance: Instance,
+    input: &InputArgs,
     compress: bool,
-) -> Schedule {
-    let instance = files::read(job_file, constraint_file);
+) -> (Schedule, SolverStats, Provenance) {
+    let instance = match input.split_longer_than {
+        Some(max_len) => {
+            let (split_instance, split) = preprocess::split_long_jobs(&instance, max_len);
+            info!(
+                "Split jobs longer than {max_len} into {} jobs in total",
+                split.original.len()
+            );
+            split_instance
+        }
+        None => instance,
+    };
+    if input
+        .efficiency_floor
+        .is_some_and(|floor| !(floor > 0.0 && floor <= 1.0))
+    {
+        error!("--efficiency-floor must be greater than 0 and at most 1");
+        process::exit(1);
+    }
+    let algo = |instance, compress| match input.efficiency_floor {
+        Some(floor) => {
+            preprocess::solve_efficient(instance, floor, |instance| algo(instance, compress))
+        }
+        None => algo(instance, compress),
+    };
+    let objective = input.objective.boxed();
+    let Some(path) = &input.improve_from else {
+        let (schedule, stats, provenance) = run_algo(algo, solver, instance, compress);
+        let provenance = provenance
+            .with_input_files(&input.job_file, &input.constraint_file)
+            .with_objective(objective.name());
+        return (schedule, stats, provenance);
+    };
+    let search = improve::SearchOptions {
+        time_limit: input.time_limit.map(Duration::from_secs),
+        checkpoint_best: input.checkpoint_best.clone(),
+    };
+    let mut improvements = vec![];
+    let (schedule, stats, provenance) = run_algo(
+        |instance, _| {
+            if instance.has_affinity() {
+                diagnostics::report(
+                    Category::Model,
+                    "Repairs and local search ignore processor affinity, so jobs may run on processors they are not allowed on",
+                );
+            }
+            let schedule = files::read_schedule(path);
+            let before = schedule.makespan();
+            let (schedule, repairs) = improve::repair(&instance, schedule);
+            if repairs.total() == 0 {
+                info!(
+                    "Schedule from {path} fits the instance without repairs, {} jobs have changed processing times",
+                    repairs.changed
+                );
+            } else {
+                diagnostics::report(
+                    Category::Repair,
+                    format!(
+                        "Schedule from {path} needed {} repairs: {repairs}",
+                        repairs.total()
+                    ),
+                );
+            }
+            let repaired = schedule.clone();
+            let (schedule, trace) =
+                improve::improve(&instance, schedule, objective.as_ref(), &search);
+            info!(
+                "Local search improved the schedule {} times: {}",
+                trace.len() - 1,
+                trace
+                    .iter()
+                    .map(|point| format!("{} after {} ms", point.makespan, point.elapsed_ms))
+                    .join(", ")
+            );
+            improvements = trace;
+            info!(
+                "Makespan was {before}, {} after repairs, and {} after local search",
+                repaired.makespan(),
+                schedule.makespan()
+            );
+            if schedule.is_equivalent(&repaired) {
+                info!("Local search could not move any job to complete earlier");
+            }
+            let searched = schedule.clone();
+            let schedule = improve::grow_allotments(&instance, schedule);
+            info!(
+                "Grew the allotments of {} jobs",
+                improve::grown_jobs(&searched, &schedule)
+            );
+            (schedule, SolverStats::default())
+        },
+        solver,
+        instance,
+        compress,
+    );
+    let provenance = provenance
+        .with_input_files(&input.job_file, &input.constraint_file)
+        .with_objective(objective.name())
+        .with_improvements(improvements);
+    (schedule, stats, provenance)
+}
 
+fn run_algo<T: FnOnce(Instance, bool) -> (Schedule, SolverStats)>(
+    algo: T,
+    solver: &'static str,
+    instance: Instance,
+    compress: bool,
+) -> (Schedule, SolverStats, Provenance) {
+    let started_at = reproducible::timestamp();
     let before = Instant::now();
-    let schedule = algo(instance, compress);
+    let (schedule, stats) = algo(instance, compress);
     let duration = before.elapsed();
-    let length = schedule
-        .jobs
-        .iter()
-        .map(|job| job.start_time + job.processing_time())
-        .max()
-        .unwrap_or(0);
+    let provenance = Provenance::new(solver, compress, &stats, started_at, duration);
+    let length = schedule.makespan();
     info!(
         "Needed {:?} to schedule {} jobs on {} processors for {} seconds",
         duration,
@@ -248,47 +2382,576 @@ fn run_algo<T: FnOnce(Instance, bool) -> Schedule>(
         schedule.processor_count,
         length
     );
+    let ratio = stats.rounding_ratio(length);
+    if let (Some(lp_bound), Some(ratio)) = (stats.lp_bound, ratio) {
+        info!("The linear program bounds the makespan by {lp_bound}, the rounded schedule is {ratio:.3} times longer");
+    }
+    if let Some(deviation) = stats.max_target_deviation.filter(|&d| d > 0) {
+        info!("Jobs started up to {deviation} seconds later than targeted by the linear program");
+    }
     println!(
-        "{},{},{},{}",
+        "{},{},{},{},{},{}",
         duration.as_millis(),
         schedule.jobs.len(),
         schedule.processor_count,
-        length
+        length,
+        stats.lp_bound.map(|b| b.to_string()).unwrap_or_default(),
+        ratio.map(|r| r.to_string()).unwrap_or_default(),
     );
-    schedule
+    (schedule, stats, provenance)
+}
+
+/// Merges the segments of split jobs back into the jobs of `instance`. If the
+/// segments of some job cannot be merged, they are reported and the schedule
+/// is kept along with the split instance and the map of its jobs.
+fn merge_segments(
+    schedule: Schedule,
+    instance: Instance,
+    max_len: i32,
+) -> (Schedule, Instance, Option<SplitMap>) {
+    let (split_instance, split) = preprocess::split_long_jobs(&instance, max_len);
+    match split.merge(&instance, &schedule) {
+        Ok(merged) => (merged, instance, None),
+        Err(profiles) => {
+            for profile in &profiles {
+                diagnostics::report(
+                    Category::Repair,
+                    format!("Cannot merge the segments of a split job, {profile}"),
+                );
+            }
+            warn!(
+                "  hint: The segments are exported as separate jobs, labeled by their original job"
+            );
+            (schedule, split_instance, Some(split))
+        }
+    }
+}
+
+fn check_model_size(size: ModelSize, limit: Option<usize>) {
+    info!("The linear program has {size}");
+    if let Some(limit) = limit {
+        if size.total() > limit {
+            error!("Linear program with {size} exceeds the allowed maximum of {limit}");
+            error!("  hint: Use solve-dp for instances of this size");
+            process::exit(1);
+        }
+    }
+}
+
+/// Exits if a partial schedule up to `horizon_limit` is requested together
+/// with --improve-from, which never runs the list scheduling
+fn check_horizon_limit(horizon_limit: Option<i32>, input: &InputArgs) {
+    if horizon_limit.is_some() && input.improve_from.is_some() {
+        error!("--horizon-limit cannot be used together with --improve-from");
+        error!("  hint: Pass the partial schedule to --improve-from without --horizon-limit to extend it");
+        process::exit(1);
+    }
+}
+
+fn dump_rounding_decisions(path: Option<&str>, rounding: &[RoundingDecision]) {
+    if let Some(path) = path {
+        files::write_rounding(path, rounding);
+        info!("Rounding decisions are written to {path}");
+    }
+}
+
+fn check_rounding_ratio(ratio: Option<f64>, limit: Option<f64>) {
+    if let (Some(ratio), Some(limit)) = (ratio, limit) {
+        if ratio > limit {
+            error!("Rounding ratio {ratio} exceeds the allowed maximum of {limit}");
+            process::exit(1);
+        }
+    }
 }
 
 fn process_schedule(
     schedule: Schedule,
-    job_file: &str,
-    constraint_file: &str,
-    svg: bool,
-    open: bool,
+    provenance: &Provenance,
+    rounding: &[RoundingDecision],
+    input: &InputArgs,
+    output: &OutputArgs,
 ) {
-    if svg {
-        let rendered = render_schedule(schedule);
-
-        fs::create_dir_all("./schedules/").expect("cannot create directory ./schedules");
-        let path = generate_filename(job_file, constraint_file);
-        let mut file = fs::File::create(path.clone())
-            .unwrap_or_else(|e| panic!("cannot create file {path}: {e}"));
-        file.write_all(rendered.as_bytes())
-            .unwrap_or_else(|e| panic!("cannot write to file {path}: {e}"));
-        info!("Result is written to {path}");
+    // the solvers consume the instance, so read it again to check the schedule
+    export_schedule(
+        schedule,
+        input.read(),
+        input.split_longer_than,
+        &generate_filename(&input.job_file, &input.constraint_file),
+        provenance,
+        rounding,
+        output,
+    );
+}
 
-        if open {
-            info!("Opening file ...");
-            if let Err(e) = open_that(&path) {
-                error!("Could not open file {path}: {e:#?}");
-            }
+/// Checks `schedule` against `instance`, merges the segments of jobs longer
+/// than `split_longer_than`, and writes the outputs, rendering the schedule
+/// to `svg_path` if requested
+fn export_schedule(
+    schedule: Schedule,
+    instance: Instance,
+    split_longer_than: Option<i32>,
+    svg_path: &str,
+    provenance: &Provenance,
+    rounding: &[RoundingDecision],
+    output: &OutputArgs,
+) {
+    let (schedule, instance, split) = match split_longer_than {
+        Some(max_len) => merge_segments(schedule, instance, max_len),
+        None => (schedule, instance, None),
+    };
+    let schedule = &schedule;
+    // a partial schedule leaves out the jobs after the horizon limit, which
+    // is not a violation
+    let (violations, deferred) = match provenance.options.horizon_limit {
+        Some(limit) => {
+            let deferred = schedule.deferred(&instance);
+            info!(
+                "Schedule is a partial preview up to {limit}, deferring {} jobs: {}",
+                deferred.len(),
+                deferred.iter().join(", ")
+            );
+            (schedule.partial_violations(&instance), deferred)
+        }
+        None => (schedule.violations(&instance), vec![]),
+    };
+    for violation in violations {
+        diagnostics::report_error(
+            Category::Verification,
+            format!("Schedule violates the instance: {violation}"),
+        );
+    }
+    // breaking soft constraints is allowed, so they are only logged
+    let soft_violations = schedule.soft_violations(&instance);
+    if !soft_violations.is_empty() {
+        info!(
+            "Schedule breaks {} of {} soft constraints with a total penalty of {}",
+            soft_violations.len(),
+            instance.soft_constraints.len(),
+            soft_violations.iter().map(|soft| soft.penalty).sum::<f64>()
+        );
+        for soft in &soft_violations {
+            let gap = if soft.min_lag == 0 {
+                "before".to_string()
+            } else {
+                format!("less than {} after", soft.min_lag)
+            };
+            info!(
+                "  job {} starts {gap} job {} completes (penalty {})",
+                soft.right, soft.left, soft.penalty
+            );
         }
+    }
+    let objectives = objective::evaluate_all(&instance, schedule);
+    info!(
+        "Objectives of the schedule: {}",
+        objectives
+            .iter()
+            .map(|(name, value)| format!("{name} {value}"))
+            .join(", ")
+    );
+    let parallelism = metrics::Parallelism::new(&instance, schedule);
+    info!(
+        "Parallelism of the schedule: mean allotment {:.2}, work {} of at least {}, idle {:.1}%",
+        parallelism.mean_allotment,
+        parallelism.work,
+        parallelism.min_work,
+        100.0 * parallelism.idle_fraction
+    );
+    let waiting_times = metrics::waiting_times(&instance, schedule);
+    let waiting = metrics::Waiting::new(&waiting_times);
+    info!(
+        "Waiting of the jobs after they became ready: total {}, mean {:.2}, max {}, {} of {} jobs waited",
+        waiting.total,
+        waiting.mean,
+        waiting.max,
+        waiting.waiting_jobs,
+        schedule.jobs.len()
+    );
+    // the chains of a partial schedule may be incomplete
+    let breakdown = if deferred.is_empty() {
+        metrics::chain_breakdown(&instance, schedule)
     } else {
+        vec![]
+    };
+    if output.summary {
+        for line in cli_output::summary(schedule) {
+            println!("{line}");
+        }
+        println!();
+        for line in cli_output::chain_summary(&breakdown) {
+            println!("{line}");
+        }
+    } else if log_enabled!(Level::Info) {
+        for line in cli_output::summary(schedule) {
+            info!("{line}");
+        }
         info!("");
-        if open {
-            info!("  hint: Ignored --open because no schedule file was written");
+        for line in cli_output::chain_summary(&breakdown) {
+            info!("{line}");
+        }
+    }
+    if output.ascii {
+        let options = RenderOptions {
+            labels: split_labels(split.as_ref()),
+            downtime: instance.downtime.clone(),
+            machine_names: instance.machine_names.clone(),
+            predecessors: predecessors(&instance),
+            ..RenderOptions::default()
+        };
+        println!();
+        for line in cli_output::gantt(schedule, &options) {
+            println!("{line}");
+        }
+    }
+    let mut artifacts = vec![];
+    if let Some(path) = &output.pin_csv {
+        pin_jobs(path, schedule, output.pin_before);
+        artifacts.push(Artifact::new(ArtifactKind::Csv, path));
+    }
+    if let Some(dir) = &output.per_processor_dir {
+        write_timelines(dir, schedule, &instance);
+        artifacts.push(Artifact::new(ArtifactKind::Dir, dir));
+    }
+    if let Some(path) = &output.utilization_csv {
+        let profile = schedule.resource_profile();
+        files::write_utilization(
+            path,
+            &profile,
+            &busy_machines(schedule, &instance, &profile),
+            output.format,
+        );
+        info!("Utilization is written to {path}");
+        artifacts.push(Artifact::new(ArtifactKind::Csv, path));
+    }
+    if output.svg {
+        let mut notes: HashMap<usize, String> = if output.svg_rounding {
+            rounding
+                .iter()
+                .map(|decision| (decision.job, format!("rounding: {}", decision.reason)))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+        for pin in &instance.pinned {
+            let note = notes.entry(pin.job.index).or_default();
+            if !note.is_empty() {
+                note.push('\n');
+            }
+            note.push_str("pinned");
+        }
+        let labels = split_labels(split.as_ref());
+        let depths = instance
+            .depths()
+            .into_iter()
+            .zip(&instance.jobs)
+            .map(|(depth, job)| (job.index, depth))
+            .collect();
+        if output.speed.is_some_and(|speed| speed <= 0.0) {
+            error!("--speed must be positive");
+            process::exit(1);
+        }
+        let options = RenderOptions {
+            theme: output.theme,
+            color_by: output.color_by,
+            depths,
+            notes,
+            labels,
+            capacity: instance.capacity.clone(),
+            downtime: instance.downtime.clone(),
+            machine_names: instance.machine_names.clone(),
+            chains: if output.svg_chains { breakdown } else { vec![] },
+            max_height: output.svg_max_height,
+            soft_violations: soft_violations
+                .iter()
+                .map(|soft| (soft.left, soft.right))
+                .collect(),
+            deferred: deferred.len(),
+            page_height: output.svg_page_height,
+            aggregate_below_px: output.svg_aggregate_below,
+            animate: output.animate.then(|| {
+                output
+                    .speed
+                    .unwrap_or(ANIMATION_SECONDS / f64::from(schedule.makespan().max(1)))
+            }),
+            predecessors: predecessors(&instance),
+            show_waiting: if output.svg_waiting {
+                waiting_times
+                    .iter()
+                    .map(|time| (time.job, time.ready))
+                    .collect()
+            } else {
+                HashMap::new()
+            },
+        };
+        let (pages, issues) = render_schedule_pages(schedule, &options);
+        for issue in &issues {
+            diagnostics::report(Category::Render, format!("Render problem: {issue}"));
+        }
+
+        fs::create_dir_all("./schedules/").expect("cannot create directory ./schedules");
+        if let [page] = pages.as_slice() {
+            output::atomic_write(svg_path, page.svg.as_bytes())
+                .unwrap_or_else(|e| panic!("cannot write to file {svg_path}: {e}"));
+            info!("Result is written to {svg_path}");
+            artifacts.push(Artifact::new(ArtifactKind::Svg, svg_path));
+        } else {
+            artifacts.extend(write_pages(svg_path, &pages));
         }
+    } else {
+        info!("");
         info!("  hint: Specify --svg to write a schedule file");
     }
+    // written last so that it includes the diagnostics of all other outputs
+    if let Some(path) = &output.schedule_json {
+        let structure = (output.with_structure && deferred.is_empty())
+            .then(|| Structure::new(&instance, schedule));
+        files::write_schedule(
+            path,
+            schedule,
+            provenance,
+            structure.as_ref(),
+            split.as_ref(),
+            &soft_violations,
+            &deferred,
+            &objectives,
+            &parallelism,
+            &waiting,
+            output.format,
+        );
+        info!("Schedule is written to {path}");
+        artifacts.push(Artifact::new(ArtifactKind::Json, path));
+    }
+    // the index is not opened by --open, which should show this schedule
+    if output.update_index {
+        let dir = path::Path::new(svg_path)
+            .parent()
+            .unwrap_or(path::Path::new("."));
+        gallery::write(dir);
+    }
+    if let Some(dir) = &output.debug_dir {
+        let run_dir = write_debug_dir(
+            dir,
+            schedule,
+            &instance,
+            provenance,
+            rounding,
+            split.as_ref(),
+            &deferred,
+        );
+        info!("Debug artifacts are written to {run_dir}");
+        artifacts.push(Artifact::new(ArtifactKind::Dir, run_dir));
+    }
+    if interrupt::requested() {
+        interrupt::finish(
+            &artifacts
+                .into_iter()
+                .map(|artifact| artifact.path)
+                .collect::<Vec<_>>(),
+        );
+    }
+    if let Some(requested) = output.open {
+        output::open_preferred(&artifacts, requested, &SystemOpener);
+    }
+}
+
+/// Labels of the jobs of a schedule whose split jobs were merged back, which
+/// name the segments of the original jobs, by job index
+fn split_labels(split: Option<&SplitMap>) -> HashMap<usize, String> {
+    split
+        .iter()
+        .flat_map(|split| (0..split.original.len()).map(|job| (job, split.label(job))))
+        .collect()
+}
+
+/// Writes the pages of a paginated schedule next to `svg_path`, adding the
+/// suffix `_page<N>` to its name, along with an HTML index that links them.
+/// Returns the index followed by the pages.
+fn write_pages(svg_path: &str, pages: &[Page]) -> Vec<Artifact> {
+    let stem = svg_path.strip_suffix(".svg").unwrap_or(svg_path);
+    let paths = (1..=pages.len())
+        .map(|k| format!("{stem}_page{k}.svg"))
+        .collect::<Vec<_>>();
+    for (page, path) in pages.iter().zip(&paths) {
+        output::atomic_write(path, page.svg.as_bytes())
+            .unwrap_or_else(|e| panic!("cannot write to file {path}: {e}"));
+    }
+    let names = paths
+        .iter()
+        .map(|path| {
+            path::Path::new(path)
+                .file_name()
+                .map_or_else(|| path.clone(), |name| name.to_string_lossy().into_owned())
+        })
+        .collect::<Vec<_>>();
+    let index = format!("{stem}.html");
+    let title = path::Path::new(stem).file_name().map_or_else(
+        || stem.to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    );
+    output::atomic_write(&index, render_page_index(&title, pages, &names).as_bytes())
+        .unwrap_or_else(|e| panic!("cannot write to file {index}: {e}"));
+    info!(
+        "Result is written to {} pages {stem}_page<N>.svg, linked from {index}",
+        pages.len()
+    );
+    let pages = paths
+        .into_iter()
+        .map(|path| Artifact::new(ArtifactKind::Svg, path));
+    [Artifact::new(ArtifactKind::Html, index)]
+        .into_iter()
+        .chain(pages)
+        .collect()
+}
+
+/// Writes the intermediate artifacts of a run to a new subdirectory of `dir`
+/// and returns its path. Warns if `dir` holds many runs, e.g. because it was
+/// passed to a batch of runs by accident.
+fn write_debug_dir(
+    dir: &str,
+    schedule: &Schedule,
+    instance: &Instance,
+    provenance: &Provenance,
+    rounding: &[RoundingDecision],
+    split: Option<&SplitMap>,
+    deferred: &[usize],
+) -> String {
+    let name = format!("{}-{}", provenance.solver, provenance.started_at);
+    let run_dir = (1..)
+        .map(|attempt| {
+            if attempt == 1 {
+                format!("{dir}/{name}")
+            } else {
+                format!("{dir}/{name}-{attempt}")
+            }
+        })
+        .find(|run_dir| !path::Path::new(run_dir).exists())
+        .expect("some run directory is free");
+    fs::create_dir_all(&run_dir)
+        .unwrap_or_else(|e| panic!("cannot create directory {run_dir}: {e}"));
+    let runs = fs::read_dir(dir)
+        .unwrap_or_else(|e| panic!("cannot read directory {dir}: {e}"))
+        .count();
+    if runs > DEBUG_RUN_LIMIT {
+        warn!("Debug directory {dir} holds {runs} runs, which take up more and more space");
+        warn!("  hint: Do not pass --debug-dir to batches of runs, and remove old runs");
+    }
+    if !rounding.is_empty() {
+        files::write_rounding(&format!("{run_dir}/rounding.csv"), rounding);
+    }
+    files::write_schedule(
+        &format!("{run_dir}/schedule.json"),
+        schedule,
+        provenance,
+        deferred
+            .is_empty()
+            .then(|| Structure::new(instance, schedule))
+            .as_ref(),
+        split,
+        &schedule.soft_violations(instance),
+        deferred,
+        &objective::evaluate_all(instance, schedule),
+        &metrics::Parallelism::new(instance, schedule),
+        &metrics::Waiting::new(&metrics::waiting_times(instance, schedule)),
+        ExportFormat::Standard,
+    );
+    let profile = schedule.resource_profile();
+    files::write_utilization(
+        &format!("{run_dir}/utilization.csv"),
+        &profile,
+        &busy_machines(schedule, instance, &profile),
+        ExportFormat::Standard,
+    );
+    write_timelines(&format!("{run_dir}/processors"), schedule, instance);
+    run_dir
+}
+
+/// Writes the jobs of `schedule` that start before `before` to a pinned-jobs
+/// file, skipping jobs that do not run on consecutive processors
+fn pin_jobs(path: &str, schedule: &Schedule, before: Option<i32>) {
+    let (pinned, skipped): (Vec<_>, Vec<_>) = schedule
+        .jobs
+        .iter()
+        .filter(|job| before.is_none_or(|before| job.start_time < before))
+        .sorted_by_key(|job| job.job.index)
+        .partition(|job| {
+            job.processors.len() == job.allotment
+                && job.processors.windows(2).all(|pair| pair[0] + 1 == pair[1])
+        });
+    if !skipped.is_empty() {
+        diagnostics::report(
+            Category::Repair,
+            format!(
+                "Cannot pin jobs {} because they do not run on consecutive processors",
+                skipped.iter().map(|job| job.job.index).join(", ")
+            ),
+        );
+        warn!("  hint: Pass --contiguous to place every job on consecutive processors");
+    }
+    files::write_pinned(path, &pinned);
+    info!("Pinned {} jobs in {path}", pinned.len());
+}
+
+/// Writes the timeline of every processor of `schedule` to `dir`. Jobs that
+/// the solver did not place on explicit processors are assigned to free ones
+/// like in the rendered schedule, avoiding the downtime of the processors of
+/// `instance`, which also names them.
+fn write_timelines(dir: &str, schedule: &Schedule, instance: &Instance) {
+    let (assignment, _, issues) = assign_processors(
+        schedule.processor_count,
+        &schedule.jobs,
+        &instance.downtime,
+        &predecessors(instance),
+    );
+    if !issues.is_empty() {
+        diagnostics::report(
+            Category::Repair,
+            format!(
+                "Jobs {} are missing from the timelines because too few processors are free when they start",
+                issues.iter().map(|issue| issue.job).join(", ")
+            ),
+        );
+    }
+    files::write_timelines(dir, schedule, &assignment, &instance.machine_names);
+    info!(
+        "Timelines of {} processors are written to {dir}",
+        schedule.processor_count
+    );
+}
+
+/// Names the busy processors at every point of the `profile` of `schedule`,
+/// assigning processors like `write_timelines`. Returns nothing if the
+/// processors of `instance` have no names.
+fn busy_machines(
+    schedule: &Schedule,
+    instance: &Instance,
+    profile: &[ProfilePoint],
+) -> Vec<Vec<String>> {
+    if instance.machine_names.is_empty() {
+        return vec![];
+    }
+    let (assignment, _, _) = assign_processors(
+        schedule.processor_count,
+        &schedule.jobs,
+        &instance.downtime,
+        &predecessors(instance),
+    );
+    profile
+        .iter()
+        .map(|point| {
+            schedule
+                .jobs
+                .iter()
+                .zip(&assignment)
+                .filter(|(job, _)| {
+                    job.start_time <= point.time && point.time < job.completion_time()
+                })
+                .flat_map(|(_, processors)| processors)
+                .filter(|&&processor| processor < schedule.processor_count)
+                .sorted_unstable()
+                .map(|&processor| instance.machine_name(processor))
+                .collect()
+        })
+        .collect()
 }
 
 fn generate_filename(job_file: &str, constraint_file: &str) -> String {
@@ -304,3 +2967,28 @@ fn generate_filename(job_file: &str, constraint_file: &str) -> String {
         .expect("invalid UTF-8 in job file name");
     format!("./schedules/{job_file}_{constraint_file}_schedule.svg")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bash_completions_mention_every_subcommand() {
+        let mut command = cli();
+        let mut script = vec![];
+        clap_complete::generate(
+            Shell::Bash,
+            &mut command,
+            "scheduling-malleable-tasks",
+            &mut script,
+        );
+        let script = String::from_utf8(script).expect("the script is UTF-8");
+        for subcommand in cli()
+            .get_subcommands()
+            .filter(|subcommand| !subcommand.is_hide_set())
+        {
+            let name = subcommand.get_name();
+            assert!(script.contains(name), "the completions lack {name}");
+        }
+    }
+}
@@ -0,0 +1,531 @@
+// Structural properties of a schedule that are derived from the precedence
+// constraints of its instance, and how a schedule uses parallelism.
+
+use std::collections::BTreeMap;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::algo::{topo_order, Instance, Schedule};
+
+/// The chains, the critical path, and the slack of a schedule, meant for
+/// tools that want to group or highlight the jobs of a schedule
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Structure {
+    /// Chain decomposition of the instance, each chain lists job ids in the
+    /// order of the precedence constraints
+    pub chains: Vec<Vec<usize>>,
+    /// Job ids along the longest path through the precedence constraints,
+    /// using the processing times of the scheduled allotments
+    pub critical_path: Vec<usize>,
+    /// Chain membership and slack of every job, sorted by id
+    pub jobs: Vec<JobStructure>,
+}
+
+/// The place of a single job in the structure of a schedule
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct JobStructure {
+    /// Id of the job
+    pub id: usize,
+    /// Index of the chain that contains the job
+    pub chain: usize,
+    /// Time by which the job could start later without delaying the makespan
+    pub slack: i32,
+}
+
+impl Structure {
+    /// Derives the structure of `schedule`, which must schedule every job of
+    /// `instance` exactly once
+    pub fn new(instance: &Instance, schedule: &Schedule) -> Self {
+        let chains = instance.chains();
+        let mut chain_of = vec![0; instance.jobs.len()];
+        for (chain_index, chain) in chains.iter().enumerate() {
+            for &job in chain {
+                chain_of[job] = chain_index;
+            }
+        }
+        let jobs = slack(instance, schedule)
+            .into_iter()
+            .enumerate()
+            .map(|(id, slack)| JobStructure {
+                id,
+                chain: chain_of[id],
+                slack,
+            })
+            .collect();
+        Structure {
+            chains,
+            critical_path: critical_path(instance, schedule),
+            jobs,
+        }
+    }
+}
+
+/// How long the jobs of one chain of the decomposition take in a schedule
+#[derive(Clone, Debug, Serialize)]
+pub struct ChainBreakdown {
+    /// Index of the chain in the chain decomposition
+    pub chain: usize,
+    /// Job ids of the chain in the order of the precedence constraints
+    pub jobs: Vec<usize>,
+    /// Sum of the processing times of the jobs at their scheduled allotments
+    pub processing_time: i32,
+    /// Time at which the last job of the chain completes
+    pub completion: i32,
+    /// Time between the completion of the chain and the makespan
+    pub slack: i32,
+}
+
+/// Breaks the makespan of `schedule` down by the chains of the decomposition
+/// of `instance`. The difference between the completion time and the
+/// processing time of a chain is the time it waits for other chains or for
+/// free processors. Jobs that are not scheduled are left out.
+pub fn chain_breakdown(instance: &Instance, schedule: &Schedule) -> Vec<ChainBreakdown> {
+    let mut scheduled = vec![None; instance.jobs.len()];
+    for job in &schedule.jobs {
+        if let Some(slot) = scheduled.get_mut(job.job.index) {
+            *slot = Some(job);
+        }
+    }
+    let makespan = schedule.makespan();
+    instance
+        .chains()
+        .into_iter()
+        .enumerate()
+        .map(|(chain, jobs)| {
+            let chain_jobs = jobs.iter().filter_map(|&j| scheduled[j]);
+            let processing_time = chain_jobs.clone().map(|job| job.processing_time()).sum();
+            let completion = chain_jobs
+                .map(|job| job.completion_time())
+                .max()
+                .unwrap_or(0);
+            ChainBreakdown {
+                chain,
+                jobs,
+                processing_time,
+                completion,
+                slack: makespan - completion,
+            }
+        })
+        .collect()
+}
+
+/// How a schedule uses the processors, meant for comparing algorithms by
+/// more than their makespans
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Parallelism {
+    /// Number of jobs by their allotment
+    pub allotments: BTreeMap<usize, usize>,
+    /// Mean allotment of the jobs, each weighted by its processing time
+    pub mean_allotment: f64,
+    /// Work executed by the schedule, i.e. the sum of the allotments times
+    /// the processing times of all jobs
+    pub work: i64,
+    /// Least work with which the jobs of the schedule can be executed, each
+    /// with its allotment of least work on the processors of the instance
+    pub min_work: i64,
+    /// Fraction of the processors times the makespan in which processors are
+    /// idle
+    pub idle_fraction: f64,
+}
+impl Parallelism {
+    /// Measures the parallelism of `schedule` on the processors of `instance`
+    pub fn new(instance: &Instance, schedule: &Schedule) -> Self {
+        let mut allotments = BTreeMap::new();
+        for job in &schedule.jobs {
+            *allotments.entry(job.allotment).or_default() += 1;
+        }
+        let time = schedule
+            .jobs
+            .iter()
+            .map(|job| i64::from(job.processing_time()))
+            .sum::<i64>();
+        let work = schedule
+            .jobs
+            .iter()
+            .map(|job| job.allotment as i64 * i64::from(job.processing_time()))
+            .sum::<i64>();
+        let min_work = schedule
+            .jobs
+            .iter()
+            .map(|job| {
+                job.job
+                    .processing_times
+                    .iter()
+                    .take(instance.processor_count)
+                    .zip(1..)
+                    .map(|(&p, l)| l * i64::from(p))
+                    .min()
+                    .unwrap_or(0)
+            })
+            .sum::<i64>();
+        let capacity = i64::from(schedule.makespan()) * schedule.processor_count as i64;
+        Parallelism {
+            allotments,
+            mean_allotment: if time == 0 {
+                0.0
+            } else {
+                work as f64 / time as f64
+            },
+            work,
+            min_work,
+            idle_fraction: if capacity == 0 {
+                0.0
+            } else {
+                (capacity - work) as f64 / capacity as f64
+            },
+        }
+    }
+    /// Work executed by the schedule relative to the least work, which is 1
+    /// if every job runs with its allotment of least work
+    pub fn work_ratio(&self) -> f64 {
+        self.work as f64 / self.min_work.max(1) as f64
+    }
+}
+
+/// When a job of a schedule became ready and when it started
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WaitingTime {
+    /// Index of the job
+    pub job: usize,
+    /// Time at which all predecessors of the job completed and their minimum
+    /// lags passed, or 0 if it has none
+    pub ready: i32,
+    /// Time at which the job started
+    pub start: i32,
+}
+impl WaitingTime {
+    /// Time between when the job became ready and when it started
+    pub fn wait(&self) -> i32 {
+        self.start - self.ready
+    }
+}
+
+/// How long the jobs of a schedule waited after they became ready, meant for
+/// diagnosing why a schedule is longer than its critical path
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Waiting {
+    /// Sum of the waiting times of all jobs
+    pub total: i64,
+    /// Mean waiting time of the jobs
+    pub mean: f64,
+    /// Longest waiting time of any job
+    pub max: i32,
+    /// Number of jobs that did not start as soon as they were ready
+    pub waiting_jobs: usize,
+}
+impl Waiting {
+    /// Summarizes the waiting times of the jobs of a schedule
+    pub fn new(times: &[WaitingTime]) -> Self {
+        let total = times.iter().map(|time| i64::from(time.wait())).sum::<i64>();
+        Waiting {
+            total,
+            mean: if times.is_empty() {
+                0.0
+            } else {
+                total as f64 / times.len() as f64
+            },
+            max: times.iter().map(WaitingTime::wait).max().unwrap_or(0),
+            waiting_jobs: times.iter().filter(|time| time.wait() > 0).count(),
+        }
+    }
+}
+
+/// Computes when every job of `schedule` became ready, i.e. the latest
+/// completion time of its predecessors in `instance` plus the minimum lag to
+/// it, or 0 if it has none, in the order of the jobs of the schedule.
+/// Predecessors that the schedule leaves out, e.g. because it is partial, are
+/// ignored.
+pub fn waiting_times(instance: &Instance, schedule: &Schedule) -> Vec<WaitingTime> {
+    let mut completions = vec![None; instance.jobs.len()];
+    for job in &schedule.jobs {
+        completions[job.job.index] = Some(job.completion_time());
+    }
+    schedule
+        .jobs
+        .iter()
+        .map(|job| {
+            let j = job.job.index;
+            let ready = instance
+                .predecessor_indices(j)
+                .iter()
+                .filter_map(|&p| Some(completions[p]? + instance.lag(p, j).min))
+                .fold(0, i32::max);
+            WaitingTime {
+                job: j,
+                ready,
+                start: job.start_time,
+            }
+        })
+        .collect()
+}
+
+/// Computes the slack of every job, i.e. its latest start time that still
+/// lets all its successors complete by the makespan after their minimum lags
+/// minus its actual start time. The latest start times are computed by a
+/// backward pass over the precedence constraints, ignoring the number of
+/// processors.
+pub fn slack(instance: &Instance, schedule: &Schedule) -> Vec<i32> {
+    let (starts, durations) = scheduled_times(instance, schedule);
+    let makespan = schedule.makespan();
+    let mut latest_start = vec![0; instance.jobs.len()];
+    for &j in instance.topological_order().iter().rev() {
+        let latest_completion = instance
+            .successor_indices(j)
+            .iter()
+            .map(|&s| latest_start[s] - instance.lag(j, s).min)
+            .min()
+            .unwrap_or(makespan);
+        latest_start[j] = latest_completion - durations[j];
+    }
+    latest_start
+        .into_iter()
+        .zip(starts)
+        .map(|(latest, start)| latest - start)
+        .collect()
+}
+
+/// Finds the longest path through the precedence constraints when every job
+/// takes as long as it does in `schedule` and waits for its minimum lags
+pub fn critical_path(instance: &Instance, schedule: &Schedule) -> Vec<usize> {
+    let (_, durations) = scheduled_times(instance, schedule);
+    let mut finished_at = vec![0; instance.jobs.len()];
+    let mut previous = vec![None; instance.jobs.len()];
+    let order = topo_order(instance).unwrap_or_else(|e| panic!("{e}"));
+    for j in order {
+        let longest = instance
+            .predecessor_indices(j)
+            .iter()
+            .map(|&p| (p, finished_at[p] + instance.lag(p, j).min))
+            .max_by_key(|&(_, released)| released);
+        finished_at[j] = longest.map_or(0, |(_, released)| released) + durations[j];
+        let longest = longest.map(|(p, _)| p);
+        previous[j] = longest;
+    }
+    let Some(last) = (0..instance.jobs.len()).max_by_key(|&j| finished_at[j]) else {
+        return vec![];
+    };
+    let mut path = vec![last];
+    while let Some(p) = previous[path[path.len() - 1]] {
+        path.push(p);
+    }
+    path.reverse();
+    path
+}
+
+/// Looks up the start time and processing time of every job of the instance
+fn scheduled_times(instance: &Instance, schedule: &Schedule) -> (Vec<i32>, Vec<i32>) {
+    let mut starts = vec![None; instance.jobs.len()];
+    let mut durations = vec![0; instance.jobs.len()];
+    for job in &schedule.jobs {
+        starts[job.job.index] = Some(job.start_time);
+        durations[job.job.index] = job.processing_time();
+    }
+    let starts = starts
+        .into_iter()
+        .enumerate()
+        .map(|(j, start)| start.unwrap_or_else(|| panic!("job {j} is not scheduled")))
+        .collect();
+    (starts, durations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{chain_breakdown, critical_path, slack, waiting_times, Waiting, WaitingTime};
+    use crate::{
+        algo::{Constraint, Instance, Job, Lag, Schedule, ScheduledJob},
+        builder::InstanceBuilder,
+    };
+
+    /// A diamond on two processors, where the first job comes before the
+    /// second and the third job, and both of them come before the last one.
+    /// The jobs take 2, 3, 1, and 2 time units, and the lag from the third
+    /// job to the last one is `lag`.
+    fn diamond(lag: i32) -> Instance {
+        let jobs = [2, 3, 1, 2]
+            .into_iter()
+            .enumerate()
+            .map(|(index, time)| Job {
+                index,
+                processing_times: vec![time, time],
+                synthesized: 0,
+                affinity: None,
+                note: None,
+            })
+            .collect();
+        let constraint = |left, right, min| Constraint(left, right, Lag { min, max: None });
+        let constraints = vec![
+            constraint(0, 1, 0),
+            constraint(0, 2, 0),
+            constraint(1, 3, 0),
+            constraint(2, 3, lag),
+        ];
+        Instance::new(2, jobs, constraints)
+    }
+
+    /// Schedules the jobs of `instance` on one processor each at the given
+    /// start times
+    fn schedule(instance: &Instance, starts: [i32; 4]) -> Schedule {
+        Schedule {
+            processor_count: 2,
+            jobs: starts
+                .into_iter()
+                .enumerate()
+                .map(|(index, start_time)| ScheduledJob {
+                    job: instance.shared_job(index),
+                    allotment: 1,
+                    start_time,
+                    processors: vec![],
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn slack_of_diamond() {
+        // the long branch through the second job is critical, and the third
+        // job may start up to two time units later
+        let instance = diamond(0);
+        let schedule = schedule(&instance, [0, 2, 2, 5]);
+        assert_eq!(schedule.violations(&instance), Vec::<String>::new());
+        assert_eq!(slack(&instance, &schedule), [0, 0, 2, 0]);
+        assert_eq!(critical_path(&instance, &schedule), [0, 1, 3]);
+    }
+
+    #[test]
+    fn slack_of_diamond_with_lag() {
+        // the lag before the last job uses up one unit of the slack of the
+        // third job, and starting it later uses up the other one
+        let instance = diamond(1);
+        assert_eq!(
+            slack(&instance, &schedule(&instance, [0, 2, 2, 5])),
+            [0, 0, 1, 0]
+        );
+        assert_eq!(
+            slack(&instance, &schedule(&instance, [0, 2, 3, 5])),
+            [0, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn breakdown_of_two_chains() {
+        // the chains 1 -> 2 and 3 -> 4, where the first job runs on both
+        // processors and the others on one processor each
+        let instance = InstanceBuilder::new()
+            .processors(2)
+            .job(1)
+            .times([3, 2])
+            .job(2)
+            .times([2, 1])
+            .job(3)
+            .times([4, 2])
+            .job(4)
+            .times([1, 1])
+            .constraint(1, 2)
+            .constraint(3, 4)
+            .build()
+            .expect("valid instance");
+        let schedule = Schedule {
+            processor_count: 2,
+            jobs: [(2, 0), (1, 2), (1, 2), (1, 6)]
+                .into_iter()
+                .enumerate()
+                .map(|(index, (allotment, start_time))| ScheduledJob {
+                    job: instance.shared_job(index),
+                    allotment,
+                    start_time,
+                    processors: vec![],
+                })
+                .collect(),
+        };
+        assert_eq!(schedule.violations(&instance), Vec::<String>::new());
+        assert_eq!(schedule.makespan(), 7);
+        let breakdown = chain_breakdown(&instance, &schedule)
+            .into_iter()
+            .map(|chain| {
+                (
+                    chain.chain,
+                    chain.jobs,
+                    chain.processing_time,
+                    chain.completion,
+                    chain.slack,
+                )
+            })
+            .collect::<Vec<_>>();
+        // the first chain takes 2 + 2 and is done at 4, while the second one
+        // takes 4 + 1 but waits for the first job, so it ends the schedule
+        assert_eq!(
+            breakdown,
+            [(0, vec![0, 1], 4, 4, 3), (1, vec![2, 3], 5, 7, 0)]
+        );
+    }
+
+    #[test]
+    fn waiting_times_of_chain() {
+        // three jobs that take 2, 3, and 1 time units in a chain, where the
+        // last one waits a lag of 1 after the second one
+        let jobs = [2, 3, 1]
+            .into_iter()
+            .enumerate()
+            .map(|(index, time)| Job {
+                index,
+                processing_times: vec![time],
+                synthesized: 0,
+                affinity: None,
+                note: None,
+            })
+            .collect();
+        let lag = |min| Lag { min, max: None };
+        let instance = Instance::new(
+            1,
+            jobs,
+            vec![Constraint(0, 1, lag(0)), Constraint(1, 2, lag(1))],
+        );
+        // the second job starts two units after the first one completes, and
+        // the third one right after the lag
+        let schedule = Schedule {
+            processor_count: 1,
+            jobs: [0, 4, 8]
+                .into_iter()
+                .enumerate()
+                .map(|(index, start_time)| ScheduledJob {
+                    job: instance.shared_job(index),
+                    allotment: 1,
+                    start_time,
+                    processors: vec![0],
+                })
+                .collect(),
+        };
+        assert_eq!(schedule.violations(&instance), Vec::<String>::new());
+        let times = waiting_times(&instance, &schedule);
+        let expected = [(0, 0, 0), (1, 2, 4), (2, 8, 8)].map(|(job, ready, start)| WaitingTime {
+            job,
+            ready,
+            start,
+        });
+        assert_eq!(times, expected);
+        assert_eq!(
+            times.iter().map(WaitingTime::wait).collect::<Vec<_>>(),
+            [0, 2, 0]
+        );
+        let waiting = Waiting::new(&times);
+        assert_eq!(
+            (waiting.total, waiting.max, waiting.waiting_jobs),
+            (2, 2, 1)
+        );
+        assert!((waiting.mean - 2.0 / 3.0).abs() < 1e-9);
+
+        // a partial schedule ignores the predecessors that it leaves out
+        let partial = Schedule {
+            processor_count: 1,
+            jobs: schedule.jobs[1..].to_vec(),
+        };
+        let times = waiting_times(&instance, &partial);
+        assert_eq!(
+            times,
+            [(1, 0, 4), (2, 8, 8)].map(|(job, ready, start)| WaitingTime { job, ready, start })
+        );
+        assert_eq!(Waiting::new(&times).total, 4);
+        assert!(Waiting::new(&[]).mean.abs() < 1e-9);
+    }
+}
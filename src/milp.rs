@@ -0,0 +1,849 @@
+// Exact mixed integer linear programs for the scheduling problem, which find
+// schedules with the smallest makespan like the dynamic program. They are
+// only practical for small instances.
+
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+use clap::ValueEnum;
+use itertools::Itertools;
+use log::{debug, info};
+
+use good_lp::{
+    constraint, default_solver, variable, variables, Expression, ProblemVariables, ResolutionError,
+    Solution, SolverModel, Variable, VariableDefinition,
+};
+
+use crate::{
+    algo::{Instance, ModelSize, Schedule, SolverStats},
+    diagnostics::{self, Category},
+    list::{self, ListOptions},
+};
+
+/// How the mixed integer linear program models the schedule
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Formulation {
+    /// A binary variable for every job, allotment, and start time up to the
+    /// horizon, with one capacity constraint per unit of time. Models the
+    /// capacity profile.
+    #[default]
+    TimeIndexed,
+    /// Integral start times, big-M constraints that sequence every pair of
+    /// jobs, and a flow of processors from every job to the jobs after it.
+    /// Its size does not depend on the processing times.
+    #[value(name = "bigm")]
+    BigM,
+    /// Integral start times, binary variables that assign the start and the
+    /// completion of every job to one of twice as many ordered event points
+    /// as there are jobs, and one capacity constraint per interval between
+    /// consecutive events. Its size does not depend on the processing times.
+    Event,
+}
+impl fmt::Display for Formulation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Formulation::TimeIndexed => "time-indexed",
+            Formulation::BigM => "bigm",
+            Formulation::Event => "event",
+        })
+    }
+}
+
+/// Whether the integer variables of a formulation stay integral, or are
+/// relaxed to continuous ones to compute the bound of the linear relaxation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Integrality {
+    Integral,
+    Relaxed,
+}
+impl Integrality {
+    /// A variable that is 0 or 1, or anything in between if relaxed
+    fn binary(self) -> VariableDefinition {
+        match self {
+            Integrality::Integral => variable().binary(),
+            Integrality::Relaxed => variable().clamp(0, 1),
+        }
+    }
+    /// A variable that is integral unless relaxed
+    fn integer(self) -> VariableDefinition {
+        match self {
+            Integrality::Integral => variable().integer(),
+            Integrality::Relaxed => variable(),
+        }
+    }
+}
+
+/// A solution of a formulation
+struct Solved {
+    /// The makespan, which may be fractional in a relaxation
+    makespan: f64,
+    /// The allotment of every job
+    allotments: Vec<usize>,
+    /// The start time of every job
+    starts: Vec<i32>,
+}
+
+/// An allotment that a job may run with: the number of processors, the
+/// processing time, and the binary variable that selects it
+type AllotmentOption = (usize, i32, Variable);
+
+/// How a formulation fares on an instance, see `compare`
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    /// The formulation
+    pub formulation: Formulation,
+    /// Size of the mixed integer linear program
+    pub size: ModelSize,
+    /// Time needed to solve the mixed integer linear program
+    pub runtime: Duration,
+    /// The optimal makespan
+    pub makespan: i32,
+    /// The bound of the linear relaxation on the makespan
+    pub relaxation: f64,
+}
+impl Comparison {
+    /// Relative gap between the optimal makespan and the bound of the linear
+    /// relaxation, which is the smaller the tighter the formulation is
+    pub fn gap(&self) -> f64 {
+        if self.makespan == 0 {
+            0.0
+        } else {
+            (f64::from(self.makespan) - self.relaxation) / f64::from(self.makespan)
+        }
+    }
+}
+
+/// Computes a schedule with the smallest makespan for the given `instance`
+/// using the mixed integer linear program of the given `formulation`. Soft
+/// constraints are treated as hard ones. LIST with the given `list_options`
+/// places the jobs at the start times of the solution, which it reproduces
+/// since they satisfy the instance.
+#[expect(clippy::needless_pass_by_value)]
+pub fn schedule(
+    instance: Instance,
+    formulation: Formulation,
+    list_options: &ListOptions,
+) -> (Schedule, SolverStats) {
+    if !instance.soft_constraints.is_empty() {
+        diagnostics::report(
+            Category::Model,
+            "The mixed integer linear program treats all soft constraints as hard ones",
+        );
+    }
+    if formulation != Formulation::TimeIndexed && instance.has_capacity_limits() {
+        diagnostics::report(
+            Category::Model,
+            format!(
+                "The {} formulation does not model the capacity profile and the downtime, only the list scheduling respects them",
+                if formulation == Formulation::BigM { "big-M" } else { "event-point" }
+            ),
+        );
+    }
+    if instance.has_affinity() {
+        diagnostics::report(
+            Category::Model,
+            "The mixed integer linear program does not model processor affinity, only the list scheduling respects it",
+        );
+    }
+    let instance = instance.enforce_soft(|_| true);
+    let before = Instant::now();
+    let Solved {
+        makespan,
+        allotments,
+        starts,
+    } = solve(&instance, formulation, Integrality::Integral)
+        .unwrap_or_else(|e| panic!("no solution: {e}"));
+    let makespan = makespan.round() as i32;
+    info!(
+        "Solved the {formulation} formulation with {} in {:?}, the optimal makespan is {makespan}",
+        model_size(&instance, formulation),
+        before.elapsed()
+    );
+    for (j, (l_j, s_j)) in allotments.iter().zip(&starts).enumerate() {
+        debug!("l_{j} = {l_j}, S_{j} = {s_j}");
+    }
+    let list = list::schedule(&instance, &allotments, &starts, list_options);
+    (
+        Schedule {
+            processor_count: instance.processor_count,
+            jobs: list.jobs,
+        },
+        SolverStats {
+            seed: list.seed,
+            ..SolverStats::default()
+        },
+    )
+}
+
+/// Solves the mixed integer linear program of the given `formulation` for
+/// `instance` and its linear relaxation, to compare the formulations by
+/// their runtime and the gap of their relaxation. Soft constraints are
+/// treated as hard ones, like `schedule` does.
+pub fn compare(instance: &Instance, formulation: Formulation) -> Comparison {
+    let instance = instance.enforce_soft(|_| true);
+    let before = Instant::now();
+    let solved = solve(&instance, formulation, Integrality::Integral)
+        .unwrap_or_else(|e| panic!("no solution: {e}"));
+    let runtime = before.elapsed();
+    let relaxed = solve(&instance, formulation, Integrality::Relaxed)
+        .unwrap_or_else(|e| panic!("no solution of the relaxation: {e}"));
+    Comparison {
+        formulation,
+        size: model_size(&instance, formulation),
+        runtime,
+        makespan: solved.makespan.round() as i32,
+        relaxation: relaxed.makespan,
+    }
+}
+
+/// Solves the given `formulation` for `instance`, with integral variables or
+/// its linear relaxation
+fn solve(
+    instance: &Instance,
+    formulation: Formulation,
+    integrality: Integrality,
+) -> Result<Solved, ResolutionError> {
+    match formulation {
+        Formulation::TimeIndexed => solve_time_indexed(instance, integrality),
+        Formulation::BigM => solve_big_m(instance, integrality),
+        Formulation::Event => solve_event(instance, integrality),
+    }
+}
+
+/// Computes the number of variables, constraints, and nonzeros of the mixed
+/// integer linear program of the given `formulation` without building it
+pub fn model_size(instance: &Instance, formulation: Formulation) -> ModelSize {
+    let n = instance.jobs.len();
+    let edges = edges(instance);
+    let max_lags = edges
+        .iter()
+        .filter(|&&(j, k)| instance.lag(j, k).max.is_some())
+        .count();
+    // the precedence constraints of an edge refer to the variables of both
+    // jobs, or of the left job only in the big-M formulation
+    let edge_nonzeros = |variables: &dyn Fn(usize, usize) -> usize| {
+        edges
+            .iter()
+            .map(|&(j, k)| {
+                let constraints = if instance.lag(j, k).max.is_some() {
+                    2
+                } else {
+                    1
+                };
+                constraints * variables(j, k)
+            })
+            .sum::<usize>()
+    };
+    // allotment options of every job in the big-M and event formulations
+    let per_job = (0..n)
+        .map(|j| match instance.pin(j) {
+            Some(_) => 1,
+            None => instance.jobs[j]
+                .max_allotment()
+                .min(instance.processor_count),
+        })
+        .collect::<Vec<_>>();
+    match formulation {
+        Formulation::TimeIndexed => {
+            let horizon = instance.horizon();
+            let mut per_job = vec![0; n];
+            let mut busy = 0;
+            for (j, l, _) in start_variables(instance, horizon) {
+                per_job[j] += 1;
+                busy += instance.jobs[j].processing_time(l).max(0) as usize;
+            }
+            let count = per_job.iter().sum::<usize>();
+            ModelSize {
+                variables: 1 + count,
+                constraints: n + horizon.max(0) as usize + n + edges.len() + max_lags,
+                // every start variable appears in the assignment of its job,
+                // the capacity of every unit of time it is busy, and the
+                // makespan bound of its job
+                nonzeros: count + busy + count + n + edge_nonzeros(&|j, k| per_job[j] + per_job[k]),
+            }
+        }
+        Formulation::BigM => {
+            let options = per_job.iter().sum::<usize>();
+            let pairs = n * n.saturating_sub(1);
+            ModelSize {
+                variables: 1 + options + n + 2 * pairs + 2 * n,
+                constraints: 2 * n + pairs + pairs / 2 + pairs + 2 * n + 1 + edges.len() + max_lags,
+                // the allotment of every job, its makespan bound, its
+                // sequencing after every other job, both directions of every
+                // pair, the flow bounds, the flow conservation, and the pool
+                // of idle processors
+                nonzeros: options
+                    + 2 * n
+                    + options
+                    + 3 * pairs
+                    + n.saturating_sub(1) * options
+                    + pairs
+                    + 2 * pairs
+                    + 2 * (n + pairs + options)
+                    + n
+                    + edges.len()
+                    + edge_nonzeros(&|j, _| 3 + per_job[j]),
+            }
+        }
+        Formulation::Event => {
+            let options = per_job.iter().sum::<usize>();
+            let events = 2 * n;
+            let intervals = events.saturating_sub(1);
+            ModelSize {
+                variables: 1 + options + n + events + 2 * n * events + n * intervals,
+                constraints: n
+                    + 2 * n
+                    + n
+                    + 4 * n * events
+                    + intervals
+                    + n
+                    + n * intervals
+                    + intervals
+                    + 2 * edges.len()
+                    + max_lags,
+                // the allotment of every job, its start and completion
+                // events, their order, the links of the events to its start
+                // and completion, the order of the events, its makespan
+                // bound, its usage in every interval, the capacity of every
+                // interval, and the order of the events of every edge
+                nonzeros: options
+                    + 2 * n * events
+                    + 2 * n * events
+                    + 6 * n * events
+                    + 2 * events * (3 * n + options)
+                    + 2 * intervals
+                    + 2 * n
+                    + options
+                    + n * intervals
+                    + options * intervals
+                    + n * intervals * events
+                    + n * intervals
+                    + 2 * events * edges.len()
+                    + edge_nonzeros(&|j, _| 2 + per_job[j]),
+            }
+        }
+    }
+}
+
+/// Lists every precedence constraint by the positions of its jobs
+fn edges(instance: &Instance) -> Vec<(usize, usize)> {
+    (0..instance.jobs.len())
+        .flat_map(|j| instance.successor_indices(j).iter().map(move |&k| (j, k)))
+        .collect()
+}
+
+/// Lists the job, allotment, and start time of every start variable of the
+/// time-indexed formulation, where every job completes by `horizon`. Pinned
+/// jobs only get the variable of their pin.
+fn start_variables(instance: &Instance, horizon: i32) -> Vec<(usize, usize, i32)> {
+    instance
+        .jobs
+        .iter()
+        .enumerate()
+        .flat_map(|(j, job)| match instance.pin(j) {
+            Some(pin) => vec![(j, pin.allotment, pin.start_time)],
+            None => (1..=job.max_allotment().min(instance.processor_count))
+                .flat_map(|l| (0..=horizon - job.processing_time(l)).map(move |t| (j, l, t)))
+                .collect(),
+        })
+        .collect()
+}
+
+/// Decides whether the instance has a schedule in which all jobs complete
+/// by `horizon`, using the time-indexed formulation. Soft constraints may be
+/// broken, and processor affinity and downtime are not modeled, so an
+/// instance without such a schedule has no schedule by `horizon` with them
+/// either.
+pub fn feasible_within(instance: &Instance, horizon: i32) -> bool {
+    let pinned_late = instance
+        .pinned
+        .iter()
+        .any(|pin| pin.completion_time() > horizon);
+    if horizon < 0 || pinned_late {
+        return instance.jobs.is_empty();
+    }
+    let instance = instance
+        .with_processor_count(instance.processor_count)
+        .with_horizon(horizon);
+    match solve_time_indexed(&instance, Integrality::Integral) {
+        Ok(solved) => solved.makespan.round() as i32 <= horizon,
+        Err(ResolutionError::Infeasible) => false,
+        Err(e) => panic!("cannot decide feasibility by {horizon}: {e}"),
+    }
+}
+
+/// Solves the time-indexed formulation, which fails if the solver finds no
+/// solution, e.g. because the horizon is too small
+fn solve_time_indexed(
+    instance: &Instance,
+    integrality: Integrality,
+) -> Result<Solved, ResolutionError> {
+    let n = instance.jobs.len();
+    let horizon = instance.horizon();
+    let mut vars = variables!();
+    let makespan = vars.add(variable().min(0));
+    let starts = start_variables(instance, horizon)
+        .into_iter()
+        .map(|(j, l, t)| (j, l, t, vars.add(integrality.binary())))
+        .collect::<Vec<_>>();
+    let mut assigned = vec![vec![]; n];
+    let mut start_times = vec![vec![]; n];
+    let mut completion_times = vec![vec![]; n];
+    let mut busy = vec![vec![]; horizon.max(0) as usize];
+    for &(j, l, t, x) in &starts {
+        let p = instance.jobs[j].processing_time(l);
+        assigned[j].push(x);
+        start_times[j].push((f64::from(t), x));
+        completion_times[j].push((f64::from(t + p), x));
+        for time in t..t + p {
+            busy[time as usize].push((l as f64, x));
+        }
+    }
+    let sum = |terms: &[(f64, Variable)]| {
+        terms
+            .iter()
+            .map(|&(coefficient, x)| coefficient * x)
+            .sum::<Expression>()
+    };
+    let problem = vars.minimise(makespan).using(default_solver);
+    // every job starts exactly once
+    let problem = assigned.iter().fold(problem, |prob, xs| {
+        prob.with(constraint!(xs.iter().sum::<Expression>() == 1))
+    });
+    // at most as many processors are busy as the capacity allows
+    let problem = busy
+        .iter()
+        .enumerate()
+        .filter(|(_, terms)| !terms.is_empty())
+        .fold(problem, |prob, (time, terms)| {
+            let capacity = instance.capacity_at(time as i32) as f64;
+            prob.with(constraint!(sum(terms) <= capacity))
+        });
+    let problem = completion_times.iter().fold(problem, |prob, terms| {
+        prob.with(constraint!(sum(terms) <= makespan))
+    });
+    let problem = edges(instance).into_iter().fold(problem, |prob, (j, k)| {
+        let lag = instance.lag(j, k);
+        let prob = prob.with(constraint!(
+            sum(&completion_times[j]) + lag.min <= sum(&start_times[k])
+        ));
+        match lag.max {
+            Some(max) => prob.with(constraint!(
+                sum(&start_times[k]) <= sum(&completion_times[j]) + max
+            )),
+            None => prob,
+        }
+    });
+    let solution = problem.solve()?;
+    let mut allotments = vec![1; n];
+    let mut start = vec![0; n];
+    for &(j, l, t, x) in &starts {
+        if solution.value(x) > 0.5 {
+            allotments[j] = l;
+            start[j] = t;
+        }
+    }
+    Ok(Solved {
+        makespan: solution.value(makespan),
+        allotments,
+        starts: start,
+    })
+}
+
+/// Solves the big-M formulation
+fn solve_big_m(instance: &Instance, integrality: Integrality) -> Result<Solved, ResolutionError> {
+    let n = instance.jobs.len();
+    let m = instance.processor_count as f64;
+    let big_m = big_m(instance);
+    let mut vars = variables!();
+    let makespan = vars.add(variable().min(0));
+    let options = allotment_options(instance, &mut vars, integrality);
+    let starts = start_time_variables(instance, &mut vars, integrality);
+    // whether job i completes before job j starts, and how many processors
+    // job j takes over from job i
+    let pairs = (0..n)
+        .flat_map(|i| (0..n).filter(move |&j| j != i).map(move |j| (i, j)))
+        .collect::<Vec<_>>();
+    let before = pairs
+        .iter()
+        .map(|_| vars.add(integrality.binary()))
+        .collect::<Vec<_>>();
+    let flows = pairs
+        .iter()
+        .map(|_| vars.add(variable().min(0)))
+        .collect::<Vec<_>>();
+    // processors that every job takes from the idle pool and returns to it
+    let sources = (0..n)
+        .map(|_| vars.add(variable().min(0)))
+        .collect::<Vec<_>>();
+    let sinks = (0..n)
+        .map(|_| vars.add(variable().min(0)))
+        .collect::<Vec<_>>();
+    let processing_time = |j: usize| processing_time_of(&options[j]);
+    // jobs without processing time occupy no processors, so no processors
+    // flow through them
+    let allotment = |j: usize| busy_processors(&options[j]);
+    let pair = |i: usize, j: usize| i * (n - 1) + if j < i { j } else { j - 1 };
+
+    let problem = vars.minimise(makespan).using(default_solver);
+    // every job runs with exactly one allotment
+    let problem = options.iter().fold(problem, |prob, options| {
+        prob.with(constraint!(
+            options.iter().map(|&(_, _, y)| y).sum::<Expression>() == 1
+        ))
+    });
+    let problem = (0..n).fold(problem, |prob, j| {
+        prob.with(constraint!(starts[j] + processing_time(j) <= makespan))
+    });
+    // jobs in sequence do not overlap, and two jobs are in sequence in at
+    // most one direction
+    let problem = pairs
+        .iter()
+        .zip(&before)
+        .fold(problem, |prob, (&(i, j), &x)| {
+            let prob = prob.with(constraint!(
+                starts[i] + processing_time(i) + big_m * x <= starts[j] + big_m
+            ));
+            if i < j {
+                prob.with(constraint!(x + before[pair(j, i)] <= 1))
+            } else {
+                prob
+            }
+        });
+    // processors only flow from a job to the jobs after it
+    let problem = flows
+        .iter()
+        .zip(&before)
+        .fold(problem, |prob, (&f, &x)| prob.with(constraint!(f <= m * x)));
+    // every job receives and passes on as many processors as it runs on
+    let problem = (0..n).fold(problem, |prob, j| {
+        let inflow = sources[j]
+            + (0..n)
+                .filter(|&i| i != j)
+                .map(|i| flows[pair(i, j)])
+                .sum::<Expression>();
+        let outflow = sinks[j]
+            + (0..n)
+                .filter(|&k| k != j)
+                .map(|k| flows[pair(j, k)])
+                .sum::<Expression>();
+        prob.with(constraint!(inflow == allotment(j)))
+            .with(constraint!(outflow == allotment(j)))
+    });
+    let problem = problem.with(constraint!(sources.iter().sum::<Expression>() <= m));
+    let problem = edges(instance).into_iter().fold(problem, |prob, (j, k)| {
+        let lag = instance.lag(j, k);
+        let prob = prob
+            .with(constraint!(before[pair(j, k)] == 1))
+            .with(constraint!(
+                starts[j] + processing_time(j) + lag.min <= starts[k]
+            ));
+        match lag.max {
+            Some(max) => prob.with(constraint!(
+                starts[k] <= starts[j] + processing_time(j) + max
+            )),
+            None => prob,
+        }
+    });
+    let solution = problem.solve()?;
+    Ok(Solved {
+        makespan: solution.value(makespan),
+        allotments: chosen_allotments(&solution, &options),
+        starts: rounded_starts(&solution, &starts),
+    })
+}
+
+/// Solves the event formulation. The start and the completion of every job
+/// are assigned to two of `2n` event points, whose times never decrease. A
+/// job runs in the intervals from its start event up to its completion
+/// event, and the jobs that run in an interval share the processors.
+#[expect(clippy::too_many_lines)]
+fn solve_event(instance: &Instance, integrality: Integrality) -> Result<Solved, ResolutionError> {
+    let n = instance.jobs.len();
+    let m = instance.processor_count as f64;
+    let horizon = f64::from(instance.horizon());
+    let big_m = big_m(instance);
+    let events = 2 * n;
+    let mut vars = variables!();
+    let makespan = vars.add(variable().min(0));
+    let options = allotment_options(instance, &mut vars, integrality);
+    let starts = start_time_variables(instance, &mut vars, integrality);
+    let times = (0..events)
+        .map(|_| vars.add(variable().clamp(0, horizon)))
+        .collect::<Vec<_>>();
+    // whether every job starts or completes at every event
+    let mut event_variables = || {
+        (0..n)
+            .map(|_| {
+                (0..events)
+                    .map(|_| vars.add(integrality.binary()))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>()
+    };
+    let starts_at = event_variables();
+    let completes_at = event_variables();
+    // processors that every job occupies in the interval after every event
+    let usage = (0..n)
+        .map(|_| {
+            (1..events)
+                .map(|_| vars.add(variable().min(0)))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let processing_time = |j: usize| processing_time_of(&options[j]);
+    let completion = |j: usize| starts[j] + processing_time(j);
+    // the index of the event at which a job starts or completes
+    let event_index = |assigned: &[Variable]| {
+        assigned
+            .iter()
+            .enumerate()
+            .map(|(e, &x)| e as f64 * x)
+            .sum::<Expression>()
+    };
+
+    let problem = vars.minimise(makespan).using(default_solver);
+    // every job runs with exactly one allotment
+    let problem = options.iter().fold(problem, |prob, options| {
+        prob.with(constraint!(
+            options.iter().map(|&(_, _, y)| y).sum::<Expression>() == 1
+        ))
+    });
+    let problem = (0..n).fold(problem, |prob, j| {
+        prob.with(constraint!(completion(j) <= makespan))
+    });
+    // every job starts at one event and completes at a later one
+    let problem = (0..n).fold(problem, |prob, j| {
+        prob.with(constraint!(starts_at[j].iter().sum::<Expression>() == 1))
+            .with(constraint!(completes_at[j].iter().sum::<Expression>() == 1))
+            .with(constraint!(
+                event_index(&starts_at[j]) + 1 <= event_index(&completes_at[j])
+            ))
+    });
+    // the events take the times at which their jobs start and complete
+    let problem =
+        (0..n)
+            .flat_map(|j| (0..events).map(move |e| (j, e)))
+            .fold(problem, |prob, (j, e)| {
+                let (start, end) = (starts_at[j][e], completes_at[j][e]);
+                prob.with(constraint!(starts[j] - times[e] + big_m * start <= big_m))
+                    .with(constraint!(times[e] - starts[j] + big_m * start <= big_m))
+                    .with(constraint!(completion(j) - times[e] + big_m * end <= big_m))
+                    .with(constraint!(times[e] - completion(j) + big_m * end <= big_m))
+            });
+    let problem = times
+        .iter()
+        .tuple_windows()
+        .fold(problem, |prob, (&earlier, &later)| {
+            prob.with(constraint!(earlier <= later))
+        });
+    // a job that runs in the interval after an event occupies its processors
+    // there, and the jobs of an interval fit on the processors
+    let problem = (0..n)
+        .flat_map(|j| (0..events.saturating_sub(1)).map(move |e| (j, e)))
+        .fold(problem, |prob, (j, e)| {
+            let runs = starts_at[j][..=e].iter().sum::<Expression>()
+                - completes_at[j][..=e].iter().sum::<Expression>();
+            prob.with(constraint!(
+                busy_processors(&options[j]) + runs * m - m <= usage[j][e]
+            ))
+        });
+    let problem = (0..events.saturating_sub(1)).fold(problem, |prob, e| {
+        prob.with(constraint!(
+            usage.iter().map(|usage| usage[e]).sum::<Expression>() <= m
+        ))
+    });
+    let problem = edges(instance).into_iter().fold(problem, |prob, (j, k)| {
+        let lag = instance.lag(j, k);
+        let prob = prob
+            .with(constraint!(
+                event_index(&completes_at[j]) <= event_index(&starts_at[k])
+            ))
+            .with(constraint!(completion(j) + lag.min <= starts[k]));
+        match lag.max {
+            Some(max) => prob.with(constraint!(starts[k] <= completion(j) + max)),
+            None => prob,
+        }
+    });
+    let solution = problem.solve()?;
+    Ok(Solved {
+        makespan: solution.value(makespan),
+        allotments: chosen_allotments(&solution, &options),
+        starts: rounded_starts(&solution, &starts),
+    })
+}
+
+/// A constant that is large enough to let any job start before any other
+/// job completes
+fn big_m(instance: &Instance) -> f64 {
+    let longest = instance
+        .jobs
+        .iter()
+        .filter_map(|job| job.processing_times.iter().max())
+        .max()
+        .copied()
+        .unwrap_or(0);
+    f64::from(instance.horizon()) + f64::from(longest)
+}
+
+/// Adds the allotment options of every job to `vars`, where pinned jobs only
+/// have the allotment of their pin
+fn allotment_options(
+    instance: &Instance,
+    vars: &mut ProblemVariables,
+    integrality: Integrality,
+) -> Vec<Vec<AllotmentOption>> {
+    instance
+        .jobs
+        .iter()
+        .enumerate()
+        .map(|(j, job)| {
+            let allotments = match instance.pin(j) {
+                Some(pin) => vec![pin.allotment],
+                None => (1..=job.max_allotment().min(instance.processor_count)).collect(),
+            };
+            allotments
+                .into_iter()
+                .map(|l| (l, job.processing_time(l), vars.add(integrality.binary())))
+                .collect()
+        })
+        .collect()
+}
+
+/// Adds the start time of every job to `vars`, which lies between 0 and the
+/// horizon, or at the pin of a pinned job
+fn start_time_variables(
+    instance: &Instance,
+    vars: &mut ProblemVariables,
+    integrality: Integrality,
+) -> Vec<Variable> {
+    let horizon = instance.horizon();
+    (0..instance.jobs.len())
+        .map(|j| {
+            vars.add(match instance.pin(j) {
+                Some(pin) => integrality.integer().clamp(pin.start_time, pin.start_time),
+                None => integrality.integer().clamp(0, horizon),
+            })
+        })
+        .collect()
+}
+
+/// The processing time of a job with the chosen option among `options`
+fn processing_time_of(options: &[AllotmentOption]) -> Expression {
+    options.iter().map(|&(_, p, y)| f64::from(p) * y).sum()
+}
+
+/// The number of processors that a job with the chosen option among
+/// `options` occupies, which is zero if it takes no time
+fn busy_processors(options: &[AllotmentOption]) -> Expression {
+    options
+        .iter()
+        .filter(|&&(_, p, _)| p > 0)
+        .map(|&(l, _, y)| l as f64 * y)
+        .sum()
+}
+
+/// The allotment of every job that `solution` chooses among its `options`
+fn chosen_allotments(solution: &impl Solution, options: &[Vec<AllotmentOption>]) -> Vec<usize> {
+    options
+        .iter()
+        .map(|options| {
+            options
+                .iter()
+                .find(|&&(_, _, y)| solution.value(y) > 0.5)
+                .map_or(1, |&(l, _, _)| l)
+        })
+        .collect()
+}
+
+/// The start time of every job in `solution`, rounded to an integer
+fn rounded_starts(solution: &impl Solution, starts: &[Variable]) -> Vec<i32> {
+    starts
+        .iter()
+        .map(|&s| solution.value(s).round() as i32)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::{compare, schedule, Formulation};
+    use crate::{
+        algo::{Instance, Schedule, ScheduledJob},
+        builder::InstanceBuilder,
+        list::ListOptions,
+    };
+
+    const FORMULATIONS: [Formulation; 3] = [
+        Formulation::TimeIndexed,
+        Formulation::BigM,
+        Formulation::Event,
+    ];
+
+    /// Generates three jobs on two processors with random processing times,
+    /// where some of the jobs form a chain
+    fn instance(seed: u64) -> Instance {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut builder = InstanceBuilder::new().processors(2);
+        for id in 0..3 {
+            let slow = rng.random_range(1..=4);
+            let fast = rng.random_range(1..=slow);
+            builder = builder.job(id).times([slow, fast]);
+        }
+        if rng.random_bool(0.5) {
+            builder = builder.constraint(0, 1);
+        }
+        if rng.random_bool(0.5) {
+            builder = builder.constraint(1, 2);
+        }
+        builder.build().expect("valid instance")
+    }
+
+    /// Finds the smallest makespan of `instance` by trying every allotment
+    /// and start time up to the horizon for every job
+    fn brute_force(instance: &Instance) -> i32 {
+        fn place(instance: &Instance, jobs: &mut Vec<ScheduledJob>, best: &mut i32) {
+            let Some(job) = instance.jobs.get(jobs.len()) else {
+                let schedule = Schedule {
+                    processor_count: instance.processor_count,
+                    jobs: jobs.clone(),
+                };
+                if schedule.violations(instance).is_empty() {
+                    *best = (*best).min(schedule.makespan());
+                }
+                return;
+            };
+            for allotment in 1..=instance.processor_count {
+                for start in 0..=instance.horizon() - job.processing_time(allotment) {
+                    jobs.push(ScheduledJob::new(job.clone(), allotment, start, vec![]));
+                    place(instance, jobs, best);
+                    jobs.pop();
+                }
+            }
+        }
+        let mut best = i32::MAX;
+        place(instance, &mut vec![], &mut best);
+        best
+    }
+
+    #[test]
+    fn formulations_agree_with_brute_force() {
+        for seed in 0..8 {
+            let optimum = brute_force(&instance(seed));
+            for formulation in FORMULATIONS {
+                let comparison = compare(&instance(seed), formulation);
+                assert_eq!(
+                    comparison.makespan, optimum,
+                    "{formulation} formulation of seed {seed}"
+                );
+                assert!(comparison.relaxation <= f64::from(optimum) + 1e-6);
+                let (schedule, _) = schedule(instance(seed), formulation, &ListOptions::default());
+                assert!(schedule.violations(&instance(seed)).is_empty());
+                assert_eq!(schedule.makespan(), optimum);
+            }
+        }
+    }
+}
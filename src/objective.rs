@@ -0,0 +1,129 @@
+// Objectives that rate schedules, so that the local search and the selection
+// among several list schedules can optimize something other than the makespan.
+
+use std::{collections::BTreeMap, fmt};
+
+use clap::ValueEnum;
+
+use crate::algo::{Instance, Schedule};
+
+/// Rates a schedule of an instance, where smaller values are better. Library
+/// users can implement it to optimize their own objectives.
+pub trait ScheduleObjective: fmt::Debug + Send + Sync {
+    /// Short name of the objective, as it appears in the outputs
+    fn name(&self) -> &'static str;
+    /// Computes the value of `schedule` for `instance`
+    fn evaluate(&self, instance: &Instance, schedule: &Schedule) -> f64;
+}
+
+/// The latest completion time of all jobs
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Makespan;
+impl ScheduleObjective for Makespan {
+    fn name(&self) -> &'static str {
+        "makespan"
+    }
+    fn evaluate(&self, _instance: &Instance, schedule: &Schedule) -> f64 {
+        f64::from(schedule.makespan())
+    }
+}
+
+/// The sum of the completion times of all jobs, each weighted by the least
+/// work the job needs with any allotment, so that large jobs count more
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WeightedCompletion;
+impl ScheduleObjective for WeightedCompletion {
+    fn name(&self) -> &'static str {
+        "weighted-completion"
+    }
+    fn evaluate(&self, _instance: &Instance, schedule: &Schedule) -> f64 {
+        schedule
+            .jobs
+            .iter()
+            .map(|job| {
+                let weight = job
+                    .job
+                    .processing_times
+                    .iter()
+                    .zip(1..)
+                    .map(|(&p, l)| l * p)
+                    .min()
+                    .unwrap_or(0);
+                f64::from(weight) * f64::from(job.completion_time())
+            })
+            .sum()
+    }
+}
+
+/// The time that processors are idle before the makespan, summed over all
+/// processors that may be busy according to the capacity profile
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TotalIdle;
+impl ScheduleObjective for TotalIdle {
+    fn name(&self) -> &'static str {
+        "idle"
+    }
+    fn evaluate(&self, instance: &Instance, schedule: &Schedule) -> f64 {
+        let makespan = schedule.makespan();
+        // the capacity only changes at the borders of its windows and the
+        // downtime
+        let mut times = instance
+            .capacity_drops()
+            .chain(instance.capacity_rises())
+            .filter(|&time| 0 < time && time < makespan)
+            .chain([0, makespan])
+            .collect::<Vec<_>>();
+        times.sort_unstable();
+        times.dedup();
+        let available = times
+            .windows(2)
+            .map(|pair| instance.capacity_at(pair[0]) as i64 * i64::from(pair[1] - pair[0]))
+            .sum::<i64>();
+        let work = schedule
+            .jobs
+            .iter()
+            .map(|job| job.allotment as i64 * i64::from(job.processing_time()))
+            .sum::<i64>();
+        (available - work) as f64
+    }
+}
+
+/// The built-in objectives that can be chosen on the command line
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Objective {
+    /// The latest completion time of all jobs
+    #[default]
+    Makespan,
+    /// The sum of the completion times, weighted by the least work of every
+    /// job
+    WeightedCompletion,
+    /// The time that processors are idle before the makespan
+    Idle,
+}
+impl Objective {
+    /// All built-in objectives, in the order of their declaration
+    pub const ALL: [Objective; 3] = [
+        Objective::Makespan,
+        Objective::WeightedCompletion,
+        Objective::Idle,
+    ];
+    /// Creates the implementation of the objective
+    pub fn boxed(self) -> Box<dyn ScheduleObjective> {
+        match self {
+            Objective::Makespan => Box::new(Makespan),
+            Objective::WeightedCompletion => Box::new(WeightedCompletion),
+            Objective::Idle => Box::new(TotalIdle),
+        }
+    }
+}
+
+/// Evaluates `schedule` with every built-in objective, by their names
+pub fn evaluate_all(instance: &Instance, schedule: &Schedule) -> BTreeMap<&'static str, f64> {
+    Objective::ALL
+        .iter()
+        .map(|objective| {
+            let objective = objective.boxed();
+            (objective.name(), objective.evaluate(instance, schedule))
+        })
+        .collect()
+}
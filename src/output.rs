@@ -0,0 +1,333 @@
+// Writes output files such that they are either complete or not there at all,
+// and keeps track of them so that --open can pick the one to show.
+
+use std::{
+    fmt, fs,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    process,
+};
+
+use clap::ValueEnum;
+use itertools::Itertools;
+use log::{error, info};
+
+/// Kind of a file that a run writes. The first three are shown to the user,
+/// in this order of preference.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum ArtifactKind {
+    /// An HTML page, such as the index of a paginated schedule
+    Html,
+    /// A rendered schedule
+    Svg,
+    /// A raster image
+    Png,
+    /// A JSON document, such as the schedule
+    Json,
+    /// A CSV file, such as the utilization
+    Csv,
+    /// A directory of files, such as the per-processor timelines
+    Dir,
+}
+impl fmt::Display for ArtifactKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ArtifactKind::Html => "HTML",
+            ArtifactKind::Svg => "SVG",
+            ArtifactKind::Png => "PNG",
+            ArtifactKind::Json => "JSON",
+            ArtifactKind::Csv => "CSV",
+            ArtifactKind::Dir => "directory",
+        })
+    }
+}
+
+/// A file or directory that a run wrote
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Artifact {
+    /// What the file contains
+    pub kind: ArtifactKind,
+    /// Where the file was written
+    pub path: String,
+}
+impl Artifact {
+    /// Records that a file of the given kind was written to `path`
+    pub fn new(kind: ArtifactKind, path: impl Into<String>) -> Self {
+        Self {
+            kind,
+            path: path.into(),
+        }
+    }
+}
+
+/// Opens files for the user, which tests can replace by a stub
+pub trait Opener {
+    /// Opens the file at `path`
+    fn open(&self, path: &str) -> io::Result<()>;
+}
+
+/// Opens files with the default application of the system
+pub struct SystemOpener;
+impl Opener for SystemOpener {
+    fn open(&self, path: &str) -> io::Result<()> {
+        open::that(path)
+    }
+}
+
+/// Picks the artifact that --open opens. If a kind is `requested`, this is
+/// the first artifact of that kind. Otherwise, it is the first HTML page,
+/// else the first SVG, else the first PNG, and else the first artifact.
+pub fn preferred(artifacts: &[Artifact], requested: Option<ArtifactKind>) -> Option<&Artifact> {
+    match requested {
+        Some(kind) => artifacts.iter().find(|artifact| artifact.kind == kind),
+        None => artifacts
+            .iter()
+            .filter(|artifact| artifact.kind <= ArtifactKind::Png)
+            .min_by_key(|artifact| artifact.kind)
+            .or_else(|| artifacts.first()),
+    }
+}
+
+/// Opens the artifact that `preferred` picks with the `opener`, or explains
+/// which artifacts were written if there is none to open
+pub fn open_preferred(
+    artifacts: &[Artifact],
+    requested: Option<ArtifactKind>,
+    opener: &dyn Opener,
+) {
+    let Some(artifact) = preferred(artifacts, requested) else {
+        let missing = requested.map_or("no file".to_string(), |kind| format!("no {kind} file"));
+        if artifacts.is_empty() {
+            info!("  hint: Ignored --open because {missing} was written");
+        } else {
+            info!(
+                "  hint: Ignored --open because {missing} was written, only {}",
+                artifacts
+                    .iter()
+                    .map(|artifact| format!("{} ({})", artifact.path, artifact.kind))
+                    .join(", ")
+            );
+        }
+        return;
+    };
+    info!("Opening {} ...", artifact.path);
+    if let Err(e) = opener.open(&artifact.path) {
+        error!("Could not open file {}: {e:#?}", artifact.path);
+    }
+}
+
+/// Writes `bytes` to `path` without ever leaving a truncated file behind.
+/// The bytes are first written to a temporary file in the same directory,
+/// which then replaces `path`. If the temporary file cannot be renamed
+/// because it ended up on a different device, it is copied and removed
+/// instead. The temporary file is removed if anything goes wrong.
+pub fn atomic_write(path: impl AsRef<Path>, bytes: &[u8]) -> io::Result<()> {
+    atomic_write_with(path, |w| w.write_all(bytes))
+}
+
+/// Like `atomic_write`, but `write` streams the contents into the temporary
+/// file through a buffer, so that large files never have to be held in
+/// memory as a whole
+pub fn atomic_write_with(
+    path: impl AsRef<Path>,
+    write: impl FnOnce(&mut dyn Write) -> io::Result<()>,
+) -> io::Result<()> {
+    let path = path.as_ref();
+    let tmp = temporary_path(path);
+    let written = fs::File::create(&tmp).and_then(|file| {
+        let mut writer = BufWriter::new(file);
+        write(&mut writer)?;
+        writer.flush()
+    });
+    let result = written.and_then(|()| copy_across_devices(&tmp, path, fs::rename(&tmp, path)));
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
+
+/// Completes moving `tmp` to `path` after the attempt to rename it ended
+/// with `renamed`. If the two are on different devices, the file is copied
+/// and removed instead.
+fn copy_across_devices(tmp: &Path, path: &Path, renamed: io::Result<()>) -> io::Result<()> {
+    match renamed {
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            fs::copy(tmp, path).and_then(|_| fs::remove_file(tmp))
+        }
+        result => result,
+    }
+}
+
+/// Picks a hidden file name next to `path` that no other process uses
+fn temporary_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{name}.{}.tmp", process::id()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::RefCell,
+        env, fs,
+        io::{self, Write},
+        path::{Path, PathBuf},
+        process,
+    };
+
+    use super::{
+        atomic_write, atomic_write_with, copy_across_devices, open_preferred, temporary_path,
+        Artifact, ArtifactKind, Opener,
+    };
+
+    /// Records the files that it is asked to open instead of opening them
+    #[derive(Default)]
+    struct StubOpener {
+        opened: RefCell<Vec<String>>,
+        fails: bool,
+    }
+    impl Opener for StubOpener {
+        fn open(&self, path: &str) -> io::Result<()> {
+            self.opened.borrow_mut().push(path.to_string());
+            if self.fails {
+                Err(io::ErrorKind::NotFound.into())
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    /// The files that `open_preferred` opens for the given artifacts
+    fn opened(artifacts: &[(ArtifactKind, &str)], requested: Option<ArtifactKind>) -> Vec<String> {
+        let artifacts = artifacts
+            .iter()
+            .map(|&(kind, path)| Artifact::new(kind, path))
+            .collect::<Vec<_>>();
+        let opener = StubOpener::default();
+        open_preferred(&artifacts, requested, &opener);
+        opener.opened.into_inner()
+    }
+
+    /// Creates an empty directory for the test with the given name
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("atomic-{name}-{}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("could not create test directory");
+        dir
+    }
+
+    /// Lists the names of the files in `dir`
+    fn files(dir: &Path) -> Vec<String> {
+        let mut names = fs::read_dir(dir)
+            .expect("could not list test directory")
+            .map(|entry| {
+                let entry = entry.expect("could not read test directory");
+                entry.file_name().to_string_lossy().into_owned()
+            })
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn replaces_file_without_leaving_temporary_file() {
+        let dir = test_dir("replace");
+        let path = dir.join("schedule.json");
+        atomic_write(&path, b"first").expect("could not write");
+        atomic_write(&path, b"second").expect("could not overwrite");
+        assert_eq!(fs::read_to_string(&path).expect("no file"), "second");
+        assert_eq!(files(&dir), ["schedule.json"]);
+    }
+
+    #[test]
+    fn failed_write_keeps_old_file() {
+        let dir = test_dir("failed");
+        let path = dir.join("schedule.svg");
+        atomic_write(&path, b"complete").expect("could not write");
+        let result = atomic_write_with(&path, |w| {
+            w.write_all(b"trunc")?;
+            Err(io::Error::other("killed while writing"))
+        });
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(&path).expect("no file"), "complete");
+        assert_eq!(files(&dir), ["schedule.svg"]);
+    }
+
+    #[test]
+    fn copies_across_devices() {
+        let dir = test_dir("cross-device");
+        let path = dir.join("utilization.csv");
+        let tmp = temporary_path(&path);
+        fs::write(&tmp, "time,busy_processors").expect("could not write");
+        let renamed = Err(io::ErrorKind::CrossesDevices.into());
+        copy_across_devices(&tmp, &path, renamed).expect("could not copy");
+        assert_eq!(
+            fs::read_to_string(&path).expect("no file"),
+            "time,busy_processors"
+        );
+        assert_eq!(files(&dir), ["utilization.csv"]);
+
+        // other errors are passed on without copying
+        fs::write(&tmp, "other").expect("could not write");
+        let renamed = Err(io::ErrorKind::PermissionDenied.into());
+        let result = copy_across_devices(&tmp, &path, renamed);
+        assert_eq!(
+            result.map_err(|e| e.kind()),
+            Err(io::ErrorKind::PermissionDenied)
+        );
+        assert_eq!(
+            fs::read_to_string(&path).expect("no file"),
+            "time,busy_processors"
+        );
+    }
+
+    #[test]
+    fn preferred_artifact_is_opened() {
+        use ArtifactKind::{Csv, Dir, Html, Json, Png, Svg};
+
+        let all = [
+            (Csv, "utilization.csv"),
+            (Png, "schedule.png"),
+            (Svg, "schedule_page1.svg"),
+            (Html, "schedule.html"),
+            (Svg, "schedule_page2.svg"),
+            (Json, "schedule.json"),
+        ];
+        assert_eq!(opened(&all, None), ["schedule.html"]);
+        assert_eq!(opened(&all[..3], None), ["schedule_page1.svg"]);
+        assert_eq!(opened(&all[..2], None), ["schedule.png"]);
+        assert_eq!(
+            opened(&[(Dir, "timelines"), (Json, "schedule.json")], None),
+            ["timelines"]
+        );
+        assert_eq!(opened(&all, Some(Json)), ["schedule.json"]);
+        assert_eq!(opened(&all, Some(Svg)), ["schedule_page1.svg"]);
+        assert_eq!(opened(&all, Some(Csv)), ["utilization.csv"]);
+    }
+
+    #[test]
+    fn nothing_is_opened_without_matching_artifact() {
+        use ArtifactKind::{Csv, Json, Svg};
+
+        assert!(opened(&[], None).is_empty());
+        assert!(opened(&[], Some(Svg)).is_empty());
+        assert!(opened(
+            &[(Csv, "utilization.csv"), (Json, "schedule.json")],
+            Some(Svg)
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn failure_to_open_is_not_fatal() {
+        let opener = StubOpener {
+            fails: true,
+            ..StubOpener::default()
+        };
+        let artifacts = [Artifact::new(ArtifactKind::Svg, "schedule.svg")];
+        open_preferred(&artifacts, None, &opener);
+        assert_eq!(opener.opened.into_inner(), ["schedule.svg"]);
+    }
+}
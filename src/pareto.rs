@@ -0,0 +1,240 @@
+// Explores the trade-off between the number of processors and the makespan by
+// solving the same instance with fewer and fewer processors.
+
+use std::{collections::BTreeMap, fmt};
+
+use clap::ValueEnum;
+use log::{debug, info};
+use serde::Serialize;
+
+use crate::{
+    algo::{Instance, Rounding, Schedule, SolverStats, INT_TOLERANCE},
+    dp, ilp,
+    list::ListOptions,
+    lp::{self, RoundingStrategy},
+    milp::{self, Formulation},
+    shelf,
+};
+
+/// An algorithm that solves the instances of an exploration
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Algorithm {
+    /// The dynamic program, see solve-dp
+    Dp,
+    /// The linear program, see solve-lp
+    #[default]
+    Lp,
+    /// The integer linear program, see solve-ilp
+    Ilp,
+    /// The exact mixed integer linear program, see solve-milp
+    Milp,
+    /// The shelf algorithm, see solve-shelf
+    Shelf,
+}
+impl Algorithm {
+    /// Name of the subcommand that runs the algorithm, which the provenance
+    /// records
+    pub fn command(self) -> &'static str {
+        match self {
+            Algorithm::Dp => "solve-dp",
+            Algorithm::Lp => "solve-lp",
+            Algorithm::Ilp => "solve-ilp",
+            Algorithm::Milp => "solve-milp",
+            Algorithm::Shelf => "solve-shelf",
+        }
+    }
+    /// Solves the instance with default options
+    pub fn solve(self, instance: Instance) -> Schedule {
+        self.solve_with_stats(instance).0
+    }
+    /// Solves the instance with default options and also returns the
+    /// statistics of the solver
+    pub fn solve_with_stats(self, instance: Instance) -> (Schedule, SolverStats) {
+        match self {
+            Algorithm::Dp => (
+                dp::schedule(
+                    instance,
+                    &dp::CheckpointOptions::default(),
+                    &dp::TraceOptions::default(),
+                ),
+                SolverStats::default(),
+            ),
+            Algorithm::Lp => lp::schedule(
+                instance,
+                false,
+                INT_TOLERANCE,
+                RoundingStrategy::default(),
+                false,
+                &ListOptions::default(),
+            ),
+            Algorithm::Ilp => ilp::schedule(
+                instance,
+                false,
+                INT_TOLERANCE,
+                Rounding::default(),
+                false,
+                &ListOptions::default(),
+            ),
+            Algorithm::Milp => {
+                milp::schedule(instance, Formulation::default(), &ListOptions::default())
+            }
+            Algorithm::Shelf => (shelf::schedule(instance), SolverStats::default()),
+        }
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Algorithm::Dp => "dp",
+            Algorithm::Lp => "lp",
+            Algorithm::Ilp => "ilp",
+            Algorithm::Milp => "milp",
+            Algorithm::Shelf => "shelf",
+        })
+    }
+}
+
+/// The outcome of solving an instance with a limited number of processors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ParetoPoint {
+    /// Number of processors the instance was restricted to
+    pub budget: usize,
+    /// Largest number of processors that are busy at the same time
+    pub machines: usize,
+    /// Makespan of the schedule
+    pub makespan: i32,
+}
+
+/// Solves `instance` with every number of processors from 1 up to its
+/// processor count and returns the resulting points in that order
+pub fn explore(instance: &Instance, algorithm: Algorithm) -> Vec<ParetoPoint> {
+    (1..=instance.processor_count)
+        .map(|budget| {
+            let schedule = algorithm.solve(instance.with_processor_count(budget));
+            let machines = schedule
+                .resource_profile()
+                .iter()
+                .map(|point| point.busy_processors)
+                .max()
+                .unwrap_or(0);
+            let point = ParetoPoint {
+                budget,
+                machines,
+                makespan: schedule.makespan(),
+            };
+            debug!("Explored {point:?}");
+            point
+        })
+        .collect()
+}
+
+/// Removes all points for which another point uses at most as many machines
+/// and has at most the same makespan, and sorts the rest by machines
+pub fn frontier(mut points: Vec<ParetoPoint>) -> Vec<ParetoPoint> {
+    points.sort_by_key(|point| (point.machines, point.makespan, point.budget));
+    let mut frontier: Vec<ParetoPoint> = vec![];
+    for point in points {
+        match frontier.last() {
+            Some(last) if last.makespan <= point.makespan => {
+                info!(
+                    "Dropping {} machines with makespan {} from budget {}, dominated by {} machines with makespan {}",
+                    point.machines, point.makespan, point.budget, last.machines, last.makespan
+                );
+            }
+            _ => frontier.push(point),
+        }
+    }
+    frontier
+}
+
+/// The outcome of searching for the smallest number of processors that meets
+/// a target makespan
+#[derive(Debug)]
+pub struct Sizing {
+    /// Makespan with every number of processors that the search tried, sorted
+    /// by the number of processors
+    pub curve: Vec<(usize, i32)>,
+    /// Smallest number of processors that meets the target, if any does
+    pub machines: Option<usize>,
+    /// Lower bounds on the makespan with every number of processors from 1
+    /// up to the processor count of the instance
+    pub bounds: Vec<LowerBounds>,
+}
+
+/// Lower bounds on the makespan of every schedule with a number of processors
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LowerBounds {
+    /// Number of processors
+    pub machines: usize,
+    /// Smallest total work spread evenly over all processors
+    pub area: i32,
+    /// Longest path if every job runs with its fastest allotment
+    pub critical_path: i32,
+}
+impl LowerBounds {
+    /// Computes the bounds of `instance` restricted to `machines` processors
+    pub fn new(instance: &Instance, machines: usize) -> Self {
+        let instance = instance.with_processor_count(machines);
+        let work = instance
+            .jobs
+            .iter()
+            .map(|job| {
+                job.processing_times
+                    .iter()
+                    .zip(1..)
+                    .map(|(&p, l)| l * p)
+                    .min()
+                    .unwrap_or(0)
+            })
+            .sum::<i32>();
+        LowerBounds {
+            machines,
+            area: (f64::from(work) / machines as f64).ceil() as i32,
+            critical_path: instance.min_critical_path_length(),
+        }
+    }
+    /// The larger of both bounds
+    pub fn max(&self) -> i32 {
+        self.area.max(self.critical_path)
+    }
+}
+
+/// Searches for the smallest number of processors up to the processor count of
+/// `instance` with which `algorithm` finds a schedule whose makespan is at
+/// most `target`. The search is binary, so it assumes that more processors
+/// never lead to a longer makespan. If even all processors miss the target,
+/// only the processor count of `instance` is tried.
+pub fn size(instance: &Instance, algorithm: Algorithm, target: i32) -> Sizing {
+    let mut curve = BTreeMap::new();
+    let mut makespan = |machines: usize| {
+        *curve.entry(machines).or_insert_with(|| {
+            let makespan = algorithm
+                .solve(instance.with_processor_count(machines))
+                .makespan();
+            debug!("Makespan with {machines} processors is {makespan}");
+            makespan
+        })
+    };
+    let machines = if makespan(instance.processor_count) <= target {
+        let (mut low, mut high) = (1, instance.processor_count);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if makespan(mid) <= target {
+                high = mid;
+            } else {
+                low = mid + 1;
+            }
+        }
+        Some(low)
+    } else {
+        None
+    };
+    Sizing {
+        curve: curve.into_iter().collect(),
+        machines,
+        bounds: (1..=instance.processor_count)
+            .map(|machines| LowerBounds::new(instance, machines))
+            .collect(),
+    }
+}
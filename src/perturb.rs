@@ -0,0 +1,354 @@
+// Perturbs instances with random noise on the processing times and the
+// constraints, for sensitivity studies of how robust a schedule is to
+// estimation errors. Every perturbed instance is checked like the solvers
+// check their input, so that it still has a schedule.
+
+use std::{collections::HashSet, fmt};
+
+use log::{info, warn};
+use rand::{rngs::StdRng, seq::index, Rng, SeedableRng};
+
+use crate::{
+    algo::{Constraint, Instance, Lag},
+    run::{self, AppError},
+};
+
+/// Perturbed instances that cannot be scheduled are drawn again at most this
+/// many times before giving up
+const MAX_ATTEMPTS: usize = 100;
+
+/// Constraints to add are sampled at most this many times per constraint
+/// before giving up, since few pairs of jobs may be left to constrain
+const SAMPLES_PER_CONSTRAINT: usize = 100;
+
+/// How strongly to perturb an instance, all in percent
+#[derive(Debug, Clone, Copy)]
+pub struct Noise {
+    /// Every processing time is scaled by a factor drawn uniformly from
+    /// `1 ± times / 100`
+    pub times: f64,
+    /// Share of the hard constraints to drop
+    pub drop_constraints: f64,
+    /// Number of hard constraints to add, relative to the number of hard
+    /// constraints of the instance
+    pub add_constraints: f64,
+}
+
+/// A difference between an instance and its perturbed copy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    /// A processing time of the job with the given index changed
+    Time {
+        job: usize,
+        allotment: usize,
+        before: i32,
+        after: i32,
+    },
+    /// The constraint between the jobs with the given indices was dropped
+    Dropped { left: usize, right: usize },
+    /// A constraint between the jobs with the given indices was added
+    Added { left: usize, right: usize },
+}
+impl Change {
+    /// The header of the CSV records of `record`
+    pub const HEADER: &str = "change,left,right,allotment,before,after";
+
+    /// Describes the change as a CSV record, where the left column holds the
+    /// job of a changed processing time
+    pub fn record(&self) -> String {
+        match self {
+            Change::Time {
+                job,
+                allotment,
+                before,
+                after,
+            } => format!("time,{job},,{allotment},{before},{after}"),
+            Change::Dropped { left, right } => format!("dropped,{left},{right},,,"),
+            Change::Added { left, right } => format!("added,{left},{right},,,"),
+        }
+    }
+}
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Time {
+                job,
+                allotment,
+                before,
+                after,
+            } => write!(
+                f,
+                "job {job} takes {after} instead of {before} on {allotment} processors"
+            ),
+            Change::Dropped { left, right } => write!(f, "dropped {left} -> {right}"),
+            Change::Added { left, right } => write!(f, "added {left} -> {right}"),
+        }
+    }
+}
+
+/// Perturbs `instance` by `noise` with a random number generator seeded with
+/// `seed`. Processing times of zero stay zero, and all others stay positive.
+/// Dropped constraints are chosen uniformly among the hard constraints, and
+/// added ones among the pairs of jobs that do not depend on each other yet,
+/// pointing in the direction of a topological order so that the constraints
+/// stay acyclic. Soft constraints, capacity windows, downtime, and processor
+/// names are kept. Perturbations after which some maximum lag or affinity
+/// cannot be met are drawn again, and the error of the last one is returned
+/// if all `MAX_ATTEMPTS` fail. Pinned jobs are not supported.
+pub fn perturb(
+    instance: &Instance,
+    noise: &Noise,
+    seed: u64,
+) -> Result<(Instance, Vec<Change>), AppError> {
+    assert!(
+        instance.pinned.is_empty(),
+        "cannot perturb an instance with pinned jobs"
+    );
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut attempt = 1;
+    loop {
+        let (perturbed, changes) = sample(instance, noise, &mut rng);
+        match run::check(&perturbed) {
+            Ok(()) => {
+                info!(
+                    "Perturbed the instance with {} changes after {attempt} attempts",
+                    changes.len()
+                );
+                return Ok((perturbed, changes));
+            }
+            Err(e) if attempt == MAX_ATTEMPTS => return Err(e),
+            Err(e) => info!("Drawing the perturbation again, because of {e}"),
+        }
+        attempt += 1;
+    }
+}
+
+/// Draws a single perturbation of `instance`, see `perturb`
+fn sample(instance: &Instance, noise: &Noise, rng: &mut StdRng) -> (Instance, Vec<Change>) {
+    let mut changes = vec![];
+
+    let spread = noise.times / 100.0;
+    let mut jobs = instance.jobs.clone();
+    for job in &mut jobs {
+        for (k, p) in job.processing_times.iter_mut().enumerate() {
+            let factor = rng.random_range(1.0 - spread..=1.0 + spread);
+            let after = if *p == 0 {
+                0
+            } else {
+                ((f64::from(*p) * factor).round() as i32).max(1)
+            };
+            if after != *p {
+                changes.push(Change::Time {
+                    job: job.index,
+                    allotment: k + 1,
+                    before: *p,
+                    after,
+                });
+                *p = after;
+            }
+        }
+    }
+
+    let count = instance.constraints.len();
+    let dropped = share(count, noise.drop_constraints).min(count);
+    let dropped = index::sample(rng, count, dropped)
+        .into_iter()
+        .collect::<HashSet<_>>();
+    let mut constraints = vec![];
+    for (i, constraint) in instance.constraints.iter().enumerate() {
+        if dropped.contains(&i) {
+            let Constraint(left, right, _) = *constraint;
+            changes.push(Change::Dropped { left, right });
+        } else {
+            constraints.push(constraint.clone());
+        }
+    }
+
+    let wanted = share(count, noise.add_constraints);
+    let order = instance.topological_order();
+    let n = order.len();
+    let mut added = HashSet::new();
+    let mut samples = 0;
+    while added.len() < wanted && samples < wanted * SAMPLES_PER_CONSTRAINT && n > 1 {
+        samples += 1;
+        let (a, b) = (rng.random_range(0..n), rng.random_range(0..n));
+        if a == b {
+            continue;
+        }
+        let (left, right) = (order[a.min(b)], order[a.max(b)]);
+        if !added.contains(&(left, right)) && !instance.reaches(left, right) {
+            added.insert((left, right));
+            let (left, right) = (instance.jobs[left].index, instance.jobs[right].index);
+            constraints.push(Constraint(left, right, Lag::default()));
+            changes.push(Change::Added { left, right });
+        }
+    }
+    if added.len() < wanted {
+        warn!(
+            "Added only {} of {wanted} constraints, because few pairs of jobs are left that do not depend on each other",
+            added.len()
+        );
+    }
+
+    let perturbed = Instance::new(instance.processor_count, jobs, constraints)
+        .with_soft_constraints(instance.soft_constraints.clone())
+        .with_capacity(instance.capacity.clone())
+        .with_downtime(instance.downtime.clone())
+        .with_machine_names(instance.machine_names.clone());
+    (perturbed, changes)
+}
+
+/// Rounds `percent` percent of `count`
+fn share(count: usize, percent: f64) -> usize {
+    (count as f64 * percent / 100.0).round() as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{perturb, Change, Noise};
+    use crate::{
+        algo::{topo_order, Constraint, Instance},
+        builder::InstanceBuilder,
+    };
+
+    /// Number of seeds that every test tries
+    const SEEDS: u64 = 200;
+
+    /// A chain of three jobs and two pairs of jobs with a constraint each on
+    /// two processors, which leaves many pairs of jobs to constrain
+    fn instance() -> Instance {
+        InstanceBuilder::new()
+            .processors(2)
+            .job(1)
+            .times([10, 6])
+            .job(2)
+            .times([20, 12])
+            .job(3)
+            .times([0, 0])
+            .job(4)
+            .times([5, 3])
+            .job(5)
+            .times([100, 60])
+            .job(6)
+            .times([1, 1])
+            .job(7)
+            .times([40, 25])
+            .constraint(1, 2)
+            .constraint(2, 3)
+            .constraint(4, 5)
+            .constraint(6, 7)
+            .build()
+            .expect("valid instance")
+    }
+
+    /// The left and right jobs of the constraints of `instance`, sorted
+    fn pairs(instance: &Instance) -> Vec<(usize, usize)> {
+        let mut pairs = instance
+            .constraints
+            .iter()
+            .map(|&Constraint(left, right, _)| (left, right))
+            .collect::<Vec<_>>();
+        pairs.sort_unstable();
+        pairs
+    }
+
+    #[test]
+    fn times_stay_within_noise() {
+        let instance = instance();
+        let noise = Noise {
+            times: 20.0,
+            drop_constraints: 0.0,
+            add_constraints: 0.0,
+        };
+        for seed in 0..SEEDS {
+            let (perturbed, changes) = perturb(&instance, &noise, seed).expect("feasible");
+            assert_eq!(pairs(&perturbed), pairs(&instance), "seed {seed}");
+            for (job, original) in perturbed.jobs.iter().zip(&instance.jobs) {
+                for (k, (&after, &before)) in job
+                    .processing_times
+                    .iter()
+                    .zip(&original.processing_times)
+                    .enumerate()
+                {
+                    if before == 0 {
+                        assert_eq!(after, 0, "seed {seed}");
+                    } else {
+                        assert!(after >= 1, "seed {seed}");
+                        let bound = f64::from(before) * 0.2 + 0.5;
+                        assert!(
+                            f64::from((after - before).abs()) <= bound,
+                            "seed {seed}: job {} takes {after} instead of {before}",
+                            job.index
+                        );
+                    }
+                    let change = Change::Time {
+                        job: job.index,
+                        allotment: k + 1,
+                        before,
+                        after,
+                    };
+                    assert_eq!(changes.contains(&change), after != before, "seed {seed}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn constraints_stay_acyclic() {
+        let instance = instance();
+        let noise = Noise {
+            times: 0.0,
+            drop_constraints: 50.0,
+            add_constraints: 100.0,
+        };
+        for seed in 0..SEEDS {
+            let (perturbed, changes) = perturb(&instance, &noise, seed).expect("feasible");
+            assert!(topo_order(&perturbed).is_ok(), "seed {seed} added a cycle");
+            let dropped = changes
+                .iter()
+                .filter_map(|change| match *change {
+                    Change::Dropped { left, right } => Some((left, right)),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            let added = changes
+                .iter()
+                .filter_map(|change| match *change {
+                    Change::Added { left, right } => Some((left, right)),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(dropped.len(), 2, "seed {seed}");
+            assert_eq!(added.len(), 4, "seed {seed}");
+            assert_eq!(changes.len(), 6, "seed {seed}");
+            for &(left, right) in &added {
+                assert!(
+                    !instance.reaches(left, right) && !instance.reaches(right, left),
+                    "seed {seed} added {left} -> {right}, which depend on each other"
+                );
+            }
+            let mut expected = pairs(&instance)
+                .into_iter()
+                .filter(|pair| !dropped.contains(pair))
+                .chain(added)
+                .collect::<Vec<_>>();
+            expected.sort_unstable();
+            assert_eq!(pairs(&perturbed), expected, "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn same_seed_gives_same_perturbation() {
+        let instance = instance();
+        let noise = Noise {
+            times: 30.0,
+            drop_constraints: 25.0,
+            add_constraints: 50.0,
+        };
+        for seed in 0..SEEDS {
+            let (_, first) = perturb(&instance, &noise, seed).expect("feasible");
+            let (_, second) = perturb(&instance, &noise, seed).expect("feasible");
+            assert_eq!(first, second, "seed {seed}");
+        }
+    }
+}
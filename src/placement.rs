@@ -0,0 +1,431 @@
+// Keeps track of the processors while a list scheduler places jobs one after
+// another.
+
+use itertools::Itertools;
+
+use crate::algo::{Downtime, Instance};
+
+/// A job occupying `allotment` processors from `start` until `end`
+#[derive(Debug, Clone, Copy)]
+pub struct Placement {
+    pub start: i32,
+    pub end: i32,
+    pub allotment: usize,
+}
+
+/// The times at which each processor is busy, kept as sorted and disjoint
+/// intervals per processor. Unlike a single time until which a processor is
+/// busy, this can represent a processor that is busy, then free, and then
+/// busy again, so jobs can be placed into earlier gaps.
+#[derive(Debug, Clone)]
+pub struct Availability {
+    busy: Vec<Vec<(i32, i32)>>,
+}
+
+impl Availability {
+    /// Creates the availability of `processor_count` idle processors
+    pub fn new(processor_count: usize) -> Self {
+        Availability {
+            busy: vec![vec![]; processor_count],
+        }
+    }
+
+    /// Finds the earliest time, not before `not_before`, at which `allotment`
+    /// processors in `affinity` are free for `duration`, and returns it along
+    /// with the processors to use, see `free_processors`. If `contiguous` is
+    /// set, the processors have consecutive indices.
+    pub fn earliest_window(
+        &self,
+        allotment: usize,
+        not_before: i32,
+        duration: i32,
+        contiguous: bool,
+        affinity: Option<&[usize]>,
+    ) -> (i32, Vec<usize>) {
+        // a window opens either at `not_before` or when a processor is done
+        let mut candidates = self
+            .busy
+            .iter()
+            .flatten()
+            .map(|&(_, end)| end)
+            .filter(|&end| end > not_before)
+            .collect::<Vec<_>>();
+        candidates.push(not_before);
+        candidates.sort_unstable();
+        candidates.dedup();
+        candidates
+            .into_iter()
+            .find_map(|start| {
+                self.free_processors(allotment, start, duration, contiguous, affinity)
+                    .map(|processors| (start, processors))
+            })
+            .unwrap_or_else(|| panic!("{allotment} processors never become free at the same time"))
+    }
+
+    /// Picks `allotment` processors in `affinity` that are free from `start`
+    /// for `duration`, if there are enough. Without contiguity, the processors
+    /// that have been idle the longest are used. Otherwise, the window of
+    /// consecutive processors that became free last is used, so that longer
+    /// idle windows stay available for wider jobs.
+    pub fn free_processors(
+        &self,
+        allotment: usize,
+        start: i32,
+        duration: i32,
+        contiguous: bool,
+        affinity: Option<&[usize]>,
+    ) -> Option<Vec<usize>> {
+        let end = start + duration;
+        let free = (0..self.busy.len())
+            .map(|p| {
+                let allowed = affinity.is_none_or(|allowed| allowed.binary_search(&p).is_ok());
+                (allowed && self.is_free(p, start, end)).then(|| self.idle_since(p, start))
+            })
+            .collect::<Vec<_>>();
+        if contiguous {
+            let first = free
+                .windows(allotment)
+                .enumerate()
+                .filter_map(|(first, window)| {
+                    window
+                        .iter()
+                        .copied()
+                        .collect::<Option<Vec<_>>>()
+                        .map(|since| (first, since.into_iter().max().unwrap_or(0)))
+                })
+                .min_by_key(|&(first, since)| (-since, first))?
+                .0;
+            Some((first..first + allotment).collect())
+        } else {
+            let mut order = free
+                .iter()
+                .enumerate()
+                .filter_map(|(p, since)| since.map(|since| (since, p)))
+                .collect::<Vec<_>>();
+            if order.len() < allotment {
+                return None;
+            }
+            order.sort_unstable();
+            let mut processors = order[..allotment]
+                .iter()
+                .map(|&(_, p)| p)
+                .collect::<Vec<_>>();
+            processors.sort_unstable();
+            Some(processors)
+        }
+    }
+
+    /// Marks the `processors` as busy from `start` until `end`. Jobs without
+    /// processing time occupy nothing, so the intervals stay sorted by both
+    /// their starts and their ends.
+    pub fn reserve(&mut self, processors: &[usize], start: i32, end: i32) {
+        if start == end {
+            return;
+        }
+        for &p in processors {
+            debug_assert!(
+                self.is_free(p, start, end),
+                "processor {p} is not free from {start} to {end}"
+            );
+            let intervals = &mut self.busy[p];
+            let position = intervals.partition_point(|&(s, _)| s < start);
+            intervals.insert(position, (start, end));
+        }
+    }
+
+    /// Marks processor `p` as busy from `start` until `end`, e.g. because it
+    /// is down, merging the interval with the busy intervals it overlaps
+    pub fn block(&mut self, p: usize, start: i32, end: i32) {
+        if start >= end {
+            return;
+        }
+        let intervals = &mut self.busy[p];
+        let first = intervals.partition_point(|&(_, e)| e < start);
+        let last = intervals.partition_point(|&(s, _)| s <= end);
+        let merged = intervals[first..last]
+            .iter()
+            .fold((start, end), |(s, e), &(other_s, other_e)| {
+                (s.min(other_s), e.max(other_e))
+            });
+        intervals.splice(first..last, [merged]);
+    }
+
+    /// Checks whether processor `p` is free from `start` until `end`. Jobs
+    /// without processing time occupy nothing, so every processor is free
+    /// for them.
+    fn is_free(&self, p: usize, start: i32, end: i32) -> bool {
+        if start == end {
+            return true;
+        }
+        let intervals = &self.busy[p];
+        // the first interval that ends after `start` must begin at `end` or
+        // later
+        let next = intervals.partition_point(|&(_, e)| e <= start);
+        intervals.get(next).is_none_or(|&(s, _)| s >= end)
+    }
+
+    /// Returns the time since which processor `p` is idle at `time`
+    fn idle_since(&self, p: usize, time: i32) -> i32 {
+        let intervals = &self.busy[p];
+        let before = intervals.partition_point(|&(_, e)| e <= time);
+        before.checked_sub(1).map_or(0, |last| intervals[last].1)
+    }
+}
+
+/// The processors of a list scheduler along with the jobs placed on them
+#[derive(Debug, Clone)]
+pub struct ProcessorPool {
+    availability: Availability,
+    processor_count: usize,
+    contiguous: bool,
+    placed: Vec<Placement>,
+}
+
+impl ProcessorPool {
+    /// Creates a pool of `processor_count` idle processors. If `contiguous` is
+    /// set, every job is placed on processors with consecutive indices.
+    pub fn new(processor_count: usize, contiguous: bool) -> Self {
+        ProcessorPool {
+            availability: Availability::new(processor_count),
+            processor_count,
+            contiguous,
+            placed: vec![],
+        }
+    }
+
+    /// Returns the largest allotment with which a job limited to the
+    /// processors in `affinity` can be placed. With contiguity, this is the
+    /// longest run of consecutive allowed processors. Allowed processors
+    /// outside the pool do not count.
+    pub fn max_allotment(&self, affinity: Option<&[usize]>) -> usize {
+        let Some(allowed) = affinity else {
+            return self.processor_count;
+        };
+        let allowed = &allowed[..allowed.partition_point(|&p| p < self.processor_count)];
+        if self.contiguous {
+            allowed
+                .iter()
+                .enumerate()
+                .chunk_by(|&(i, &p)| p - i)
+                .into_iter()
+                .map(|(_, run)| run.count())
+                .max()
+                .unwrap_or(0)
+        } else {
+            allowed.len()
+        }
+    }
+
+    /// Computes the earliest time, not before `not_before`, at which
+    /// `allotment` processors are free for `duration`, using only the
+    /// processors in `affinity` if it is given
+    pub fn earliest_fit(
+        &self,
+        allotment: usize,
+        not_before: i32,
+        duration: i32,
+        affinity: Option<&[usize]>,
+    ) -> i32 {
+        assert!(
+            (1..=self.max_allotment(affinity)).contains(&allotment),
+            "cannot place a job on {allotment} of {} processors",
+            self.max_allotment(affinity)
+        );
+        self.availability
+            .earliest_window(allotment, not_before, duration, self.contiguous, affinity)
+            .0
+    }
+
+    /// Occupies `allotment` processors that are free from `start_time` until
+    /// `end` and returns their indices in ascending order. Only processors in
+    /// `affinity` are used if it is given.
+    pub fn occupy(
+        &mut self,
+        allotment: usize,
+        start_time: i32,
+        end: i32,
+        affinity: Option<&[usize]>,
+    ) -> Vec<usize> {
+        let processors = self
+            .availability
+            .free_processors(
+                allotment,
+                start_time,
+                end - start_time,
+                self.contiguous,
+                affinity,
+            )
+            .unwrap_or_else(|| panic!("not enough free processors at {start_time}"));
+        self.availability.reserve(&processors, start_time, end);
+        self.placed.push(Placement {
+            start: start_time,
+            end,
+            allotment,
+        });
+        processors
+    }
+
+    /// Blocks the processors while they are down, so that no job is placed
+    /// on them during their downtime. Downtime of processors outside the
+    /// pool is ignored.
+    pub fn block_downtime(&mut self, downtime: &[Downtime]) {
+        for down in downtime
+            .iter()
+            .filter(|down| down.processor < self.processor_count)
+        {
+            self.availability.block(down.processor, down.from, down.to);
+        }
+    }
+
+    /// Occupies exactly the given `processors` from `start_time` until `end`,
+    /// e.g. for a pinned job, which must not move
+    pub fn occupy_exactly(&mut self, processors: &[usize], start_time: i32, end: i32) {
+        self.availability.reserve(processors, start_time, end);
+        self.placed.push(Placement {
+            start: start_time,
+            end,
+            allotment: processors.len(),
+        });
+    }
+
+    /// Returns all jobs that were placed so far
+    pub fn placed(&self) -> &[Placement] {
+        &self.placed
+    }
+}
+
+/// Finds the earliest start time, not before `from`, at which a job of the
+/// given `duration` can run on `allotment` processors next to the `others`
+/// without exceeding the capacity of the `instance` at any time while it runs.
+/// Windows of the capacity profile that are too narrow for the job are
+/// skipped entirely. Jobs without processing time occupy no capacity, so they
+/// start at `from`.
+pub fn earliest_start(
+    instance: &Instance,
+    others: &[Placement],
+    from: i32,
+    duration: i32,
+    allotment: usize,
+) -> i32 {
+    if duration == 0 {
+        return from;
+    }
+    // the earliest start is either `from`, the time at which another job
+    // ends, or the end of a capacity window
+    let mut candidates = others
+        .iter()
+        .map(|p| p.end)
+        .chain(instance.capacity_rises())
+        .filter(|&end| end > from)
+        .collect::<Vec<_>>();
+    candidates.push(from);
+    candidates.sort_unstable();
+    candidates.dedup();
+    candidates
+        .into_iter()
+        .find(|&start| {
+            let end = start + duration;
+            // the usage only rises when a job starts and the capacity only
+            // drops when a window starts or a processor goes down, so it
+            // suffices to check the start of the new job and all such points
+            // while it runs
+            others
+                .iter()
+                .map(|p| p.start)
+                .chain(instance.capacity_drops())
+                .filter(|&t| start < t && t < end)
+                .chain([start])
+                .all(|t| {
+                    let busy = others
+                        .iter()
+                        .filter(|p| p.start <= t && t < p.end)
+                        .map(|p| p.allotment)
+                        .sum::<usize>();
+                    busy + allotment <= instance.capacity_at(t)
+                })
+        })
+        .expect("no start time found although all jobs and capacity windows end eventually")
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::Availability;
+
+    /// Checks whether processor `p` is free from `start` until `end` by
+    /// looking at every interval in which it is busy
+    fn is_free(busy: &[Vec<(i32, i32)>], p: usize, start: i32, end: i32) -> bool {
+        busy[p].iter().all(|&(s, e)| e <= start || end <= s)
+    }
+
+    /// Finds the earliest start, not before `not_before`, by trying every
+    /// time step until all processors are free for good
+    fn brute_force(
+        busy: &[Vec<(i32, i32)>],
+        allotment: usize,
+        not_before: i32,
+        duration: i32,
+        contiguous: bool,
+        allowed: &[usize],
+    ) -> Option<i32> {
+        let last = busy.iter().flatten().map(|&(_, e)| e).max().unwrap_or(0);
+        (not_before..=last.max(not_before)).find(|&start| {
+            let free = (0..busy.len())
+                .map(|p| allowed.contains(&p) && is_free(busy, p, start, start + duration))
+                .collect::<Vec<_>>();
+            if contiguous {
+                free.windows(allotment)
+                    .any(|window| window.iter().all(|&f| f))
+            } else {
+                free.iter().filter(|&&f| f).count() >= allotment
+            }
+        })
+    }
+
+    #[test]
+    fn earliest_window_agrees_with_brute_force() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..500 {
+            let processor_count = rng.random_range(1..=4);
+            let mut availability = Availability::new(processor_count);
+            let mut busy = vec![vec![]; processor_count];
+            for (p, intervals) in busy.iter_mut().enumerate() {
+                for _ in 0..rng.random_range(0..=3) {
+                    let start = rng.random_range(0..20);
+                    let end = start + rng.random_range(1..=6);
+                    availability.block(p, start, end);
+                    intervals.push((start, end));
+                }
+            }
+            let allotment = rng.random_range(1..=processor_count);
+            let not_before = rng.random_range(0..15);
+            let duration = rng.random_range(1..=5);
+            let contiguous = rng.random_bool(0.5);
+            let allowed = (0..processor_count)
+                .filter(|_| rng.random_bool(0.8))
+                .collect::<Vec<_>>();
+            let affinity = rng.random_bool(0.5).then_some(allowed.as_slice());
+            let allowed = affinity.map_or_else(|| (0..processor_count).collect(), <[_]>::to_vec);
+
+            let Some(expected) =
+                brute_force(&busy, allotment, not_before, duration, contiguous, &allowed)
+            else {
+                // the affinity is too small for the allotment
+                continue;
+            };
+            let (start, processors) =
+                availability.earliest_window(allotment, not_before, duration, contiguous, affinity);
+            assert_eq!(start, expected, "busy {busy:?}, allowed {allowed:?}");
+            assert_eq!(processors.len(), allotment);
+            assert!(processors.is_sorted());
+            for &p in &processors {
+                assert!(allowed.contains(&p));
+                assert!(is_free(&busy, p, start, start + duration));
+            }
+            if contiguous {
+                assert_eq!(processors[allotment - 1] - processors[0], allotment - 1);
+            }
+        }
+    }
+}
@@ -0,0 +1,628 @@
+// Transformations of an instance that happen before solving it, along with
+// the steps that translate the resulting schedule back.
+
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use itertools::Itertools;
+use log::{debug, info};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    algo::{Constraint, Instance, Job, Lag, Schedule, ScheduledJob, SoftConstraint, SolverStats},
+    diagnostics::{self, Category},
+    list::{self, ListOptions},
+};
+
+/// Records which jobs of a split instance belong to which job of the
+/// original instance
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SplitMap {
+    /// Longest processing time with one processor that a job could have
+    /// without being split
+    pub max_len: i32,
+    /// Index of the original job of every job of the split instance
+    pub original: Vec<usize>,
+    /// Indices of the jobs of the split instance that make up every original
+    /// job, in the order in which they have to run
+    pub segments: Vec<Vec<usize>>,
+}
+
+/// How the segments of a job ran if they could not be merged
+#[derive(Debug)]
+pub struct Profile {
+    /// Index of the original job
+    pub job: usize,
+    /// Start time, completion time, and allotment of every segment
+    pub segments: Vec<(i32, i32, usize)>,
+}
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "job {} runs on {}",
+            self.job,
+            self.segments
+                .iter()
+                .map(|(start, end, allotment)| format!(
+                    "{allotment} processors from {start} to {end}"
+                ))
+                .join(", then ")
+        )
+    }
+}
+
+/// Groups of interchangeable jobs of an instance, see `identical_jobs`
+#[derive(Debug)]
+pub struct Groups {
+    /// Group of the job at every position
+    pub of: Vec<usize>,
+    /// Positions of the jobs of every group in ascending order, so the first
+    /// one represents the group
+    pub members: Vec<Vec<usize>>,
+}
+impl Groups {
+    /// Creates the instance with only the first job of every group, whose
+    /// positions are the numbers of the groups
+    pub fn quotient(&self, instance: &Instance) -> Instance {
+        instance.restrict_to(&self.members.iter().map(|group| group[0]).collect_vec())
+    }
+    /// Number of jobs in every group
+    pub fn multiplicities(&self) -> Vec<usize> {
+        self.members.iter().map(Vec::len).collect()
+    }
+    /// Copies the value of every group to all of its jobs, ordered by their
+    /// positions
+    pub fn expand<T: Clone>(&self, values: &[T]) -> Vec<T> {
+        self.of.iter().map(|&group| values[group].clone()).collect()
+    }
+    /// Number of waves that run before the job at position `j` if the jobs
+    /// of its group run in the order of their positions, `width` of them
+    /// next to each other
+    pub fn waves_before(&self, j: usize, width: usize) -> usize {
+        let group = &self.members[self.of[j]];
+        let rank = group.iter().position(|&k| k == j).unwrap_or(0);
+        rank / width.max(1)
+    }
+}
+
+/// Replaces every job that takes longer than `max_len` with one processor by
+/// a chain of segments. Each processing time of the job is divided among the
+/// segments as evenly as possible, giving every segment at least one unit of
+/// time. The first segment keeps the index of the job, the other segments
+/// are appended to the jobs. All predecessors of the job precede every
+/// segment and all successors follow every segment, but the lags only apply
+/// before the first and after the last segment.
+pub fn split_long_jobs(instance: &Instance, max_len: i32) -> (Instance, SplitMap) {
+    assert!(
+        max_len > 0,
+        "jobs cannot be split into segments of {max_len}"
+    );
+    let n = instance.jobs.len();
+    let mut jobs = instance.jobs.clone();
+    let mut original = (0..n).collect::<Vec<_>>();
+    let mut segments = (0..n).map(|j| vec![j]).collect::<Vec<_>>();
+    for (j, job) in instance.jobs.iter().enumerate() {
+        let count = (job.processing_time(1) + max_len - 1) / max_len;
+        if count <= 1 {
+            continue;
+        }
+        debug!("Splitting job {j} into {count} segments");
+        for segment in 0..count {
+            let processing_times = job
+                .processing_times
+                .iter()
+                .map(|&p| (p / count + i32::from(segment < p % count)).max(1))
+                .collect();
+            if segment == 0 {
+                jobs[j].processing_times = processing_times;
+            } else {
+                segments[j].push(jobs.len());
+                original.push(j);
+                jobs.push(Job {
+                    index: jobs.len(),
+                    processing_times,
+                    synthesized: job.synthesized,
+                    affinity: job.affinity.clone(),
+                    note: job.note.clone(),
+                });
+            }
+        }
+    }
+
+    let mut constraints = vec![];
+    // segments precede each other in their order, including themselves
+    for chain in segments.iter().filter(|chain| chain.len() > 1) {
+        for (a, &left) in chain.iter().enumerate() {
+            for &right in &chain[a..] {
+                constraints.push(Constraint(left, right, Lag::default()));
+            }
+        }
+    }
+    for Constraint(left, right, lag) in &instance.constraints {
+        if left == right {
+            if segments[*left].len() == 1 {
+                constraints.push(Constraint(*left, *right, *lag));
+            }
+            continue;
+        }
+        let (from, to) = (&segments[*left], &segments[*right]);
+        for &l in from {
+            for &r in to {
+                let lag = if l == from[from.len() - 1] && r == to[0] {
+                    *lag
+                } else {
+                    Lag::default()
+                };
+                constraints.push(Constraint(l, r, lag));
+            }
+        }
+    }
+
+    // soft constraints run from the last segment to the first one
+    let soft_constraints = instance
+        .soft_constraints
+        .iter()
+        .map(|soft| SoftConstraint {
+            left: segments[soft.left][segments[soft.left].len() - 1],
+            right: segments[soft.right][0],
+            ..*soft
+        })
+        .collect();
+
+    (
+        Instance::new(instance.processor_count, jobs, constraints)
+            .with_soft_constraints(soft_constraints)
+            .with_capacity(instance.capacity.clone())
+            .with_downtime(instance.downtime.clone())
+            .with_machine_names(instance.machine_names.clone()),
+        SplitMap {
+            max_len,
+            original,
+            segments,
+        },
+    )
+}
+
+/// Solves the concave closure of `instance` with `solve` and turns the
+/// schedule back into one of `instance`. Every job that is not pinned gets the
+/// allotment whose original processing time is closest to the processing time
+/// it was planned with, and LIST places the jobs with these allotments no
+/// earlier than planned, using `list_options`. The bound of the linear program
+/// stays valid, since the closure only shortens jobs. The difference between
+/// the planned and the actual processing times is logged as the error that
+/// the closure induces.
+pub fn solve_concave(
+    instance: Instance,
+    solve: impl FnOnce(Instance) -> (Schedule, SolverStats),
+    list_options: &ListOptions,
+) -> (Schedule, SolverStats) {
+    let closed = instance.concave_closure();
+    info!(
+        "The concave closure changes the processing times of {} of {} jobs",
+        instance
+            .jobs
+            .iter()
+            .zip(&closed.jobs)
+            .filter(|(job, closed)| job.processing_times != closed.processing_times)
+            .count(),
+        instance.jobs.len()
+    );
+    let (planned, mut stats) = solve(closed);
+    let n = instance.jobs.len();
+    let mut allotments = vec![1; n];
+    let mut targets = vec![0; n];
+    let mut errors = vec![0; n];
+    for scheduled in &planned.jobs {
+        let j = scheduled.job.index;
+        let job = &instance.jobs[j];
+        let allotment = match instance.pin(j) {
+            Some(pin) => pin.allotment,
+            None => job.closest_allotment(scheduled.processing_time()),
+        };
+        if allotment != scheduled.allotment {
+            debug!(
+                "Mapping allotment {} of job {j} back to {allotment}",
+                scheduled.allotment
+            );
+        }
+        allotments[j] = allotment;
+        targets[j] = scheduled.start_time;
+        errors[j] = job.processing_time(allotment) - scheduled.processing_time();
+    }
+    let list = list::schedule(&instance, &allotments, &targets, list_options);
+    let schedule = Schedule {
+        processor_count: instance.processor_count,
+        jobs: list.jobs,
+    };
+    let (worst, max_error) = errors
+        .iter()
+        .copied()
+        .enumerate()
+        .max_by_key(|&(_, error)| error.abs())
+        .unwrap_or_default();
+    info!(
+        "Mapping the allotments back changes the processing times by {} in total and by up to {max_error} for job {worst}, and the makespan from {} to {}",
+        errors.iter().copied().map(i32::abs).sum::<i32>(),
+        planned.makespan(),
+        schedule.makespan()
+    );
+    for decision in &mut stats.rounding {
+        decision.chosen_allotment = allotments[decision.job];
+    }
+    stats.max_target_deviation = stats
+        .max_target_deviation
+        .map(|_| list::max_target_deviation(&list.target_deviations));
+    stats.seed = list.seed;
+    (schedule, stats)
+}
+
+/// Solves `instance` such that no job runs with an allotment whose efficiency
+/// `p(1) / (l p(l))` is below `floor`. Every job keeps its allotments up to
+/// the first one below the floor, and the larger allotments are pruned by
+/// giving them the processing time of the largest kept one, so no solver
+/// gains anything from them. Pinned jobs keep all their allotments. Jobs
+/// that `solve` places on pruned allotments anyway get the smallest allotment
+/// with the same processing time, running on the first of their processors,
+/// and the schedule refers to the jobs of `instance` again.
+pub fn solve_efficient(
+    instance: Instance,
+    floor: f64,
+    solve: impl FnOnce(Instance) -> (Schedule, SolverStats),
+) -> (Schedule, SolverStats) {
+    let jobs = (0..instance.jobs.len())
+        .map(|j| instance.shared_job(j))
+        .collect::<Vec<_>>();
+    let mut pruned = 0;
+    let restricted = instance
+        .jobs
+        .iter()
+        .map(|job| {
+            let p1 = f64::from(job.processing_time(1));
+            let kept = if instance.pin(job.index).is_some() {
+                job.processing_times.len()
+            } else {
+                job.processing_times
+                    .iter()
+                    .zip(1..)
+                    .take_while(|&(&p, l)| p1 >= floor * (l * p) as f64)
+                    .count()
+                    .max(1)
+            };
+            pruned += job.processing_times.len() - kept;
+            let last = job.processing_time(kept);
+            Job {
+                processing_times: job
+                    .processing_times
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &p)| if i < kept { p } else { last })
+                    .collect(),
+                ..job.clone()
+            }
+        })
+        .collect::<Vec<_>>();
+    info!(
+        "Efficiency floor {floor} prunes {pruned} of {} allotment options",
+        jobs.iter()
+            .map(|job| job.processing_times.len())
+            .sum::<usize>()
+    );
+    let pinned = instance
+        .pinned
+        .iter()
+        .map(|pin| ScheduledJob {
+            job: Arc::new(restricted[pin.job.index].clone()),
+            ..pin.clone()
+        })
+        .collect();
+    let restricted = Instance::new(instance.processor_count, restricted, instance.constraints)
+        .with_soft_constraints(instance.soft_constraints)
+        .with_capacity(instance.capacity)
+        .with_downtime(instance.downtime)
+        .with_pinned(pinned)
+        .with_machine_names(instance.machine_names);
+    let (schedule, mut stats) = solve(restricted);
+    let mut shrunk = 0;
+    let schedule = Schedule {
+        processor_count: schedule.processor_count,
+        jobs: schedule
+            .jobs
+            .into_iter()
+            .map(|scheduled| {
+                let time = scheduled.processing_time();
+                let allotment = scheduled
+                    .job
+                    .processing_times
+                    .iter()
+                    .position(|&p| p == time)
+                    .map_or(scheduled.allotment, |i| i + 1);
+                if allotment < scheduled.allotment {
+                    shrunk += 1;
+                }
+                let mut processors = scheduled.processors;
+                processors.truncate(allotment);
+                ScheduledJob {
+                    job: jobs[scheduled.job.index].clone(),
+                    allotment,
+                    processors,
+                    ..scheduled
+                }
+            })
+            .collect(),
+    };
+    if shrunk > 0 {
+        info!(
+            "Moved {shrunk} jobs from pruned allotments to kept ones with the same processing time"
+        );
+    }
+    let allotments = schedule
+        .jobs
+        .iter()
+        .map(|scheduled| (scheduled.job.index, scheduled.allotment))
+        .collect::<HashMap<_, _>>();
+    for decision in &mut stats.rounding {
+        if let Some(&allotment) = allotments.get(&decision.job) {
+            decision.chosen_allotment = allotment;
+        }
+    }
+    (schedule, stats)
+}
+
+/// Solves `instance` with all times divided by `granularity` and rounded
+/// outwards, see `Instance::coarsen`, and turns the schedule back into one of
+/// `instance`. LIST places the jobs with their planned allotments and exact
+/// processing times no earlier than their planned start times multiplied by
+/// `granularity`, using `list_options`. If `solve` is exact, the optimal
+/// makespan is at least `granularity` times the coarse makespan minus twice
+/// the number of jobs, since an optimal schedule can be turned into a coarse
+/// one by rounding its start times down and then delaying the `k`-th job to
+/// start by `2 k` coarse units. The gap between this bound and the makespan
+/// is logged.
+pub fn solve_coarse(
+    instance: Instance,
+    granularity: i32,
+    solve: impl FnOnce(Instance) -> (Schedule, SolverStats),
+    list_options: &ListOptions,
+) -> (Schedule, SolverStats) {
+    let coarse = instance.coarsen(granularity);
+    info!(
+        "Coarsening by {granularity} shrinks the horizon from {} to {}",
+        instance.horizon(),
+        coarse.horizon()
+    );
+    let (planned, mut stats) = solve(coarse);
+    let n = instance.jobs.len();
+    let mut allotments = vec![1; n];
+    let mut targets = vec![0; n];
+    for scheduled in &planned.jobs {
+        allotments[scheduled.job.index] = scheduled.allotment;
+        targets[scheduled.job.index] = scheduled.start_time * granularity;
+    }
+    let list = list::schedule(&instance, &allotments, &targets, list_options);
+    let schedule = Schedule {
+        processor_count: instance.processor_count,
+        jobs: list.jobs,
+    };
+    let bound = (granularity * (planned.makespan() - 2 * n as i32))
+        .max(instance.min_critical_path_length());
+    info!(
+        "Mapping the coarse schedule back changes the makespan from {} to {}, which is at most {} above the optimum",
+        granularity * planned.makespan(),
+        schedule.makespan(),
+        (schedule.makespan() - bound).max(0)
+    );
+    stats.seed = list.seed;
+    (schedule, stats)
+}
+
+/// Concatenates the chains of `instance`, see `Instance::chains`, from the
+/// shortest on until at most `max_chains` remain, which shrinks the state
+/// space of the dynamic program. The length of a chain is the sum of the
+/// fastest processing times of its jobs. Two chains are concatenated by
+/// constraints from every job of the one to every job of the other, in the
+/// direction that does not close a cycle, and pairs that would close a cycle
+/// either way are skipped. Returns the instance with the added constraints
+/// along with them. Every schedule of the returned instance satisfies
+/// `instance`, but its optimum may be longer.
+pub fn merge_chains(instance: &Instance, max_chains: usize) -> (Instance, Vec<Constraint>) {
+    let mut added = vec![];
+    let mut merged = with_constraints(instance, &added);
+    loop {
+        let chains = merged.chains();
+        if chains.len() <= max_chains {
+            break;
+        }
+        let fastest = |j: usize| {
+            merged.jobs[j]
+                .processing_times
+                .iter()
+                .min()
+                .copied()
+                .unwrap_or(0)
+        };
+        let by_length = chains
+            .iter()
+            .sorted_by_key(|chain| (chain.iter().map(|&j| fastest(j)).sum::<i32>(), chain[0]))
+            .collect::<Vec<_>>();
+        let edges = by_length
+            .iter()
+            .tuple_combinations()
+            .flat_map(|(a, b)| [(a, b), (b, a)])
+            .filter(|(first, second)| !reaches_any(&merged, second, first))
+            .map(|(first, second)| {
+                first
+                    .iter()
+                    .cartesian_product(second.iter())
+                    .filter(|&(&j, k)| merged.successor_indices(j).binary_search(k).is_err())
+                    .map(|(&j, &k)| {
+                        Constraint(merged.jobs[j].index, merged.jobs[k].index, Lag::default())
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .find(|edges| !edges.is_empty());
+        let Some(edges) = edges else {
+            diagnostics::report(
+                Category::Model,
+                format!(
+                    "Cannot merge {} chains any further without closing a cycle",
+                    chains.len()
+                ),
+            );
+            break;
+        };
+        debug!("Merging two chains with {} constraints", edges.len());
+        added.extend(edges);
+        merged = with_constraints(instance, &added);
+    }
+    info!(
+        "Merged the chains of the instance into {} by adding {} constraints",
+        merged.chains().len(),
+        added.len()
+    );
+    (merged, added)
+}
+
+/// Checks whether a path along the constraints leads from a job at one of the
+/// positions `from` to a job at one of the positions `to`
+fn reaches_any(instance: &Instance, from: &[usize], to: &[usize]) -> bool {
+    from.iter()
+        .any(|&j| to.contains(&j) || to.iter().any(|&k| instance.reaches(j, k)))
+}
+
+/// Creates a copy of `instance` with the `added` constraints
+fn with_constraints(instance: &Instance, added: &[Constraint]) -> Instance {
+    let constraints = instance.constraints.iter().chain(added).cloned().collect();
+    Instance::new(instance.processor_count, instance.jobs.clone(), constraints)
+        .with_horizon(instance.horizon())
+        .with_soft_constraints(instance.soft_constraints.clone())
+        .with_capacity(instance.capacity.clone())
+        .with_downtime(instance.downtime.clone())
+        .with_pinned(instance.pinned.clone())
+        .with_machine_names(instance.machine_names.clone())
+}
+
+/// Groups the jobs of `instance` that are interchangeable, i.e. that have
+/// the same processing times and affinity, and the same predecessors and
+/// successors with the same lags. Pinned jobs and jobs with soft constraints
+/// stay on their own. The groups are numbered by their first job, so the
+/// representatives of the groups keep their order.
+pub fn identical_jobs(instance: &Instance) -> Groups {
+    let soft = instance
+        .soft_constraints
+        .iter()
+        .flat_map(|soft| [soft.left, soft.right])
+        .collect::<Vec<_>>();
+    let mut by_key = HashMap::new();
+    let mut of = vec![];
+    let mut members: Vec<Vec<usize>> = vec![];
+    for (j, job) in instance.jobs.iter().enumerate() {
+        let group = members.len();
+        let group = if instance.pin(job.index).is_some() || soft.contains(&j) {
+            group
+        } else {
+            let predecessors = instance
+                .predecessor_indices(j)
+                .iter()
+                .map(|&i| (i, instance.lag(i, j)))
+                .map(|(i, lag)| (i, lag.min, lag.max))
+                .collect::<Vec<_>>();
+            let successors = instance
+                .successor_indices(j)
+                .iter()
+                .map(|&k| (k, instance.lag(j, k)))
+                .map(|(k, lag)| (k, lag.min, lag.max))
+                .collect::<Vec<_>>();
+            let key = (
+                job.processing_times.clone(),
+                job.affinity.clone(),
+                predecessors,
+                successors,
+            );
+            *by_key.entry(key).or_insert(group)
+        };
+        if group == members.len() {
+            members.push(vec![]);
+        }
+        members[group].push(j);
+        of.push(group);
+    }
+    Groups { of, members }
+}
+
+impl SplitMap {
+    /// Labels every job of the split instance by the index of its original
+    /// job, followed by the number of the segment if the job was split
+    pub fn label(&self, job: usize) -> String {
+        let original = self.original[job];
+        let chain = &self.segments[original];
+        match chain.iter().position(|&segment| segment == job) {
+            Some(k) if chain.len() > 1 => format!("{original}.{}", k + 1),
+            _ => original.to_string(),
+        }
+    }
+
+    /// Merges the segments of every split job of a schedule of the split
+    /// instance into a single job of the `original` instance. This only works
+    /// if the segments of every job run back to back with the same allotment,
+    /// otherwise the segments of the jobs that cannot be merged are returned.
+    pub fn merge(
+        &self,
+        original: &Instance,
+        schedule: &Schedule,
+    ) -> Result<Schedule, Vec<Profile>> {
+        let mut by_index = vec![None; self.original.len()];
+        for job in &schedule.jobs {
+            by_index[job.job.index] = Some(job);
+        }
+        let mut jobs = vec![];
+        let mut profiles = vec![];
+        for (j, chain) in self.segments.iter().enumerate() {
+            let parts = chain
+                .iter()
+                .map(|&segment| {
+                    by_index[segment]
+                        .unwrap_or_else(|| panic!("segment {segment} is not scheduled"))
+                })
+                .collect::<Vec<_>>();
+            let first = parts[0];
+            let job = &original.jobs[j];
+            let mergeable = parts.iter().all(|part| part.allotment == first.allotment)
+                && parts
+                    .iter()
+                    .tuple_windows()
+                    .all(|(a, b)| a.completion_time() == b.start_time)
+                && parts[parts.len() - 1].completion_time() - first.start_time
+                    == job.processing_time(first.allotment);
+            if mergeable {
+                let processors = if parts.iter().all(|part| part.processors == first.processors) {
+                    first.processors.clone()
+                } else {
+                    vec![]
+                };
+                jobs.push(ScheduledJob {
+                    job: original.shared_job(j),
+                    allotment: first.allotment,
+                    start_time: first.start_time,
+                    processors,
+                });
+            } else {
+                profiles.push(Profile {
+                    job: j,
+                    segments: parts
+                        .iter()
+                        .map(|part| (part.start_time, part.completion_time(), part.allotment))
+                        .collect(),
+                });
+            }
+        }
+        if profiles.is_empty() {
+            Ok(Schedule {
+                processor_count: schedule.processor_count,
+                jobs,
+            })
+        } else {
+            Err(profiles)
+        }
+    }
+}
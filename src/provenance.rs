@@ -0,0 +1,195 @@
+// Records how a schedule was produced so that exported schedule files can be
+// traced back to the solver, its options, and the input files.
+
+use std::{fs, time::Duration};
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    algo::{Constraint, SolverStats},
+    generate::GenerateConfig,
+    improve::Improvement,
+    objective::{Makespan, ScheduleObjective},
+    reproducible,
+};
+
+/// The origin of a computed schedule
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Provenance {
+    /// Name of the subcommand that computed the schedule
+    pub solver: &'static str,
+    /// Version of this crate
+    pub version: &'static str,
+    /// Commit hash of the source tree, if it was known at build time
+    pub git_hash: Option<&'static str>,
+    /// Path and hash of the job file, if the instance was read from or
+    /// saved to files
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub job_file: Option<InputFile>,
+    /// Path and hash of the constraint file, if the instance was read from or
+    /// saved to files
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub constraint_file: Option<InputFile>,
+    /// Generator run that produced the instance, if it was generated in the
+    /// same process
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generator: Option<Generator>,
+    /// Options that influence the result of the solver
+    pub options: SolverOptions,
+    /// Seconds since the Unix epoch at which solving started, see
+    /// `reproducible::timestamp`
+    pub started_at: u64,
+    /// Milliseconds needed to compute the schedule, 0 in reproducible mode
+    pub duration_ms: u128,
+    /// How the local search of --improve-from improved the schedule over
+    /// time
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub improvements: Vec<Improvement>,
+}
+
+/// An input file of a solver run
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct InputFile {
+    /// The path as given on the command line
+    pub path: String,
+    /// FNV-1a hash of the file contents
+    pub hash: String,
+}
+
+/// The generator run that produced an instance, which regenerates it exactly
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Generator {
+    /// Parameters of the generator
+    pub config: GenerateConfig,
+    /// Seed from which the instance was generated, after all resamples
+    pub seed: u64,
+}
+
+/// Solver options that influence the resulting schedule
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SolverOptions {
+    /// Whether idle times were removed from the schedule
+    pub compress: bool,
+    /// Rounding parameter ρ of the LP algorithm
+    pub rho: Option<f64>,
+    /// Allotment parameter µ of the ILP algorithm
+    pub mu: Option<usize>,
+    /// Seed that reproduces the tie-breaking of the list scheduling via --seed
+    pub seed: Option<u64>,
+    /// Objective that the local search and the choice among several list
+    /// schedules minimized
+    pub objective: &'static str,
+    /// Time from which on jobs were deferred, if the schedule is a partial
+    /// preview
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub horizon_limit: Option<i32>,
+    /// Constraints by the indices of their jobs that the solver added to
+    /// merge chains via --merge-chains. They are not part of the instance,
+    /// and the schedule is only optimal for the instance with them.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub added_constraints: Vec<(usize, usize)>,
+}
+
+impl Provenance {
+    /// Collects the provenance of a solver run that started at `started_at`
+    /// seconds since the Unix epoch and took `duration`. The origin of the instance is added via
+    /// `with_input_files` or `with_generator`.
+    pub fn new(
+        solver: &'static str,
+        compress: bool,
+        stats: &SolverStats,
+        started_at: u64,
+        duration: Duration,
+    ) -> Self {
+        Provenance {
+            solver,
+            version: env!("CARGO_PKG_VERSION"),
+            git_hash: option_env!("GIT_HASH"),
+            job_file: None,
+            constraint_file: None,
+            generator: None,
+            options: SolverOptions {
+                compress,
+                rho: stats.rho,
+                mu: stats.mu,
+                seed: stats.seed,
+                objective: Makespan.name(),
+                horizon_limit: None,
+                added_constraints: vec![],
+            },
+            started_at,
+            duration_ms: reproducible::duration_ms(duration),
+            improvements: vec![],
+        }
+    }
+
+    /// Records the paths and hashes of the files that hold the instance
+    pub fn with_input_files(mut self, job_file: &str, constraint_file: &str) -> Self {
+        self.job_file = Some(InputFile::new(job_file));
+        self.constraint_file = Some(InputFile::new(constraint_file));
+        self
+    }
+
+    /// Records the name of the objective that was minimized
+    pub fn with_objective(mut self, objective: &'static str) -> Self {
+        self.options.objective = objective;
+        self
+    }
+
+    /// Records the time from which on jobs were deferred, if any
+    pub fn with_horizon_limit(mut self, horizon_limit: Option<i32>) -> Self {
+        self.options.horizon_limit = horizon_limit;
+        self
+    }
+
+    /// Records the constraints that the solver added to the instance
+    pub fn with_added_constraints(mut self, added: &[Constraint]) -> Self {
+        self.options.added_constraints = added
+            .iter()
+            .map(|&Constraint(left, right, _)| (left, right))
+            .collect();
+        self
+    }
+
+    /// Records the trace of the local search
+    pub fn with_improvements(mut self, improvements: Vec<Improvement>) -> Self {
+        self.improvements = improvements;
+        self
+    }
+
+    /// Records the generator run that produced the instance
+    pub fn with_generator(mut self, config: GenerateConfig, seed: u64) -> Self {
+        self.generator = Some(Generator { config, seed });
+        self
+    }
+}
+
+impl InputFile {
+    fn new(path: &str) -> Self {
+        let contents = fs::read(path).unwrap_or_else(|e| panic!("cannot read file {path}: {e}"));
+        InputFile {
+            path: path.to_string(),
+            hash: hash_contents(&contents),
+        }
+    }
+}
+
+/// Hashes the contents of the files at `paths`, one after another, like
+/// `hash_contents`
+pub fn hash_files(paths: &[&str]) -> String {
+    let contents = paths
+        .iter()
+        .flat_map(|path| fs::read(path).unwrap_or_else(|e| panic!("cannot read {path}: {e}")))
+        .collect::<Vec<_>>();
+    hash_contents(&contents)
+}
+
+/// Computes the 64-bit FNV-1a hash of `bytes`. Unlike the hasher of the
+/// standard library, its output is stable across Rust versions.
+pub fn hash_contents(bytes: &[u8]) -> String {
+    let hash = bytes.iter().fold(0xcbf2_9ce4_8422_2325_u64, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("fnv1a64:{hash:016x}")
+}
@@ -1,13 +1,25 @@
 // Small helper to render schedules to csv files, but it is not adjusted to account for allotments.
 // It is copied over from a previous project for potential future work and needs fixing.
 
-use crate::{Schedule, ScheduledJob};
+use std::{cmp::Reverse, collections::HashMap, fmt};
+
+use clap::ValueEnum;
+use itertools::Itertools;
+
+use crate::{
+    algo::{format_processors, machine_name, CapacityWindow, Downtime, Instance, ProfilePoint},
+    metrics::ChainBreakdown,
+    Schedule, ScheduledJob,
+};
+pub use ascii::render_ascii;
+pub use pareto::{render_pareto, render_size};
 use svg::{
     node::element::{
-        path::Data, Group, LinearGradient, Path, Rectangle, Stop, Style, Text, Title, SVG,
+        path::Data, Group, LinearGradient, Path, Pattern, Rectangle, Stop, Style, Text, Title, SVG,
     },
     Document,
 };
+pub use theme::Theme;
 
 const SCALE_MARGIN: usize = 50; // px
 const LEFT_MARGIN: usize = SCALE_MARGIN + 20; // px
@@ -16,42 +28,332 @@ const TOP_MARGIN: usize = TOP_HEADER_MARGIN + 20; // px
 const RIGHT_MARGIN: usize = 30; // px
 const BOTTOM_MARGIN: usize = 20; // px
 const MACHINE_WIDTH: usize = 150; // px
-const MACHINE_HEIGHT_SCALE: usize = 15; // px for each unit of processing time
+const MACHINE_HEIGHT_SCALE: usize = 15; // px for each unit of processing time, at most
+const DEFAULT_MAX_HEIGHT: usize = 4000; // px
+const EMPTY_SCHEDULE_LENGTH: usize = 10; // units of time shown if the makespan is 0
+const MIN_LABEL_HEIGHT: usize = 18; // px, shorter boxes get their label outside
+const MIN_TICK_SPACING: usize = 4; // px
+const MIN_TICK_LABEL_SPACING: usize = 60; // px
 const MACHINE_SPACING: usize = 10; // px
+const MACHINE_NAME_LENGTH: usize = 15; // characters that fit above a machine
+const UTILIZATION_WIDTH: usize = 40; // px
+const CHAIN_BAR_WIDTH: usize = 6; // px
+const CHAIN_BAR_SPACING: usize = 2; // px
+const CHAIN_HEADER_WIDTH: usize = 70; // px
+const ARROW_HEAD_LENGTH: f32 = 8.0; // px
+const ARROW_HEAD_WIDTH: f32 = 8.0; // px
+
+mod animate;
+mod ascii;
+mod pareto;
+mod theme;
+
+/// What determines the fill of a job in the rendered schedule
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ColorBy {
+    /// The index of the job, so that neighboring jobs differ
+    #[default]
+    Job,
+    /// The depth of the job in the precedence graph, so that jobs on the
+    /// same level look alike
+    Depth,
+}
+
+/// Options that control how a schedule is rendered
+#[derive(Debug, Default)]
+pub struct RenderOptions {
+    /// The color theme of the document
+    pub theme: Theme,
+    /// What determines the fill of a job
+    pub color_by: ColorBy,
+    /// Depths of the jobs in the precedence graph, by job index, which break
+    /// ties between jobs that start at the same time
+    pub depths: HashMap<usize, usize>,
+    /// Additional lines for the tooltips of jobs, by job index
+    pub notes: HashMap<usize, String>,
+    /// Labels that replace the index of jobs, by job index
+    pub labels: HashMap<usize, String>,
+    /// Capacity profile whose unavailable processors are shaded
+    pub capacity: Vec<CapacityWindow>,
+    /// Maintenance windows that are drawn as hatched blocks on their
+    /// processors, and on which no job is placed
+    pub downtime: Vec<Downtime>,
+    /// Names of the processors, which are called "Processor <i>" if empty
+    pub machine_names: Vec<String>,
+    /// Chains that get a bar up to their completion time next to the
+    /// utilization bars, none if empty
+    pub chains: Vec<ChainBreakdown>,
+    /// Largest height of the document in px, to which the time axis is
+    /// scaled down, defaults to `DEFAULT_MAX_HEIGHT`
+    pub max_height: Option<usize>,
+    /// Soft constraints that the schedule breaks, by the indices of their
+    /// left and right jobs, which are drawn as dashed arrows
+    pub soft_violations: Vec<(usize, usize)>,
+    /// Number of jobs that a partial schedule leaves out, which the title
+    /// mentions
+    pub deferred: usize,
+    /// Largest height of a page in px. Schedules that would be taller at
+    /// full scale are split into pages by time ranges instead of being
+    /// scaled down, none if unset.
+    pub page_height: Option<usize>,
+    /// Jobs drawn shorter than this many px are merged with the jobs that
+    /// run back to back with them on the same processors into one block,
+    /// none if unset
+    pub aggregate_below_px: Option<usize>,
+    /// Seconds of playback per unit of time of an animated document, in
+    /// which jobs appear when they start and grow until they complete, none
+    /// for a static document
+    pub animate: Option<f64>,
+    /// Predecessors of the jobs by job index, on whose processors the jobs
+    /// are kept where possible, so that related jobs line up
+    pub predecessors: HashMap<usize, Vec<usize>>,
+    /// Times at which the jobs became ready, by job index. Jobs that started
+    /// later get a dashed outline above their box from that time on, none if
+    /// empty.
+    pub show_waiting: HashMap<usize, i32>,
+}
+
+impl RenderOptions {
+    /// Looks up the depth of a job, which is 0 if it is unknown
+    fn depth(&self, job_index: usize) -> usize {
+        self.depths.get(&job_index).copied().unwrap_or(0)
+    }
+    /// Looks up the name of a processor
+    fn machine_name(&self, processor: usize) -> String {
+        machine_name(&self.machine_names, processor)
+    }
+    /// Title of the document, which mentions the deferred jobs of a partial
+    /// schedule
+    fn title(&self) -> String {
+        if self.deferred == 0 {
+            "Schedule".to_string()
+        } else {
+            format!("Schedule (partial, {} jobs deferred)", self.deferred)
+        }
+    }
+}
+
+/// A job that is shown in the rendered schedule, along with its processors
+type Shown<'a> = (&'a ScheduledJob, &'a [usize]);
+
+/// A page of a schedule that was split by time ranges
+#[derive(Debug)]
+pub struct Page {
+    /// First time shown on the page
+    pub from: i32,
+    /// Time at which the page ends, which is the first time of the next page
+    pub to: i32,
+    /// The rendered SVG
+    pub svg: String,
+}
+
+/// Maps times to vertical positions in the document. Jobs, bars, and the
+/// ticks of the time scale all share one scale.
+#[derive(Clone, Copy, Debug)]
+struct TimeScale {
+    /// Pixels for each unit of time
+    px_per_unit: f64,
+    /// Time between two ticks of the time scale
+    tick_step: usize,
+    /// Time between two labeled ticks, a multiple of `tick_step`
+    label_step: usize,
+    /// First time shown, to which earlier times are clamped
+    from: i32,
+    /// Last time shown, to which later times are clamped
+    to: i32,
+}
+impl TimeScale {
+    /// Picks the largest scale of at most `MACHINE_HEIGHT_SCALE` px per unit
+    /// at which a schedule of length `height` fits into a document of
+    /// `max_height` px, along with the ticks that stay readable at that scale.
+    /// The scale shows the times from 0 until `height`.
+    fn fit(height: usize, max_height: usize) -> Self {
+        let available = max_height.saturating_sub(TOP_MARGIN + BOTTOM_MARGIN).max(1);
+        let px_per_unit = if height == 0 {
+            MACHINE_HEIGHT_SCALE as f64
+        } else {
+            (available as f64 / height as f64).min(MACHINE_HEIGHT_SCALE as f64)
+        };
+        // steps of 1, 2, and 5 times a power of ten
+        let mut steps = (0..).flat_map(|e| [1, 2, 5].map(|m| m * 10_usize.pow(e)));
+        let tick_step = steps
+            .clone()
+            .find(|&step| step as f64 * px_per_unit >= MIN_TICK_SPACING as f64)
+            .expect("tick steps are unbounded");
+        let label_step = steps
+            .find(|&step| {
+                step.is_multiple_of(tick_step)
+                    && step as f64 * px_per_unit >= MIN_TICK_LABEL_SPACING as f64
+            })
+            .expect("tick steps are unbounded");
+        TimeScale {
+            px_per_unit,
+            tick_step,
+            label_step,
+            from: 0,
+            to: height as i32,
+        }
+    }
+    /// The same scale, showing the times from `from` until `to` only
+    fn window(self, from: i32, to: i32) -> Self {
+        TimeScale { from, to, ..self }
+    }
+    /// Vertical position of `time` in the document, clamped to the times
+    /// that are shown
+    fn y(self, time: i32) -> usize {
+        let shown = time.clamp(self.from, self.to) - self.from;
+        TOP_MARGIN + (f64::from(shown) * self.px_per_unit).round() as usize
+    }
+    /// Number of pixels between `from` and `to`
+    fn length(self, from: i32, to: i32) -> usize {
+        self.y(to).saturating_sub(self.y(from))
+    }
+}
+
+/// A problem that occurred while placing a job on the processors of the rendered schedule
+#[derive(Debug)]
+pub struct RenderIssue {
+    /// Index of the job that could not be placed
+    pub job: usize,
+    /// Start time of the job
+    pub start_time: i32,
+    /// Number of processors the job needs
+    pub allotment: usize,
+    /// Number of processors that were free at the start time of the job
+    pub available: usize,
+}
+impl fmt::Display for RenderIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "job {} needs {} processors at time {} but only {} are free",
+            self.job, self.allotment, self.start_time, self.available
+        )
+    }
+}
+
+/// Renders the schedule to an SVG string. Jobs that cannot be placed on enough
+/// free processors are still drawn in an error style, using overflow lanes to
+/// the right of the regular processors, and reported as `RenderIssue`s.
+pub fn render_schedule_checked(
+    schedule: &Schedule,
+    options: &RenderOptions,
+) -> (String, Vec<RenderIssue>) {
+    // Schedules without jobs or whose jobs all take no time get a time scale
+    // of a few units, so that the chart keeps its axes and proportions.
+    let height = match makespan(schedule) {
+        0 => EMPTY_SCHEDULE_LENGTH,
+        makespan => makespan as usize,
+    };
+    let scale = TimeScale::fit(height, options.max_height.unwrap_or(DEFAULT_MAX_HEIGHT));
+    let (assignment, lane_count, issues) = assign_processors(
+        schedule.processor_count,
+        &schedule.jobs,
+        &options.downtime,
+        &options.predecessors,
+    );
+    let svg = render_window(
+        schedule,
+        options,
+        (&assignment, lane_count),
+        scale,
+        &options.title(),
+    );
+    (svg, issues)
+}
 
-pub fn render_schedule(schedule: Schedule) -> String {
+/// Renders the schedule like `render_schedule_checked`, but splits it into
+/// pages by time ranges if it would be taller than `options.page_height` at
+/// full scale. All pages share the scale and the processors of the jobs, and
+/// jobs that run across the end of a page are drawn on both pages with a
+/// continuation marker. Returns a single page if the schedule fits.
+pub fn render_schedule_pages(
+    schedule: &Schedule,
+    options: &RenderOptions,
+) -> (Vec<Page>, Vec<RenderIssue>) {
+    let makespan = makespan(schedule);
+    // number of time units that fit on a page at full scale
+    let pages = options.page_height.map(|page_height| {
+        let available = page_height.saturating_sub(TOP_MARGIN + BOTTOM_MARGIN);
+        (page_height, (available / MACHINE_HEIGHT_SCALE).max(1))
+    });
+    let Some((page_height, page_length)) =
+        pages.filter(|&(_, page_length)| makespan as usize > page_length)
+    else {
+        let (svg, issues) = render_schedule_checked(schedule, options);
+        let page = Page {
+            from: 0,
+            to: makespan,
+            svg,
+        };
+        return (vec![page], issues);
+    };
+    let scale = TimeScale::fit(page_length, page_height);
+    let (assignment, lane_count, issues) = assign_processors(
+        schedule.processor_count,
+        &schedule.jobs,
+        &options.downtime,
+        &options.predecessors,
+    );
+    let starts = (0..makespan).step_by(page_length).collect::<Vec<_>>();
+    let pages = starts
+        .iter()
+        .enumerate()
+        .map(|(k, &from)| {
+            let to = (from + page_length as i32).min(makespan);
+            let title = format!("{} (page {} of {})", options.title(), k + 1, starts.len());
+            let svg = render_window(
+                schedule,
+                options,
+                (&assignment, lane_count),
+                scale.window(from, to),
+                &title,
+            );
+            Page { from, to, svg }
+        })
+        .collect();
+    (pages, issues)
+}
+
+/// Renders the part of the schedule that `scale` shows, where the jobs were
+/// placed on the processors of `placement` by `assign_processors`, along
+/// with the total number of lanes
+fn render_window(
+    schedule: &Schedule,
+    options: &RenderOptions,
+    placement: (&[Vec<usize>], usize),
+    scale: TimeScale,
+    title: &str,
+) -> String {
+    let theme = options.theme;
     // Create the linear gradient for the background
+    let (top, bottom) = theme.background();
     let gradient = LinearGradient::new()
         .set("id", "background")
         .set("y1", "0")
         .set("y2", "1")
         .set("x1", "0")
         .set("x2", "0")
-        .add(Stop::new().set("stop-color", "#eeeeee").set("offset", "5%"))
-        .add(
-            Stop::new()
-                .set("stop-color", "#b0b0ee")
-                .set("offset", "95%"),
-        );
+        .add(Stop::new().set("stop-color", top).set("offset", "5%"))
+        .add(Stop::new().set("stop-color", bottom).set("offset", "95%"));
 
     let document = (0..schedule.processor_count)
-        .map(create_machine_header)
+        .map(|i| create_machine_header(i, &options.machine_name(i)))
         .fold(
-            Document::new()
-                .set("version", "1.1")
-                .set("xmlns", "http://www.w3.org/2000/svg")
-                .set("xmlns:svg", "http://www.w3.org/2000/svg")
-                .add(gradient)
-                .add(Style::new(
-                    r"
-    text { font-family:monospace; font-size:10px; fill:black; }
-    #title { text-anchor:middle; font-size:25px; }
-    .machine-header { text-anchor:middle; font-size:17px; }
-    .machine-box { stroke-width:1; stroke:black; }
-    .machine-label { text-anchor:middle; dominant-baseline:middle; font-size:15px; }
-    .scale-label { text-anchor:end; dominant-baseline:middle; font-size:10px; }
-    ",
-                ))
+            theme
+                .patterns()
+                .into_iter()
+                .fold(
+                    Document::new()
+                        .set("version", "1.1")
+                        .set("xmlns", "http://www.w3.org/2000/svg")
+                        .set("xmlns:svg", "http://www.w3.org/2000/svg")
+                        .add(gradient)
+                        .add(create_error_hatch())
+                        .add(create_downtime_hatch()),
+                    svg::node::element::SVG::add,
+                )
+                .add(Style::new(theme.css()))
                 // background
                 .add(
                     Rectangle::new()
@@ -63,7 +365,7 @@ pub fn render_schedule(schedule: Schedule) -> String {
                 )
                 // title
                 .add(
-                    Text::new("Schedule")
+                    Text::new(title)
                         .set("id", "title")
                         .set("x", "50%")
                         .set("y", 24),
@@ -71,25 +373,78 @@ pub fn render_schedule(schedule: Schedule) -> String {
             svg::node::element::SVG::add,
         );
 
-    // Create the SVG document
-    let height = schedule
+    let profile = schedule.resource_profile();
+    let document = document.add(create_capacity_envelope(
+        &options.capacity,
+        schedule.processor_count,
+        scale,
+    ));
+    let document = document.add(create_downtime_blocks(
+        options,
+        schedule.processor_count,
+        scale,
+    ));
+    let (assignment, lane_count) = placement;
+    // jobs without processing time are shown on the page on which they start
+    let shown = schedule
         .jobs
         .iter()
-        .map(|job| job.start_time + job.processing_time())
-        .max()
-        .unwrap_or(0) as usize;
-    let body = add_jobs_to_doc(document, schedule.processor_count, schedule.jobs)
-        .add(create_time_scale(height))
-        .set(
-            "width",
-            LEFT_MARGIN + schedule.processor_count * (MACHINE_WIDTH + MACHINE_SPACING)
-                - MACHINE_SPACING
-                + RIGHT_MARGIN,
+        .zip(assignment)
+        .filter(|(job, _)| {
+            job.start_time < scale.to
+                && (job.completion_time() > scale.from || job.start_time >= scale.from)
+        })
+        .map(|(job, processors)| (job, processors.as_slice()))
+        .collect::<Vec<_>>();
+    let document = add_jobs_to_doc(document, schedule.processor_count, &shown, options, scale);
+    let utilization_x = LEFT_MARGIN + lane_count * (MACHINE_WIDTH + MACHINE_SPACING);
+    let chains_x = utilization_x + UTILIZATION_WIDTH + MACHINE_SPACING;
+    let document = if makespan(schedule) == 0 {
+        let note = if schedule.jobs.is_empty() {
+            "no jobs scheduled"
+        } else {
+            "all jobs take no time"
+        };
+        document.add(
+            Text::new(note)
+                .set("x", (LEFT_MARGIN + utilization_x) / 2)
+                .set("y", scale.y(scale.to / 2))
+                .set("class", "empty-note"),
         )
-        .set(
-            "height",
-            TOP_MARGIN + height * MACHINE_HEIGHT_SCALE + BOTTOM_MARGIN,
+    } else {
+        document
+    };
+    let document = (schedule.processor_count..lane_count)
+        .map(|lane| create_overflow_header(lane, schedule.processor_count))
+        .fold(document, svg::node::element::SVG::add)
+        .add(create_time_scale(scale))
+        .add(create_utilization_bars(
+            &profile,
+            schedule.processor_count,
+            utilization_x,
+            scale,
+        ));
+    let (document, right_x) = if options.chains.is_empty() {
+        (document, utilization_x + UTILIZATION_WIDTH)
+    } else {
+        (
+            document.add(create_chain_bars(&options.chains, chains_x, scale)),
+            chains_x + chain_bars_width(options.chains.len()),
         )
+    };
+    let document = match options.animate {
+        Some(speed) => document.add(animate::playhead(
+            SCALE_MARGIN,
+            right_x - SCALE_MARGIN,
+            makespan(schedule),
+            scale,
+            speed,
+        )),
+        None => document,
+    };
+    let body = document
+        .set("width", right_x + RIGHT_MARGIN)
+        .set("height", scale.y(scale.to) + BOTTOM_MARGIN)
         .to_string();
 
     format!(
@@ -98,76 +453,657 @@ pub fn render_schedule(schedule: Schedule) -> String {
     )
 }
 
-fn add_jobs_to_doc(document: SVG, processor_count: usize, mut jobs: Vec<ScheduledJob>) -> SVG {
-    jobs.sort_by_key(|job| job.start_time);
-    jobs.into_iter()
-        .fold(
-            (document, vec![0; processor_count]),
-            |(doc, mut used_until), job| {
-                let y = TOP_MARGIN + job.start_time as usize * MACHINE_HEIGHT_SCALE;
-                let processors: Vec<usize> = used_until
-                    .iter()
-                    .enumerate()
-                    .filter(|(_, used)| **used <= job.start_time)
-                    .take(job.allotment)
-                    .map(|(proc, _)| proc)
-                    .collect();
-                assert!(
-                    job.allotment <= processors.len(),
-                    "insufficient number of processors available"
-                );
-                let end = job.start_time + job.processing_time();
-                for proc in &processors {
-                    used_until[*proc] = end;
-                }
-                (add_job_to_doc(doc, processors, y, &job), used_until)
-            },
+/// Renders an HTML page that links the pages of a schedule, which are
+/// written to the files `names`
+pub fn render_page_index(title: &str, pages: &[Page], names: &[String]) -> String {
+    let title = escape_html(title);
+    let items = pages
+        .iter()
+        .zip(names)
+        .map(|(page, name)| {
+            format!(
+                r#"    <li><a href="{}">{} to {} s</a></li>"#,
+                escape_html(name),
+                page.from,
+                page.to
+            )
+        })
+        .join("\n");
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+  <meta charset="utf-8">
+  <title>{title}</title>
+</head>
+<body>
+  <h1>{title}</h1>
+  <ol>
+{items}
+  </ol>
+</body>
+</html>
+"#
+    )
+}
+
+/// Escapes the characters of `text` that have a meaning in HTML, so that it
+/// can be placed in the text and the attributes of elements
+pub fn escape_html(text: &str) -> String {
+    text.chars().fold(String::new(), |mut escaped, c| {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+        escaped
+    })
+}
+
+/// Latest completion time of all jobs of `schedule`, at least 0
+fn makespan(schedule: &Schedule) -> i32 {
+    schedule
+        .jobs
+        .iter()
+        .map(ScheduledJob::completion_time)
+        .max()
+        .unwrap_or(0)
+        .max(0)
+}
+
+/// Adds the `jobs` on their processors to the document, followed by the
+/// arrows of the broken soft constraints between them
+fn add_jobs_to_doc(
+    document: SVG,
+    processor_count: usize,
+    jobs: &[Shown],
+    options: &RenderOptions,
+    scale: TimeScale,
+) -> SVG {
+    // horizontal center, start, and completion of every job by index
+    let anchors = jobs
+        .iter()
+        .map(|&(job, processors)| {
+            let (first, last) = processors
+                .iter()
+                .minmax()
+                .into_option()
+                .map_or((0, 0), |(&first, &last)| (first, last));
+            let x = LEFT_MARGIN + (first + last) * (MACHINE_WIDTH + MACHINE_SPACING) / 2;
+            (
+                job.job.index,
+                (
+                    x + MACHINE_WIDTH / 2,
+                    scale.y(job.start_time),
+                    scale.y(job.completion_time()),
+                ),
+            )
+        })
+        .collect::<HashMap<_, _>>();
+    let (runs, singles) = aggregate(jobs, processor_count, options, scale);
+    let document = runs.iter().fold(document, |doc, run| {
+        add_run_to_doc(doc, run, options, scale)
+    });
+    let document = singles
+        .iter()
+        .sorted_by_key(|(job, _)| (job.start_time, options.depth(job.job.index), job.job.index))
+        .fold(document, |doc, &(job, processors)| {
+            let error = processors.iter().any(|&proc| proc >= processor_count);
+            add_job_to_doc(doc, processors.to_vec(), job, error, options, scale)
+        });
+    options
+        .soft_violations
+        .iter()
+        .filter_map(|&(left, right)| {
+            let &(from_x, _, from_y) = anchors.get(&left)?;
+            let &(to_x, to_y, _) = anchors.get(&right)?;
+            Some(create_soft_violation_arrow(
+                left,
+                right,
+                (from_x, from_y),
+                (to_x, to_y),
+            ))
+        })
+        .fold(document, SVG::add)
+}
+
+/// Splits the `jobs` into runs of at least two jobs that are drawn shorter
+/// than `options.aggregate_below_px` and run back to back on the same
+/// processors, and the remaining jobs. Runs never span idle time, jobs with
+/// different allotments, jobs without processing time, or overflow lanes.
+fn aggregate<'a>(
+    jobs: &[Shown<'a>],
+    processor_count: usize,
+    options: &RenderOptions,
+    scale: TimeScale,
+) -> (Vec<Vec<Shown<'a>>>, Vec<Shown<'a>>) {
+    let Some(threshold) = options.aggregate_below_px else {
+        return (vec![], jobs.to_vec());
+    };
+    let small = |job: &ScheduledJob, processors: &[usize]| {
+        job.processing_time() > 0
+            && scale.length(job.start_time, job.completion_time()) < threshold
+            && processors.iter().all(|&proc| proc < processor_count)
+    };
+    let mut runs: Vec<Vec<Shown>> = vec![];
+    for &(job, processors) in jobs
+        .iter()
+        .sorted_by_key(|(job, processors)| (*processors, job.start_time, job.job.index))
+    {
+        let extends = |run: &[Shown]| {
+            run.last().is_some_and(|&(last, last_processors)| {
+                last_processors == processors
+                    && last.allotment == job.allotment
+                    && last.completion_time() == job.start_time
+                    && small(last, last_processors)
+                    && small(job, processors)
+            })
+        };
+        match runs.last_mut() {
+            Some(run) if extends(run) => run.push((job, processors)),
+            _ => runs.push(vec![(job, processors)]),
+        }
+    }
+    let (runs, singles): (Vec<_>, Vec<_>) = runs.into_iter().partition(|run| run.len() > 1);
+    (runs, singles.into_iter().flatten().collect())
+}
+
+/// Adds a run of jobs as one block labeled with the number of jobs on each
+/// of its processors, whose tooltip lists the jobs
+fn add_run_to_doc(document: SVG, run: &[Shown], options: &RenderOptions, scale: TimeScale) -> SVG {
+    let (first, processors) = run[0];
+    let (last, _) = run[run.len() - 1];
+    let processing_time = run
+        .iter()
+        .map(|(job, _)| job.processing_time())
+        .sum::<i32>();
+    let label = format!("{} jobs", run.len());
+    let tooltip = format!(
+        "{label}\n\nallotment: {} processors\nprocessing time: {processing_time} s\n\n{}",
+        first.allotment,
+        run.iter()
+            .map(|(job, _)| format!(
+                "job {}: {} to {} s",
+                job.job.index,
+                job.start_time,
+                job.completion_time()
+            ))
+            .join("\n")
+    );
+    let w = MACHINE_WIDTH;
+    let y = scale.y(first.start_time);
+    let h = scale
+        .length(first.start_time, last.completion_time())
+        .max(1);
+    let label_inside = h >= MIN_LABEL_HEIGHT;
+    let hidden = options
+        .animate
+        .and_then(|speed| animate::hide_until(first.start_time, speed));
+    let growth = options
+        .animate
+        .and_then(|speed| animate::grow(first.start_time, last.completion_time(), h, speed));
+    let document = create_job_links(processors, y + h / 2)
+        .into_iter()
+        .map(|link| hidden.iter().cloned().fold(link, Path::add))
+        .fold(document, SVG::add);
+    let document = match processors.iter().max() {
+        Some(&rightmost) if !label_inside => document.add(hidden.iter().cloned().fold(
+            create_outside_label(
+                &label,
+                LEFT_MARGIN + rightmost * (MACHINE_WIDTH + MACHINE_SPACING) + w,
+                y + h / 2,
+            ),
+            Group::add,
+        )),
+        _ => document,
+    };
+    processors.iter().fold(document, |doc, &processor| {
+        let x = LEFT_MARGIN + processor * (MACHINE_WIDTH + MACHINE_SPACING);
+        let group = Group::new().add(
+            growth.iter().cloned().fold(
+                Rectangle::new()
+                    .set("x", x)
+                    .set("y", y)
+                    .set("width", w)
+                    .set("height", h)
+                    .set("class", "machine-box aggregate"),
+                Rectangle::add,
+            ),
+        );
+        let group = hidden.iter().cloned().fold(group, Group::add);
+        let group = if label_inside {
+            group.add(
+                Text::new(label.clone())
+                    .set("x", x + w / 2)
+                    .set("y", y + h / 2)
+                    .set("class", "machine-label"),
+            )
+        } else {
+            group
+        };
+        doc.add(group.add(Title::new(tooltip.clone())))
+    })
+}
+
+/// Looks up the predecessors of every job of `instance` by job index, see
+/// `RenderOptions::predecessors`
+pub fn predecessors(instance: &Instance) -> HashMap<usize, Vec<usize>> {
+    instance
+        .jobs
+        .iter()
+        .enumerate()
+        .map(|(j, job)| {
+            let predecessors = instance
+                .predecessor_indices(j)
+                .iter()
+                .map(|&i| instance.jobs[i].index)
+                .collect();
+            (job.index, predecessors)
+        })
+        .collect()
+}
+
+/// Assigns processors to the jobs in the order of their start times, then
+/// of their allotments from large to small, and then of their ids, so that
+/// the same schedule gets the same processors regardless of the order of its
+/// jobs. If the solver placed every job on explicit processors, these are
+/// used as they are. Otherwise, jobs that were placed on explicit processors
+/// keep them if they are free, and all other jobs prefer the free processors
+/// of the predecessor in `predecessors` that runs the longest, followed by
+/// the free processors with the lowest indices. Jobs that cannot be placed on
+/// enough free processors are placed on overflow lanes, numbered from
+/// `processor_count` upwards, and reported as issues. Jobs without
+/// processing time occupy no processors, so they may share them with running
+/// jobs. No other job is placed on a processor while it is down according to
+/// `downtime`. Returns the processors of each job in the order of `jobs`, the
+/// total number of lanes, and the issues.
+pub fn assign_processors(
+    processor_count: usize,
+    jobs: &[ScheduledJob],
+    downtime: &[Downtime],
+    predecessors: &HashMap<usize, Vec<usize>>,
+) -> (Vec<Vec<usize>>, usize, Vec<RenderIssue>) {
+    if jobs.iter().all(|job| job.processors.len() == job.allotment) {
+        let lane_count = jobs
+            .iter()
+            .flat_map(|job| job.processors.iter().map(|&proc| proc + 1))
+            .fold(processor_count, usize::max);
+        let assignment = jobs.iter().map(|job| job.processors.clone()).collect();
+        return (assignment, lane_count, vec![]);
+    }
+    let mut order = (0..jobs.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&i| {
+        let job = &jobs[i];
+        (job.start_time, Reverse(job.allotment), job.job.index)
+    });
+    let position = jobs
+        .iter()
+        .enumerate()
+        .map(|(i, job)| (job.job.index, i))
+        .collect::<HashMap<_, _>>();
+    let mut assignment = vec![vec![]; jobs.len()];
+    let mut used_until = vec![0; processor_count];
+    let mut issues = vec![];
+    for i in order {
+        let job = &jobs[i];
+        let instantaneous = job.processing_time() == 0;
+        let up = |proc: usize| {
+            !downtime.iter().any(|down| {
+                down.processor == proc
+                    && down.from < job.completion_time()
+                    && job.start_time < down.to
+            })
+        };
+        let free = |proc: usize, used: i32| instantaneous || (used <= job.start_time && up(proc));
+        let explicit = job.processors.len() == job.allotment
+            && job
+                .processors
+                .iter()
+                .all(|&proc| proc < processor_count && free(proc, used_until[proc]));
+        let mut processors: Vec<usize> = if explicit {
+            job.processors.clone()
+        } else {
+            // the predecessor that runs the longest, which is placed already
+            let preferred = predecessors
+                .get(&job.job.index)
+                .into_iter()
+                .flatten()
+                .filter_map(|index| position.get(index))
+                .max_by_key(|&&p| {
+                    let predecessor = &jobs[p];
+                    (
+                        predecessor.processing_time(),
+                        predecessor.completion_time(),
+                        Reverse(predecessor.job.index),
+                    )
+                })
+                .map_or(&[][..], |&p| assignment[p].as_slice());
+            let mut processors = preferred
+                .iter()
+                .copied()
+                .filter(|&proc| proc < processor_count && free(proc, used_until[proc]))
+                .take(job.allotment)
+                .collect::<Vec<_>>();
+            let rest = (0..processor_count)
+                .filter(|&proc| !processors.contains(&proc) && free(proc, used_until[proc]))
+                .take(job.allotment - processors.len())
+                .collect::<Vec<_>>();
+            processors.extend(rest);
+            processors.sort_unstable();
+            processors
+        };
+        if processors.len() < job.allotment {
+            issues.push(RenderIssue {
+                job: job.job.index,
+                start_time: job.start_time,
+                allotment: job.allotment,
+                available: processors.len(),
+            });
+            // fill up with free overflow lanes, and open new ones if needed
+            let missing = job.allotment - processors.len();
+            let overflow: Vec<usize> = (processor_count..used_until.len())
+                .filter(|&lane| used_until[lane] <= job.start_time)
+                .take(missing)
+                .collect();
+            let opened = missing - overflow.len();
+            processors.extend(overflow);
+            processors.extend(used_until.len()..used_until.len() + opened);
+            used_until.resize(used_until.len() + opened, 0);
+        }
+        if !instantaneous {
+            let end = job.start_time + job.processing_time();
+            for proc in &processors {
+                used_until[*proc] = end;
+            }
+        }
+        assignment[i] = processors;
+    }
+    (assignment, used_until.len(), issues)
+}
+
+/// Creates the outline above the box of `job` at `x` from the time `ready`
+/// at which the job became ready until it started, whose tooltip tells how
+/// long it waited
+fn create_waiting_ghost(
+    x: usize,
+    ready: i32,
+    job: &ScheduledJob,
+    options: &RenderOptions,
+    scale: TimeScale,
+) -> Group {
+    let y = scale.y(ready);
+    let group = Group::new()
+        .add(
+            Rectangle::new()
+                .set("x", x)
+                .set("y", y)
+                .set("width", MACHINE_WIDTH)
+                .set("height", scale.y(job.start_time) - y)
+                .set("class", "waiting"),
         )
-        .0
+        .add(Title::new(format!(
+            "Job {} waited {} s after it became ready at {ready} s",
+            job.job.index,
+            job.start_time - ready
+        )));
+    options
+        .animate
+        .and_then(|speed| animate::hide_until(ready, speed))
+        .into_iter()
+        .fold(group, Group::add)
 }
 
-fn add_job_to_doc(document: SVG, processors: Vec<usize>, y: usize, job: &ScheduledJob) -> SVG {
-    assert_eq!(
+/// Adds the boxes of a job on all of its processors. Boxes that are too short
+/// for their label get a single label to the right of the rightmost box,
+/// connected to it by a leader line. Jobs without processing time get a thin
+/// marker line instead of a box. Boxes of jobs that run across the first or
+/// the last time that `scale` shows get a continuation marker at that edge.
+fn add_job_to_doc(
+    document: SVG,
+    processors: Vec<usize>,
+    job: &ScheduledJob,
+    error: bool,
+    options: &RenderOptions,
+    scale: TimeScale,
+) -> SVG {
+    let fill = options.theme.fill_class(match options.color_by {
+        ColorBy::Job => job.job.index,
+        ColorBy::Depth => options.depth(job.job.index),
+    });
+    let label = options
+        .labels
+        .get(&job.job.index)
+        .cloned()
+        .unwrap_or_else(|| job.job.index.to_string());
+    debug_assert_eq!(
         processors.len(),
         job.allotment,
         "mismatched number of processors for allotment"
     );
     let processing_time = job.processing_time() as usize;
     let w = MACHINE_WIDTH;
-    let h = MACHINE_HEIGHT_SCALE * processing_time;
+    let y = scale.y(job.start_time);
+    let h = scale.length(job.start_time, job.completion_time()).max(1);
+    let label_inside = h >= MIN_LABEL_HEIGHT;
+    let continued = processing_time > 0 && job.start_time < scale.from;
+    let continues = processing_time > 0 && job.completion_time() > scale.to;
+    let hidden = options
+        .animate
+        .and_then(|speed| animate::hide_until(job.start_time, speed));
+    let growth = options
+        .animate
+        .and_then(|speed| animate::grow(job.start_time, job.completion_time(), h, speed));
+    let document = create_job_links(&processors, y + h / 2)
+        .into_iter()
+        .map(|link| hidden.iter().cloned().fold(link, Path::add))
+        .fold(document, SVG::add);
+    let document = match processors.iter().max() {
+        Some(&rightmost) if !label_inside => document.add(hidden.iter().cloned().fold(
+            create_outside_label(
+                &label,
+                LEFT_MARGIN + rightmost * (MACHINE_WIDTH + MACHINE_SPACING) + w,
+                y + h / 2,
+            ),
+            Group::add,
+        )),
+        _ => document,
+    };
+    let ready = options
+        .show_waiting
+        .get(&job.job.index)
+        .copied()
+        .filter(|&ready| ready < job.start_time);
     processors.into_iter().fold(document, |doc, processor| {
         let x = LEFT_MARGIN + processor * (MACHINE_WIDTH + MACHINE_SPACING);
-        let machine_box = Rectangle::new()
-            .set("x", x)
-            .set("y", y)
-            .set("width", w)
-            .set("height", h)
-            .set("fill", "#0000f8")
-            .set("class", "machine-box");
-
-        let machine_label = Text::new(job.job.index.to_string())
+        let doc = match ready {
+            Some(ready) => doc.add(create_waiting_ghost(x, ready, job, options, scale)),
+            None => doc,
+        };
+        let class = |name: &str| {
+            if error {
+                format!("{name} {fill} error")
+            } else {
+                format!("{name} {fill}")
+            }
+        };
+        let group = if processing_time == 0 {
+            Group::new().add(
+                Path::new()
+                    .set("class", class("job-marker"))
+                    .set("d", Data::new().move_to((x, y)).horizontal_line_by(w)),
+            )
+        } else {
+            Group::new().add(
+                growth.iter().cloned().fold(
+                    Rectangle::new()
+                        .set("x", x)
+                        .set("y", y)
+                        .set("width", w)
+                        .set("height", h)
+                        .set("class", class("machine-box")),
+                    Rectangle::add,
+                ),
+            )
+        };
+        let group = hidden.iter().cloned().fold(group, Group::add);
+        let group = [(continued, y), (continues, y + h)]
+            .into_iter()
+            .filter(|&(marked, _)| marked)
+            .fold(group, |group, (_, edge)| {
+                group.add(
+                    Path::new()
+                        .set("class", "continuation")
+                        .set("d", Data::new().move_to((x, edge)).horizontal_line_by(w)),
+                )
+            });
+
+        let machine_label = Text::new(label.clone())
             .set("x", x + w / 2) // Centered on the rectangle
             .set("y", y + h / 2)
-            .set("class", "machine-label");
+            .set("class", format!("machine-label {fill}"));
 
-        let tooltip = Title::new(format!(
-            "Job {}\n\nallotment: {} processors\nprocessing time: {} s",
-            job.job.index, job.allotment, processing_time
-        ));
+        let mut tooltip = format!(
+            "Job {label}\n\nallotment: {} processors\nprocessing time: {} s\nmachine: {}",
+            job.allotment,
+            processing_time,
+            options.machine_name(processor)
+        );
+        if let Some(allowed) = &job.job.affinity {
+            tooltip.push_str("\naffinity: processors ");
+            tooltip.push_str(&format_processors(allowed));
+        }
+        if let Some(note) = options.notes.get(&job.job.index) {
+            tooltip.push('\n');
+            tooltip.push_str(note);
+        }
+        // the text of the title is escaped when the document is written, so
+        // notes may contain any characters
+        if let Some(note) = &job.job.note {
+            tooltip.push_str("\nnote: ");
+            tooltip.push_str(note);
+        }
+        if continued {
+            tooltip.push_str("\ncontinued from the previous page");
+        }
+        if continues {
+            tooltip.push_str("\ncontinues on the next page");
+        }
+        let tooltip = Title::new(tooltip);
 
-        let group = Group::new()
-            .add(machine_box)
-            .add(machine_label)
-            .add(tooltip);
+        let group = if label_inside {
+            group.add(machine_label)
+        } else {
+            group
+        };
 
-        doc.add(group)
+        doc.add(group.add(tooltip))
     })
 }
 
-fn create_machine_header(i: usize) -> Text {
+/// Creates the label of a job whose boxes are too short for it, placed to the
+/// right of the box that ends at `x` and connected to its middle at `y` by a
+/// leader line
+fn create_outside_label(label: &str, x: usize, y: usize) -> Group {
+    let offset = MACHINE_SPACING / 2;
+    Group::new()
+        .add(
+            Path::new().set("class", "leader-line").set(
+                "d",
+                Data::new()
+                    .move_to((x, y))
+                    .line_by((offset as i32, -(offset as i32))),
+            ),
+        )
+        .add(
+            Text::new(label)
+                .set("x", x + offset + 2)
+                .set("y", y - offset)
+                .set("class", "outside-label"),
+        )
+}
+
+/// Creates a dashed arrow from the completion of the `left` job at `from` to
+/// the start of the `right` job at `to`, which marks a broken soft constraint
+fn create_soft_violation_arrow(
+    left: usize,
+    right: usize,
+    from: (usize, usize),
+    to: (usize, usize),
+) -> Group {
+    let (from_x, from_y) = (from.0 as f32, from.1 as f32);
+    let (to_x, to_y) = (to.0 as f32, to.1 as f32);
+    let length = (to_x - from_x).hypot(to_y - from_y);
+    // an arrow without length points downwards like time
+    let (dx, dy) = if length > 0.0 {
+        ((to_x - from_x) / length, (to_y - from_y) / length)
+    } else {
+        (0.0, 1.0)
+    };
+    let (base_x, base_y) = (to_x - dx * ARROW_HEAD_LENGTH, to_y - dy * ARROW_HEAD_LENGTH);
+    let (wing_x, wing_y) = (-dy * ARROW_HEAD_WIDTH / 2.0, dx * ARROW_HEAD_WIDTH / 2.0);
+    Group::new()
+        .add(Title::new(format!(
+            "job {right} starts too soon after job {left}, which breaks a soft constraint"
+        )))
+        .add(
+            Path::new().set("class", "soft-violation").set(
+                "d",
+                Data::new()
+                    .move_to((from_x, from_y))
+                    .line_to((base_x, base_y)),
+            ),
+        )
+        .add(
+            Path::new().set("class", "soft-violation-head").set(
+                "d",
+                Data::new()
+                    .move_to((to_x, to_y))
+                    .line_to((base_x + wing_x, base_y + wing_y))
+                    .line_to((base_x - wing_x, base_y - wing_y))
+                    .close(),
+            ),
+        )
+}
+
+/// Connects the rectangles of a job that runs on processors which are not
+/// next to each other by dashed lines at height `y`
+fn create_job_links(processors: &[usize], y: usize) -> Vec<Path> {
+    processors
+        .iter()
+        .copied()
+        .sorted_unstable()
+        .tuple_windows()
+        .filter(|&(left, right)| right > left + 1)
+        .map(|(left, right)| {
+            let from = LEFT_MARGIN + left * (MACHINE_WIDTH + MACHINE_SPACING) + MACHINE_WIDTH;
+            let to = LEFT_MARGIN + right * (MACHINE_WIDTH + MACHINE_SPACING);
+            Path::new()
+                .set("class", "job-link")
+                .set("d", Data::new().move_to((from, y)).horizontal_line_to(to))
+        })
+        .collect()
+}
+
+/// Creates the header of the `i`-th machine with its `name`. Names that are
+/// too long to fit above the machine are truncated and shown in full in a
+/// tooltip.
+fn create_machine_header(i: usize, name: &str) -> Text {
     let x = LEFT_MARGIN + i * (MACHINE_WIDTH + MACHINE_SPACING) + (MACHINE_WIDTH / 2);
     let y = TOP_HEADER_MARGIN;
-    Text::new(format!("Processor {i}"))
+    let header = if name.chars().count() > MACHINE_NAME_LENGTH {
+        let truncated = name
+            .chars()
+            .take(MACHINE_NAME_LENGTH - 1)
+            .chain(['…'])
+            .collect::<String>();
+        Text::new(truncated).add(Title::new(name))
+    } else {
+        Text::new(name)
+    };
+    header
         .set("x", x)
         .set("y", y)
         .set("width", "100%")
@@ -175,23 +1111,250 @@ fn create_machine_header(i: usize) -> Text {
         .set("class", "machine-header")
 }
 
-fn create_time_scale(height_seconds: usize) -> Group {
-    (0..=height_seconds)
+fn create_overflow_header(i: usize, processor_count: usize) -> Text {
+    let x = LEFT_MARGIN + i * (MACHINE_WIDTH + MACHINE_SPACING) + (MACHINE_WIDTH / 2);
+    let y = TOP_HEADER_MARGIN;
+    Text::new(format!("Overflow {}", i - processor_count))
+        .set("x", x)
+        .set("y", y)
+        .set("class", "machine-header error")
+}
+
+/// Shades the processors beyond the capacity behind the machines, from the
+/// first to the last processor that may not be busy. Where windows overlap,
+/// the smallest capacity is shaded. Windows are cut off at the times that
+/// `scale` shows.
+fn create_capacity_envelope(
+    capacity: &[CapacityWindow],
+    processor_count: usize,
+    scale: TimeScale,
+) -> Group {
+    let bounds = capacity
+        .iter()
+        .flat_map(|window| [window.from, window.to])
+        .map(|time| time.clamp(scale.from, scale.to))
+        .sorted_unstable()
+        .dedup()
+        .collect::<Vec<_>>();
+    bounds
+        .iter()
+        .tuple_windows()
+        .filter_map(|(&from, &to)| {
+            let allowed = capacity
+                .iter()
+                .filter(|window| window.from <= from && from < window.to)
+                .map(|window| window.capacity)
+                .fold(processor_count, usize::min);
+            (allowed < processor_count).then_some((from, to, allowed))
+        })
+        .map(|(from, to, allowed)| {
+            let x = LEFT_MARGIN + allowed * (MACHINE_WIDTH + MACHINE_SPACING);
+            let width =
+                (processor_count - allowed) * (MACHINE_WIDTH + MACHINE_SPACING) - MACHINE_SPACING;
+            Group::new()
+                .add(
+                    Rectangle::new()
+                        .set("x", x)
+                        .set("y", scale.y(from))
+                        .set("width", width)
+                        .set("height", scale.length(from, to))
+                        .set("class", "capacity-window"),
+                )
+                .add(Title::new(format!(
+                    "{from} to {to} s\n\ncapacity: {allowed} of {processor_count} processors"
+                )))
+        })
+        .fold(Group::new(), Group::add)
+}
+
+/// Draws a hatched block on every processor while it is down, see
+/// `RenderOptions::downtime`, cut off at the times that `scale` shows
+fn create_downtime_blocks(
+    options: &RenderOptions,
+    processor_count: usize,
+    scale: TimeScale,
+) -> Group {
+    options
+        .downtime
+        .iter()
+        .filter(|down| {
+            down.processor < processor_count && down.from < scale.to && scale.from < down.to
+        })
+        .map(|down| {
+            Group::new()
+                .add(
+                    Rectangle::new()
+                        .set(
+                            "x",
+                            LEFT_MARGIN + down.processor * (MACHINE_WIDTH + MACHINE_SPACING),
+                        )
+                        .set("y", scale.y(down.from))
+                        .set("width", MACHINE_WIDTH)
+                        .set("height", scale.length(down.from, down.to))
+                        .set("class", "downtime"),
+                )
+                .add(Title::new(format!(
+                    "{} to {} s\n\n{} is down",
+                    down.from,
+                    down.to,
+                    options.machine_name(down.processor)
+                )))
+        })
+        .fold(Group::new(), Group::add)
+}
+
+/// Draws the fraction of busy processors over time as bars next to the machines
+fn create_utilization_bars(
+    profile: &[ProfilePoint],
+    processor_count: usize,
+    x: usize,
+    scale: TimeScale,
+) -> Group {
+    profile
+        .iter()
+        .zip(profile.iter().skip(1))
+        .filter(|(point, next)| {
+            point.busy_processors > 0 && point.time < scale.to && scale.from < next.time
+        })
+        .map(|(point, next)| {
+            let width = UTILIZATION_WIDTH * point.busy_processors / processor_count.max(1);
+            Group::new()
+                .add(
+                    Rectangle::new()
+                        .set("x", x)
+                        .set("y", scale.y(point.time))
+                        .set("width", width.min(UTILIZATION_WIDTH))
+                        .set("height", scale.length(point.time, next.time))
+                        .set("class", "utilization-bar"),
+                )
+                .add(Title::new(format!(
+                    "{} to {} s\n\nbusy: {} of {} processors\nactive jobs: {}",
+                    point.time,
+                    next.time,
+                    point.busy_processors,
+                    processor_count,
+                    point.active_jobs
+                )))
+        })
+        .fold(
+            Group::new().add(
+                Text::new("Load")
+                    .set("x", x + UTILIZATION_WIDTH / 2)
+                    .set("y", TOP_HEADER_MARGIN)
+                    .set("class", "machine-header"),
+            ),
+            svg::node::element::Group::add,
+        )
+}
+
+/// Width of the bars of `count` chains, including the room for their header
+fn chain_bars_width(count: usize) -> usize {
+    (count * (CHAIN_BAR_WIDTH + CHAIN_BAR_SPACING)).max(CHAIN_HEADER_WIDTH)
+}
+
+/// Creates one bar per chain that reaches from time zero to the completion
+/// time of the chain, highlighting the chains without slack
+fn create_chain_bars(chains: &[ChainBreakdown], x: usize, scale: TimeScale) -> Group {
+    chains
+        .iter()
+        .enumerate()
+        .map(|(i, chain)| {
+            let class = if chain.slack == 0 {
+                "chain-bar critical"
+            } else {
+                "chain-bar"
+            };
+            Group::new()
+                .add(
+                    Rectangle::new()
+                        .set("x", x + i * (CHAIN_BAR_WIDTH + CHAIN_BAR_SPACING))
+                        .set("y", scale.y(0))
+                        .set("width", CHAIN_BAR_WIDTH)
+                        .set("height", scale.length(0, chain.completion))
+                        .set("class", class),
+                )
+                .add(Title::new(format!(
+                    "chain {}\n\njobs: {}\nprocessing time: {} s\ncompletion: {} s\nslack: {} s",
+                    chain.chain,
+                    chain.jobs.iter().join(", "),
+                    chain.processing_time,
+                    chain.completion,
+                    chain.slack
+                )))
+        })
+        .fold(
+            Group::new().add(
+                Text::new("Chains")
+                    .set("x", x + chain_bars_width(chains.len()) / 2)
+                    .set("y", TOP_HEADER_MARGIN)
+                    .set("class", "machine-header"),
+            ),
+            svg::node::element::Group::add,
+        )
+}
+
+fn create_error_hatch() -> Pattern {
+    Pattern::new()
+        .set("id", "error-hatch")
+        .set("width", 8)
+        .set("height", 8)
+        .set("patternUnits", "userSpaceOnUse")
+        .set("patternTransform", "rotate(45)")
+        .add(
+            Rectangle::new()
+                .set("width", 8)
+                .set("height", 8)
+                .set("fill", "#ffd0d0"),
+        )
+        .add(
+            Rectangle::new()
+                .set("width", 3)
+                .set("height", 8)
+                .set("fill", "#d00000"),
+        )
+}
+
+/// Creates the gray hatch pattern of processors that are down
+fn create_downtime_hatch() -> Pattern {
+    Pattern::new()
+        .set("id", "downtime-hatch")
+        .set("width", 8)
+        .set("height", 8)
+        .set("patternUnits", "userSpaceOnUse")
+        .set("patternTransform", "rotate(45)")
+        .add(
+            Rectangle::new()
+                .set("width", 8)
+                .set("height", 8)
+                .set("fill", "#e0e0e0"),
+        )
+        .add(
+            Rectangle::new()
+                .set("width", 3)
+                .set("height", 8)
+                .set("fill", "#909090"),
+        )
+}
+
+/// Creates the time axis with ticks every `tick_step` and labels every
+/// `label_step` of the scale, plus labeled ticks at the first and the last
+/// time that the scale shows
+fn create_time_scale(scale: TimeScale) -> Group {
+    let (from, to) = (scale.from as usize, scale.to as usize);
+    (from.next_multiple_of(scale.tick_step)..=to)
+        .step_by(scale.tick_step)
+        .chain((!from.is_multiple_of(scale.tick_step)).then_some(from))
+        .chain((!to.is_multiple_of(scale.tick_step)).then_some(to))
         .map(|t| {
-            let scaled_t = t * MACHINE_HEIGHT_SCALE;
-            let is_big = scaled_t % (5 * MACHINE_HEIGHT_SCALE) == 0;
+            let y = scale.y(t as i32);
+            let is_big = t.is_multiple_of(scale.label_step);
             let width = if is_big { 10 } else { 5 };
-            let line = Group::new().add(create_line(
-                SCALE_MARGIN - width,
-                TOP_MARGIN + scaled_t,
-                width,
-                0,
-            ));
-            if is_big || t == height_seconds {
+            let line = Group::new().add(create_line(SCALE_MARGIN - width, y, width, 0));
+            if is_big || t == from || t == to {
                 line.add(
                     Text::new(t.to_string())
                         .set("x", SCALE_MARGIN - 15)
-                        .set("y", TOP_MARGIN + scaled_t)
+                        .set("y", y)
                         .set("class", "scale-label"),
                 )
             } else {
@@ -203,7 +1366,7 @@ fn create_time_scale(height_seconds: usize) -> Group {
                 SCALE_MARGIN,
                 TOP_MARGIN,
                 0,
-                height_seconds * MACHINE_HEIGHT_SCALE,
+                scale.length(scale.from, scale.to),
             )),
             svg::node::element::Group::add,
         )
@@ -214,5 +1377,392 @@ fn create_line(x: usize, y: usize, w: usize, h: usize) -> Path {
         .set("fill", "none")
         .set("stroke", "black")
         .set("stroke-width", 2)
+        .set("class", "scale-line")
         .set("d", Data::new().move_to((x, y)).line_by((w, h)))
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use clap::ValueEnum;
+    use itertools::Itertools;
+
+    use super::{
+        assign_processors, predecessors, render_page_index, render_schedule_checked,
+        render_schedule_pages, RenderIssue, RenderOptions, Theme, BOTTOM_MARGIN,
+        EMPTY_SCHEDULE_LENGTH, LEFT_MARGIN, MACHINE_HEIGHT_SCALE, MACHINE_SPACING, MACHINE_WIDTH,
+        RIGHT_MARGIN, TOP_MARGIN, UTILIZATION_WIDTH,
+    };
+    use crate::{algo::Instance, builder::InstanceBuilder, Schedule, ScheduledJob};
+
+    /// Two jobs that run at the same time on a single processor, so that the
+    /// second one has to be drawn on an overflow lane
+    fn overlapping() -> Schedule {
+        let instance = InstanceBuilder::new()
+            .processors(1)
+            .job(1)
+            .times([3])
+            .job(2)
+            .times([2])
+            .build()
+            .expect("valid instance");
+        let job = |index| ScheduledJob {
+            job: instance.shared_job(index),
+            allotment: 1,
+            start_time: 0,
+            processors: vec![],
+        };
+        Schedule {
+            processor_count: 1,
+            jobs: vec![job(0), job(1)],
+        }
+    }
+
+    #[test]
+    fn infeasible_schedule_is_drawn_on_overflow_lane() {
+        for &theme in Theme::value_variants() {
+            let options = RenderOptions {
+                theme,
+                ..RenderOptions::default()
+            };
+            let (svg, issues) = render_schedule_checked(&overlapping(), &options);
+            assert_eq!(issues.len(), 1, "{theme:?}");
+            let RenderIssue {
+                job,
+                start_time,
+                allotment,
+                available,
+            } = issues[0];
+            assert_eq!((job, start_time, allotment, available), (1, 0, 1, 0));
+            assert!(svg.contains("Overflow 0"), "{theme:?} has no overflow lane");
+            assert!(
+                svg.contains(r#"class="machine-header error""#),
+                "{theme:?} does not mark the overflow header"
+            );
+            let error_box = format!(r#"class="machine-box {} error""#, theme.fill_class(1));
+            assert_eq!(svg.matches(&error_box).count(), 1, "{theme:?}");
+            // the error hatch must not be overridden by the fill of the job
+            let hatch = svg.find(".machine-box.error ").expect("error style");
+            let fill = svg
+                .rfind(&format!(".machine-box.{} ", theme.fill_class(1)))
+                .expect("fill style");
+            assert!(hatch > fill, "{theme:?} overrides the error hatch");
+        }
+    }
+
+    /// Checks that every tag of `svg` is closed in the right order, and that
+    /// everything but the XML declaration is inside the root `svg` element
+    fn assert_well_formed(svg: &str) {
+        let mut open = vec![];
+        let mut roots = 0;
+        let mut rest = svg;
+        while let Some(start) = rest.find('<') {
+            let end = rest[start..].find('>').expect("unclosed tag") + start;
+            let tag = &rest[start + 1..end];
+            rest = &rest[end + 1..];
+            if tag.starts_with('?') || tag.starts_with('!') {
+                continue;
+            }
+            if let Some(name) = tag.strip_prefix('/') {
+                assert_eq!(open.pop(), Some(name.trim()), "mismatched closing tag");
+                continue;
+            }
+            let name = tag.split_whitespace().next().expect("tag without name");
+            if open.is_empty() {
+                assert_eq!(name, "svg", "element outside of the root");
+                roots += 1;
+            }
+            if !tag.ends_with('/') {
+                open.push(name);
+            }
+        }
+        assert!(open.is_empty(), "unclosed elements {open:?}");
+        assert_eq!(roots, 1);
+    }
+
+    /// Reads the numeric attribute `name` of the root `svg` element
+    fn root_attribute(svg: &str, name: &str) -> usize {
+        let root = &svg[svg.find("<svg").expect("no root")..];
+        let root = &root[..root.find('>').expect("unclosed root")];
+        let value =
+            &root[root.find(&format!(" {name}=\"")).expect("no attribute") + name.len() + 3..];
+        value[..value.find('"').expect("unclosed attribute")]
+            .parse()
+            .expect("numeric attribute")
+    }
+
+    #[test]
+    fn empty_schedules_render_placeholder_chart() {
+        let instance = InstanceBuilder::new()
+            .processors(2)
+            .job(1)
+            .times([0, 0])
+            .build()
+            .expect("valid instance");
+        let instantaneous = Schedule {
+            processor_count: 2,
+            jobs: vec![ScheduledJob {
+                job: instance.shared_job(0),
+                allotment: 1,
+                start_time: 0,
+                processors: vec![],
+            }],
+        };
+        let empty = Schedule {
+            processor_count: 2,
+            jobs: vec![],
+        };
+        let options = RenderOptions {
+            page_height: Some(100),
+            ..RenderOptions::default()
+        };
+        for (schedule, note) in [
+            (empty, "no jobs scheduled"),
+            (instantaneous, "all jobs take no time"),
+        ] {
+            let (svg, issues) = render_schedule_checked(&schedule, &RenderOptions::default());
+            assert!(issues.is_empty());
+            assert_well_formed(&svg);
+            assert!(svg.contains(note), "the chart does not say {note}");
+            assert!(svg.contains("Processor 0") && svg.contains("Processor 1"));
+            assert_eq!(
+                root_attribute(&svg, "width"),
+                LEFT_MARGIN
+                    + 2 * (MACHINE_WIDTH + MACHINE_SPACING)
+                    + UTILIZATION_WIDTH
+                    + RIGHT_MARGIN
+            );
+            assert_eq!(
+                root_attribute(&svg, "height"),
+                TOP_MARGIN + EMPTY_SCHEDULE_LENGTH * MACHINE_HEIGHT_SCALE + BOTTOM_MARGIN
+            );
+            // pagination keeps a single page instead of none
+            let (pages, _) = render_schedule_pages(&schedule, &options);
+            assert_eq!(pages.len(), 1);
+            assert_eq!(pages[0].svg, svg);
+        }
+    }
+
+    #[test]
+    fn tall_schedule_is_split_into_pages() {
+        // 25 jobs of 3 time units one after another on a single processor,
+        // with pages of 10 time units
+        let instance = (1..=25)
+            .fold(InstanceBuilder::new().processors(1), |builder, id| {
+                builder.job(id).times([3])
+            })
+            .build()
+            .expect("valid instance");
+        let schedule = Schedule {
+            processor_count: 1,
+            jobs: (0..25)
+                .map(|index| ScheduledJob {
+                    job: instance.shared_job(index),
+                    allotment: 1,
+                    start_time: 3 * index as i32,
+                    processors: vec![],
+                })
+                .collect(),
+        };
+        let page_height = TOP_MARGIN + 10 * MACHINE_HEIGHT_SCALE + BOTTOM_MARGIN;
+        let options = RenderOptions {
+            page_height: Some(page_height),
+            ..RenderOptions::default()
+        };
+        let (pages, issues) = render_schedule_pages(&schedule, &options);
+        assert!(issues.is_empty());
+        assert_eq!(
+            pages
+                .iter()
+                .map(|page| (page.from, page.to))
+                .collect::<Vec<_>>(),
+            [0, 10, 20, 30, 40, 50, 60, 70].map(|from| (from, (from + 10).min(75)))
+        );
+        for page in &pages {
+            assert_well_formed(&page.svg);
+            assert!(root_attribute(&page.svg, "height") <= page_height);
+        }
+        for index in 0..25 {
+            let tooltip = format!("Job {index}\n");
+            assert!(
+                pages.iter().any(|page| page.svg.contains(&tooltip)),
+                "job {index} is on no page"
+            );
+        }
+        // the job from 9 to 12 runs across the end of the first page
+        assert!(pages[0].svg.contains("Job 3\n") && pages[1].svg.contains("Job 3\n"));
+        assert!(pages[0].svg.contains("continues on the next page"));
+        assert!(pages[1].svg.contains("continued from the previous page"));
+
+        let names = (1..=pages.len())
+            .map(|k| format!("schedule_page{k}.svg"))
+            .collect::<Vec<_>>();
+        let index = render_page_index("Schedule", &pages, &names);
+        assert_eq!(index.matches("<li><a href=").count(), pages.len());
+        assert!(index.contains(r#"<a href="schedule_page8.svg">70 to 75 s</a>"#));
+    }
+
+    #[test]
+    fn notes_are_escaped_in_tooltips() {
+        let note = "says \"hi\" & <bye>\ntwo lines – ünïcode";
+        let job = crate::algo::Job {
+            index: 0,
+            processing_times: vec![2],
+            synthesized: 0,
+            affinity: None,
+            note: Some(note.to_string()),
+        };
+        let schedule = Schedule {
+            processor_count: 1,
+            jobs: vec![ScheduledJob::new(job, 1, 0, vec![0])],
+        };
+        let (svg, _) = render_schedule_checked(&schedule, &RenderOptions::default());
+        assert_well_formed(&svg);
+        assert!(!svg.contains("<bye>"), "the note is not escaped");
+        assert!(
+            svg.contains("&lt;bye&gt;") && svg.contains("&amp;"),
+            "the note is missing"
+        );
+        assert!(svg.contains("two lines – ünïcode"));
+    }
+
+    /// Schedules the jobs of `instance` with the given allotments and start
+    /// times, without processors, so that the renderer assigns them
+    fn unassigned(instance: &Instance, placements: &[(usize, i32)]) -> Vec<ScheduledJob> {
+        placements
+            .iter()
+            .enumerate()
+            .map(|(i, &(allotment, start_time))| ScheduledJob {
+                job: instance.shared_job(i),
+                allotment,
+                start_time,
+                processors: vec![],
+            })
+            .collect()
+    }
+
+    #[test]
+    fn shuffled_jobs_render_identically() {
+        // the first two jobs start together and fill all processors, and the
+        // third job waits for the second one
+        let instance = InstanceBuilder::new()
+            .processors(3)
+            .job(1)
+            .times([4, 2, 2])
+            .job(2)
+            .times([4, 2, 2])
+            .job(3)
+            .times([3, 3, 3])
+            .job(4)
+            .times([2, 2, 2])
+            .constraint(2, 3)
+            .constraint(3, 4)
+            .build()
+            .expect("valid instance");
+        let jobs = unassigned(&instance, &[(1, 0), (2, 0), (1, 2), (3, 5)]);
+        let options = RenderOptions {
+            predecessors: predecessors(&instance),
+            ..RenderOptions::default()
+        };
+        let render = |jobs: Vec<ScheduledJob>| {
+            let schedule = Schedule {
+                processor_count: 3,
+                jobs,
+            };
+            let (svg, issues) = render_schedule_checked(&schedule, &options);
+            assert!(issues.is_empty(), "{issues:?}");
+            svg
+        };
+        let expected = render(jobs.clone());
+        for shuffled in jobs.iter().cloned().permutations(jobs.len()) {
+            let order = shuffled.iter().map(|job| job.job.index).collect::<Vec<_>>();
+            assert!(
+                render(shuffled) == expected,
+                "{order:?} renders differently"
+            );
+        }
+    }
+
+    #[test]
+    fn jobs_stay_on_processors_of_longest_predecessor() {
+        let instance = InstanceBuilder::new()
+            .processors(2)
+            .job(1)
+            .times([2, 2])
+            .job(2)
+            .times([4, 4])
+            .job(3)
+            .times([2, 2])
+            .constraint(1, 3)
+            .constraint(2, 3)
+            .build()
+            .expect("valid instance");
+        let jobs = unassigned(&instance, &[(1, 0), (1, 0), (1, 4)]);
+        let (assignment, lane_count, issues) =
+            assign_processors(2, &jobs, &[], &predecessors(&instance));
+        assert_eq!(assignment, [vec![0], vec![1], vec![1]]);
+        assert_eq!(lane_count, 2);
+        assert!(issues.is_empty());
+        // without predecessors, the lowest free processor is taken
+        let (assignment, _, _) = assign_processors(2, &jobs, &[], &HashMap::new());
+        assert_eq!(assignment, [vec![0], vec![1], vec![0]]);
+
+        // explicit processors of all jobs are kept as they are
+        let mut explicit = jobs.clone();
+        for (job, processors) in explicit.iter_mut().zip([vec![1], vec![0], vec![0]]) {
+            job.processors = processors;
+        }
+        let (assignment, lane_count, issues) =
+            assign_processors(2, &explicit, &[], &predecessors(&instance));
+        assert_eq!(assignment, [vec![1], vec![0], vec![0]]);
+        assert_eq!(lane_count, 2);
+        assert!(issues.is_empty());
+    }
+
+    #[test]
+    fn waiting_jobs_get_ghosts() {
+        let instance = InstanceBuilder::new()
+            .processors(2)
+            .job(1)
+            .times([2, 2])
+            .job(2)
+            .times([3, 2])
+            .job(3)
+            .times([1, 1])
+            .constraint(1, 2)
+            .constraint(2, 3)
+            .build()
+            .expect("valid instance");
+        // the second job waits two units, and the third one none
+        let schedule = Schedule {
+            processor_count: 2,
+            jobs: [(1, 0), (2, 4), (1, 6)]
+                .into_iter()
+                .enumerate()
+                .map(|(i, (allotment, start_time))| ScheduledJob {
+                    job: instance.shared_job(i),
+                    allotment,
+                    start_time,
+                    processors: (0..allotment).collect(),
+                })
+                .collect(),
+        };
+        let (svg, _) = render_schedule_checked(&schedule, &RenderOptions::default());
+        assert!(!svg.contains(r#"class="waiting""#));
+        let options = RenderOptions {
+            show_waiting: HashMap::from([(0, 0), (1, 2), (2, 6)]),
+            ..RenderOptions::default()
+        };
+        let (svg, _) = render_schedule_checked(&schedule, &options);
+        // one ghost on each processor of the second job
+        assert_eq!(svg.matches(r#"class="waiting""#).count(), 2);
+        assert_eq!(
+            svg.matches("Job 1 waited 2 s after it became ready at 2 s")
+                .count(),
+            2
+        );
+        assert!(!svg.contains("Job 0 waited"));
+        assert!(!svg.contains("Job 2 waited"));
+    }
+}
@@ -0,0 +1,66 @@
+// Animates rendered schedules with SMIL, so that jobs appear when they start
+// and grow until they complete while a playhead moves along the time axis,
+// e.g. for presentations. Only animation elements are added, so viewers
+// without SMIL support show the static schedule.
+
+use svg::node::element::{path::Data, Animate, AnimateTransform, Path, Set};
+
+use super::TimeScale;
+
+/// Formats a time of the schedule as a SMIL clock value in seconds of playback
+fn clock(time: i32, speed: f64) -> String {
+    format!("{:.3}s", f64::from(time.max(0)) * speed)
+}
+
+/// Hides an element until `start`, none if it is visible from the beginning
+pub(super) fn hide_until(start: i32, speed: f64) -> Option<Set> {
+    (start > 0).then(|| {
+        Set::new()
+            .set("attributeName", "visibility")
+            .set("to", "hidden")
+            .set("begin", "0s")
+            .set("end", clock(start, speed))
+    })
+}
+
+/// Grows the height of a box from 0 to `height` between `start` and `end`,
+/// none if the box takes no time
+pub(super) fn grow(start: i32, end: i32, height: usize, speed: f64) -> Option<Animate> {
+    (end > start).then(|| {
+        Animate::new()
+            .set("attributeName", "height")
+            .set("from", 0)
+            .set("to", height)
+            .set("begin", clock(start, speed))
+            .set("dur", clock(end - start, speed))
+            .set("fill", "freeze")
+    })
+}
+
+/// Creates a line from `x` to `x + width` that moves along the time axis of
+/// `scale` until `end`. It is hidden in the static document and while the
+/// animation is not playing.
+pub(super) fn playhead(x: usize, width: usize, end: i32, scale: TimeScale, speed: f64) -> Path {
+    let y = scale.y(scale.from);
+    let distance = scale.y(end) - y;
+    Path::new()
+        .set("class", "playhead")
+        .set("visibility", "hidden")
+        .set("d", Data::new().move_to((x, y)).horizontal_line_by(width))
+        .add(
+            Set::new()
+                .set("attributeName", "visibility")
+                .set("to", "visible")
+                .set("begin", "0s")
+                .set("dur", clock(end, speed)),
+        )
+        .add(
+            AnimateTransform::new()
+                .set("attributeName", "transform")
+                .set("type", "translate")
+                .set("from", "0 0")
+                .set("to", format!("0 {distance}"))
+                .set("begin", "0s")
+                .set("dur", clock(end, speed)),
+        )
+}
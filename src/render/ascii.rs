@@ -0,0 +1,286 @@
+// Renders a schedule as a compact text Gantt chart, for a quick look at it in
+// a terminal without an SVG viewer, e.g. over ssh on a cluster.
+
+use std::cmp::Reverse;
+
+use itertools::Itertools;
+
+use super::{assign_processors, makespan, RenderOptions};
+use crate::Schedule;
+
+const IDLE: char = '.';
+const FILL: char = '=';
+const NAME_LENGTH: usize = 12; // characters of a processor name in front of its row
+const SYMBOLS: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const SYMBOL_OVERFLOW: char = '#'; // used once all symbols are taken
+
+/// Renders the schedule as lines of text that are at most `width` characters
+/// wide. Every processor gets a row that starts with its name, followed by
+/// one cell per `scale` units of time, where `scale` is the smallest integer
+/// that fits the makespan into the row. A job fills the cells in which it
+/// runs with its label followed by `=`, or with a single letter that the
+/// legend explains if its label does not fit. Idle cells are dots. Of the
+/// jobs that share a cell, the one that runs longest in it is shown, except
+/// that jobs shorter than a cell take precedence in the cell where they
+/// start, so that they show up at least once unless they start in the same
+/// cell as another short job. The processors of the jobs are those of
+/// `assign_processors`, and jobs that do not fit are shown on overflow rows.
+/// The footer gives the makespan, the scale, the legend, and the jobs that
+/// could not be shown.
+pub fn render_ascii(schedule: &Schedule, options: &RenderOptions, width: usize) -> Vec<String> {
+    // schedules whose jobs all take no time still get a cell
+    let length = makespan(schedule).max(1) as usize;
+    let (assignment, lane_count, _) = assign_processors(
+        schedule.processor_count,
+        &schedule.jobs,
+        &options.downtime,
+        &options.predecessors,
+    );
+    let names = (0..lane_count)
+        .map(|lane| {
+            if lane < schedule.processor_count {
+                truncate(&options.machine_name(lane))
+            } else {
+                format!("overflow {}", lane - schedule.processor_count + 1)
+            }
+        })
+        .collect::<Vec<_>>();
+    let name_width = names
+        .iter()
+        .map(|name| name.chars().count())
+        .max()
+        .unwrap_or(0);
+    let available = width.saturating_sub(name_width + 3).max(1);
+    let scale = length.div_ceil(available);
+    let cell_count = length.div_ceil(scale);
+
+    // the shown job of every cell of every lane, along with its priority
+    let mut cells = vec![vec![None; cell_count]; lane_count];
+    for (i, (job, processors)) in schedule.jobs.iter().zip(&assignment).enumerate() {
+        let start = job.start_time.max(0) as usize;
+        let end = job.completion_time().max(0) as usize;
+        let short = end - start < scale;
+        let first = (start / scale).min(cell_count - 1);
+        let last = (end.saturating_sub(1) / scale).clamp(first, cell_count - 1);
+        for cell in first..=last {
+            let overlap = end.min((cell + 1) * scale) - start.max(cell * scale).min(end);
+            let priority = (short && cell == first, overlap, Reverse(i));
+            for &lane in processors {
+                let shown = &mut cells[lane][cell];
+                if shown.is_none_or(|(_, other)| priority > other) {
+                    *shown = Some((i, priority));
+                }
+            }
+        }
+    }
+
+    let label = |i: usize| {
+        let index = schedule.jobs[i].job.index;
+        options
+            .labels
+            .get(&index)
+            .cloned()
+            .unwrap_or_else(|| index.to_string())
+    };
+    let mut symbols: Vec<(usize, char)> = vec![];
+    let mut lines = vec![];
+    for (name, lane) in names.iter().zip(&cells) {
+        let mut row = format!("{name:>name_width$} |");
+        for (shown, run) in &lane
+            .iter()
+            .map(|cell| cell.map(|(i, _)| i))
+            .chunk_by(|&i| i)
+        {
+            let length = run.count();
+            let Some(i) = shown else {
+                row.extend(std::iter::repeat_n(IDLE, length));
+                continue;
+            };
+            let label = label(i);
+            if label.chars().count() <= length {
+                let fill = length - label.chars().count();
+                row.push_str(&label);
+                row.extend(std::iter::repeat_n(FILL, fill));
+            } else {
+                let symbol = match symbols.iter().find(|&&(job, _)| job == i) {
+                    Some(&(_, symbol)) => symbol,
+                    None => {
+                        let symbol = SYMBOLS
+                            .chars()
+                            .nth(symbols.len())
+                            .unwrap_or(SYMBOL_OVERFLOW);
+                        symbols.push((i, symbol));
+                        symbol
+                    }
+                };
+                row.extend(std::iter::repeat_n(symbol, length));
+            }
+        }
+        lines.push(row);
+    }
+
+    lines.push(String::new());
+    lines.push(format!(
+        "makespan {}, one cell is {scale} units of time",
+        makespan(schedule)
+    ));
+    let legend = symbols
+        .iter()
+        .filter(|&&(_, symbol)| symbol != SYMBOL_OVERFLOW)
+        .map(|&(i, symbol)| format!("{symbol} = {}", label(i)))
+        .collect::<Vec<_>>();
+    lines.extend(wrap("legend: ", &legend, width));
+    if symbols.iter().any(|&(_, symbol)| symbol == SYMBOL_OVERFLOW) {
+        lines.push(format!("{SYMBOL_OVERFLOW} = jobs beyond the legend"));
+    }
+    let hidden = (0..schedule.jobs.len())
+        .filter(|&i| {
+            !cells
+                .iter()
+                .flatten()
+                .any(|cell| cell.is_some_and(|(j, _)| i == j))
+        })
+        .map(label)
+        .collect::<Vec<_>>();
+    lines.extend(wrap("too short to show: ", &hidden, width));
+    lines
+}
+
+/// Shortens a processor name to `NAME_LENGTH` characters, ending it with an
+/// ellipsis if it is longer
+fn truncate(name: &str) -> String {
+    if name.chars().count() <= NAME_LENGTH {
+        name.to_string()
+    } else {
+        let mut short = name.chars().take(NAME_LENGTH - 1).collect::<String>();
+        short.push('…');
+        short
+    }
+}
+
+/// Joins `items` by commas into lines of at most `width` characters where
+/// possible, the first of which starts with `prefix`. Returns no lines if
+/// there are no items.
+fn wrap(prefix: &str, items: &[String], width: usize) -> Vec<String> {
+    let indent = prefix.chars().count();
+    let mut lines = vec![];
+    let mut line = prefix.to_string();
+    let mut empty = true;
+    for (k, item) in items.iter().enumerate() {
+        let separator = if k + 1 < items.len() { "," } else { "" };
+        let fits = line.chars().count() + 1 + item.chars().count() + separator.len() <= width;
+        if !empty && !fits {
+            lines.push(line);
+            line = " ".repeat(indent);
+        } else if !empty {
+            line.push(' ');
+        }
+        line.push_str(item);
+        line.push_str(separator);
+        empty = false;
+    }
+    if !items.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::render_ascii;
+    use crate::{builder::InstanceBuilder, render::RenderOptions, Schedule, ScheduledJob};
+
+    /// Schedules jobs that take the given time on any number of processors
+    /// at the given start times on the given processors
+    fn schedule(processor_count: usize, jobs: &[(i32, i32, &[usize])]) -> Schedule {
+        let mut builder = InstanceBuilder::new().processors(processor_count);
+        for (id, &(time, _, _)) in jobs.iter().enumerate() {
+            builder = builder.job(id as i32 + 1).times_fn(|_| time);
+        }
+        let instance = builder.build().expect("valid instance");
+        Schedule {
+            processor_count,
+            jobs: jobs
+                .iter()
+                .enumerate()
+                .map(|(i, &(_, start_time, processors))| ScheduledJob {
+                    job: instance.shared_job(i),
+                    allotment: processors.len(),
+                    start_time,
+                    processors: processors.to_vec(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Options that name the processors
+    fn named(names: &[&str]) -> RenderOptions {
+        RenderOptions {
+            machine_names: names.iter().map(ToString::to_string).collect(),
+            ..RenderOptions::default()
+        }
+    }
+
+    #[test]
+    fn uncompressed_schedule() {
+        let schedule = schedule(2, &[(4, 0, &[0, 1]), (3, 4, &[0]), (2, 5, &[1])]);
+        assert_eq!(
+            render_ascii(&schedule, &named(&["p0", "p1"]), 20),
+            [
+                "p0 |0===1==",
+                "p1 |0===.2=",
+                "",
+                "makespan 7, one cell is 1 units of time",
+            ]
+        );
+    }
+
+    #[test]
+    fn compressed_schedule_with_legend() {
+        let schedule = schedule(1, &[(7, 0, &[0]), (1, 7, &[0]), (4, 8, &[0])]);
+        let options = RenderOptions {
+            labels: HashMap::from([(0, "long".to_string()), (2, "tail".to_string())]),
+            ..named(&["p"])
+        };
+        // the job of length 1 is shorter than a cell of 2, so it takes the
+        // cell that it shares with the end of the first job
+        assert_eq!(
+            render_ascii(&schedule, &options, 10),
+            [
+                "p |aaa1bb",
+                "",
+                "makespan 12, one cell is 2 units of time",
+                "legend: a = long,",
+                "        b = tail",
+            ]
+        );
+    }
+
+    #[test]
+    fn short_jobs_take_precedence_in_their_first_cell() {
+        let schedule = schedule(
+            2,
+            &[
+                (1, 0, &[0]),
+                (8, 1, &[0]),
+                (1, 0, &[1]),
+                (1, 1, &[1]),
+                (7, 2, &[1]),
+            ],
+        );
+        // job 3 starts in the same cell as the short job 2, which wins by
+        // its position, so it cannot be shown
+        assert_eq!(
+            render_ascii(&schedule, &named(&["p0", "p1"]), 8),
+            [
+                "p0 |01=",
+                "p1 |24=",
+                "",
+                "makespan 9, one cell is 3 units of time",
+                "too short to show: 3",
+            ]
+        );
+    }
+}
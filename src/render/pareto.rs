@@ -0,0 +1,323 @@
+// Renders the trade-off between processors and makespan as a step chart, and
+// the makespans that a search for the number of machines tried as a line chart.
+
+use svg::{
+    node::element::{path::Data, Circle, Group, Path, Text, Title},
+    Document,
+};
+
+use crate::pareto::{LowerBounds, ParetoPoint};
+
+const WIDTH: usize = 640; // px
+const HEIGHT: usize = 420; // px
+const MARGIN: usize = 60; // px
+const POINT_RADIUS: usize = 5; // px
+const LEGEND_LINE_LENGTH: usize = 24; // px
+const LEGEND_SPACING: usize = 16; // px
+
+/// Renders the points of a Pareto frontier, sorted by machines, as a step
+/// chart of the makespan over the number of machines. Each point is labeled
+/// with its number of machines.
+pub fn render_pareto(frontier: &[ParetoPoint]) -> String {
+    let max_machines = frontier
+        .iter()
+        .map(|p| p.machines)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    let max_makespan = frontier
+        .iter()
+        .map(|p| p.makespan)
+        .max()
+        .unwrap_or(0)
+        .max(1) as usize;
+    let plot_width = WIDTH - 2 * MARGIN;
+    let plot_height = HEIGHT - 2 * MARGIN;
+    let x = |machines: usize| MARGIN + machines * plot_width / max_machines;
+    let y = |makespan: i32| HEIGHT - MARGIN - makespan.max(0) as usize * plot_height / max_makespan;
+
+    // adding a machine keeps the makespan until the next point is reached
+    let steps = frontier.iter().skip(1).fold(
+        frontier.first().map_or_else(Data::new, |first| {
+            Data::new().move_to((x(first.machines), y(first.makespan)))
+        }),
+        |data, point| {
+            data.horizontal_line_to(x(point.machines))
+                .vertical_line_to(y(point.makespan))
+        },
+    );
+    let step_line = Path::new()
+        .set("fill", "none")
+        .set("stroke", "#0000f8")
+        .set("stroke-width", 2)
+        .set("d", steps);
+
+    let points = frontier
+        .iter()
+        .map(|point| {
+            let (cx, cy) = (x(point.machines), y(point.makespan));
+            Group::new()
+                .add(
+                    Circle::new()
+                        .set("cx", cx)
+                        .set("cy", cy)
+                        .set("r", POINT_RADIUS)
+                        .set("fill", "#0000f8"),
+                )
+                .add(
+                    Text::new(if point.machines == 1 {
+                        "1 machine".to_string()
+                    } else {
+                        format!("{} machines", point.machines)
+                    })
+                    .set("x", cx)
+                    .set("y", cy - 2 * POINT_RADIUS)
+                    .set("text-anchor", "middle"),
+                )
+                .add(Title::new(format!(
+                    "{} machines, makespan {}, budget {}",
+                    point.machines, point.makespan, point.budget
+                )))
+        })
+        .fold(Group::new(), Group::add);
+
+    let body = Document::new()
+        .set("version", "1.1")
+        .set("xmlns", "http://www.w3.org/2000/svg")
+        .set("width", WIDTH)
+        .set("height", HEIGHT)
+        .set("font-family", "sans-serif")
+        .set("font-size", 12)
+        .add(
+            Text::new("Pareto frontier")
+                .set("x", WIDTH / 2)
+                .set("y", MARGIN / 2)
+                .set("text-anchor", "middle")
+                .set("font-size", 18),
+        )
+        .add(create_axes(max_makespan))
+        .add(step_line)
+        .add(points)
+        .to_string();
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?>
+{body}"#
+    )
+}
+
+/// Renders the makespan over the number of machines that a size search tried
+/// as a line chart, along with the `target` makespan as a dashed line and the
+/// lower `bounds` as step lines. The smallest number of machines that meets
+/// the target is highlighted.
+pub fn render_size(
+    curve: &[(usize, i32)],
+    bounds: &[LowerBounds],
+    target: i32,
+    machines: Option<usize>,
+) -> String {
+    let max_machines = curve
+        .iter()
+        .map(|&(k, _)| k)
+        .chain(bounds.iter().map(|bound| bound.machines))
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    let max_makespan = curve
+        .iter()
+        .map(|&(_, makespan)| makespan)
+        .chain(bounds.iter().map(LowerBounds::max))
+        .chain([target])
+        .max()
+        .unwrap_or(0)
+        .max(1) as usize;
+    let plot_width = WIDTH - 2 * MARGIN;
+    let plot_height = HEIGHT - 2 * MARGIN;
+    let x = |machines: usize| MARGIN + machines * plot_width / max_machines;
+    let y = |makespan: i32| HEIGHT - MARGIN - makespan.max(0) as usize * plot_height / max_makespan;
+
+    let line = curve.iter().skip(1).fold(
+        curve.first().map_or_else(Data::new, |&(k, makespan)| {
+            Data::new().move_to((x(k), y(makespan)))
+        }),
+        |data, &(k, makespan)| data.line_to((x(k), y(makespan))),
+    );
+    let curve_line = Path::new()
+        .set("fill", "none")
+        .set("stroke", "#0000f8")
+        .set("stroke-width", 2)
+        .set("d", line);
+    let target_line = Path::new()
+        .set("fill", "none")
+        .set("stroke", "#f80000")
+        .set("stroke-width", 1)
+        .set("stroke-dasharray", "6 4")
+        .set(
+            "d",
+            Data::new()
+                .move_to((MARGIN, y(target)))
+                .horizontal_line_to(WIDTH - MARGIN),
+        )
+        .add(Title::new(format!("target makespan {target}")));
+
+    // a bound holds from its number of machines until the next one
+    let bound_line = |bound: fn(&LowerBounds) -> i32, dasharray: &str, name: &str| {
+        let steps = bounds.iter().skip(1).fold(
+            bounds.first().map_or_else(Data::new, |first| {
+                Data::new().move_to((x(first.machines), y(bound(first))))
+            }),
+            |data, point| {
+                data.horizontal_line_to(x(point.machines))
+                    .vertical_line_to(y(bound(point)))
+            },
+        );
+        Path::new()
+            .set("fill", "none")
+            .set("stroke", "#808080")
+            .set("stroke-width", 1)
+            .set("stroke-dasharray", dasharray)
+            .set("d", steps)
+            .add(Title::new(name.to_string()))
+    };
+    let area_line = bound_line(|bound| bound.area, "2 2", "area lower bound");
+    let critical_path_line = bound_line(
+        |bound| bound.critical_path,
+        "8 3 2 3",
+        "critical path lower bound",
+    );
+
+    let points = curve
+        .iter()
+        .map(|&(k, makespan)| {
+            let (cx, cy) = (x(k), y(makespan));
+            let color = if Some(k) == machines {
+                "#f80000"
+            } else {
+                "#0000f8"
+            };
+            Group::new()
+                .add(
+                    Circle::new()
+                        .set("cx", cx)
+                        .set("cy", cy)
+                        .set("r", POINT_RADIUS)
+                        .set("fill", color),
+                )
+                .add(
+                    Text::new(k.to_string())
+                        .set("x", cx)
+                        .set("y", cy - 2 * POINT_RADIUS)
+                        .set("text-anchor", "middle"),
+                )
+                .add(Title::new(format!("{k} machines, makespan {makespan}")))
+        })
+        .fold(Group::new(), Group::add);
+
+    let body = Document::new()
+        .set("version", "1.1")
+        .set("xmlns", "http://www.w3.org/2000/svg")
+        .set("width", WIDTH)
+        .set("height", HEIGHT)
+        .set("font-family", "sans-serif")
+        .set("font-size", 12)
+        .add(
+            Text::new("Machine count")
+                .set("x", WIDTH / 2)
+                .set("y", MARGIN / 2)
+                .set("text-anchor", "middle")
+                .set("font-size", 18),
+        )
+        .add(create_axes(max_makespan))
+        .add(area_line)
+        .add(critical_path_line)
+        .add(target_line)
+        .add(curve_line)
+        .add(points)
+        .add(create_legend(&[
+            ("makespan", "#0000f8", 2, "none"),
+            ("target", "#f80000", 1, "6 4"),
+            ("area bound", "#808080", 1, "2 2"),
+            ("critical path bound", "#808080", 1, "8 3 2 3"),
+        ]))
+        .to_string();
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?>
+{body}"#
+    )
+}
+
+/// Draws a legend in the top right corner with one line per entry, given by
+/// its label, color, stroke width, and dash array
+fn create_legend(entries: &[(&str, &str, usize, &str)]) -> Group {
+    let left = WIDTH - MARGIN - 150;
+    entries
+        .iter()
+        .zip(0..)
+        .map(|(&(label, color, width, dasharray), i)| {
+            let top = MARGIN + i * LEGEND_SPACING;
+            Group::new()
+                .add(
+                    Path::new()
+                        .set("fill", "none")
+                        .set("stroke", color)
+                        .set("stroke-width", width)
+                        .set("stroke-dasharray", dasharray)
+                        .set(
+                            "d",
+                            Data::new()
+                                .move_to((left, top))
+                                .horizontal_line_to(left + LEGEND_LINE_LENGTH),
+                        ),
+                )
+                .add(
+                    Text::new(label)
+                        .set("x", left + LEGEND_LINE_LENGTH + 6)
+                        .set("y", top + 4),
+                )
+        })
+        .fold(Group::new(), Group::add)
+}
+
+/// Draws both axes along with their labels and the range of the makespan
+fn create_axes(max_makespan: usize) -> Group {
+    let axes = Path::new()
+        .set("fill", "none")
+        .set("stroke", "black")
+        .set("stroke-width", 2)
+        .set(
+            "d",
+            Data::new()
+                .move_to((MARGIN, MARGIN))
+                .line_to((MARGIN, HEIGHT - MARGIN))
+                .line_to((WIDTH - MARGIN, HEIGHT - MARGIN)),
+        );
+    let axis_labels = Group::new()
+        .add(
+            Text::new("machines")
+                .set("x", WIDTH / 2)
+                .set("y", HEIGHT - MARGIN / 3)
+                .set("text-anchor", "middle"),
+        )
+        .add(
+            Text::new("makespan")
+                .set("x", MARGIN / 3)
+                .set("y", HEIGHT / 2)
+                .set("text-anchor", "middle")
+                .set(
+                    "transform",
+                    format!("rotate(-90 {} {})", MARGIN / 3, HEIGHT / 2),
+                ),
+        )
+        .add(
+            Text::new(max_makespan.to_string())
+                .set("x", MARGIN - 8)
+                .set("y", MARGIN)
+                .set("text-anchor", "end"),
+        )
+        .add(
+            Text::new("0")
+                .set("x", MARGIN - 8)
+                .set("y", HEIGHT - MARGIN)
+                .set("text-anchor", "end"),
+        );
+    Group::new().add(axes).add(axis_labels)
+}
@@ -0,0 +1,226 @@
+// Color themes for rendered schedules. Each theme generates the CSS and the
+// fill patterns that the SVG document needs.
+
+use std::fmt::Write;
+
+use clap::ValueEnum;
+use svg::node::element::{Circle, Path, Pattern, Rectangle};
+
+/// The look of a rendered schedule
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Theme {
+    /// Blue jobs on a light gradient
+    #[default]
+    Default,
+    /// White background, black outlines, and patterned job fills that can be
+    /// told apart when printed in grayscale
+    Print,
+    /// Dark background with high-contrast job fills
+    Dark,
+}
+
+/// The colors of a theme
+struct Palette {
+    /// Top and bottom color of the background gradient
+    background: (&'static str, &'static str),
+    /// Color of titles, headers, and the time scale
+    text: &'static str,
+    /// Color of the job outlines
+    outline: &'static str,
+    /// Fills for the jobs, used round-robin by job index or depth
+    fills: &'static [Fill],
+}
+
+/// How a job box is filled
+enum Fill {
+    /// A solid color given as `#rrggbb`
+    Solid(&'static str),
+    /// A black-and-white pattern with the given id
+    Pattern(&'static str),
+}
+
+const DEFAULT: Palette = Palette {
+    background: ("#eeeeee", "#b0b0ee"),
+    text: "black",
+    outline: "black",
+    fills: &[Fill::Solid("#0000f8")],
+};
+const PRINT: Palette = Palette {
+    background: ("#ffffff", "#ffffff"),
+    text: "black",
+    outline: "black",
+    fills: &[
+        Fill::Solid("#ffffff"),
+        Fill::Pattern("fill-diagonal"),
+        Fill::Solid("#bdbdbd"),
+        Fill::Pattern("fill-dots"),
+        Fill::Pattern("fill-crosshatch"),
+        Fill::Pattern("fill-horizontal"),
+    ],
+};
+const DARK: Palette = Palette {
+    background: ("#1e1e24", "#2c2c3a"),
+    text: "#e8e8e8",
+    outline: "#f5f5f5",
+    fills: &[
+        Fill::Solid("#ffb000"),
+        Fill::Solid("#00c2ff"),
+        Fill::Solid("#ff5fa2"),
+        Fill::Solid("#7cff4f"),
+        Fill::Solid("#c9a0ff"),
+    ],
+};
+
+impl Theme {
+    fn palette(self) -> &'static Palette {
+        match self {
+            Theme::Default => &DEFAULT,
+            Theme::Print => &PRINT,
+            Theme::Dark => &DARK,
+        }
+    }
+    /// Returns the top and bottom color of the background gradient
+    pub fn background(self) -> (&'static str, &'static str) {
+        self.palette().background
+    }
+    /// Returns the CSS class of the fill used for jobs with the given key,
+    /// such as their index or their depth
+    pub fn fill_class(self, key: usize) -> String {
+        format!("fill-{}", key % self.palette().fills.len())
+    }
+    /// Generates the style sheet of the document
+    pub fn css(self) -> String {
+        let palette = self.palette();
+        let mut fills = String::new();
+        for (i, fill) in palette.fills.iter().enumerate() {
+            let (paint, label) = match fill {
+                Fill::Solid(color) => ((*color).to_string(), contrast_color(color)),
+                Fill::Pattern(id) => (format!("url(#{id})"), "black"),
+            };
+            let _ = writeln!(fills, "    .machine-box.fill-{i} {{ fill:{paint}; }}");
+            let _ = writeln!(fills, "    .machine-label.fill-{i} {{ fill:{label}; }}");
+        }
+        let halo = if matches!(self, Theme::Print) {
+            "stroke:white; stroke-width:3px; paint-order:stroke;"
+        } else {
+            ""
+        };
+        // the error hatch has to come after the fills, which have the same
+        // specificity and would override it otherwise
+        format!(
+            r"
+    text {{ font-family:monospace; font-size:10px; fill:{text}; }}
+    #title {{ text-anchor:middle; font-size:25px; }}
+    .machine-header {{ text-anchor:middle; font-size:17px; }}
+    .machine-box {{ stroke-width:1; stroke:{outline}; }}
+    .machine-box.aggregate {{ fill:{text}; fill-opacity:0.3; }}
+    .job-marker {{ stroke:{outline}; stroke-width:3; }}
+    .job-marker.error {{ stroke:#d00000; }}
+    .job-link {{ stroke:{outline}; stroke-width:2; stroke-dasharray:4 3; }}
+    .continuation {{ stroke:{outline}; stroke-width:3; stroke-dasharray:2 2; }}
+    .waiting {{ fill:none; stroke:{outline}; stroke-width:1; stroke-dasharray:3 3; }}
+    .machine-header.error {{ fill:#d00000; }}
+    .machine-label {{ text-anchor:middle; dominant-baseline:middle; font-size:15px; {halo} }}
+    .outside-label {{ dominant-baseline:middle; font-size:10px; {halo} }}
+    .leader-line {{ stroke:{outline}; stroke-width:1; fill:none; }}
+    .soft-violation {{ stroke:#ff8c00; stroke-width:2; stroke-dasharray:6 3; fill:none; }}
+    .soft-violation-head {{ fill:#ff8c00; }}
+    .scale-label {{ text-anchor:end; dominant-baseline:middle; font-size:10px; }}
+    .scale-line {{ stroke:{text}; }}
+    .empty-note {{ text-anchor:middle; dominant-baseline:middle; font-size:15px; font-style:italic; }}
+    .utilization-bar {{ fill:{text}; fill-opacity:0.35; }}
+    .chain-bar {{ fill:{text}; fill-opacity:0.2; }}
+    .chain-bar.critical {{ fill-opacity:0.6; }}
+    .capacity-window {{ fill:{text}; fill-opacity:0.12; }}
+    .downtime {{ fill:url(#downtime-hatch); stroke:#909090; stroke-width:1; }}
+    .playhead {{ stroke:#d00000; stroke-width:2; }}
+{fills}    .machine-box.error {{ stroke:#d00000; fill:url(#error-hatch); }}
+    ",
+            text = palette.text,
+            outline = palette.outline,
+        )
+    }
+    /// Creates the fill patterns referenced by the style sheet
+    pub fn patterns(self) -> Vec<Pattern> {
+        self.palette()
+            .fills
+            .iter()
+            .filter_map(|fill| match fill {
+                Fill::Solid(_) => None,
+                Fill::Pattern(id) => Some(create_pattern(id)),
+            })
+            .collect()
+    }
+}
+
+/// Picks black or white, whichever is easier to read on the given `#rrggbb` color
+fn contrast_color(background: &str) -> &'static str {
+    let channel = |i: usize| {
+        u8::from_str_radix(&background[i..i + 2], 16).map_or(0.0, |c| f64::from(c) / 255.0)
+    };
+    // relative luminance as defined by WCAG, without gamma correction
+    let luminance = 0.2126 * channel(1) + 0.7152 * channel(3) + 0.0722 * channel(5);
+    if luminance > 0.5 {
+        "black"
+    } else {
+        "white"
+    }
+}
+
+fn create_pattern(id: &str) -> Pattern {
+    let pattern = Pattern::new()
+        .set("id", id)
+        .set("width", 8)
+        .set("height", 8)
+        .set("patternUnits", "userSpaceOnUse")
+        .add(
+            Rectangle::new()
+                .set("width", 8)
+                .set("height", 8)
+                .set("fill", "white"),
+        );
+    let line = |d: &str| {
+        Path::new()
+            .set("d", d)
+            .set("stroke", "black")
+            .set("stroke-width", 1)
+    };
+    match id {
+        "fill-diagonal" => pattern.add(line("M -2 2 L 2 -2 M 0 8 L 8 0 M 6 10 L 10 6")),
+        "fill-crosshatch" => pattern
+            .add(line("M -2 2 L 2 -2 M 0 8 L 8 0 M 6 10 L 10 6"))
+            .add(line("M -2 6 L 2 10 M 0 0 L 8 8 M 6 -2 L 10 2")),
+        "fill-horizontal" => pattern.add(line("M 0 2 L 8 2 M 0 6 L 8 6")),
+        _ => pattern.add(
+            Circle::new()
+                .set("cx", 4)
+                .set("cy", 4)
+                .set("r", 1.5)
+                .set("fill", "black"),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::ValueEnum;
+
+    use super::Theme;
+
+    #[test]
+    fn error_hatch_overrides_fills() {
+        for theme in Theme::value_variants() {
+            let css = theme.css();
+            let error = css
+                .find(".machine-box.error ")
+                .unwrap_or_else(|| panic!("{theme:?} has no error style"));
+            let last_fill = css
+                .rfind(".machine-box.fill-")
+                .unwrap_or_else(|| panic!("{theme:?} has no fills"));
+            assert!(
+                error > last_fill,
+                "{theme:?} overrides the error hatch with a fill"
+            );
+        }
+    }
+}
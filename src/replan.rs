@@ -0,0 +1,286 @@
+// Plans the remainder of a schedule that is already being executed. Jobs that
+// are done are kept as instantaneous jobs at the replan instant, so that the
+// jobs keep their indices, and running jobs are pinned there with the time
+// they still need. The solvers plan on a copy of the instance in which the
+// replan instant is time 0, and the schedule is moved back afterwards.
+
+use std::collections::HashMap;
+
+use crate::algo::{CapacityWindow, Constraint, Downtime, Instance, Schedule, ScheduledJob};
+
+/// How far the execution of a job has progressed at the replan instant
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Progress {
+    /// The job completed, so its successors may start right away
+    Done,
+    /// The job runs on `allotment` consecutive processors from
+    /// `first_processor` on, and still needs `remaining_fraction` of its
+    /// processing time
+    Running {
+        remaining_fraction: f64,
+        allotment: usize,
+        first_processor: usize,
+    },
+    /// The job has not started yet
+    Pending,
+}
+
+/// Builds the instance that remains of `instance` at time `at`, given the
+/// progress of its jobs by position, where jobs without progress are
+/// pending. Done jobs take no time and are pinned at `at`, and their
+/// constraints to other jobs are dropped. Running jobs are pinned at `at`
+/// with their allotment, and every processing time is scaled by the
+/// remaining fraction, rounded up. Pending jobs are kept as they are. All
+/// times, including those of the capacity windows and downtime, stay
+/// absolute, see `rebase` for the copy that the solvers plan on.
+pub fn residual(instance: &Instance, progress: &HashMap<usize, Progress>, at: i32) -> Instance {
+    assert!(
+        instance.pinned.is_empty(),
+        "cannot replan an instance with pinned jobs"
+    );
+    let progress = |j: usize| progress.get(&j).copied().unwrap_or(Progress::Pending);
+    let mut jobs = instance.jobs.clone();
+    let mut pinned = vec![];
+    for (j, job) in jobs.iter_mut().enumerate() {
+        match progress(j) {
+            Progress::Done => {
+                job.processing_times.fill(0);
+                let processor = job
+                    .affinity
+                    .as_ref()
+                    .and_then(|allowed| allowed.first().copied())
+                    .unwrap_or(0);
+                pinned.push(ScheduledJob::new(job.clone(), 1, at, vec![processor]));
+            }
+            Progress::Running {
+                remaining_fraction,
+                allotment,
+                first_processor,
+            } => {
+                for p in &mut job.processing_times {
+                    *p = (f64::from(*p) * remaining_fraction).ceil() as i32;
+                }
+                pinned.push(ScheduledJob::new(
+                    job.clone(),
+                    allotment,
+                    at,
+                    (first_processor..first_processor + allotment).collect(),
+                ));
+            }
+            Progress::Pending => {}
+        }
+    }
+    let done = |index: usize| progress(index) == Progress::Done;
+    let constraints = instance
+        .constraints
+        .iter()
+        .filter(|Constraint(left, ..)| !done(*left))
+        .cloned()
+        .collect();
+    let soft_constraints = instance
+        .soft_constraints
+        .iter()
+        .filter(|soft| !done(soft.left))
+        .copied()
+        .collect();
+    Instance::new(instance.processor_count, jobs, constraints)
+        .with_soft_constraints(soft_constraints)
+        .with_capacity(instance.capacity.clone())
+        .with_downtime(instance.downtime.clone())
+        .with_pinned(pinned)
+        .with_machine_names(instance.machine_names.clone())
+}
+
+/// Moves `instance` back in time by `at`, so that `at` becomes time 0.
+/// Capacity windows and downtime that end before `at` are dropped, and those
+/// that run across it are cut off there.
+pub fn rebase(instance: &Instance, at: i32) -> Instance {
+    let capacity = instance
+        .capacity
+        .iter()
+        .filter(|window| window.to > at)
+        .map(|window| CapacityWindow {
+            from: (window.from - at).max(0),
+            to: window.to - at,
+            capacity: window.capacity,
+        })
+        .collect();
+    let downtime = instance
+        .downtime
+        .iter()
+        .filter(|down| down.to > at)
+        .map(|down| Downtime {
+            processor: down.processor,
+            from: (down.from - at).max(0),
+            to: down.to - at,
+        })
+        .collect();
+    let pinned = instance
+        .pinned
+        .iter()
+        .map(|pin| ScheduledJob {
+            start_time: pin.start_time - at,
+            ..pin.clone()
+        })
+        .collect();
+    Instance::new(
+        instance.processor_count,
+        instance.jobs.clone(),
+        instance.constraints.clone(),
+    )
+    .with_soft_constraints(instance.soft_constraints.clone())
+    .with_capacity(capacity)
+    .with_downtime(downtime)
+    .with_pinned(pinned)
+    .with_machine_names(instance.machine_names.clone())
+}
+
+/// Moves a schedule of the rebased instance forward by `at`, so that it
+/// reports absolute times again
+pub fn to_absolute(schedule: Schedule, at: i32) -> Schedule {
+    Schedule {
+        processor_count: schedule.processor_count,
+        jobs: schedule
+            .jobs
+            .into_iter()
+            .map(|job| ScheduledJob {
+                start_time: job.start_time + at,
+                ..job
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{rebase, residual, to_absolute, Progress};
+    use crate::{
+        algo::{Downtime, Instance, Schedule},
+        builder::InstanceBuilder,
+        list,
+    };
+
+    /// Replans `instance` at `at` with LIST and returns the schedule with
+    /// absolute times, after checking it against the residual instance
+    fn replan(instance: &Instance, progress: &HashMap<usize, Progress>, at: i32) -> Schedule {
+        let residual = residual(instance, progress, at);
+        let schedule = to_absolute(list::greedy(&rebase(&residual, at)), at);
+        assert!(
+            schedule.violations(&residual).is_empty(),
+            "{:?}",
+            schedule.violations(&residual)
+        );
+        schedule
+    }
+
+    /// The start time of the job with the given index
+    fn start(schedule: &Schedule, index: usize) -> i32 {
+        schedule
+            .jobs
+            .iter()
+            .find(|job| job.job.index == index)
+            .expect("the job is scheduled")
+            .start_time
+    }
+
+    #[test]
+    fn running_job_finishing_at_replan_instant() {
+        let instance = InstanceBuilder::new()
+            .processors(2)
+            .job(1)
+            .times([6, 3])
+            .job(2)
+            .times([2, 2])
+            .constraint(1, 2)
+            .build()
+            .expect("valid instance");
+        let progress = HashMap::from([(
+            0,
+            Progress::Running {
+                remaining_fraction: 0.0,
+                allotment: 1,
+                first_processor: 0,
+            },
+        )]);
+        let residual = residual(&instance, &progress, 10);
+        assert_eq!(residual.jobs[0].processing_times, [0, 0]);
+        assert_eq!(residual.constraints.len(), 1);
+        assert_eq!(residual.pinned.len(), 1);
+        let pin = &residual.pinned[0];
+        assert_eq!(
+            (
+                pin.job.index,
+                pin.allotment,
+                pin.start_time,
+                &pin.processors[..]
+            ),
+            (0, 1, 10, &[0][..])
+        );
+
+        let schedule = replan(&instance, &progress, 10);
+        assert_eq!(start(&schedule, 0), 10);
+        assert_eq!(start(&schedule, 1), 10);
+        assert_eq!(schedule.makespan(), 12);
+    }
+
+    #[test]
+    fn done_job_releases_its_successors() {
+        let instance = InstanceBuilder::new()
+            .processors(2)
+            .job(1)
+            .times([4, 2])
+            .job(2)
+            .times([3, 3])
+            .job(3)
+            .times([5, 3])
+            .constraint(1, 2)
+            .build()
+            .expect("valid instance");
+        let progress = HashMap::from([(0, Progress::Done), (2, Progress::Pending)]);
+        let residual = residual(&instance, &progress, 7);
+        assert_eq!(residual.jobs[0].processing_times, [0, 0]);
+        assert!(residual.constraints.is_empty());
+
+        let schedule = replan(&instance, &progress, 7);
+        assert_eq!(start(&schedule, 0), 7);
+        assert_eq!(start(&schedule, 1), 7);
+        assert_eq!(start(&schedule, 2), 7);
+        assert_eq!(schedule.makespan(), 12);
+    }
+
+    #[test]
+    fn rebase_cuts_downtime_at_replan_instant() {
+        let instance = InstanceBuilder::new()
+            .processors(2)
+            .job(1)
+            .times([4, 2])
+            .build()
+            .expect("valid instance")
+            .with_downtime(vec![
+                Downtime {
+                    processor: 0,
+                    from: 0,
+                    to: 3,
+                },
+                Downtime {
+                    processor: 1,
+                    from: 5,
+                    to: 12,
+                },
+                Downtime {
+                    processor: 0,
+                    from: 15,
+                    to: 20,
+                },
+            ]);
+        let rebased = rebase(&instance, 10);
+        let downtime = rebased
+            .downtime
+            .iter()
+            .map(|down| (down.processor, down.from, down.to))
+            .collect::<Vec<_>>();
+        assert_eq!(downtime, [(0, 5, 10), (1, 0, 2)]);
+    }
+}
@@ -0,0 +1,86 @@
+// Keeps the written files of a run identical between runs of the same
+// command. All timestamps, recorded durations, and default seeds that end up
+// in written files are taken from here, so that reproducible mode can fix
+// them. Reproducible mode is on if --reproducible is passed or if the
+// environment variable SOURCE_DATE_EPOCH is set, like in reproducible builds.
+
+use std::{
+    env,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        OnceLock,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use rand::Rng;
+
+use crate::diagnostics::{self, Category};
+
+/// Environment variable holding the seconds since the Unix epoch that all
+/// written files record as the current time
+const SOURCE_DATE_EPOCH: &str = "SOURCE_DATE_EPOCH";
+
+/// Whether --reproducible was passed
+static REPRODUCIBLE: AtomicBool = AtomicBool::new(false);
+
+/// Turns on reproducible mode for the rest of the run
+pub fn enable() {
+    REPRODUCIBLE.store(true, Ordering::Relaxed);
+}
+
+/// Checks whether written files must not differ between runs of the same
+/// command
+pub fn enabled() -> bool {
+    fixed_timestamp().is_some()
+}
+
+/// Returns the seconds since the Unix epoch that written files record as the
+/// current time. In reproducible mode, this is the value of
+/// SOURCE_DATE_EPOCH, or 0 if it is not set.
+pub fn timestamp() -> u64 {
+    fixed_timestamp().unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs())
+    })
+}
+
+/// Returns the number of milliseconds that written files record for a
+/// measured `duration`, which is 0 in reproducible mode
+pub fn duration_ms(duration: Duration) -> u128 {
+    if enabled() {
+        0
+    } else {
+        duration.as_millis()
+    }
+}
+
+/// Returns a seed for random choices if none was given, which is 0 in
+/// reproducible mode and random otherwise
+pub fn seed() -> u64 {
+    if enabled() {
+        0
+    } else {
+        rand::rng().random()
+    }
+}
+
+/// Reads the fixed timestamp of reproducible mode once, or `None` if
+/// reproducible mode is off. A SOURCE_DATE_EPOCH that is not a number is
+/// reported and treated as 0.
+fn fixed_timestamp() -> Option<u64> {
+    static FIXED: OnceLock<Option<u64>> = OnceLock::new();
+    *FIXED.get_or_init(|| match env::var(SOURCE_DATE_EPOCH) {
+        Ok(value) => Some(value.trim().parse().unwrap_or_else(|_| {
+            diagnostics::report(
+                Category::Input,
+                format!(
+                    "{SOURCE_DATE_EPOCH} is {value:?}, which is not a number of seconds, using 0"
+                ),
+            );
+            0
+        })),
+        Err(_) => REPRODUCIBLE.load(Ordering::Relaxed).then_some(0),
+    })
+}
@@ -0,0 +1,206 @@
+// Runs the pipeline of the solve commands, i.e. reading, solving, verifying,
+// exporting, and rendering an instance, as a single function call, so that
+// other code can embed it without going through the command line. It only
+// offers the default options of every algorithm and the most common outputs,
+// the solve commands remain the way to reach all options.
+
+use std::{fmt, time::Instant};
+
+use crate::{
+    algo::{topo_order, CycleError, Instance, Schedule, SolverStats},
+    diagnostics::{self, Category, Diagnostic},
+    files::{self, ExportFormat, ReadError, ReadOptions},
+    metrics::{self, Parallelism, Waiting},
+    objective,
+    output::atomic_write,
+    pareto::Algorithm,
+    provenance::Provenance,
+    render::{self, render_schedule_checked, RenderOptions},
+    reproducible,
+};
+
+/// Where the instance of a run comes from
+#[derive(Debug)]
+pub enum Input {
+    /// A job file and a constraint file, read with the default options
+    Files {
+        job_file: String,
+        constraint_file: String,
+    },
+    /// An instance that is already in memory, e.g. from `InstanceBuilder`
+    #[expect(dead_code, reason = "constructed by code that embeds the pipeline")]
+    Instance(Instance),
+}
+
+/// What a run solves and which files it writes
+#[derive(Debug)]
+pub struct RunConfig {
+    /// The instance to solve
+    pub input: Input,
+    /// The algorithm that solves the instance with its default options
+    pub algorithm: Algorithm,
+    /// Output JSON file containing the schedule, see --schedule-json
+    pub schedule_json: Option<String>,
+    /// Output SVG file containing the rendered schedule
+    pub svg: Option<String>,
+}
+
+/// Everything a run produced
+#[derive(Debug)]
+#[expect(
+    dead_code,
+    reason = "some fields are only read by code that embeds the pipeline"
+)]
+pub struct RunOutcome {
+    /// The computed schedule
+    pub schedule: Schedule,
+    /// Statistics of the solver
+    pub stats: SolverStats,
+    /// The origin of the schedule, as written to the schedule JSON
+    pub provenance: Provenance,
+    /// Descriptions of every way in which the schedule violates the instance,
+    /// empty if it is valid
+    pub violations: Vec<String>,
+    /// How the schedule uses the processors
+    pub parallelism: Parallelism,
+    /// Diagnostics reported so far, including those of the run
+    pub diagnostics: Vec<Diagnostic>,
+    /// Paths of all files that the run wrote
+    pub written: Vec<String>,
+}
+
+/// Reason why a run cannot solve its instance
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AppError {
+    /// The job or constraint file cannot be read
+    Read(ReadError),
+    /// The constraints contain a cycle, so no job on it can ever start
+    Cycle(CycleError),
+    /// Some maximum lags can never be met
+    InfeasibleLags(Vec<String>),
+    /// Some jobs are only allowed on processors that do not exist
+    InfeasibleAffinities(Vec<String>),
+    /// Some pinned jobs overlap, exceed the capacity, or start too early
+    InfeasiblePins(Vec<String>),
+}
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (kind, conflicts) = match self {
+            AppError::Read(e) => return write!(f, "cannot read the instance: {e}"),
+            AppError::Cycle(e) => return write!(f, "{e}"),
+            AppError::InfeasibleLags(conflicts) => ("lags", conflicts),
+            AppError::InfeasibleAffinities(conflicts) => ("affinities", conflicts),
+            AppError::InfeasiblePins(conflicts) => ("pins", conflicts),
+        };
+        write!(f, "infeasible {kind}: {}", conflicts.join(", "))
+    }
+}
+
+/// Solves the instance of `config` with its algorithm, checks the schedule
+/// against the instance, and writes the requested outputs. Violations of the
+/// schedule are reported as diagnostics and returned in the outcome, like the
+/// solve commands export invalid schedules, too. Fails before solving if the
+/// instance cannot be satisfied.
+pub fn run(config: RunConfig) -> Result<RunOutcome, AppError> {
+    let (instance, provenance_files) = match config.input {
+        Input::Files {
+            job_file,
+            constraint_file,
+        } => (
+            files::read(&job_file, &constraint_file, &ReadOptions::default())
+                .map_err(AppError::Read)?,
+            Some((job_file, constraint_file)),
+        ),
+        Input::Instance(instance) => (instance, None),
+    };
+    check(&instance)?;
+    // the solvers consume the instance, so keep a copy to check the schedule
+    let copy = instance.with_processor_count(instance.processor_count);
+
+    let started_at = reproducible::timestamp();
+    let before = Instant::now();
+    let (schedule, stats) = config.algorithm.solve_with_stats(instance);
+    let provenance = Provenance::new(
+        config.algorithm.command(),
+        false,
+        &stats,
+        started_at,
+        before.elapsed(),
+    );
+    let provenance = match &provenance_files {
+        Some((job_file, constraint_file)) => provenance.with_input_files(job_file, constraint_file),
+        None => provenance,
+    };
+    let instance = copy;
+
+    let violations = schedule.violations(&instance);
+    for violation in &violations {
+        diagnostics::report_error(
+            Category::Verification,
+            format!("Schedule violates the instance: {violation}"),
+        );
+    }
+    let parallelism = Parallelism::new(&instance, &schedule);
+    let mut written = vec![];
+    if let Some(path) = config.svg {
+        let options = RenderOptions {
+            capacity: instance.capacity.clone(),
+            downtime: instance.downtime.clone(),
+            machine_names: instance.machine_names.clone(),
+            predecessors: render::predecessors(&instance),
+            ..RenderOptions::default()
+        };
+        let (svg, issues) = render_schedule_checked(&schedule, &options);
+        for issue in &issues {
+            diagnostics::report(Category::Render, format!("Render problem: {issue}"));
+        }
+        atomic_write(&path, svg.as_bytes())
+            .unwrap_or_else(|e| panic!("cannot write to file {path}: {e}"));
+        written.push(path);
+    }
+    // written last so that it includes the diagnostics of all other outputs
+    if let Some(path) = config.schedule_json {
+        files::write_schedule(
+            &path,
+            &schedule,
+            &provenance,
+            None,
+            None,
+            &schedule.soft_violations(&instance),
+            &[],
+            &objective::evaluate_all(&instance, &schedule),
+            &parallelism,
+            &Waiting::new(&metrics::waiting_times(&instance, &schedule)),
+            ExportFormat::default(),
+        );
+        written.push(path);
+    }
+    Ok(RunOutcome {
+        schedule,
+        stats,
+        provenance,
+        violations,
+        parallelism,
+        diagnostics: diagnostics::all(),
+        written,
+    })
+}
+
+/// Checks that the constraints of `instance` contain no cycle and that its
+/// maximum lags, affinities, and pins can be met, which the solvers rely on
+pub fn check(instance: &Instance) -> Result<(), AppError> {
+    topo_order(instance).map_err(AppError::Cycle)?;
+    let conflicts = instance.lag_conflicts();
+    if !conflicts.is_empty() {
+        return Err(AppError::InfeasibleLags(conflicts));
+    }
+    let conflicts = instance.affinity_conflicts();
+    if !conflicts.is_empty() {
+        return Err(AppError::InfeasibleAffinities(conflicts));
+    }
+    let conflicts = instance.pin_conflicts();
+    if !conflicts.is_empty() {
+        return Err(AppError::InfeasiblePins(conflicts));
+    }
+    Ok(())
+}
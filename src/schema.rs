@@ -0,0 +1,90 @@
+// Versions of the JSON documents that the CLI writes for other tools, along
+// with their JSON Schemas. Every document carries its version in the field
+// `schema_version`, so that readers can tell which fields to expect.
+
+use clap::ValueEnum;
+use schemars::{schema::RootSchema, schema_for};
+
+use crate::{
+    baseline::Baseline,
+    certify::Certificate,
+    diagnostics::{self, Category},
+    files::ScheduleFile,
+    generate::Metadata,
+};
+
+/// Version of the schedule JSON written via --schedule-json. The major
+/// version changes whenever a field is renamed or removed.
+pub const SCHEDULE_VERSION: &str = "1.6.0";
+
+/// Version of the metadata JSON written next to generated job files
+pub const METADATA_VERSION: &str = "1.0.0";
+
+/// Version of the baseline database written by `baseline record`
+pub const BASELINE_VERSION: &str = "1.1.0";
+
+/// Version of the optimality certificate written via --certificate of
+/// solve-dp
+pub const CERTIFICATE_VERSION: &str = "1.0.0";
+
+/// A JSON document that the CLI writes
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Document {
+    /// The schedule written via --schedule-json, which --improve-from reads
+    Schedule,
+    /// The metadata of a generated instance, written next to its job file
+    Metadata,
+    /// The baseline database written by `baseline record`, which `baseline
+    /// compare` reads
+    Baseline,
+    /// The optimality certificate written via --certificate of solve-dp,
+    /// which `certify` reads
+    Certificate,
+}
+impl Document {
+    /// Current version of the document
+    pub fn version(self) -> &'static str {
+        match self {
+            Document::Schedule => SCHEDULE_VERSION,
+            Document::Metadata => METADATA_VERSION,
+            Document::Baseline => BASELINE_VERSION,
+            Document::Certificate => CERTIFICATE_VERSION,
+        }
+    }
+    /// Derives the JSON Schema of the current version of the document
+    pub fn schema(self) -> RootSchema {
+        let mut schema = match self {
+            Document::Schedule => schema_for!(ScheduleFile<'static>),
+            Document::Metadata => schema_for!(Metadata),
+            Document::Baseline => schema_for!(Baseline),
+            Document::Certificate => schema_for!(Certificate),
+        };
+        let title = format!("{self:?} {}", self.version());
+        schema.schema.metadata().title = Some(title);
+        schema
+    }
+}
+
+/// Checks the `schema_version` of a document read from `path` before it is
+/// parsed. Documents of the current major version are read as they are.
+/// Documents without a version were written before versioning was introduced
+/// and have the same fields as version 1, so they are read with a deprecation
+/// warning. All other versions are rejected.
+pub fn check_version(path: &str, document: Document, json: &serde_json::Value) {
+    let major = |version: &str| version.split('.').next().unwrap_or_default().to_string();
+    match json.get("schema_version").map(serde_json::Value::as_str) {
+        None => diagnostics::report(
+            Category::Input,
+            format!(
+                "{path} has no schema version, reading it as version {} is deprecated, write it again to upgrade",
+                document.version()
+            ),
+        ),
+        Some(Some(version)) if major(version) == major(document.version()) => {}
+        Some(_) => panic!(
+            "{path} has unsupported schema version {}, expected {}",
+            json["schema_version"],
+            document.version()
+        ),
+    }
+}
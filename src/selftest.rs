@@ -0,0 +1,410 @@
+// Cross-checks the solvers against each other on small random instances.
+// The dynamic program and the mixed integer linear program are exact, so they
+// must agree and no other solver may beat them, and every
+// solver must produce schedules that satisfy their instance. Instances on
+// which a check fails are shrunk and saved as a reproduction.
+
+use std::{fmt, fs, mem};
+
+use itertools::Itertools;
+use log::{debug, info};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    algo::{Instance, Rounding, Schedule, INT_TOLERANCE},
+    diagnostics::{self, Category},
+    files,
+    generate::{self, GenerateConfig},
+    ilp,
+    list::{self, ListOptions},
+    lp::{self, RoundingStrategy},
+    pareto::Algorithm,
+    preprocess,
+};
+
+/// Largest number of processors of the generated instances, which keeps the
+/// dynamic program fast
+const MAX_PROCESSORS: usize = 3;
+
+/// Largest processing time of a job in the generated instances
+const MAX_PROCESSING_TIME: i32 = 10;
+
+/// A solver under test
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Solver {
+    /// One of the algorithms with default options, see `Algorithm::solve`
+    Algorithm(Algorithm),
+    /// LIST with every job on the allotment with the least work, see
+    /// `list::greedy`
+    Greedy,
+}
+impl Solver {
+    /// All solvers under test, starting with the exact ones
+    pub const ALL: [Solver; 6] = [
+        Solver::Algorithm(Algorithm::Dp),
+        Solver::Algorithm(Algorithm::Milp),
+        Solver::Algorithm(Algorithm::Lp),
+        Solver::Algorithm(Algorithm::Ilp),
+        Solver::Algorithm(Algorithm::Shelf),
+        Solver::Greedy,
+    ];
+    /// Whether the solver always finds a schedule with the smallest makespan
+    fn is_exact(self) -> bool {
+        matches!(self, Solver::Algorithm(Algorithm::Dp | Algorithm::Milp))
+    }
+    /// Solves the instance with default options
+    fn solve(self, instance: Instance) -> Schedule {
+        match self {
+            Solver::Algorithm(algorithm) => algorithm.solve(instance),
+            Solver::Greedy => list::greedy(&instance),
+        }
+    }
+}
+impl fmt::Display for Solver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Solver::Algorithm(algorithm) => write!(f, "{algorithm}"),
+            Solver::Greedy => f.write_str("greedy"),
+        }
+    }
+}
+
+/// A check that failed on an instance
+#[derive(Clone, Debug)]
+pub enum Problem {
+    /// The schedule of a solver does not satisfy the instance
+    Violation {
+        solver: Solver,
+        violations: Vec<String>,
+    },
+    /// A solver found a shorter schedule than the exact one
+    BeatsExact {
+        solver: Solver,
+        makespan: i32,
+        exact: i32,
+    },
+    /// An exact solver found a longer schedule than another exact one
+    MissesOptimum {
+        solver: Solver,
+        makespan: i32,
+        optimum: i32,
+    },
+    /// Grouping identical jobs changed the bound of the linear program of a
+    /// solver, although the linear program has an optimum in which identical
+    /// jobs agree
+    GroupingChangesBound {
+        solver: Solver,
+        bound: f64,
+        grouped: f64,
+    },
+}
+impl Problem {
+    fn solver(&self) -> Solver {
+        match self {
+            Problem::Violation { solver, .. }
+            | Problem::BeatsExact { solver, .. }
+            | Problem::MissesOptimum { solver, .. }
+            | Problem::GroupingChangesBound { solver, .. } => *solver,
+        }
+    }
+    /// Whether `other` is the same kind of problem of the same solver, which
+    /// is what shrinking an instance has to preserve
+    fn reproduces(&self, other: &Problem) -> bool {
+        mem::discriminant(self) == mem::discriminant(other) && self.solver() == other.solver()
+    }
+}
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::Violation { solver, violations } => {
+                write!(
+                    f,
+                    "{solver} violates the instance: {}",
+                    violations.join(", ")
+                )
+            }
+            Problem::BeatsExact {
+                solver,
+                makespan,
+                exact,
+            } => write!(
+                f,
+                "{solver} has makespan {makespan}, which beats the optimum {exact}"
+            ),
+            Problem::MissesOptimum {
+                solver,
+                makespan,
+                optimum,
+            } => write!(
+                f,
+                "{solver} is exact but has makespan {makespan}, which misses the optimum {optimum}"
+            ),
+            Problem::GroupingChangesBound {
+                solver,
+                bound,
+                grouped,
+            } => write!(
+                f,
+                "{solver} has the bound {bound}, but {grouped} with --group-identical"
+            ),
+        }
+    }
+}
+
+/// A problem along with the shrunk instance that reproduces it
+#[derive(Debug)]
+pub struct Failure {
+    /// Seed from which the original instance was generated
+    pub seed: u64,
+    /// The problem on the shrunk instance
+    pub problem: Problem,
+    /// Job file of the saved instance
+    pub job_file: String,
+    /// Constraint file of the saved instance
+    pub constraint_file: String,
+}
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "seed {}: {}, reproduced by {} and {}",
+            self.seed, self.problem, self.job_file, self.constraint_file
+        )
+    }
+}
+
+/// Outcome of a self test
+#[derive(Debug)]
+pub struct Report {
+    /// Number of instances that were checked
+    pub instances: usize,
+    /// Makespans of every solver divided by the optimum on the instances
+    /// without failures, in the order of `Solver::ALL`
+    pub ratios: Vec<(Solver, Vec<f64>)>,
+    /// Failed checks, at most one per instance
+    pub failures: Vec<Failure>,
+}
+impl Report {
+    /// Describes the makespan ratios of every solver in one line each
+    pub fn lines(&self) -> Vec<String> {
+        self.ratios
+            .iter()
+            .map(|(solver, ratios)| {
+                let max = ratios.iter().copied().fold(1.0, f64::max);
+                let mean = if ratios.is_empty() {
+                    1.0
+                } else {
+                    ratios.iter().sum::<f64>() / ratios.len() as f64
+                };
+                format!(
+                    "{solver}: makespan at most {max:.3} and on average {mean:.3} times the optimum on {} instances",
+                    ratios.len()
+                )
+            })
+            .chain([format!(
+                "{} of {} instances failed",
+                self.failures.len(),
+                self.instances
+            )])
+            .collect()
+    }
+}
+
+/// Generates `instances` random instances with at most `max_jobs` jobs from
+/// consecutive seeds starting at `seed`, solves each with every solver, and
+/// checks the schedules. The shrunk instance of every failed check is saved
+/// to `dir`.
+pub fn run(instances: usize, seed: u64, max_jobs: usize, dir: &str) -> Report {
+    assert!(max_jobs >= 1, "max_jobs must be at least 1");
+    let mut report = Report {
+        instances,
+        ratios: Solver::ALL.iter().map(|&solver| (solver, vec![])).collect(),
+        failures: vec![],
+    };
+    for seed in (0..instances as u64).map(|i| seed.wrapping_add(i)) {
+        let instance = random_instance(seed, max_jobs);
+        let (makespans, problems) = check(&instance);
+        if let Some(problem) = problems.into_iter().next() {
+            diagnostics::report_error(Category::Verification, format!("Seed {seed}: {problem}"));
+            let (instance, problem) = shrink(instance, problem);
+            report.failures.push(save(dir, seed, instance, problem));
+            continue;
+        }
+        debug!("Seed {seed}: all checks passed with makespans {makespans:?}");
+        let optimum = f64::from(makespans[0].max(1));
+        for ((_, ratios), makespan) in report.ratios.iter_mut().zip(makespans) {
+            ratios.push(f64::from(makespan) / optimum);
+        }
+    }
+    info!(
+        "Checked {instances} instances with {}",
+        Solver::ALL.iter().join(", ")
+    );
+    report
+}
+
+/// Generates a small instance with random parameters from `seed`
+fn random_instance(seed: u64, max_jobs: usize) -> Instance {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let n = rng.random_range(1..=max_jobs);
+    let config = GenerateConfig {
+        n,
+        m: rng.random_range(1..=MAX_PROCESSORS),
+        min_p: 1,
+        max_p: rng.random_range(1..=MAX_PROCESSING_TIME),
+        omega: rng.random_range(1..=n),
+        min_chain: 1,
+        max_chain: n,
+        concave: rng.random(),
+    };
+    config
+        .validate()
+        .unwrap_or_else(|errors| panic!("the self test chose invalid parameters: {errors:?}"));
+    generate::instance(&config, &mut rng)
+}
+
+/// Solves `instance` with every solver and returns their makespans in the
+/// order of `Solver::ALL` along with the problems that came up
+fn check(instance: &Instance) -> (Vec<i32>, Vec<Problem>) {
+    let schedules = Solver::ALL
+        .iter()
+        .map(|&solver| (solver, solver.solve(copy(instance))))
+        .collect::<Vec<_>>();
+    let mut problems = vec![];
+    for (solver, schedule) in &schedules {
+        let violations = schedule.violations(instance);
+        if !violations.is_empty() {
+            problems.push(Problem::Violation {
+                solver: *solver,
+                violations,
+            });
+        }
+    }
+    let exact = schedules
+        .iter()
+        .filter(|(solver, _)| solver.is_exact())
+        .map(|(_, schedule)| schedule.makespan())
+        .min();
+    if let Some(exact) = exact {
+        for (solver, schedule) in &schedules {
+            if schedule.makespan() < exact {
+                problems.push(Problem::BeatsExact {
+                    solver: *solver,
+                    makespan: schedule.makespan(),
+                    exact,
+                });
+            } else if solver.is_exact() && schedule.makespan() > exact {
+                problems.push(Problem::MissesOptimum {
+                    solver: *solver,
+                    makespan: schedule.makespan(),
+                    optimum: exact,
+                });
+            }
+        }
+    }
+    problems.extend(check_grouping(instance));
+    let makespans = schedules
+        .iter()
+        .map(|(_, schedule)| schedule.makespan())
+        .collect();
+    (makespans, problems)
+}
+
+/// Solves `instance` with the linear program and the ILP both with and
+/// without grouping identical jobs, and returns the problems of those whose
+/// bounds differ. Instances without identical jobs are skipped.
+fn check_grouping(instance: &Instance) -> Vec<Problem> {
+    let groups = preprocess::identical_jobs(instance);
+    if groups.members.len() == instance.jobs.len() {
+        return vec![];
+    }
+    let bound = |solver, group_identical| {
+        let options = ListOptions::default();
+        let (_, stats) = match solver {
+            Solver::Algorithm(Algorithm::Lp) => lp::schedule(
+                copy(instance),
+                false,
+                INT_TOLERANCE,
+                RoundingStrategy::default(),
+                group_identical,
+                &options,
+            ),
+            _ => ilp::schedule(
+                copy(instance),
+                false,
+                INT_TOLERANCE,
+                Rounding::default(),
+                group_identical,
+                &options,
+            ),
+        };
+        stats.lp_bound.unwrap_or_default()
+    };
+    [Algorithm::Lp, Algorithm::Ilp]
+        .into_iter()
+        .map(Solver::Algorithm)
+        .filter_map(|solver| {
+            let (bound, grouped) = (bound(solver, false), bound(solver, true));
+            debug!("{solver} has the bound {bound}, and {grouped} with grouping");
+            ((bound - grouped).abs() > INT_TOLERANCE).then_some(Problem::GroupingChangesBound {
+                solver,
+                bound,
+                grouped,
+            })
+        })
+        .collect()
+}
+
+/// Removes processors and jobs from `instance` as long as the same problem
+/// still comes up, and returns the smallest instance along with its problem
+fn shrink(mut instance: Instance, mut problem: Problem) -> (Instance, Problem) {
+    while instance.processor_count > 1 {
+        let smaller = instance.with_processor_count(instance.processor_count - 1);
+        let Some(other) = reproduce(&smaller, &problem) else {
+            break;
+        };
+        (instance, problem) = (smaller, other);
+    }
+    let mut j = 0;
+    while j < instance.jobs.len() && instance.jobs.len() > 1 {
+        let positions = (0..instance.jobs.len()).filter(|&k| k != j).collect_vec();
+        let smaller = instance.restrict_to(&positions);
+        match reproduce(&smaller, &problem) {
+            Some(other) => (instance, problem) = (smaller, other),
+            None => j += 1,
+        }
+    }
+    info!(
+        "Shrunk the instance to {} jobs on {} processors",
+        instance.jobs.len(),
+        instance.processor_count
+    );
+    (instance, problem)
+}
+
+/// Checks `instance` and returns the problem that reproduces `problem`, if any
+fn reproduce(instance: &Instance, problem: &Problem) -> Option<Problem> {
+    check(instance)
+        .1
+        .into_iter()
+        .find(|other| problem.reproduces(other))
+}
+
+/// Writes `instance` to job and constraint files named after `seed` in `dir`
+fn save(dir: &str, seed: u64, instance: Instance, problem: Problem) -> Failure {
+    fs::create_dir_all(dir).unwrap_or_else(|e| panic!("cannot create directory {dir}: {e}"));
+    let job_file = format!("{dir}/seed-{seed}-jobs.csv");
+    let constraint_file = format!("{dir}/seed-{seed}-constraints.csv");
+    files::write(&job_file, &constraint_file, instance);
+    Failure {
+        seed,
+        problem,
+        job_file,
+        constraint_file,
+    }
+}
+
+/// Copies `instance` for a solver, which consumes it
+fn copy(instance: &Instance) -> Instance {
+    instance.restrict_to(&(0..instance.jobs.len()).collect_vec())
+}
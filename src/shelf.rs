@@ -0,0 +1,249 @@
+// A fast approximation that levels the precedence graph and packs the jobs of
+// every level into shelves, i.e. rows of jobs that start at the same time,
+// which are stacked on top of each other. It needs neither a linear program
+// nor LIST, so it scales to instances with hundreds of thousands of jobs.
+
+use std::{cmp::Reverse, time::Instant};
+
+use itertools::Itertools;
+use log::{debug, info};
+
+use crate::{
+    algo::{topo_layers, Instance, Schedule, ScheduledJob},
+    diagnostics::{self, Category},
+    list::{self, ListOptions},
+};
+
+/// Number of shelf heights that are tried for every level, spaced
+/// geometrically between the shortest and the longest possible height
+const HEIGHT_CANDIDATES: usize = 16;
+
+/// A row of jobs that start at the same time on disjoint processors
+#[derive(Debug)]
+struct Shelf {
+    /// Positions of the jobs along with their allotments, from the first
+    /// processor on
+    jobs: Vec<(usize, usize)>,
+    /// Longest processing time of the jobs
+    height: i32,
+}
+
+/// Schedules `instance` in shelves. The jobs are grouped into levels by their
+/// depth in the precedence graph. For every level, a few shelf heights are
+/// tried: every job gets the smallest allotment with which it fits under the
+/// height, and the jobs are packed into shelves by first-fit decreasing work.
+/// The height with the lowest stack of shelves wins, and the shelves of all
+/// levels are stacked in order, so that every job starts after its
+/// predecessors and their minimum lags. Soft constraints are treated as hard
+/// ones. Instances with a capacity profile, downtime, pinned jobs, or
+/// processor affinity are placed by LIST at the start times of the shelves
+/// instead, which is much slower.
+pub fn schedule(instance: Instance) -> Schedule {
+    if !instance.soft_constraints.is_empty() {
+        diagnostics::report(
+            Category::Model,
+            "The shelf algorithm treats all soft constraints as hard ones",
+        );
+    }
+    let instance = instance.enforce_soft(|_| true);
+    let before = Instant::now();
+    let levels = topo_layers(&instance).unwrap_or_else(|e| panic!("{e}"));
+
+    let n = instance.jobs.len();
+    let mut jobs = Vec::with_capacity(n);
+    let mut completions = vec![0; n];
+    let mut shelf_count = 0;
+    let mut time = 0;
+    for level in &levels {
+        let shelves = pack_level(&instance, level);
+        debug!(
+            "Packed {} jobs of a level into {} shelves",
+            level.len(),
+            shelves.len()
+        );
+        shelf_count += shelves.len();
+        for shelf in shelves {
+            let ready = shelf
+                .jobs
+                .iter()
+                .flat_map(|&(j, _)| instance.predecessor_indices(j).iter().map(move |&p| (p, j)))
+                .map(|(p, j)| completions[p] + instance.lag(p, j).min)
+                .max()
+                .unwrap_or(0);
+            let start = time.max(ready);
+            let mut processor = 0;
+            for (j, allotment) in shelf.jobs {
+                let job = &instance.jobs[j];
+                completions[j] = start + job.processing_time(allotment);
+                jobs.push(ScheduledJob {
+                    job: instance.shared_job(j),
+                    allotment,
+                    start_time: start,
+                    processors: (processor..processor + allotment).collect(),
+                });
+                processor += allotment;
+            }
+            time = start + shelf.height;
+        }
+    }
+    info!(
+        "Packed {n} jobs on {} levels into {shelf_count} shelves in {:?}",
+        levels.len(),
+        before.elapsed()
+    );
+
+    if instance.has_capacity_limits() || !instance.pinned.is_empty() || instance.has_affinity() {
+        info!("Placing the jobs with LIST to respect the capacity, downtime, pins, and affinity");
+        let mut allotments = vec![1; n];
+        let mut starts = vec![0; n];
+        for job in &jobs {
+            allotments[job.job.index] = job.allotment;
+            starts[job.job.index] = job.start_time;
+        }
+        let list = list::schedule(&instance, &allotments, &starts, &ListOptions::default());
+        jobs = list.jobs;
+    }
+    Schedule {
+        processor_count: instance.processor_count,
+        jobs,
+    }
+}
+
+/// Packs the jobs at the given positions into shelves with every candidate
+/// height and returns the shelves of the height whose shelves are the lowest
+/// in total, preferring larger heights, which need fewer processors, on ties
+fn pack_level(instance: &Instance, level: &[usize]) -> Vec<Shelf> {
+    let shortest = level
+        .iter()
+        .map(|&j| {
+            (1..=max_allotment(instance, j))
+                .map(|l| instance.jobs[j].processing_time(l))
+                .min()
+                .unwrap_or(0)
+        })
+        .max()
+        .unwrap_or(0);
+    let longest = level
+        .iter()
+        .map(|&j| instance.jobs[j].processing_time(1))
+        .max()
+        .unwrap_or(0)
+        .max(shortest);
+    let low = f64::from(shortest.max(1));
+    let ratio = f64::from(longest.max(1)) / low;
+    let heights = (0..HEIGHT_CANDIDATES)
+        .map(|k| (low * ratio.powf(k as f64 / (HEIGHT_CANDIDATES - 1) as f64)).round() as i32)
+        .chain([shortest, longest])
+        .filter(|&height| shortest <= height && height <= longest)
+        .sorted_unstable()
+        .dedup();
+    heights
+        .map(|height| pack(instance, level, height))
+        .min_by_key(|(height, shelves)| {
+            (
+                shelves.iter().map(|shelf| shelf.height).sum::<i32>(),
+                Reverse(*height),
+            )
+        })
+        .map(|(_, shelves)| shelves)
+        .unwrap_or_default()
+}
+
+/// Packs the jobs at the given positions into shelves by first-fit
+/// decreasing work, giving every job the smallest allotment with which it
+/// takes at most `height`. Every job fits since `height` is at least the
+/// shortest processing time of every job.
+fn pack(instance: &Instance, level: &[usize], height: i32) -> (i32, Vec<Shelf>) {
+    let m = instance.processor_count;
+    let jobs = level
+        .iter()
+        .map(|&j| {
+            let job = &instance.jobs[j];
+            let allotment = (1..=max_allotment(instance, j))
+                .find(|&l| job.processing_time(l) <= height)
+                .expect("every job fits under the height");
+            (j, allotment)
+        })
+        .sorted_by_key(|&(j, l)| {
+            (
+                Reverse(l as i64 * i64::from(instance.jobs[j].processing_time(l))),
+                j,
+            )
+        });
+    let mut shelves: Vec<Shelf> = vec![];
+    let mut free = FirstFit::new(level.len());
+    for (j, allotment) in jobs {
+        let time = instance.jobs[j].processing_time(allotment);
+        let index = free.first(allotment).unwrap_or(shelves.len());
+        if index == shelves.len() {
+            shelves.push(Shelf {
+                jobs: vec![],
+                height: 0,
+            });
+            free.set(index, m);
+        }
+        let shelf = &mut shelves[index];
+        shelf.jobs.push((j, allotment));
+        shelf.height = shelf.height.max(time);
+        free.set(index, free.get(index) - allotment);
+    }
+    (height, shelves)
+}
+
+/// Largest allotment of the job at position `j` on the processors of
+/// `instance`
+fn max_allotment(instance: &Instance, j: usize) -> usize {
+    instance.jobs[j]
+        .max_allotment()
+        .min(instance.processor_count)
+        .max(1)
+}
+
+/// The free processors of every shelf in a tree of maxima, which finds the
+/// first shelf with enough free processors in logarithmic time. Shelves that
+/// were not opened yet have no free processors.
+struct FirstFit {
+    /// Number of leaves, a power of two
+    leaves: usize,
+    /// Maxima of the subtrees, where the children of node `k` are `2k` and
+    /// `2k + 1` and the leaves start at `leaves`
+    tree: Vec<usize>,
+}
+impl FirstFit {
+    /// Creates a tree for up to `shelves` shelves
+    fn new(shelves: usize) -> Self {
+        let leaves = shelves.next_power_of_two();
+        FirstFit {
+            leaves,
+            tree: vec![0; 2 * leaves],
+        }
+    }
+    /// Number of free processors of the shelf at `index`
+    fn get(&self, index: usize) -> usize {
+        self.tree[self.leaves + index]
+    }
+    /// Sets the number of free processors of the shelf at `index`
+    fn set(&mut self, index: usize, free: usize) {
+        let mut k = self.leaves + index;
+        self.tree[k] = free;
+        while k > 1 {
+            k /= 2;
+            self.tree[k] = self.tree[2 * k].max(self.tree[2 * k + 1]);
+        }
+    }
+    /// Finds the first shelf with at least `width` free processors
+    fn first(&self, width: usize) -> Option<usize> {
+        if self.tree[1] < width {
+            return None;
+        }
+        let mut k = 1;
+        while k < self.leaves {
+            k = if self.tree[2 * k] >= width {
+                2 * k
+            } else {
+                2 * k + 1
+            };
+        }
+        Some(k - self.leaves)
+    }
+}
@@ -0,0 +1,259 @@
+// Small statistics helpers for comparing algorithms over many runs, kept here
+// to avoid depending on a statistics crate.
+
+/// Largest number of pairs for which the p-value of the signed-rank test is
+/// computed exactly instead of by the normal approximation
+const MAX_EXACT_PAIRS: usize = 25;
+
+/// Outcome of a Wilcoxon signed-rank test
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SignedRankTest {
+    /// Number of pairs with a nonzero difference, pairs without a difference
+    /// are dropped
+    pub pairs: usize,
+    /// Sum of the ranks of the positive differences
+    pub statistic: f64,
+    /// Two-sided p-value of the hypothesis that the differences are
+    /// distributed symmetrically around zero
+    pub p_value: f64,
+}
+
+/// Runs a two-sided Wilcoxon signed-rank test on the paired samples `a` and
+/// `b`. The absolute differences get average ranks if they are tied. Without
+/// ties and for at most `MAX_EXACT_PAIRS` pairs, the p-value is exact,
+/// otherwise it uses the normal approximation with tie and continuity
+/// correction.
+pub fn wilcoxon_signed_rank(a: &[f64], b: &[f64]) -> SignedRankTest {
+    assert_eq!(a.len(), b.len(), "samples must be paired");
+    let differences = a
+        .iter()
+        .zip(b)
+        .map(|(x, y)| x - y)
+        .filter(|&d| d != 0.0)
+        .collect::<Vec<_>>();
+    let n = differences.len();
+    if n == 0 {
+        return SignedRankTest {
+            pairs: 0,
+            statistic: 0.0,
+            p_value: 1.0,
+        };
+    }
+    let magnitudes = differences.iter().map(|d| d.abs()).collect::<Vec<_>>();
+    let ranks = average_ranks(&magnitudes);
+    let statistic = differences
+        .iter()
+        .zip(&ranks)
+        .filter(|&(&d, _)| d > 0.0)
+        .map(|(_, &rank)| rank)
+        .sum::<f64>();
+    let ties = tie_sizes(&magnitudes);
+    let p_value = if n <= MAX_EXACT_PAIRS && ties.iter().all(|&t| t == 1) {
+        exact_p_value(n, statistic)
+    } else {
+        let n = n as f64;
+        let mean = n * (n + 1.0) / 4.0;
+        let correction = ties
+            .iter()
+            .map(|&t| {
+                let t = t as f64;
+                t * t * t - t
+            })
+            .sum::<f64>()
+            / 48.0;
+        let deviation = (n * (n + 1.0) * (2.0 * n + 1.0) / 24.0 - correction).sqrt();
+        if deviation == 0.0 {
+            1.0
+        } else {
+            let z = ((statistic - mean).abs() - 0.5).max(0.0) / deviation;
+            (2.0 * (1.0 - normal_cdf(z))).min(1.0)
+        }
+    };
+    SignedRankTest {
+        pairs: n,
+        statistic,
+        p_value,
+    }
+}
+
+/// Computes the two-sided p-value of the rank sum `statistic` of `n` pairs
+/// without ties by counting the subsets of the ranks 1 to `n` by their sums
+fn exact_p_value(n: usize, statistic: f64) -> f64 {
+    let max = n * (n + 1) / 2;
+    let mut counts = vec![0_u64; max + 1];
+    counts[0] = 1;
+    for rank in 1..=n {
+        for sum in (rank..=max).rev() {
+            counts[sum] += counts[sum - rank];
+        }
+    }
+    let total = counts.iter().sum::<u64>() as f64;
+    // the distribution is symmetric, so the smaller tail is doubled
+    let statistic = statistic.round() as usize;
+    let tail = statistic.min(max - statistic);
+    let tail = counts[..=tail].iter().sum::<u64>() as f64;
+    (2.0 * tail / total).min(1.0)
+}
+
+/// Ranks `values` from 1 on in ascending order, giving tied values the
+/// average of their ranks
+fn average_ranks(values: &[f64]) -> Vec<f64> {
+    let mut order = (0..values.len()).collect::<Vec<_>>();
+    order.sort_by(|&i, &j| values[i].total_cmp(&values[j]));
+    let mut ranks = vec![0.0; values.len()];
+    let mut start = 0;
+    while start < order.len() {
+        let end = (start..order.len())
+            .find(|&k| values[order[k]].total_cmp(&values[order[start]]).is_ne())
+            .unwrap_or(order.len());
+        // ranks start + 1 to end share their average
+        let rank = (start + 1 + end) as f64 / 2.0;
+        for &i in &order[start..end] {
+            ranks[i] = rank;
+        }
+        start = end;
+    }
+    ranks
+}
+
+/// Returns the number of occurrences of every distinct value
+fn tie_sizes(values: &[f64]) -> Vec<usize> {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    sorted
+        .chunk_by(|a, b| a.total_cmp(b).is_eq())
+        .map(<[f64]>::len)
+        .collect()
+}
+
+/// Computes the `q`-quantile of the ascending `sorted` values, interpolating
+/// linearly between the two closest values
+pub fn quantile(sorted: &[f64], q: f64) -> f64 {
+    assert!(!sorted.is_empty(), "quantile of no values");
+    let position = q.clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let below = position.floor() as usize;
+    let above = position.ceil() as usize;
+    sorted[below] + (sorted[above] - sorted[below]) * (position - below as f64)
+}
+
+/// Counts how many of the `values` fall into each of `bins` bins of equal
+/// width between the smallest and the largest value. Returns the lower end
+/// of every bin along with its count, where the last bin includes the
+/// largest value.
+pub fn histogram(values: &[f64], bins: usize) -> Vec<(f64, usize)> {
+    assert!(bins > 0, "histogram without bins");
+    let (Some(min), Some(max)) = (
+        values.iter().copied().min_by(f64::total_cmp),
+        values.iter().copied().max_by(f64::total_cmp),
+    ) else {
+        return vec![];
+    };
+    let width = (max - min) / bins as f64;
+    let mut counts = vec![0; bins];
+    for &value in values {
+        let bin = if width == 0.0 {
+            0
+        } else {
+            (((value - min) / width) as usize).min(bins - 1)
+        };
+        counts[bin] += 1;
+    }
+    counts
+        .into_iter()
+        .enumerate()
+        .map(|(bin, count)| (min + width * bin as f64, count))
+        .collect()
+}
+
+/// Approximates the cumulative distribution function of the standard normal
+/// distribution by formula 7.1.26 of Abramowitz and Stegun, which is accurate
+/// to about 1e-7
+fn normal_cdf(x: f64) -> f64 {
+    let z = x.abs() / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.327_591_1 * z);
+    let polynomial = t
+        * (0.254_829_592
+            + t * (-0.284_496_736
+                + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    let erf = 1.0 - polynomial * (-z * z).exp();
+    if x >= 0.0 {
+        (1.0 + erf) / 2.0
+    } else {
+        (1.0 - erf) / 2.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{average_ranks, histogram, normal_cdf, quantile, wilcoxon_signed_rank};
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-4,
+            "{actual} is not close to {expected}"
+        );
+    }
+
+    #[test]
+    fn exact_signed_rank_test() {
+        // differences 1 to 5, all positive: only one of 32 sign patterns is
+        // as extreme in each direction
+        let test = wilcoxon_signed_rank(&[2.0, 3.0, 4.0, 5.0, 6.0], &[1.0; 5]);
+        assert_eq!(test.pairs, 5);
+        assert_close(test.statistic, 15.0);
+        assert_close(test.p_value, 2.0 / 32.0);
+
+        // differences 1, -2, 3, -4, 5, 6: 14 of 64 subsets of the ranks sum
+        // to at most 21 - 15 = 6
+        let test = wilcoxon_signed_rank(
+            &[1.0, 0.0, 3.0, 0.0, 5.0, 6.0],
+            &[0.0, 2.0, 0.0, 4.0, 0.0, 0.0],
+        );
+        assert_eq!(test.pairs, 6);
+        assert_close(test.statistic, 15.0);
+        assert_close(test.p_value, 28.0 / 64.0);
+    }
+
+    #[test]
+    fn approximate_signed_rank_test_with_ties() {
+        // four tied differences of 1 share the rank 2.5, so W = 10 with mean
+        // 5 and variance 7.5 - 60 / 48 = 6.25, which gives z = 4.5 / 2.5
+        let test = wilcoxon_signed_rank(&[2.0; 4], &[1.0; 4]);
+        assert_eq!(test.pairs, 4);
+        assert_close(test.statistic, 10.0);
+        assert_close(test.p_value, 2.0 * (1.0 - 0.964_070));
+    }
+
+    #[test]
+    fn equal_samples_are_not_different() {
+        let test = wilcoxon_signed_rank(&[1.0, 2.0, 3.0], &[1.0, 2.0, 3.0]);
+        assert_eq!(test.pairs, 0);
+        assert_close(test.p_value, 1.0);
+    }
+
+    #[test]
+    fn tied_values_share_their_ranks() {
+        assert_eq!(average_ranks(&[3.0, 1.0, 3.0, 2.0]), [3.5, 1.0, 3.5, 2.0]);
+    }
+
+    #[test]
+    fn quantiles_and_histogram() {
+        let sorted = [1.0, 2.0, 3.0, 4.0];
+        assert_close(quantile(&sorted, 0.0), 1.0);
+        assert_close(quantile(&sorted, 0.5), 2.5);
+        assert_close(quantile(&sorted, 1.0), 4.0);
+        assert_eq!(
+            histogram(&[0.0, 1.0, 2.0, 3.0, 4.0], 2),
+            [(0.0, 2), (2.0, 3)]
+        );
+        assert_eq!(histogram(&[1.0, 1.0], 3), [(1.0, 2), (1.0, 0), (1.0, 0)]);
+        assert!(histogram(&[], 3).is_empty());
+    }
+
+    #[test]
+    fn normal_distribution() {
+        assert_close(normal_cdf(0.0), 0.5);
+        assert_close(normal_cdf(1.96), 0.975);
+        assert_close(normal_cdf(-1.96), 0.025);
+    }
+}
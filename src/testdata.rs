@@ -0,0 +1,182 @@
+// Reference instances with recorded makespans, used to catch regressions in
+// the quality of the computed schedules.
+
+use std::{fs, path::Path};
+
+use csv::{ReaderBuilder, Writer};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    algo::{Instance, Rounding, Schedule, INT_TOLERANCE},
+    dp,
+    files::{self, ReadOptions},
+    ilp,
+    list::ListOptions,
+    lp::{self, RoundingStrategy},
+    output::atomic_write,
+};
+
+/// Name of the file that holds the reference makespans
+const REFERENCE_FILE: &str = "reference.csv";
+
+/// The makespan that an algorithm achieved on a reference instance
+#[derive(Debug, Serialize, Deserialize)]
+struct Reference {
+    instance: String,
+    algorithm: String,
+    makespan: i32,
+}
+
+/// Solves every instance in `dir` with every algorithm. Without `check`, the
+/// makespans are written to the reference file. With `check`, they are
+/// compared to the reference file and the function returns `false` if any
+/// makespan is worse than its reference by more than the relative
+/// `tolerance`. Schedules that violate the instance always fail.
+pub fn run(dir: &str, check: bool, tolerance: f64) -> bool {
+    let mut ok = true;
+    let mut results = vec![];
+    for instance in instance_names(dir) {
+        for algorithm in ["solve-dp", "solve-lp", "solve-ilp"] {
+            let (schedule, instance_copy) = solve(dir, &instance, algorithm);
+            let violations = schedule.violations(&instance_copy);
+            for violation in &violations {
+                error!("{algorithm} on {instance}: {violation}");
+            }
+            ok &= violations.is_empty();
+            results.push(Reference {
+                instance: instance.clone(),
+                algorithm: algorithm.to_string(),
+                makespan: schedule.makespan(),
+            });
+        }
+    }
+
+    let path = Path::new(dir).join(REFERENCE_FILE);
+    let path = path.to_str().expect("invalid UTF-8 in testdata path");
+    if check {
+        let mut rdr = ReaderBuilder::new()
+            .from_path(path)
+            .unwrap_or_else(|e| panic!("cannot read {path}: {e}"));
+        let references = rdr
+            .deserialize()
+            .collect::<Result<Vec<Reference>, _>>()
+            .unwrap_or_else(|e| panic!("cannot parse {path}: {e}"));
+        for result in &results {
+            let Some(reference) = references
+                .iter()
+                .find(|r| r.instance == result.instance && r.algorithm == result.algorithm)
+            else {
+                error!(
+                    "No reference for {} on {}",
+                    result.algorithm, result.instance
+                );
+                ok = false;
+                continue;
+            };
+            let limit = f64::from(reference.makespan) * (1.0 + tolerance);
+            if f64::from(result.makespan) > limit {
+                error!(
+                    "{} on {} has makespan {}, reference is {}",
+                    result.algorithm, result.instance, result.makespan, reference.makespan
+                );
+                ok = false;
+            } else {
+                info!(
+                    "{} on {} has makespan {}, reference is {}",
+                    result.algorithm, result.instance, result.makespan, reference.makespan
+                );
+            }
+        }
+    } else {
+        let mut wtr = Writer::from_writer(vec![]);
+        for result in &results {
+            wtr.serialize(result)
+                .unwrap_or_else(|e| panic!("cannot write {path}: {e}"));
+        }
+        let bytes = wtr
+            .into_inner()
+            .unwrap_or_else(|e| panic!("cannot flush {path}: {e}"));
+        atomic_write(path, &bytes).unwrap_or_else(|e| panic!("cannot write {path}: {e}"));
+        info!("Reference makespans are written to {path}");
+    }
+    ok
+}
+
+/// Lists the names of all instances in `dir`, i.e. the `<name>` part of every
+/// `jobs_<name>.csv` file, in sorted order
+pub fn instance_names(dir: &str) -> Vec<String> {
+    let mut names = fs::read_dir(dir)
+        .unwrap_or_else(|e| panic!("cannot read directory {dir}: {e}"))
+        .filter_map(|entry| {
+            let name = entry.ok()?.file_name().into_string().ok()?;
+            Some(
+                name.strip_prefix("jobs_")?
+                    .strip_suffix(".csv")?
+                    .to_string(),
+            )
+        })
+        .collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+/// Solves the instance with the given name using `algorithm`. Returns the
+/// schedule and a fresh copy of the instance to check it against.
+fn solve(dir: &str, name: &str, algorithm: &str) -> (Schedule, Instance) {
+    let read = || read_instance(dir, name);
+    let schedule = match algorithm {
+        "solve-dp" => dp::schedule(
+            read(),
+            &dp::CheckpointOptions::default(),
+            &dp::TraceOptions::default(),
+        ),
+        "solve-lp" => {
+            lp::schedule(
+                read(),
+                false,
+                INT_TOLERANCE,
+                RoundingStrategy::default(),
+                false,
+                &ListOptions::default(),
+            )
+            .0
+        }
+        "solve-ilp" => {
+            ilp::schedule(
+                read(),
+                false,
+                INT_TOLERANCE,
+                Rounding::default(),
+                false,
+                &ListOptions::default(),
+            )
+            .0
+        }
+        _ => unreachable!("unknown algorithm {algorithm}"),
+    };
+    (schedule, read())
+}
+
+/// Paths of the job file and the constraint file of the instance with the
+/// given name in `dir`
+pub fn instance_files(dir: &str, name: &str) -> (String, String) {
+    let dir = Path::new(dir);
+    let path = |file: String| {
+        dir.join(file)
+            .to_str()
+            .expect("invalid UTF-8 in testdata path")
+            .to_string()
+    };
+    (
+        path(format!("jobs_{name}.csv")),
+        path(format!("constraints_for_{name}.csv")),
+    )
+}
+
+/// Reads the instance with the given name from `dir`
+pub fn read_instance(dir: &str, name: &str) -> Instance {
+    let (job_file, constraint_file) = instance_files(dir, name);
+    files::read(&job_file, &constraint_file, &ReadOptions::default())
+        .unwrap_or_else(|e| panic!("cannot read {name}: {e}"))
+}
@@ -0,0 +1,169 @@
+// Streams the candidate states that the dynamic program explores to a file,
+// one JSON object per line, and aggregates such traces.
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+};
+
+use log::info;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+/// Why the dynamic program does not continue from a candidate state
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Pruned {
+    /// The job would have to start before time 0
+    NegativeStart,
+    /// The job would start before a predecessor at the front of another chain
+    /// completes and its minimum lag passed
+    Precedence,
+    /// The job would start before the front job of another chain starts, so
+    /// the jobs would not be added in the order of their start times
+    StartOrder,
+    /// More processors would be busy than there are
+    ProcessorCount,
+    /// The job is pinned to another allotment or completion time, or it would
+    /// start too late for a missing job to be done before a pinned job
+    /// starts, or need more processors than the pinned jobs leave free
+    Pinned,
+    /// The state was reached before on another path
+    Known,
+}
+impl Pruned {
+    /// All reasons in the order in which the search checks them
+    pub const ALL: [Pruned; 6] = [
+        Pruned::Pinned,
+        Pruned::NegativeStart,
+        Pruned::Precedence,
+        Pruned::StartOrder,
+        Pruned::ProcessorCount,
+        Pruned::Known,
+    ];
+}
+impl fmt::Display for Pruned {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Pruned::NegativeStart => "negative-start",
+            Pruned::Precedence => "precedence",
+            Pruned::StartOrder => "start-order",
+            Pruned::ProcessorCount => "processor-count",
+            Pruned::Pinned => "pinned",
+            Pruned::Known => "known",
+        })
+    }
+}
+
+/// A candidate state of the dynamic program, reached by adding a job to the
+/// state with the given `ideal`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TraceEvent {
+    /// Number of scheduled jobs in the candidate state
+    pub depth: usize,
+    /// How far every chain has advanced before adding the job
+    pub ideal: Vec<usize>,
+    /// Index of the added job
+    pub job: usize,
+    /// Number of processors of the added job
+    pub allotment: usize,
+    /// Completion time of the added job
+    pub completion_time: i32,
+    /// Why the search did not continue from the candidate, if it did not
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pruned: Option<Pruned>,
+}
+
+/// Writes a random sample of the explored candidates to a trace file
+#[derive(Debug)]
+pub struct Tracer {
+    writer: BufWriter<File>,
+    sample: f64,
+    rng: StdRng,
+    written: usize,
+}
+
+impl Tracer {
+    /// Creates the trace file at `path`, to which every candidate is written
+    /// with probability `sample`. The sample is drawn from a fixed seed, so
+    /// tracing the same search twice writes the same candidates.
+    pub fn create(path: &str, sample: f64) -> Self {
+        assert!(
+            sample > 0.0 && sample <= 1.0,
+            "trace sample {sample} is not in (0, 1]"
+        );
+        let file =
+            File::create(path).unwrap_or_else(|e| panic!("cannot create trace file {path}: {e}"));
+        Tracer {
+            writer: BufWriter::new(file),
+            sample,
+            rng: StdRng::seed_from_u64(0),
+            written: 0,
+        }
+    }
+
+    /// Writes the candidate described by `event` if it is part of the sample.
+    /// The event is only built if it is written.
+    pub fn record(&mut self, event: impl FnOnce() -> TraceEvent) {
+        if self.sample < 1.0 && self.rng.random::<f64>() >= self.sample {
+            return;
+        }
+        serde_json::to_writer(&mut self.writer, &event()).expect("cannot serialize trace event");
+        self.writer
+            .write_all(b"\n")
+            .expect("cannot write to trace file");
+        self.written += 1;
+    }
+
+    /// Flushes the trace file
+    pub fn finish(mut self) {
+        self.writer.flush().expect("cannot write to trace file");
+        info!("Traced {} candidate states", self.written);
+    }
+}
+
+/// Number of candidates in a trace that were accepted and pruned for each
+/// reason
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Counts {
+    /// Candidates the search continued from
+    pub accepted: usize,
+    /// Pruned candidates, in the order of `Pruned::ALL`
+    pub pruned: [usize; Pruned::ALL.len()],
+}
+impl Counts {
+    fn add(&mut self, pruned: Option<Pruned>) {
+        match pruned {
+            Some(reason) => {
+                let position = Pruned::ALL
+                    .iter()
+                    .position(|&r| r == reason)
+                    .expect("unknown pruning reason");
+                self.pruned[position] += 1;
+            }
+            None => self.accepted += 1,
+        }
+    }
+    /// Total number of candidates
+    pub fn explored(&self) -> usize {
+        self.accepted + self.pruned.iter().sum::<usize>()
+    }
+}
+
+/// Reads the trace file at `path` and counts its candidates by depth
+pub fn stats(path: &str) -> BTreeMap<usize, Counts> {
+    let file = File::open(path).unwrap_or_else(|e| panic!("cannot read trace file {path}: {e}"));
+    let mut counts = BTreeMap::<usize, Counts>::new();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.unwrap_or_else(|e| panic!("cannot read trace file {path}: {e}"));
+        if line.trim().is_empty() {
+            continue;
+        }
+        let event: TraceEvent = serde_json::from_str(&line)
+            .unwrap_or_else(|e| panic!("bad trace event in line {}: {e}", index + 1));
+        counts.entry(event.depth).or_default().add(event.pruned);
+    }
+    counts
+}
@@ -0,0 +1,84 @@
+// Interrupts the dynamic program with SIGINT like Ctrl-C does, so that
+// stopping a long run keeps writing its partial results.
+
+#![cfg(unix)]
+
+use std::{
+    env, fs,
+    path::Path,
+    process::{self, Command},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Exit status of an interrupted run, see `interrupt::INTERRUPTED_EXIT_CODE`
+const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+/// Writes 24 independent jobs on 4 processors, which need 48 units of time,
+/// so that the search up to a horizon of 47 takes far longer than the test
+fn write_instance(dir: &Path) {
+    let jobs = (1..=24)
+        .map(|id| format!("{id},8,5,4,3\n"))
+        .collect::<String>();
+    fs::write(dir.join("jobs.csv"), format!("id,p1,p2,p3,p4\n{jobs}"))
+        .expect("cannot write the job file");
+    fs::write(dir.join("constraints.csv"), "id0,id1\n").expect("cannot write the constraint file");
+}
+
+#[test]
+fn interrupted_search_writes_checkpoint() {
+    let dir = env::temp_dir().join(format!("interrupt-{}", process::id()));
+    fs::create_dir_all(&dir).expect("cannot create the test directory");
+    write_instance(&dir);
+    let checkpoint = dir.join("checkpoint.json");
+    let _ = fs::remove_file(&checkpoint);
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_scheduling-malleable-tasks"))
+        .current_dir(&dir)
+        .args([
+            "solve-dp",
+            "--job-file",
+            "jobs.csv",
+            "--constraint-file",
+            "constraints.csv",
+            "--horizon",
+            "47",
+            "--checkpoint",
+            "checkpoint.json",
+            "--checkpoint-interval",
+            "3600",
+        ])
+        .spawn()
+        .expect("cannot run the binary");
+    // give the search time to start before interrupting it
+    thread::sleep(Duration::from_secs(2));
+    assert!(
+        child.try_wait().expect("cannot poll the binary").is_none(),
+        "the search ended before it was interrupted"
+    );
+    let killed = Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .expect("cannot run kill");
+    assert!(killed.success(), "cannot send SIGINT");
+
+    let deadline = Instant::now() + Duration::from_secs(60);
+    let status = loop {
+        if let Some(status) = child.try_wait().expect("cannot poll the binary") {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            panic!("the search did not stop within a minute of SIGINT");
+        }
+        thread::sleep(Duration::from_millis(50));
+    };
+    assert_eq!(status.code(), Some(INTERRUPTED_EXIT_CODE));
+    let contents = fs::read_to_string(&checkpoint).expect("no checkpoint was written");
+    let json = serde_json::from_str::<serde_json::Value>(&contents)
+        .expect("the checkpoint is not valid JSON");
+    assert!(
+        json["stack"].is_array(),
+        "the checkpoint has no search path"
+    );
+}
@@ -0,0 +1,38 @@
+// Reads the malformed instances in tests/fixtures/malformed, each of which
+// used to crash the CLI, and checks that it reports the file, the row, and
+// the reason instead of panicking.
+
+use std::{fs, process::Command};
+
+#[test]
+fn malformed_instances_are_reported() {
+    let fixtures = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/malformed");
+    let mut dirs = fs::read_dir(fixtures)
+        .expect("cannot list the fixtures")
+        .map(|entry| entry.expect("cannot read the fixtures").path())
+        .collect::<Vec<_>>();
+    dirs.sort();
+    assert!(!dirs.is_empty(), "there are no fixtures");
+    for dir in dirs {
+        let expected =
+            fs::read_to_string(dir.join("expected.txt")).expect("the fixture has no expected.txt");
+        let output = Command::new(env!("CARGO_BIN_EXE_scheduling-malleable-tasks"))
+            .current_dir(&dir)
+            .args(["stats", "-j", "jobs.csv", "-c", "constraints.csv"])
+            .output()
+            .expect("cannot run the binary");
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert_eq!(
+            output.status.code(),
+            Some(1),
+            "{} did not fail cleanly:\n{stdout}{stderr}",
+            dir.display()
+        );
+        assert!(
+            stdout.contains(expected.trim_end()),
+            "{} does not report {expected}:\n{stdout}{stderr}",
+            dir.display()
+        );
+    }
+}
@@ -0,0 +1,32 @@
+// Solves the bundled reference instances in testdata with every algorithm
+// through `regen-testdata --check`, so that changes to the rounding or the
+// list scheduling that make the schedules worse fail the tests.
+
+use std::process::Command;
+
+/// Relative amount by which a makespan may exceed its reference, which
+/// leaves room for the solver backend choosing among optimal solutions of
+/// the linear programs differently on other platforms
+const TOLERANCE: &str = "0.05";
+
+#[test]
+fn makespans_stay_within_tolerance_of_references() {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/testdata");
+    let output = Command::new(env!("CARGO_BIN_EXE_scheduling-malleable-tasks"))
+        .args([
+            "regen-testdata",
+            "--check",
+            "--dir",
+            dir,
+            "--tolerance",
+            TOLERANCE,
+        ])
+        .output()
+        .expect("cannot run the binary");
+    assert!(
+        output.status.success(),
+        "schedules are worse than their references or violate their instances:\n{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
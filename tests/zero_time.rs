@@ -0,0 +1,86 @@
+// Solves a chain with a job that takes no time with every solver and renders
+// it, so that instantaneous jobs keep respecting their precedence constraints
+// and are drawn as markers instead of empty boxes.
+
+use std::{
+    env, fs,
+    process::{self, Command},
+};
+
+/// The solvers that accept a job and a constraint file and write a schedule
+const SOLVERS: [&str; 5] = [
+    "solve-dp",
+    "solve-lp",
+    "solve-ilp",
+    "solve-milp",
+    "solve-shelf",
+];
+
+#[test]
+fn zero_time_job_stays_in_its_chain() {
+    let fixture = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/zero-time");
+    for solver in SOLVERS {
+        let dir = env::temp_dir().join(format!("zero-time-{solver}-{}", process::id()));
+        fs::create_dir_all(&dir).expect("cannot create the test directory");
+        for file in ["jobs.csv", "constraints.csv"] {
+            fs::copy(format!("{fixture}/{file}"), dir.join(file)).expect("cannot copy the fixture");
+        }
+        let output = Command::new(env!("CARGO_BIN_EXE_scheduling-malleable-tasks"))
+            .current_dir(&dir)
+            .args([
+                solver,
+                "-j",
+                "jobs.csv",
+                "-c",
+                "constraints.csv",
+                "--svg",
+                "--schedule-json",
+                "schedule.json",
+            ])
+            .output()
+            .expect("cannot run the binary");
+        assert!(
+            output.status.success(),
+            "{solver} failed:\n{}{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        let contents = fs::read_to_string(dir.join("schedule.json")).expect("no schedule written");
+        let json = serde_json::from_str::<serde_json::Value>(&contents)
+            .expect("the schedule is not valid JSON");
+        // the start and completion time of every job by index
+        let mut times = [None; 4];
+        for job in json["jobs"].as_array().expect("the schedule has jobs") {
+            let index = job["job"]["index"].as_u64().expect("numeric index") as usize;
+            let allotment = job["allotment"].as_u64().expect("numeric allotment") as usize;
+            let start = job["start_time"].as_i64().expect("numeric start time");
+            let duration = job["job"]["processing_times"][allotment - 1]
+                .as_i64()
+                .expect("numeric processing time");
+            times[index] = Some((start, start + duration));
+        }
+        let [Some(first), Some(zero), Some(last), Some(_)] = times else {
+            panic!("{solver} left out jobs: {times:?}");
+        };
+        assert_eq!(
+            zero.0, zero.1,
+            "{solver} gave the job without time a duration"
+        );
+        assert!(
+            first.1 <= zero.0,
+            "{solver} starts job 2 before job 1 completes"
+        );
+        assert!(
+            zero.1 <= last.0,
+            "{solver} starts job 3 before job 2 completes"
+        );
+
+        let svg = fs::read_to_string(dir.join("schedules/jobs_constraints_schedule.svg"))
+            .expect("no schedule rendered");
+        assert!(
+            svg.contains("job-marker"),
+            "{solver} did not draw the job without time as a marker"
+        );
+    }
+}